@@ -1,10 +1,19 @@
 use cumulus_primitives_core::ParaId;
-use parachain_template_runtime::{AccountId, AuraId, Signature, EXISTENTIAL_DEPOSIT};
+use pallet_xcm_transact_filter::{CallPattern, OriginClass};
+use parachain_template_runtime::{
+	precompiles::FrontierPrecompiles,
+	xcm_config::{RelayAssetId, RelayAssetOwner},
+	AccountId, AuraId, EcosystemPalletId, PotId, Signature, TreasuryPalletId, EXISTENTIAL_DEPOSIT,
+	SS58_PREFIX, TOKEN_DECIMALS, TOKEN_SYMBOL,
+};
 use sc_chain_spec::{ChainSpecExtension, ChainSpecGroup};
 use sc_service::ChainType;
 use serde::{Deserialize, Serialize};
 use sp_core::{sr25519, Pair, Public};
-use sp_runtime::traits::{IdentifyAccount, Verify};
+use sp_runtime::{
+	traits::{AccountIdConversion, IdentifyAccount, Verify},
+	Perbill,
+};
 
 /// Specialized `ChainSpec` for the normal parachain runtime.
 pub type ChainSpec =
@@ -64,9 +73,9 @@ pub fn template_session_keys(keys: AuraId) -> parachain_template_runtime::Sessio
 pub fn development_config() -> ChainSpec {
 	// Give your base currency a unit name and decimal places
 	let mut properties = sc_chain_spec::Properties::new();
-	properties.insert("tokenSymbol".into(), "UNIT".into());
-	properties.insert("tokenDecimals".into(), 12.into());
-	properties.insert("ss58Format".into(), 42.into());
+	properties.insert("tokenSymbol".into(), TOKEN_SYMBOL.into());
+	properties.insert("tokenDecimals".into(), TOKEN_DECIMALS.into());
+	properties.insert("ss58Format".into(), SS58_PREFIX.into());
 
 	ChainSpec::from_genesis(
 		// Name
@@ -119,9 +128,9 @@ pub fn development_config() -> ChainSpec {
 pub fn local_testnet_config() -> ChainSpec {
 	// Give your base currency a unit name and decimal places
 	let mut properties = sc_chain_spec::Properties::new();
-	properties.insert("tokenSymbol".into(), "UNIT".into());
-	properties.insert("tokenDecimals".into(), 12.into());
-	properties.insert("ss58Format".into(), 42.into());
+	properties.insert("tokenSymbol".into(), TOKEN_SYMBOL.into());
+	properties.insert("tokenDecimals".into(), TOKEN_DECIMALS.into());
+	properties.insert("ss58Format".into(), SS58_PREFIX.into());
 
 	ChainSpec::from_genesis(
 		// Name
@@ -191,11 +200,31 @@ fn testnet_genesis(
 		balances: parachain_template_runtime::BalancesConfig {
 			balances: endowed_accounts.iter().cloned().map(|k| (k, 1 << 60)).collect(),
 		},
+		assets: parachain_template_runtime::AssetsConfig {
+			// The relay chain token is sufficient, so it can be received without holding `UNIT`.
+			assets: vec![(RelayAssetId::get(), RelayAssetOwner::get(), true, EXISTENTIAL_DEPOSIT)],
+			metadata: vec![(
+				RelayAssetId::get(),
+				b"Relay Chain Token".to_vec(),
+				b"ROC".to_vec(),
+				12,
+			)],
+			accounts: vec![],
+		},
+		inflation: parachain_template_runtime::InflationConfig {
+			inflation_rate: Perbill::from_percent(5),
+			destinations: vec![
+				// Collator rewards are paid from the staking pot.
+				(PotId::get().into_account(), Perbill::from_percent(50)),
+				(TreasuryPalletId::get().into_account(), Perbill::from_percent(30)),
+				(EcosystemPalletId::get().into_account(), Perbill::from_percent(20)),
+			],
+		},
 		parachain_info: parachain_template_runtime::ParachainInfoConfig { parachain_id: id },
-		collator_selection: parachain_template_runtime::CollatorSelectionConfig {
+		collator_staking: parachain_template_runtime::CollatorStakingConfig {
 			invulnerables: invulnerables.iter().cloned().map(|(acc, _)| acc).collect(),
 			candidacy_bond: EXISTENTIAL_DEPOSIT * 16,
-			..Default::default()
+			desired_collators: 8,
 		},
 		session: parachain_template_runtime::SessionConfig {
 			keys: invulnerables
@@ -217,5 +246,41 @@ fn testnet_genesis(
 		polkadot_xcm: parachain_template_runtime::PolkadotXcmConfig {
 			safe_xcm_version: Some(SAFE_XCM_VERSION),
 		},
+		transact_filter: parachain_template_runtime::TransactFilterConfig {
+			// Remote origins may only move funds by default; governance can allow more. The relay
+			// chain's executive body manages the collators and the XCMP queue.
+			allowed_calls: [OriginClass::Sibling, OriginClass::RemoteAccount]
+				.into_iter()
+				.flat_map(|class| {
+					[
+						(class, CallPattern::call("Balances", "transfer")),
+						(class, CallPattern::call("Balances", "transfer_keep_alive")),
+					]
+				})
+				.chain([
+					(OriginClass::Relay, CallPattern::pallet("CollatorStaking")),
+					(OriginClass::Relay, CallPattern::pallet("XcmpQueue")),
+				])
+				.collect(),
+		},
+		evm: parachain_template_runtime::EVMConfig {
+			// Solidity refuses to call accounts without code, so give the precompiles code that
+			// reverts if ever executed instead of them.
+			accounts: FrontierPrecompiles::used_addresses()
+				.into_iter()
+				.map(|address| {
+					let account = pallet_evm::GenesisAccount {
+						nonce: Default::default(),
+						balance: Default::default(),
+						storage: Default::default(),
+						// PUSH1 0x00 PUSH1 0x00 REVERT
+						code: vec![0x60, 0x00, 0x60, 0x00, 0xfd],
+					};
+					(address, account)
+				})
+				.collect(),
+		},
+		ethereum: Default::default(),
+		base_fee: Default::default(),
 	}
 }
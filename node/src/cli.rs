@@ -88,6 +88,21 @@ pub struct Cli {
 	#[clap(flatten)]
 	pub run: cumulus_client_cli::RunCmd,
 
+	/// Share the Aura key with another collator, coordinating who authors through this lease
+	/// file.
+	#[clap(long, parse(from_os_str))]
+	pub collator_lease: Option<PathBuf>,
+
+	/// Claim slots in the collator lease under this identifier. Defaults to the libp2p peer ID of
+	/// the node.
+	#[clap(long, requires = "collator-lease")]
+	pub failover_id: Option<String>,
+
+	/// Run as standby collator: only author once no block sealed by the shared key was imported
+	/// for this many of the key's slots.
+	#[clap(long, requires = "collator-lease")]
+	pub standby_after: Option<u64>,
+
 	/// Relay chain arguments
 	#[clap(raw = true)]
 	pub relay_chain_args: Vec<String>,
@@ -317,10 +317,37 @@ pub fn run() -> Result<()> {
 				info!("Parachain genesis state: {}", genesis_state);
 				info!("Is collating: {}", if config.role.is_authority() { "yes" } else { "no" });
 
-				crate::service::start_parachain_node(config, polkadot_config, collator_options, id)
-					.await
-					.map(|r| r.0)
-					.map_err(Into::into)
+				let failover = match cli.collator_lease.clone() {
+					Some(lease) => {
+						let holder = match cli.failover_id.clone() {
+							Some(id) => id,
+							None => config
+								.network
+								.node_key
+								.clone()
+								.into_keypair()
+								.map_err(|e| format!("Failed to read the node key: {}", e))?
+								.public()
+								.to_peer_id()
+								.to_base58(),
+						};
+						let lease = crate::failover::Lease::new(lease, holder)
+							.map_err(|e| format!("Collator lease error: {}", e))?;
+						Some(crate::failover::Failover::new(lease, cli.standby_after))
+					},
+					None => None,
+				};
+
+				crate::service::start_parachain_node(
+					config,
+					polkadot_config,
+					collator_options,
+					id,
+					failover,
+				)
+				.await
+				.map(|r| r.0)
+				.map_err(Into::into)
 			})
 		},
 	}
@@ -0,0 +1,229 @@
+//! Detection and reporting of Aura equivocations.
+//!
+//! The Aura import queue accepts every correctly sealed block, so an author that seals two blocks
+//! for the same slot goes unnoticed. This task follows block imports, remembers who authored the
+//! blocks of recent slots and flags a second block of the same author for the same slot. Every
+//! equivocation is logged and counted in a Prometheus metric. Nodes holding an Aura key, the
+//! reporting key, also report it to the runtime, which disables the offender until the next
+//! session.
+
+use std::{collections::BTreeMap, sync::Arc};
+
+use futures::StreamExt;
+use pallet_collator_offences_runtime_api::{CollatorOffencesApi, EquivocationProof};
+use parachain_template_runtime::{opaque::Block, AuraId, Hash};
+use sc_client_api::BlockchainEvents;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_consensus_aura::{
+	digests::CompatibleDigestItem, sr25519::AuthoritySignature, AuraApi, Slot,
+};
+use sp_core::crypto::key_types::AURA;
+use sp_keystore::{SyncCryptoStore, SyncCryptoStorePtr};
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block as BlockT, Header as HeaderT},
+	DigestItem,
+};
+use substrate_prometheus_endpoint::{register, Counter, PrometheusError, Registry, U64};
+
+type Header = <Block as BlockT>::Header;
+
+const LOG_TARGET: &str = "aura-equivocation";
+
+/// Number of slots, counted back from the latest imported one, whose blocks are remembered.
+const SLOT_HISTORY: u64 = 1_000;
+
+/// Prometheus metrics of the equivocation watcher.
+pub struct Metrics {
+	equivocations: Counter<U64>,
+}
+
+impl Metrics {
+	/// Register the metrics in `registry`.
+	pub fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			equivocations: register(
+				Counter::new(
+					"parachain_aura_equivocations_total",
+					"Number of imported blocks whose author had already sealed a block for the slot",
+				)?,
+				registry,
+			)?,
+		})
+	}
+}
+
+/// The blocks imported for recent slots and their authors.
+#[derive(Default)]
+struct SeenBlocks {
+	blocks: BTreeMap<Slot, Vec<(AuraId, Header)>>,
+}
+
+impl SeenBlocks {
+	/// Remember `header`, authored by `author` in `slot`, and return an equivocation proof if the
+	/// author already sealed another block for that slot.
+	fn note(
+		&mut self,
+		slot: Slot,
+		author: AuraId,
+		header: Header,
+	) -> Option<EquivocationProof<Header, AuraId>> {
+		self.blocks = self.blocks.split_off(&Slot::from((*slot).saturating_sub(SLOT_HISTORY)));
+
+		let seen = self.blocks.entry(slot).or_default();
+		if seen.iter().any(|(_, known)| known.hash() == header.hash()) {
+			return None
+		}
+		let first_header =
+			seen.iter().find(|(who, _)| who == &author).map(|(_, known)| known.clone());
+		seen.push((author.clone(), header.clone()));
+
+		first_header.map(|first_header| EquivocationProof {
+			offender: author,
+			slot,
+			first_header,
+			second_header: header,
+		})
+	}
+}
+
+/// The Aura slot `header` was authored in.
+pub(crate) fn slot_of(header: &Header) -> Option<Slot> {
+	header.digest().logs().iter().find_map(|log| {
+		<DigestItem as CompatibleDigestItem<AuthoritySignature>>::as_aura_pre_digest(log)
+	})
+}
+
+/// The authority Aura assigns `slot` to in a block built on top of `parent`.
+pub(crate) fn slot_author<C>(client: &C, parent: Hash, slot: Slot) -> Option<AuraId>
+where
+	C: ProvideRuntimeApi<Block>,
+	C::Api: AuraApi<Block, AuraId>,
+{
+	let authorities = client.runtime_api().authorities(&BlockId::Hash(parent)).ok()?;
+	if authorities.is_empty() {
+		return None
+	}
+
+	let index = *slot % authorities.len() as u64;
+	Some(authorities[index as usize].clone())
+}
+
+/// Follow block imports and log, count and report equivocations.
+///
+/// Reports are only submitted when `keystore` is given and holds an Aura key at that time.
+pub async fn run<C>(client: Arc<C>, metrics: Option<Metrics>, keystore: Option<SyncCryptoStorePtr>)
+where
+	C: BlockchainEvents<Block> + HeaderBackend<Block> + ProvideRuntimeApi<Block>,
+	C::Api: AuraApi<Block, AuraId> + CollatorOffencesApi<Block, AuraId>,
+{
+	let mut seen = SeenBlocks::default();
+	let mut imports = client.import_notification_stream();
+
+	while let Some(notification) = imports.next().await {
+		let header = notification.header;
+		let authored = slot_of(&header)
+			.and_then(|slot| Some((slot, slot_author(&*client, *header.parent_hash(), slot)?)));
+		let (slot, author) = match authored {
+			Some(authored) => authored,
+			None => continue,
+		};
+		let proof = match seen.note(slot, author, header) {
+			Some(proof) => proof,
+			None => continue,
+		};
+
+		log::warn!(
+			target: LOG_TARGET,
+			"🚨 Author {:?} sealed both {:?} and {:?} for slot {}",
+			proof.offender,
+			proof.first_header.hash(),
+			proof.second_header.hash(),
+			*slot,
+		);
+		if let Some(metrics) = &metrics {
+			metrics.equivocations.inc();
+		}
+
+		let can_report = keystore.as_ref().map_or(false, |keystore| {
+			!SyncCryptoStore::sr25519_public_keys(&**keystore, AURA).is_empty()
+		});
+		if !can_report {
+			continue
+		}
+
+		let best = BlockId::Hash(client.info().best_hash);
+		match client.runtime_api().submit_report_equivocation_unsigned_extrinsic(&best, proof) {
+			Ok(Some(())) =>
+				log::info!(target: LOG_TARGET, "Submitted equivocation report for slot {}", *slot),
+			Ok(None) => log::warn!(
+				target: LOG_TARGET,
+				"Equivocation report for slot {} was rejected by the transaction pool",
+				*slot,
+			),
+			Err(e) => log::warn!(
+				target: LOG_TARGET,
+				"Failed to submit equivocation report for slot {}: {:?}",
+				*slot,
+				e,
+			),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::{sr25519, H256};
+	use sp_runtime::Digest;
+
+	fn author(seed: u8) -> AuraId {
+		sr25519::Public::from_raw([seed; 32]).into()
+	}
+
+	fn header(number: u32, salt: u8) -> Header {
+		Header::new(number, H256::repeat_byte(salt), H256::zero(), H256::zero(), Digest::default())
+	}
+
+	#[test]
+	fn second_block_of_an_author_in_a_slot_is_an_equivocation() {
+		let mut seen = SeenBlocks::default();
+		let slot = Slot::from(10);
+
+		assert!(seen.note(slot, author(1), header(5, 1)).is_none());
+		// Another author in the same slot, or the same author in another slot, is fine.
+		assert!(seen.note(slot, author(2), header(5, 2)).is_none());
+		assert!(seen.note(Slot::from(11), author(1), header(6, 3)).is_none());
+
+		let proof = seen.note(slot, author(1), header(5, 4)).expect("author 1 equivocated");
+		assert_eq!(proof.offender, author(1));
+		assert_eq!(proof.slot, slot);
+		assert_eq!(proof.first_header, header(5, 1));
+		assert_eq!(proof.second_header, header(5, 4));
+	}
+
+	#[test]
+	fn reimported_blocks_are_not_equivocations() {
+		let mut seen = SeenBlocks::default();
+		let slot = Slot::from(10);
+
+		assert!(seen.note(slot, author(1), header(5, 1)).is_none());
+		assert!(seen.note(slot, author(1), header(5, 1)).is_none());
+		assert_eq!(seen.blocks[&slot].len(), 1);
+	}
+
+	#[test]
+	fn slots_older_than_the_history_are_forgotten() {
+		let mut seen = SeenBlocks::default();
+
+		assert!(seen.note(Slot::from(10), author(1), header(5, 1)).is_none());
+		assert!(seen.note(Slot::from(10 + SLOT_HISTORY), author(2), header(6, 2)).is_none());
+		assert!(seen.blocks.contains_key(&Slot::from(10)));
+
+		assert!(seen.note(Slot::from(11 + SLOT_HISTORY), author(2), header(7, 3)).is_none());
+		assert!(!seen.blocks.contains_key(&Slot::from(10)));
+		// A block of the forgotten slot can no longer be told apart from the first one.
+		assert!(seen.note(Slot::from(10), author(1), header(5, 4)).is_none());
+	}
+}
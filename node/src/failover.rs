@@ -0,0 +1,206 @@
+//! Active/standby collator failover.
+//!
+//! Two collators may share an Aura key as long as they share a lease file, e.g. on a common
+//! volume. Before authoring in one of the key's slots a collator claims that slot in the lease,
+//! under an exclusive file lock. A slot can only be claimed once and claims only move forward, so
+//! the shared key never seals two blocks for one slot, not even when a single collator is asked
+//! to author twice in the same slot. Each collator claims under its own failover id, its libp2p
+//! peer ID unless given explicitly.
+//!
+//! The primary claims every slot of the key. The standby follows the chain and only claims once
+//! no block sealed by the key was imported for a configured number of the key's slots, and keeps
+//! claiming while it holds the lease. When the primary comes back it claims the next slot of the
+//! key and the standby steps back.
+
+use std::{
+	fs::{self, File, OpenOptions},
+	io::{self, Write},
+	path::PathBuf,
+	sync::{
+		atomic::{AtomicU64, Ordering},
+		Arc,
+	},
+};
+
+use fs2::FileExt;
+use futures::StreamExt;
+use parachain_template_runtime::{opaque::Block, AuraId, Hash};
+use sc_client_api::BlockchainEvents;
+use sp_api::ProvideRuntimeApi;
+use sp_consensus_aura::{AuraApi, Slot};
+use sp_core::{crypto::key_types::AURA, ByteArray};
+use sp_keystore::{SyncCryptoStore, SyncCryptoStorePtr};
+use sp_runtime::{generic::BlockId, traits::Header as HeaderT};
+
+use crate::equivocation::{slot_author, slot_of};
+
+const LOG_TARGET: &str = "collator-failover";
+
+/// The last slot claimed in a lease file and who claimed it.
+struct LeaseState {
+	holder: String,
+	slot: u64,
+}
+
+impl LeaseState {
+	fn parse(contents: &str) -> io::Result<Self> {
+		let invalid = || io::Error::new(io::ErrorKind::InvalidData, "malformed collator lease");
+		let (holder, slot) = contents.trim().rsplit_once(' ').ok_or_else(invalid)?;
+		Ok(Self { holder: holder.to_owned(), slot: slot.parse().map_err(|_| invalid())? })
+	}
+}
+
+/// A lease file shared by the collators using the same Aura key.
+pub struct Lease {
+	path: PathBuf,
+	holder: String,
+	/// Locked for as long as the lease is used, so no other running collator claims as `holder`.
+	_holder_lock: File,
+}
+
+impl Lease {
+	/// Use the lease at `path`, claiming slots as `holder`. Fails if `holder` is empty, contains
+	/// whitespace or slashes, or is used by another running collator sharing the lease.
+	pub fn new(path: PathBuf, holder: String) -> io::Result<Self> {
+		if holder.is_empty() || holder.contains(|c: char| c.is_whitespace() || c == '/') {
+			return Err(io::Error::new(
+				io::ErrorKind::InvalidInput,
+				format!("invalid collator failover id {:?}", holder),
+			))
+		}
+
+		let holder_lock = OpenOptions::new()
+			.write(true)
+			.create(true)
+			.open(path.with_extension(format!("{}.lock", holder)))?;
+		holder_lock.try_lock_exclusive().map_err(|_| {
+			io::Error::new(
+				io::ErrorKind::AlreadyExists,
+				format!("collator failover id {:?} is used by another collator", holder),
+			)
+		})?;
+
+		Ok(Self { path, holder, _holder_lock: holder_lock })
+	}
+
+	/// Claim `slot`. A lease last claimed by another collator is only taken over if `take_over`
+	/// agrees. Returns whether this collator may author `slot`.
+	pub fn claim(&self, slot: Slot, take_over: impl FnOnce() -> bool) -> io::Result<bool> {
+		// The state file is replaced on every claim, so lock a separate file that stays put.
+		let lock = OpenOptions::new()
+			.write(true)
+			.create(true)
+			.open(self.path.with_extension("lock"))?;
+		lock.lock_exclusive()?;
+		let claimed = self.claim_locked(slot, take_over);
+		let _ = lock.unlock();
+		claimed
+	}
+
+	fn claim_locked(&self, slot: Slot, take_over: impl FnOnce() -> bool) -> io::Result<bool> {
+		match fs::read_to_string(&self.path) {
+			Ok(contents) => {
+				let state = LeaseState::parse(&contents)?;
+				if *slot <= state.slot || (state.holder != self.holder && !take_over()) {
+					return Ok(false)
+				}
+			},
+			Err(e) if e.kind() == io::ErrorKind::NotFound => {},
+			Err(e) => return Err(e),
+		}
+
+		// Write the new state aside and rename it over the old one, so a crash never leaves a
+		// lease behind that forgot the last claimed slot.
+		let staged = self.path.with_extension("new");
+		let mut file = File::create(&staged)?;
+		writeln!(file, "{} {}", self.holder, *slot)?;
+		file.sync_all()?;
+		fs::rename(&staged, &self.path)?;
+		Ok(true)
+	}
+}
+
+/// Failover settings of a collator sharing its Aura key with another one.
+#[derive(Clone)]
+pub struct Failover {
+	lease: Arc<Lease>,
+	/// The number of the key's slots without a block after which a standby takes over. `None`
+	/// for the primary.
+	standby_after: Option<u64>,
+	/// The latest slot in which a block sealed by the shared key was imported, or in which the
+	/// standby started watching.
+	last_seen: Arc<AtomicU64>,
+}
+
+impl Failover {
+	/// Coordinate through `lease`. A standby is given the number of the key's slots it waits for
+	/// the primary before taking over.
+	pub fn new(lease: Lease, standby_after: Option<u64>) -> Self {
+		Self { lease: Arc::new(lease), standby_after, last_seen: Default::default() }
+	}
+
+	/// Check whether this collator may author `slot` on top of `parent`, claiming the slot in
+	/// the lease if its key is the slot's author.
+	pub fn may_author<C>(
+		&self,
+		client: &C,
+		keystore: &SyncCryptoStorePtr,
+		parent: Hash,
+		slot: Slot,
+	) -> io::Result<bool>
+	where
+		C: ProvideRuntimeApi<Block>,
+		C::Api: AuraApi<Block, AuraId>,
+	{
+		let author = match slot_author(client, parent, slot) {
+			Some(author) if is_local(keystore, &author) => author,
+			// Aura will not author this slot anyway.
+			_ => return Ok(true),
+		};
+		let authorities = client
+			.runtime_api()
+			.authorities(&BlockId::Hash(parent))
+			.map_or(1, |authorities| authorities.len() as u64);
+
+		// Start counting from the first slot the standby sees.
+		let _ = self.last_seen.compare_exchange(0, *slot, Ordering::Relaxed, Ordering::Relaxed);
+		let last_seen = self.last_seen.load(Ordering::Relaxed);
+		let standby_after = self.standby_after;
+		let claimed = self.lease.claim(slot, || match standby_after {
+			None => true,
+			Some(slots) => (*slot).saturating_sub(last_seen) > slots.saturating_mul(authorities),
+		})?;
+
+		if claimed && standby_after.is_some() {
+			log::info!(target: LOG_TARGET, "Standby authoring slot {} for {:?}", *slot, author);
+		}
+		Ok(claimed)
+	}
+
+	/// Follow block imports and remember the latest slot authored with the shared key.
+	pub async fn track_shared_key<C>(self, client: Arc<C>, keystore: SyncCryptoStorePtr)
+	where
+		C: BlockchainEvents<Block> + ProvideRuntimeApi<Block>,
+		C::Api: AuraApi<Block, AuraId>,
+	{
+		let mut imports = client.import_notification_stream();
+
+		while let Some(notification) = imports.next().await {
+			let header = notification.header;
+			let slot = match slot_of(&header) {
+				Some(slot) => slot,
+				None => continue,
+			};
+			let is_shared_key = slot_author(&*client, *header.parent_hash(), slot)
+				.map_or(false, |author| is_local(&keystore, &author));
+			if is_shared_key {
+				self.last_seen.fetch_max(*slot, Ordering::Relaxed);
+			}
+		}
+	}
+}
+
+/// Whether `keystore` holds the private part of `key`.
+fn is_local(keystore: &SyncCryptoStorePtr, key: &AuraId) -> bool {
+	SyncCryptoStore::has_keys(&**keystore, &[(key.to_raw_vec(), AURA)])
+}
@@ -5,34 +5,115 @@
 
 #![warn(missing_docs)]
 
-use std::sync::Arc;
+use std::{collections::BTreeMap, sync::Arc};
 
-use parachain_template_runtime::{opaque::Block, AccountId, Balance, Index as Nonce};
+use fc_rpc::{
+	EthBlockDataCacheTask, OverrideHandle, RuntimeApiStorageOverride, SchemaV1Override,
+	SchemaV2Override, SchemaV3Override, StorageOverride,
+};
+use fc_rpc_core::types::{FeeHistoryCache, FilterPool};
+use fp_storage::EthereumStorageSchema;
+use parachain_template_runtime::{
+	opaque::Block, AccountId, Balance, BlockNumber, Call, Event, Hash, Index as Nonce,
+};
 
-use sc_client_api::AuxStore;
+use sc_client_api::{
+	backend::{Backend, StateBackend, StorageProvider},
+	AuxStore, BlockchainEvents,
+};
+use sc_network::NetworkService;
 pub use sc_rpc::{DenyUnsafe, SubscriptionTaskExecutor};
+use sc_transaction_pool::{ChainApi, Pool};
 use sc_transaction_pool_api::TransactionPool;
 use sp_api::ProvideRuntimeApi;
 use sp_block_builder::BlockBuilder;
 use sp_blockchain::{Error as BlockChainError, HeaderBackend, HeaderMetadata};
+use sp_runtime::traits::BlakeTwo256;
+
+/// Maximum number of log filters installed at the same time.
+const MAX_STORED_FILTERS: usize = 500;
 
 /// A type representing all RPC extensions.
 pub type RpcExtension = jsonrpc_core::IoHandler<sc_rpc::Metadata>;
 
 /// Full client dependencies
-pub struct FullDeps<C, P> {
+pub struct FullDeps<C, P, A: ChainApi> {
 	/// The client instance to use.
 	pub client: Arc<C>,
 	/// Transaction pool instance.
 	pub pool: Arc<P>,
 	/// Whether to deny unsafe calls
 	pub deny_unsafe: DenyUnsafe,
+	/// Dependencies of the Ethereum RPC.
+	pub eth: EthDeps<A>,
+}
+
+/// Dependencies of the Ethereum RPC.
+pub struct EthDeps<A: ChainApi> {
+	/// Graph of the transaction pool.
+	pub graph: Arc<Pool<A>>,
+	/// The network service.
+	pub network: Arc<NetworkService<Block, Hash>>,
+	/// Whether the node is an authority.
+	pub is_authority: bool,
+	/// The Frontier mapping database.
+	pub frontier_backend: Arc<fc_db::Backend<Block>>,
+	/// Readers of the Ethereum storage for each storage schema.
+	pub overrides: Arc<OverrideHandle<Block>>,
+	/// Cache of Ethereum blocks and transaction statuses.
+	pub block_data_cache: Arc<EthBlockDataCacheTask<Block>>,
+	/// Pool of installed log filters.
+	pub filter_pool: FilterPool,
+	/// Cache of the fee history.
+	pub fee_history_cache: FeeHistoryCache,
+	/// Number of blocks kept in the fee history cache.
+	pub fee_history_limit: u64,
+	/// Maximum number of logs returned by a single query.
+	pub max_past_logs: u32,
+}
+
+/// Readers of the Ethereum storage of each storage schema, falling back to the runtime API.
+pub fn overrides_handle<C, BE>(client: Arc<C>) -> Arc<OverrideHandle<Block>>
+where
+	C: ProvideRuntimeApi<Block> + StorageProvider<Block, BE> + AuxStore,
+	C: HeaderBackend<Block> + HeaderMetadata<Block, Error = BlockChainError>,
+	C: Send + Sync + 'static,
+	C::Api: fp_rpc::EthereumRuntimeRPCApi<Block>,
+	BE: Backend<Block> + 'static,
+	BE::State: StateBackend<BlakeTwo256>,
+{
+	let mut overrides_map = BTreeMap::new();
+	overrides_map.insert(
+		EthereumStorageSchema::V1,
+		Box::new(SchemaV1Override::new(client.clone()))
+			as Box<dyn StorageOverride<_> + Send + Sync>,
+	);
+	overrides_map.insert(
+		EthereumStorageSchema::V2,
+		Box::new(SchemaV2Override::new(client.clone()))
+			as Box<dyn StorageOverride<_> + Send + Sync>,
+	);
+	overrides_map.insert(
+		EthereumStorageSchema::V3,
+		Box::new(SchemaV3Override::new(client.clone()))
+			as Box<dyn StorageOverride<_> + Send + Sync>,
+	);
+
+	Arc::new(OverrideHandle {
+		schemas: overrides_map,
+		fallback: Box::new(RuntimeApiStorageOverride::new(client)),
+	})
 }
 
 /// Instantiate all RPC extensions.
-pub fn create_full<C, P>(deps: FullDeps<C, P>) -> RpcExtension
+pub fn create_full<C, P, A, BE>(
+	deps: FullDeps<C, P, A>,
+	subscription_task_executor: SubscriptionTaskExecutor,
+) -> RpcExtension
 where
 	C: ProvideRuntimeApi<Block>
+		+ StorageProvider<Block, BE>
+		+ BlockchainEvents<Block>
 		+ HeaderBackend<Block>
 		+ AuxStore
 		+ HeaderMetadata<Block, Error = BlockChainError>
@@ -41,17 +122,93 @@ where
 		+ 'static,
 	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>,
 	C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Nonce>,
+	C::Api: pallet_collator_stats_rpc::CollatorStatsRuntimeApi<Block, AccountId, BlockNumber>,
+	C::Api: pallet_contracts_rpc::ContractsRuntimeApi<Block, AccountId, Balance, BlockNumber, Hash>,
+	C::Api: xcm_dry_run_rpc::XcmDryRunRuntimeApi<Block, Call, Event>,
+	C::Api: xcm_fees_rpc::XcmFeesRuntimeApi<Block, Call, Balance>,
+	C::Api: pallet_xcm_overweight_rpc::XcmOverweightRuntimeApi<Block>,
+	C::Api: fp_rpc::EthereumRuntimeRPCApi<Block>,
+	C::Api: fp_rpc::ConvertTransactionRuntimeApi<Block>,
 	C::Api: BlockBuilder<Block>,
-	P: TransactionPool + Sync + Send + 'static,
+	P: TransactionPool<Block = Block> + Sync + Send + 'static,
+	A: ChainApi<Block = Block> + 'static,
+	BE: Backend<Block> + 'static,
+	BE::State: StateBackend<BlakeTwo256>,
 {
+	use fc_rpc::{
+		EthApi, EthApiServer, EthFilterApi, EthFilterApiServer, EthPubSubApi, EthPubSubApiServer,
+		HexEncodedIdProvider, NetApi, NetApiServer, Web3Api, Web3ApiServer,
+	};
+	use jsonrpc_pubsub::manager::SubscriptionManager;
+	use pallet_collator_stats_rpc::{CollatorStats, CollatorStatsApi};
+	use pallet_contracts_rpc::{Contracts, ContractsApi};
 	use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApi};
+	use pallet_xcm_overweight_rpc::{XcmOverweight, XcmOverweightApi};
 	use substrate_frame_rpc_system::{FullSystem, SystemApi};
+	use xcm_dry_run_rpc::{XcmDryRun, XcmDryRunApi};
+	use xcm_fees_rpc::{XcmFeesApi, XcmFeesEstimator};
 
 	let mut io = jsonrpc_core::IoHandler::default();
-	let FullDeps { client, pool, deny_unsafe } = deps;
+	let FullDeps { client, pool, deny_unsafe, eth } = deps;
+	let EthDeps {
+		graph,
+		network,
+		is_authority,
+		frontier_backend,
+		overrides,
+		block_data_cache,
+		filter_pool,
+		fee_history_cache,
+		fee_history_limit,
+		max_past_logs,
+	} = eth;
+
+	io.extend_with(SystemApi::to_delegate(FullSystem::new(
+		client.clone(),
+		pool.clone(),
+		deny_unsafe,
+	)));
+	io.extend_with(TransactionPaymentApi::to_delegate(TransactionPayment::new(client.clone())));
+	io.extend_with(CollatorStatsApi::to_delegate(CollatorStats::new(client.clone())));
+	io.extend_with(ContractsApi::to_delegate(Contracts::new(client.clone())));
+	io.extend_with(XcmDryRunApi::to_delegate(XcmDryRun::new(client.clone(), deny_unsafe)));
+	io.extend_with(XcmFeesApi::to_delegate(XcmFeesEstimator::new(client.clone())));
+	io.extend_with(XcmOverweightApi::to_delegate(XcmOverweight::new(client.clone())));
 
-	io.extend_with(SystemApi::to_delegate(FullSystem::new(client.clone(), pool, deny_unsafe)));
-	io.extend_with(TransactionPaymentApi::to_delegate(TransactionPayment::new(client)));
+	io.extend_with(EthApiServer::to_delegate(EthApi::new(
+		client.clone(),
+		pool.clone(),
+		graph,
+		Some(parachain_template_runtime::evm_config::TransactionConverter),
+		network.clone(),
+		Vec::new(),
+		overrides.clone(),
+		frontier_backend.clone(),
+		is_authority,
+		block_data_cache.clone(),
+		fee_history_cache,
+		fee_history_limit,
+	)));
+	io.extend_with(EthFilterApiServer::to_delegate(EthFilterApi::new(
+		client.clone(),
+		frontier_backend,
+		filter_pool,
+		MAX_STORED_FILTERS,
+		max_past_logs,
+		block_data_cache,
+	)));
+	io.extend_with(NetApiServer::to_delegate(NetApi::new(client.clone(), network.clone(), true)));
+	io.extend_with(Web3ApiServer::to_delegate(Web3Api::new(client.clone())));
+	io.extend_with(EthPubSubApiServer::to_delegate(EthPubSubApi::new(
+		pool,
+		client,
+		network,
+		SubscriptionManager::<HexEncodedIdProvider>::with_id_provider(
+			HexEncodedIdProvider::default(),
+			Arc::new(subscription_task_executor),
+		),
+		overrides,
+	)));
 
 	io
 }
@@ -1,14 +1,22 @@
 //! Service and ServiceFactory implementation. Specialized wrapper over substrate service.
 
 // std
-use std::{sync::Arc, time::Duration};
+use std::{
+	collections::BTreeMap,
+	path::PathBuf,
+	sync::{Arc, Mutex},
+	time::Duration,
+};
 
 use cumulus_client_cli::CollatorOptions;
 // Local Runtime Types
 use parachain_template_runtime::{
-	opaque::Block, AccountId, Balance, Hash, Index as Nonce, RuntimeApi,
+	opaque::Block, AccountId, AuraId, Balance, BlockNumber, Call, Event, Hash, Index as Nonce,
+	RuntimeApi,
 };
 
+use crate::failover::Failover;
+
 // Cumulus Imports
 use cumulus_client_consensus_aura::{AuraConsensus, BuildAuraConsensusParams, SlotProportion};
 use cumulus_client_consensus_common::ParachainConsensus;
@@ -21,11 +29,18 @@ use cumulus_relay_chain_inprocess_interface::build_inprocess_relay_chain;
 use cumulus_relay_chain_interface::{RelayChainError, RelayChainInterface, RelayChainResult};
 use cumulus_relay_chain_rpc_interface::RelayChainRPCInterface;
 
+// Frontier Imports
+use fc_mapping_sync::{MappingSyncWorker, SyncStrategy};
+use fc_rpc::{EthBlockDataCacheTask, EthTask};
+use futures::StreamExt;
+
 // Substrate Imports
-use sc_client_api::ExecutorProvider;
+use sc_client_api::{BlockchainEvents, ExecutorProvider};
 use sc_executor::NativeElseWasmExecutor;
 use sc_network::NetworkService;
-use sc_service::{Configuration, PartialComponents, Role, TFullBackend, TFullClient, TaskManager};
+use sc_service::{
+	BasePath, Configuration, PartialComponents, Role, TFullBackend, TFullClient, TaskManager,
+};
 use sc_telemetry::{Telemetry, TelemetryHandle, TelemetryWorker, TelemetryWorkerHandle};
 use sp_api::ConstructRuntimeApi;
 use sp_keystore::SyncCryptoStorePtr;
@@ -34,6 +49,15 @@ use substrate_prometheus_endpoint::Registry;
 
 use polkadot_service::CollatorPair;
 
+/// Number of blocks after which an unused log filter is dropped.
+const FILTER_RETAIN_THRESHOLD: u64 = 100;
+/// Number of blocks kept in the fee history cache.
+const FEE_HISTORY_LIMIT: u64 = 2048;
+/// Maximum number of logs returned by a single `eth_getLogs` query.
+const MAX_PAST_LOGS: u32 = 10_000;
+/// Number of Ethereum blocks and of transaction status sets cached for the RPC.
+const ETH_BLOCK_DATA_CACHE_SIZE: usize = 50;
+
 /// Native executor instance.
 pub struct TemplateRuntimeExecutor;
 
@@ -164,6 +188,28 @@ where
 	Ok(params)
 }
 
+/// Directory of the Frontier mapping database of the chain configured in `config`.
+pub fn frontier_database_dir(config: &Configuration) -> PathBuf {
+	let config_dir = config
+		.base_path
+		.as_ref()
+		.map(|base_path| base_path.config_dir(config.chain_spec.id()))
+		.unwrap_or_else(|| {
+			BasePath::from_project("", "", "parachain-collator").config_dir(config.chain_spec.id())
+		});
+	config_dir.join("frontier").join("db")
+}
+
+/// Open the Frontier database mapping Ethereum block and transaction hashes to Substrate ones.
+pub fn open_frontier_backend(config: &Configuration) -> Result<Arc<fc_db::Backend<Block>>, String> {
+	Ok(Arc::new(fc_db::Backend::<Block>::new(&fc_db::DatabaseSettings {
+		source: fc_db::DatabaseSource::RocksDb {
+			path: frontier_database_dir(config),
+			cache_size: 0,
+		},
+	})?))
+}
+
 async fn build_relay_chain_interface(
 	polkadot_config: Configuration,
 	parachain_config: &Configuration,
@@ -214,7 +260,16 @@ where
 		+ sp_block_builder::BlockBuilder<Block>
 		+ cumulus_primitives_core::CollectCollationInfo<Block>
 		+ pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>
-		+ substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Nonce>,
+		+ substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Nonce>
+		+ pallet_collator_stats_rpc::CollatorStatsRuntimeApi<Block, AccountId, BlockNumber>
+		+ sp_consensus_aura::AuraApi<Block, AuraId>
+		+ pallet_collator_offences_runtime_api::CollatorOffencesApi<Block, AuraId>
+		+ pallet_contracts_rpc::ContractsRuntimeApi<Block, AccountId, Balance, BlockNumber, Hash>
+		+ xcm_dry_run_rpc::XcmDryRunRuntimeApi<Block, Call, Event>
+		+ xcm_fees_rpc::XcmFeesRuntimeApi<Block, Call, Balance>
+		+ pallet_xcm_overweight_rpc::XcmOverweightRuntimeApi<Block>
+		+ fp_rpc::EthereumRuntimeRPCApi<Block>
+		+ fp_rpc::ConvertTransactionRuntimeApi<Block>,
 	sc_client_api::StateBackendFor<TFullBackend<Block>, Block>: sp_api::StateBackend<BlakeTwo256>,
 	Executor: sc_executor::NativeExecutionDispatch + 'static,
 	RB: Fn(
@@ -263,6 +318,7 @@ where
 	let client = params.client.clone();
 	let backend = params.backend.clone();
 	let mut task_manager = params.task_manager;
+	let frontier_backend = open_frontier_backend(&parachain_config)?;
 
 	let (relay_chain_interface, collator_key) = build_relay_chain_interface(
 		polkadot_config,
@@ -297,18 +353,46 @@ where
 			warp_sync: None,
 		})?;
 
+	let filter_pool = Arc::new(Mutex::new(BTreeMap::new()));
+	let fee_history_cache = Arc::new(Mutex::new(BTreeMap::new()));
+	let overrides = crate::rpc::overrides_handle(client.clone());
+	let block_data_cache = Arc::new(EthBlockDataCacheTask::new(
+		task_manager.spawn_handle(),
+		overrides.clone(),
+		ETH_BLOCK_DATA_CACHE_SIZE,
+		ETH_BLOCK_DATA_CACHE_SIZE,
+		prometheus_registry.clone(),
+	));
+
 	let rpc_extensions_builder = {
 		let client = client.clone();
 		let transaction_pool = transaction_pool.clone();
+		let network = network.clone();
+		let frontier_backend = frontier_backend.clone();
+		let overrides = overrides.clone();
+		let filter_pool = filter_pool.clone();
+		let fee_history_cache = fee_history_cache.clone();
 
-		Box::new(move |deny_unsafe, _| {
+		Box::new(move |deny_unsafe, subscription_task_executor| {
 			let deps = crate::rpc::FullDeps {
 				client: client.clone(),
 				pool: transaction_pool.clone(),
 				deny_unsafe,
+				eth: crate::rpc::EthDeps {
+					graph: transaction_pool.pool().clone(),
+					network: network.clone(),
+					is_authority: validator,
+					frontier_backend: frontier_backend.clone(),
+					overrides: overrides.clone(),
+					block_data_cache: block_data_cache.clone(),
+					filter_pool: filter_pool.clone(),
+					fee_history_cache: fee_history_cache.clone(),
+					fee_history_limit: FEE_HISTORY_LIMIT,
+					max_past_logs: MAX_PAST_LOGS,
+				},
 			};
 
-			Ok(crate::rpc::create_full(deps))
+			Ok(crate::rpc::create_full(deps, subscription_task_executor))
 		})
 	};
 
@@ -325,6 +409,49 @@ where
 		telemetry: telemetry.as_mut(),
 	})?;
 
+	task_manager.spawn_essential_handle().spawn(
+		"frontier-mapping-sync-worker",
+		None,
+		MappingSyncWorker::new(
+			client.import_notification_stream(),
+			Duration::new(6, 0),
+			client.clone(),
+			backend.clone(),
+			frontier_backend.clone(),
+			SyncStrategy::Parachain,
+		)
+		.for_each(|()| futures::future::ready(())),
+	);
+	task_manager.spawn_essential_handle().spawn(
+		"frontier-filter-pool",
+		None,
+		EthTask::filter_pool_task(client.clone(), filter_pool, FILTER_RETAIN_THRESHOLD),
+	);
+	task_manager.spawn_essential_handle().spawn(
+		"frontier-schema-cache-task",
+		None,
+		EthTask::ethereum_schema_cache_task(client.clone(), frontier_backend),
+	);
+	task_manager.spawn_essential_handle().spawn(
+		"frontier-fee-history",
+		None,
+		EthTask::fee_history_task(client.clone(), overrides, fee_history_cache, FEE_HISTORY_LIMIT),
+	);
+
+	let equivocation_metrics = prometheus_registry
+		.as_ref()
+		.map(crate::equivocation::Metrics::register)
+		.transpose()?;
+	task_manager.spawn_handle().spawn(
+		"aura-equivocation-watcher",
+		None,
+		crate::equivocation::run(
+			client.clone(),
+			equivocation_metrics,
+			validator.then(|| params.keystore_container.sync_keystore()),
+		),
+	);
+
 	let announce_block = {
 		let network = network.clone();
 		Arc::new(move |hash, data| network.announce_block(hash, data))
@@ -429,11 +556,15 @@ pub fn parachain_build_import_queue(
 }
 
 /// Start a parachain node.
+///
+/// With `failover` set, the node shares its Aura key with another collator and coordinates with
+/// it through a lease before authoring.
 pub async fn start_parachain_node(
 	parachain_config: Configuration,
 	polkadot_config: Configuration,
 	collator_options: CollatorOptions,
 	id: ParaId,
+	failover: Option<Failover>,
 ) -> sc_service::error::Result<(
 	TaskManager,
 	Arc<TFullClient<Block, RuntimeApi, NativeElseWasmExecutor<TemplateRuntimeExecutor>>>,
@@ -456,6 +587,16 @@ pub async fn start_parachain_node(
 		 force_authoring| {
 			let slot_duration = cumulus_client_consensus_aura::slot_duration(&*client)?;
 
+			if let Some(failover) = &failover {
+				task_manager.spawn_handle().spawn(
+					"collator-failover",
+					None,
+					failover.clone().track_shared_key(client.clone(), keystore.clone()),
+				);
+			}
+			let failover_client = client.clone();
+			let failover_keystore = keystore.clone();
+
 			let proposer_factory = sc_basic_authorship::ProposerFactory::with_proof_recording(
 				task_manager.spawn_handle(),
 				client.clone(),
@@ -467,8 +608,11 @@ pub async fn start_parachain_node(
 			Ok(AuraConsensus::build::<sp_consensus_aura::sr25519::AuthorityPair, _, _, _, _, _, _>(
 				BuildAuraConsensusParams {
 					proposer_factory,
-					create_inherent_data_providers: move |_, (relay_parent, validation_data)| {
+					create_inherent_data_providers: move |at, (relay_parent, validation_data)| {
 						let relay_chain_interface = relay_chain_interface.clone();
+						let failover = failover.clone();
+						let client = failover_client.clone();
+						let keystore = failover_keystore.clone();
 						async move {
 							let parachain_inherent =
 							cumulus_primitives_parachain_inherent::ParachainInherentData::create_at(
@@ -490,6 +634,23 @@ pub async fn start_parachain_node(
 									"Failed to create parachain inherent",
 								)
 							})?;
+
+							// Claim the slot last, once nothing else can stop the block.
+							if let Some(failover) = failover {
+								let may_author = failover
+									.may_author(&*client, &keystore, at, *slot)
+									.map_err(|e| {
+										Box::<dyn std::error::Error + Send + Sync>::from(format!(
+											"Failed to claim slot in the collator lease: {}",
+											e
+										))
+									})?;
+								if !may_author {
+									return Err(Box::<dyn std::error::Error + Send + Sync>::from(
+										"Slot is left to the collator holding the lease",
+									))
+								}
+							}
 							Ok((time, slot, parachain_inherent))
 						}
 					},
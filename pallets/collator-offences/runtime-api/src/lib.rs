@@ -0,0 +1,21 @@
+//! Runtime API definition for reporting collator offences.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+use sp_runtime::traits::Block as BlockT;
+
+pub use sp_consensus_slots::EquivocationProof;
+
+sp_api::decl_runtime_apis! {
+	/// Lets the node report Aura equivocations it detected on import.
+	pub trait CollatorOffencesApi<AuthorityId> where
+		AuthorityId: Codec,
+	{
+		/// Submit an unsigned equivocation report to the transaction pool. Returns `None` if the
+		/// pool rejected it.
+		fn submit_report_equivocation_unsigned_extrinsic(
+			equivocation_proof: EquivocationProof<<Block as BlockT>::Header, AuthorityId>,
+		) -> Option<()>;
+	}
+}
@@ -0,0 +1,250 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Collator offences.
+//!
+//! Misbehaving collators are disabled for the rest of the session through `pallet_session`'s
+//! disabled set. With `pallet_aura::Config::DisabledValidators` pointing at the session pallet,
+//! blocks authored by a disabled collator are rejected, so its slots are skipped right away. The
+//! session pallet clears the disabled set on rotation, which re-enables the collator at the next
+//! session.
+//!
+//! A collator is disabled either by `DisableOrigin`, e.g. for stalling, or by anyone submitting
+//! proof that it equivocated: two different headers for the same slot, both carrying its Aura
+//! seal. Collator nodes that detect an equivocation on import report it as an unsigned
+//! transaction through `submit_unsigned_equivocation_report`. At least one collator is always
+//! left enabled so the chain can reach the next session.
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use frame_support::{dispatch::DispatchResultWithPostInfo, pallet_prelude::*, weights::Pays};
+	use frame_system::{
+		offchain::{SendTransactionTypes, SubmitTransaction},
+		pallet_prelude::*,
+	};
+	use sp_consensus_aura::digests::CompatibleDigestItem;
+	use sp_consensus_slots::{EquivocationProof, Slot};
+	use sp_runtime::{traits::Header as HeaderT, DigestItem, RuntimeAppPublic};
+	use sp_std::prelude::*;
+
+	type AuthoritySignature<T> =
+		<<T as pallet_aura::Config>::AuthorityId as RuntimeAppPublic>::Signature;
+
+	/// Number of blocks an unsigned equivocation report stays valid in the transaction pool.
+	const REPORT_LONGEVITY: u64 = 64;
+
+	/// Proof that an Aura authority sealed two different headers for the same slot.
+	pub type AuraEquivocationProof<T> = EquivocationProof<
+		<T as frame_system::Config>::Header,
+		<T as pallet_aura::Config>::AuthorityId,
+	>;
+
+	/// Configure the pallet by specifying the parameters and types on which it depends.
+	#[pallet::config]
+	pub trait Config:
+		frame_system::Config
+		+ pallet_session::Config
+		+ pallet_aura::Config
+		+ SendTransactionTypes<Call<Self>>
+	{
+		/// Because this pallet emits events, it depends on the runtime's definition of an event.
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// Origin allowed to disable a collator without a proof.
+		type DisableOrigin: EnsureOrigin<Self::Origin>;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	// Pallets use events to inform users when important changes are made.
+	// https://docs.substrate.io/v3/runtime/events-and-errors
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// A collator was disabled until the next session. [collator]
+		CollatorDisabled(T::ValidatorId),
+		/// A collator was proven to have sealed two blocks for one slot. [collator, slot]
+		EquivocationReported(T::ValidatorId, Slot),
+	}
+
+	// Errors inform users that something went wrong.
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The account is not a collator of the current session.
+		NotCollator,
+		/// The collator is already disabled.
+		AlreadyDisabled,
+		/// Disabling the collator would leave no one to author blocks.
+		TooManyDisabled,
+		/// The offender is not the Aura authority of the reported slot.
+		NotSlotAuthor,
+		/// The reported slot has not been reached yet.
+		FutureSlot,
+		/// Both headers are the same.
+		SameHeader,
+		/// A header is not sealed by the offender for the reported slot.
+		InvalidSeal,
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Disable `collator` until the next session.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(2, 1))]
+		pub fn disable_collator(
+			origin: OriginFor<T>,
+			collator: T::ValidatorId,
+		) -> DispatchResultWithPostInfo {
+			T::DisableOrigin::ensure_origin(origin)?;
+
+			let index = pallet_session::Pallet::<T>::validators()
+				.iter()
+				.position(|who| who == &collator)
+				.ok_or(Error::<T>::NotCollator)?;
+			Self::do_disable(index as u32)?;
+			Ok(().into())
+		}
+
+		/// Report an Aura equivocation and disable the offender until the next session. Valid
+		/// reports are free.
+		// Dominated by verifying the two seals.
+		#[pallet::weight(100_000_000 + T::DbWeight::get().reads_writes(4, 1))]
+		pub fn report_equivocation(
+			origin: OriginFor<T>,
+			equivocation_proof: Box<AuraEquivocationProof<T>>,
+		) -> DispatchResultWithPostInfo {
+			ensure_signed(origin)?;
+
+			Self::do_report_equivocation(*equivocation_proof)
+		}
+
+		/// Report an Aura equivocation without a signature. Only accepted from the local node,
+		/// which submits it through [`Pallet::submit_unsigned_equivocation_report`], so it only
+		/// gets included by collators that detected the equivocation themselves.
+		#[pallet::weight(100_000_000 + T::DbWeight::get().reads_writes(4, 1))]
+		pub fn report_equivocation_unsigned(
+			origin: OriginFor<T>,
+			equivocation_proof: Box<AuraEquivocationProof<T>>,
+		) -> DispatchResultWithPostInfo {
+			ensure_none(origin)?;
+
+			Self::do_report_equivocation(*equivocation_proof)
+		}
+	}
+
+	#[pallet::validate_unsigned]
+	impl<T: Config> ValidateUnsigned for Pallet<T> {
+		type Call = Call<T>;
+
+		fn validate_unsigned(source: TransactionSource, call: &Self::Call) -> TransactionValidity {
+			let equivocation_proof = match call {
+				Call::report_equivocation_unsigned { equivocation_proof } => equivocation_proof,
+				_ => return InvalidTransaction::Call.into(),
+			};
+			if !matches!(source, TransactionSource::Local | TransactionSource::InBlock) {
+				return InvalidTransaction::Call.into()
+			}
+
+			let index = Self::check_equivocation_proof(equivocation_proof)
+				.map_err(|_| InvalidTransaction::BadProof)?;
+			if pallet_session::Pallet::<T>::disabled_validators().contains(&index) {
+				return InvalidTransaction::Stale.into()
+			}
+
+			ValidTransaction::with_tag_prefix("CollatorOffences")
+				.priority(TransactionPriority::max_value())
+				.and_provides((equivocation_proof.offender.clone(), *equivocation_proof.slot))
+				.longevity(REPORT_LONGEVITY)
+				.propagate(false)
+				.build()
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// Verify `equivocation_proof` and disable the offender.
+		fn do_report_equivocation(
+			equivocation_proof: AuraEquivocationProof<T>,
+		) -> DispatchResultWithPostInfo {
+			let index = Self::check_equivocation_proof(&equivocation_proof)?;
+			let collator = Self::do_disable(index)?;
+			Self::deposit_event(Event::EquivocationReported(collator, equivocation_proof.slot));
+			Ok(Pays::No.into())
+		}
+
+		/// Submit an unsigned equivocation report to the local transaction pool. Returns `None` if
+		/// the pool rejected it.
+		pub fn submit_unsigned_equivocation_report(
+			equivocation_proof: AuraEquivocationProof<T>,
+		) -> Option<()> {
+			let call = Call::report_equivocation_unsigned {
+				equivocation_proof: Box::new(equivocation_proof),
+			};
+			SubmitTransaction::<T, Call<T>>::submit_unsigned_transaction(call.into()).ok()
+		}
+
+		/// Disable the session validator at `index`, keeping at least one collator enabled.
+		fn do_disable(index: u32) -> Result<T::ValidatorId, DispatchError> {
+			let validators = pallet_session::Pallet::<T>::validators();
+			let collator =
+				validators.get(index as usize).cloned().ok_or(Error::<T>::NotCollator)?;
+			let disabled = pallet_session::Pallet::<T>::disabled_validators();
+			ensure!(!disabled.contains(&index), Error::<T>::AlreadyDisabled);
+			ensure!(
+				disabled.len().saturating_add(1) < validators.len(),
+				Error::<T>::TooManyDisabled
+			);
+
+			pallet_session::Pallet::<T>::disable_index(index);
+			Self::deposit_event(Event::CollatorDisabled(collator.clone()));
+			Ok(collator)
+		}
+
+		/// Check `proof` against the current Aura authorities and return the offender's index.
+		pub fn check_equivocation_proof(proof: &AuraEquivocationProof<T>) -> Result<u32, Error<T>> {
+			let authorities = pallet_aura::Pallet::<T>::authorities();
+			let index = authorities
+				.iter()
+				.position(|authority| authority == &proof.offender)
+				.ok_or(Error::<T>::NotCollator)?;
+			// Aura hands out slots round robin over the authorities.
+			ensure!(
+				*proof.slot % authorities.len() as u64 == index as u64,
+				Error::<T>::NotSlotAuthor
+			);
+			ensure!(proof.slot <= pallet_aura::Pallet::<T>::current_slot(), Error::<T>::FutureSlot);
+			ensure!(
+				proof.first_header.hash() != proof.second_header.hash(),
+				Error::<T>::SameHeader
+			);
+
+			for header in [&proof.first_header, &proof.second_header] {
+				ensure!(
+					Self::sealed_slot(header, &proof.offender) == Some(proof.slot),
+					Error::<T>::InvalidSeal
+				);
+			}
+			Ok(index as u32)
+		}
+
+		/// The slot `header` was authored in, if it carries a valid seal of `author`.
+		fn sealed_slot(header: &T::Header, author: &T::AuthorityId) -> Option<Slot> {
+			let mut header = header.clone();
+			// The seal is the last digest and signs the hash of the header without it.
+			let seal = header.digest_mut().pop()?;
+			let signature =
+				<DigestItem as CompatibleDigestItem<AuthoritySignature<T>>>::as_aura_seal(&seal)?;
+			let slot = header.digest().logs().iter().find_map(|log| {
+				<DigestItem as CompatibleDigestItem<AuthoritySignature<T>>>::as_aura_pre_digest(log)
+			})?;
+
+			author.verify(&header.hash(), &signature).then(|| slot)
+		}
+	}
+}
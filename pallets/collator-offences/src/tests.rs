@@ -0,0 +1,175 @@
+use crate::{mock::*, AuraEquivocationProof, Call as OffencesCall, Error, Event as OffencesEvent};
+use frame_support::{
+	assert_noop, assert_ok,
+	traits::{DisabledValidators, Hooks},
+	weights::Pays,
+};
+use sp_consensus_aura::{digests::CompatibleDigestItem, sr25519::AuthoritySignature, Slot};
+use sp_core::{Pair, H256};
+use sp_runtime::{
+	traits::{BadOrigin, Header as HeaderT, ValidateUnsigned},
+	transaction_validity::{InvalidTransaction, TransactionSource},
+	Digest, DigestItem,
+};
+
+fn pre_digest(slot: u64) -> DigestItem {
+	<DigestItem as CompatibleDigestItem<AuthoritySignature>>::aura_pre_digest(slot.into())
+}
+
+/// Start a block in `slot`, as Aura would.
+fn go_to_slot(slot: u64) {
+	System::initialize(&2, &Default::default(), &Digest { logs: vec![pre_digest(slot)] });
+	Aura::on_initialize(2);
+}
+
+/// A header for `slot` sealed by collator `who`, made unique by `parent`.
+fn sealed_header(who: u64, slot: u64, parent: u8) -> Header {
+	let mut header = Header::new(
+		1,
+		Default::default(),
+		Default::default(),
+		H256::repeat_byte(parent),
+		Digest { logs: vec![pre_digest(slot)] },
+	);
+	let signature = aura_pair(who).sign(header.hash().as_ref());
+	header
+		.digest_mut()
+		.push(<DigestItem as CompatibleDigestItem<AuthoritySignature>>::aura_seal(signature));
+	header
+}
+
+fn proof(
+	offender: u64,
+	slot: u64,
+	first_header: Header,
+	second_header: Header,
+) -> Box<AuraEquivocationProof<Test>> {
+	Box::new(AuraEquivocationProof::<Test> {
+		offender: aura_pair(offender).public(),
+		slot: Slot::from(slot),
+		first_header,
+		second_header,
+	})
+}
+
+#[test]
+fn disable_origin_disables_until_next_session() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(CollatorOffences::disable_collator(Origin::signed(1), 2), BadOrigin);
+
+		assert_ok!(CollatorOffences::disable_collator(Origin::root(), 2));
+		assert_eq!(Session::disabled_validators(), vec![1]);
+		assert!(<Session as DisabledValidators>::is_disabled(1));
+		System::assert_last_event(Event::CollatorOffences(OffencesEvent::CollatorDisabled(2)));
+
+		Session::rotate_session();
+		assert!(Session::disabled_validators().is_empty());
+	});
+}
+
+#[test]
+fn at_least_one_collator_stays_enabled() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			CollatorOffences::disable_collator(Origin::root(), 4),
+			Error::<Test>::NotCollator
+		);
+
+		assert_ok!(CollatorOffences::disable_collator(Origin::root(), 1));
+		assert_noop!(
+			CollatorOffences::disable_collator(Origin::root(), 1),
+			Error::<Test>::AlreadyDisabled
+		);
+		assert_ok!(CollatorOffences::disable_collator(Origin::root(), 3));
+		assert_noop!(
+			CollatorOffences::disable_collator(Origin::root(), 2),
+			Error::<Test>::TooManyDisabled
+		);
+	});
+}
+
+#[test]
+fn equivocation_report_disables_offender() {
+	new_test_ext().execute_with(|| {
+		go_to_slot(5);
+		// Slot 4 belongs to the second of the three collators.
+		let report = proof(2, 4, sealed_header(2, 4, 1), sealed_header(2, 4, 2));
+
+		let info = CollatorOffences::report_equivocation(Origin::signed(9), report).unwrap();
+		assert_eq!(info.pays_fee, Pays::No);
+		assert_eq!(Session::disabled_validators(), vec![1]);
+		System::assert_last_event(Event::CollatorOffences(OffencesEvent::EquivocationReported(
+			2,
+			Slot::from(4),
+		)));
+	});
+}
+
+#[test]
+fn invalid_equivocation_reports_are_rejected() {
+	new_test_ext().execute_with(|| {
+		go_to_slot(5);
+
+		assert_noop!(
+			CollatorOffences::report_equivocation(
+				Origin::signed(9),
+				proof(2, 4, sealed_header(2, 4, 1), sealed_header(2, 4, 1)),
+			),
+			Error::<Test>::SameHeader
+		);
+		assert_noop!(
+			CollatorOffences::report_equivocation(
+				Origin::signed(9),
+				proof(2, 5, sealed_header(2, 5, 1), sealed_header(2, 5, 2)),
+			),
+			Error::<Test>::NotSlotAuthor
+		);
+		assert_noop!(
+			CollatorOffences::report_equivocation(
+				Origin::signed(9),
+				proof(2, 7, sealed_header(2, 7, 1), sealed_header(2, 7, 2)),
+			),
+			Error::<Test>::FutureSlot
+		);
+		// Sealed by someone else.
+		assert_noop!(
+			CollatorOffences::report_equivocation(
+				Origin::signed(9),
+				proof(2, 4, sealed_header(2, 4, 1), sealed_header(3, 4, 2)),
+			),
+			Error::<Test>::InvalidSeal
+		);
+		// Sealed by the offender, but for another of its slots.
+		assert_noop!(
+			CollatorOffences::report_equivocation(
+				Origin::signed(9),
+				proof(2, 4, sealed_header(2, 4, 1), sealed_header(2, 1, 2)),
+			),
+			Error::<Test>::InvalidSeal
+		);
+		assert!(Session::disabled_validators().is_empty());
+	});
+}
+
+#[test]
+fn unsigned_reports_are_only_accepted_from_the_local_node() {
+	new_test_ext().execute_with(|| {
+		go_to_slot(5);
+		let report = proof(2, 4, sealed_header(2, 4, 1), sealed_header(2, 4, 2));
+		let call =
+			OffencesCall::report_equivocation_unsigned { equivocation_proof: report.clone() };
+
+		assert_eq!(
+			CollatorOffences::validate_unsigned(TransactionSource::External, &call),
+			InvalidTransaction::Call.into(),
+		);
+		assert!(CollatorOffences::validate_unsigned(TransactionSource::Local, &call).is_ok());
+
+		assert_ok!(CollatorOffences::report_equivocation_unsigned(Origin::none(), report));
+		assert_eq!(Session::disabled_validators(), vec![1]);
+		assert_eq!(
+			CollatorOffences::validate_unsigned(TransactionSource::Local, &call),
+			InvalidTransaction::Stale.into(),
+		);
+	});
+}
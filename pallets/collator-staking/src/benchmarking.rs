@@ -0,0 +1,213 @@
+//! Benchmarking setup for pallet-collator-staking
+
+use super::*;
+
+#[allow(unused)]
+use crate::Pallet as CollatorStaking;
+use codec::Decode;
+use frame_benchmarking::{account, benchmarks, impl_benchmark_test_suite, whitelisted_caller};
+use frame_support::{
+	assert_ok,
+	traits::{Currency, EnsureOrigin, Get},
+	BoundedVec,
+};
+use frame_system::RawOrigin;
+use pallet_authorship::EventHandler;
+use pallet_session::SessionManager;
+use sp_runtime::{
+	traits::{Bounded, TrailingZeroInput, Zero},
+	Perbill,
+};
+use sp_std::prelude::*;
+
+const SEED: u32 = 0;
+
+fn funded<T: Config>(name: &'static str, index: u32) -> T::AccountId {
+	let who: T::AccountId = account(name, index, SEED);
+	T::Currency::make_free_balance_be(&who, BalanceOf::<T>::max_value() / 4u32.into());
+	who
+}
+
+/// Insert an active candidate directly, bypassing the session key check.
+fn candidate<T: Config>(index: u32) -> T::AccountId {
+	let who = funded::<T>("candidate", index);
+	let bond = CandidacyBond::<T>::get();
+	assert_ok!(T::Currency::reserve(&who, bond));
+	Candidates::<T>::insert(
+		&who,
+		CandidateInfo {
+			bond,
+			delegated: Zero::zero(),
+			delegators: 0,
+			commission: Perbill::from_percent(10),
+			reward_per_stake: REWARD_PRECISION,
+			status: CandidateStatus::Active,
+		},
+	);
+	assert_ok!(ActiveCandidates::<T>::try_mutate(|active| active.try_push(who.clone())));
+	who
+}
+
+/// A delegator of `candidate`, delegating `MinDelegation`.
+fn delegator<T: Config>(candidate: &T::AccountId) -> T::AccountId {
+	let who: T::AccountId = whitelisted_caller();
+	T::Currency::make_free_balance_be(&who, BalanceOf::<T>::max_value() / 4u32.into());
+	assert_ok!(CollatorStaking::<T>::delegate(
+		RawOrigin::Signed(who.clone()).into(),
+		candidate.clone(),
+		T::MinDelegation::get(),
+	));
+	who
+}
+
+/// Make the delegators of `candidate` owed `amount` per unit of stake, held in the pot.
+fn accrue_rewards<T: Config>(candidate: &T::AccountId, amount: BalanceOf<T>) {
+	Candidates::<T>::mutate(candidate, |info| {
+		info.as_mut().unwrap().reward_per_stake = 2 * REWARD_PRECISION
+	});
+	T::Currency::make_free_balance_be(&CollatorStaking::<T>::account_id(), amount * 4u32.into());
+	UnclaimedRewards::<T>::put(amount);
+}
+
+benchmarks! {
+	where_clause { where T: pallet_session::Config }
+
+	set_invulnerables {
+		let b in 1 .. T::MaxInvulnerables::get();
+		let origin = T::UpdateOrigin::successful_origin();
+		let new = (0..b).map(|i| account("invulnerable", i, SEED)).collect::<Vec<T::AccountId>>();
+	}: {
+		assert_ok!(CollatorStaking::<T>::set_invulnerables(origin, new));
+	}
+	verify {
+		assert_eq!(Invulnerables::<T>::get().len() as u32, b);
+	}
+
+	set_candidacy_bond {
+		let origin = T::UpdateOrigin::successful_origin();
+		let bond = T::MinDelegation::get();
+	}: {
+		assert_ok!(CollatorStaking::<T>::set_candidacy_bond(origin, bond));
+	}
+	verify {
+		assert_eq!(CandidacyBond::<T>::get(), bond);
+	}
+
+	register_as_candidate {
+		let caller: T::AccountId = whitelisted_caller();
+		T::Currency::make_free_balance_be(&caller, BalanceOf::<T>::max_value() / 4u32.into());
+		let keys = <T as pallet_session::Config>::Keys::decode(
+			&mut TrailingZeroInput::zeroes(),
+		)
+		.unwrap();
+		assert_ok!(pallet_session::Pallet::<T>::set_keys(
+			RawOrigin::Signed(caller.clone()).into(),
+			keys,
+			Vec::new(),
+		));
+		let bond = CandidacyBond::<T>::get();
+	}: _(RawOrigin::Signed(caller.clone()), bond, Perbill::from_percent(10))
+	verify {
+		assert!(Candidates::<T>::contains_key(&caller));
+	}
+
+	set_commission {
+		let candidate = candidate::<T>(0);
+	}: _(RawOrigin::Signed(candidate.clone()), Perbill::from_percent(20))
+	verify {
+		assert_eq!(Candidates::<T>::get(&candidate).unwrap().commission, Perbill::from_percent(20));
+	}
+
+	set_desired_collators {
+		let origin = T::UpdateOrigin::successful_origin();
+		let max = T::MaxCollators::get();
+	}: {
+		assert_ok!(CollatorStaking::<T>::set_desired_collators(origin, max));
+	}
+	verify {
+		assert_eq!(DesiredCollators::<T>::get(), max);
+	}
+
+	delegate {
+		let candidate = candidate::<T>(0);
+		let amount = T::MinDelegation::get();
+		// Hit the payout path of an existing delegation.
+		let caller = delegator::<T>(&candidate);
+		accrue_rewards::<T>(&candidate, amount);
+	}: _(RawOrigin::Signed(caller.clone()), candidate.clone(), amount)
+	verify {
+		assert_eq!(Delegations::<T>::get(&candidate, &caller).unwrap().amount, amount + amount);
+	}
+
+	undelegate {
+		let candidate = candidate::<T>(0);
+		let caller: T::AccountId = whitelisted_caller();
+		T::Currency::make_free_balance_be(&caller, BalanceOf::<T>::max_value() / 4u32.into());
+		let amount = T::MinDelegation::get();
+		assert_ok!(CollatorStaking::<T>::delegate(
+			RawOrigin::Signed(caller.clone()).into(),
+			candidate.clone(),
+			amount + amount,
+		));
+	}: _(RawOrigin::Signed(caller.clone()), candidate.clone(), amount)
+	verify {
+		assert_eq!(Unbonding::<T>::get(&caller).len(), 1);
+	}
+
+	claim_rewards {
+		let candidate = candidate::<T>(0);
+		let caller = delegator::<T>(&candidate);
+		accrue_rewards::<T>(&candidate, T::MinDelegation::get());
+	}: _(RawOrigin::Signed(caller.clone()), candidate.clone())
+	verify {
+		assert!(CollatorStaking::<T>::pending_rewards(&candidate, &caller).is_zero());
+	}
+
+	withdraw_unbonded {
+		let u in 1 .. T::MaxUnbondingChunks::get();
+		let caller: T::AccountId = whitelisted_caller();
+		T::Currency::make_free_balance_be(&caller, BalanceOf::<T>::max_value() / 4u32.into());
+		let amount = T::MinDelegation::get();
+		assert_ok!(T::Currency::reserve(&caller, amount * u.into()));
+		let chunks = (0..u)
+			.map(|_| UnbondingChunk { amount, unlock_at: Zero::zero() })
+			.collect::<Vec<_>>();
+		Unbonding::<T>::insert(&caller, BoundedVec::try_from(chunks).unwrap());
+	}: _(RawOrigin::Signed(caller.clone()))
+	verify {
+		assert!(Unbonding::<T>::get(&caller).is_empty());
+	}
+
+	leave_candidates {
+		let candidate = candidate::<T>(0);
+	}: _(RawOrigin::Signed(candidate.clone()))
+	verify {
+		assert!(Candidates::<T>::get(&candidate).is_none());
+	}
+
+	note_author {
+		let candidate = candidate::<T>(0);
+		delegator::<T>(&candidate);
+		T::Currency::make_free_balance_be(
+			&CollatorStaking::<T>::account_id(),
+			T::Currency::minimum_balance() * 1_000u32.into(),
+		);
+	}: {
+		<CollatorStaking<T> as EventHandler<_, _>>::note_author(candidate.clone());
+	}
+	verify {
+		assert!(!UnclaimedRewards::<T>::get().is_zero());
+	}
+
+	new_session {
+		let c in 1 .. T::MaxCandidates::get();
+		DesiredCollators::<T>::put(T::MaxCollators::get());
+		for i in 0..c {
+			candidate::<T>(i);
+		}
+	}: {
+		<CollatorStaking<T> as SessionManager<_>>::new_session(0);
+	}
+}
+
+impl_benchmark_test_suite!(CollatorStaking, crate::mock::new_test_ext(), crate::mock::Test,);
@@ -0,0 +1,886 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Delegated collator staking.
+//!
+//! Accounts register as collator candidates by bonding some of their own funds, and any token
+//! holder may delegate to a candidate to back it. At every new session the `DesiredCollators`
+//! candidates with the largest total backing (own bond plus delegations) are handed to
+//! `pallet_session` alongside the invulnerables.
+//!
+//! Block authors are rewarded through `pallet_authorship::EventHandler`: half of the spendable
+//! balance of the pot account is paid out per authored block. The candidate keeps its commission
+//! plus the share earned by its own bond, while the delegators' share is accrued per unit of
+//! stake and can be claimed at any time with `claim_rewards`.
+//!
+//! Bonds and delegations are held as reserves. Undelegating or leaving the candidate set moves
+//! the funds into an unbonding queue from which they can only be withdrawn once
+//! `UnbondingDelay` blocks have passed.
+//!
+//! The pallet replaces `pallet_collator_selection`; chains running it migrate their collators
+//! with [`migration::MigrateFromCollatorSelection`].
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
+pub mod migration;
+pub mod weights;
+pub use weights::WeightInfo;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use crate::WeightInfo;
+	use codec::{Decode, Encode, MaxEncodedLen};
+	use frame_support::{
+		dispatch::DispatchResultWithPostInfo,
+		pallet_prelude::*,
+		traits::{Currency, ExistenceRequirement, ReservableCurrency, ValidatorRegistration},
+		transactional, PalletId,
+	};
+	use frame_system::pallet_prelude::*;
+	use pallet_session::SessionManager;
+	use scale_info::TypeInfo;
+	use sp_runtime::{
+		helpers_128bit::multiply_by_rational,
+		traits::{AccountIdConversion, Convert, SaturatedConversion, Saturating, Zero},
+		Perbill, RuntimeDebug,
+	};
+	use sp_staking::SessionIndex;
+	use sp_std::prelude::*;
+
+	pub(crate) type BalanceOf<T> =
+		<<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+
+	/// Fixed point precision used by the per-stake reward accumulator.
+	pub(crate) const REWARD_PRECISION: u128 = 1_000_000_000_000_000_000;
+
+	/// A convertor from collators id. Since this pallet does not have stash/controller, this is
+	/// just identity.
+	pub struct IdentityCollator;
+	impl<T> sp_runtime::traits::Convert<T, Option<T>> for IdentityCollator {
+		fn convert(t: T) -> Option<T> {
+			Some(t)
+		}
+	}
+
+	/// Whether a candidate takes part in the collator election.
+	#[derive(Clone, Copy, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+	pub enum CandidateStatus {
+		/// The candidate is eligible to be selected.
+		Active,
+		/// The candidate left or was kicked; the record only lives on until every delegator has
+		/// undelegated.
+		Leaving,
+	}
+
+	/// Everything this pallet knows about a collator candidate.
+	#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+	pub struct CandidateInfo<Balance> {
+		/// The candidate's own reserved bond.
+		pub bond: Balance,
+		/// The sum of all delegations backing this candidate.
+		pub delegated: Balance,
+		/// The number of accounts delegating to this candidate.
+		pub delegators: u32,
+		/// The share of every block reward kept by the candidate before the rest is split.
+		pub commission: Perbill,
+		/// Rewards accrued per unit of delegated stake, scaled by `REWARD_PRECISION`.
+		pub reward_per_stake: u128,
+		/// Whether the candidate is still standing for election.
+		pub status: CandidateStatus,
+	}
+
+	impl<Balance: Saturating + Copy> CandidateInfo<Balance> {
+		/// The total stake backing this candidate.
+		pub fn total_backing(&self) -> Balance {
+			self.bond.saturating_add(self.delegated)
+		}
+	}
+
+	/// A single delegation of an account to a candidate.
+	#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+	pub struct Delegation<Balance> {
+		/// The amount reserved for this delegation.
+		pub amount: Balance,
+		/// The part of the candidate's accumulated rewards already accounted for.
+		pub reward_debt: u128,
+	}
+
+	/// Funds waiting out the unbonding delay.
+	#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+	pub struct UnbondingChunk<Balance, BlockNumber> {
+		/// The amount that will be unreserved.
+		pub amount: Balance,
+		/// The block from which the chunk can be withdrawn.
+		pub unlock_at: BlockNumber,
+	}
+
+	/// Configure the pallet by specifying the parameters and types on which it depends.
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// Because this pallet emits events, it depends on the runtime's definition of an event.
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// The currency used for bonds, delegations and rewards.
+		type Currency: ReservableCurrency<Self::AccountId>;
+
+		/// Origin that can dictate updating parameters of this pallet.
+		type UpdateOrigin: EnsureOrigin<Self::Origin>;
+
+		/// Account identifier from which block rewards are paid.
+		#[pallet::constant]
+		type PotId: Get<PalletId>;
+
+		/// Maximum number of candidates standing for election at the same time.
+		#[pallet::constant]
+		type MaxCandidates: Get<u32>;
+
+		/// Maximum number of invulnerables.
+		#[pallet::constant]
+		type MaxInvulnerables: Get<u32>;
+
+		/// Upper bound for `DesiredCollators`.
+		#[pallet::constant]
+		type MaxCollators: Get<u32>;
+
+		/// The smallest amount that can be delegated to a candidate.
+		#[pallet::constant]
+		type MinDelegation: Get<BalanceOf<Self>>;
+
+		/// Number of blocks unbonded funds stay reserved before they can be withdrawn.
+		#[pallet::constant]
+		type UnbondingDelay: Get<Self::BlockNumber>;
+
+		/// Maximum number of pending unbonding chunks per account.
+		#[pallet::constant]
+		type MaxUnbondingChunks: Get<u32>;
+
+		/// A selected candidate that has not produced a block for this many blocks is removed
+		/// from the candidate set. Should be a multiple of the session length.
+		#[pallet::constant]
+		type KickThreshold: Get<Self::BlockNumber>;
+
+		/// A stable ID for a validator.
+		type ValidatorId: Member + Parameter;
+
+		/// A conversion from account ID to validator ID.
+		///
+		/// Its cost must be at most one storage read.
+		type ValidatorIdOf: Convert<Self::AccountId, Option<Self::ValidatorId>>;
+
+		/// Validate a user is registered.
+		type ValidatorRegistration: ValidatorRegistration<Self::ValidatorId>;
+
+		/// Weight information for extrinsics in this pallet.
+		type WeightInfo: WeightInfo;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	/// The invulnerable collators, always part of the collator set.
+	#[pallet::storage]
+	#[pallet::getter(fn invulnerables)]
+	pub type Invulnerables<T: Config> =
+		StorageValue<_, BoundedVec<T::AccountId, T::MaxInvulnerables>, ValueQuery>;
+
+	/// The number of candidates (on top of the invulnerables) selected every session.
+	#[pallet::storage]
+	#[pallet::getter(fn desired_collators)]
+	pub type DesiredCollators<T> = StorageValue<_, u32, ValueQuery>;
+
+	/// The minimum own bond required to register as a candidate.
+	#[pallet::storage]
+	#[pallet::getter(fn candidacy_bond)]
+	pub type CandidacyBond<T> = StorageValue<_, BalanceOf<T>, ValueQuery>;
+
+	/// All known candidates, including those leaving, which live on until their delegators have
+	/// undelegated.
+	#[pallet::storage]
+	#[pallet::getter(fn candidates)]
+	pub type Candidates<T: Config> =
+		StorageMap<_, Twox64Concat, T::AccountId, CandidateInfo<BalanceOf<T>>>;
+
+	/// The active candidates, those standing for election.
+	#[pallet::storage]
+	#[pallet::getter(fn active_candidates)]
+	pub type ActiveCandidates<T: Config> =
+		StorageValue<_, BoundedVec<T::AccountId, T::MaxCandidates>, ValueQuery>;
+
+	/// Delegations, keyed by candidate and then delegator.
+	#[pallet::storage]
+	#[pallet::getter(fn delegations)]
+	pub type Delegations<T: Config> = StorageDoubleMap<
+		_,
+		Twox64Concat,
+		T::AccountId,
+		Twox64Concat,
+		T::AccountId,
+		Delegation<BalanceOf<T>>,
+	>;
+
+	/// Funds of an account that are waiting out the unbonding delay.
+	#[pallet::storage]
+	#[pallet::getter(fn unbonding)]
+	pub type Unbonding<T: Config> = StorageMap<
+		_,
+		Twox64Concat,
+		T::AccountId,
+		BoundedVec<UnbondingChunk<BalanceOf<T>, T::BlockNumber>, T::MaxUnbondingChunks>,
+		ValueQuery,
+	>;
+
+	/// The candidates elected at the last session rotation.
+	#[pallet::storage]
+	#[pallet::getter(fn selected_candidates)]
+	pub type SelectedCandidates<T: Config> =
+		StorageValue<_, BoundedVec<T::AccountId, T::MaxCollators>, ValueQuery>;
+
+	/// Last block authored by a selected candidate.
+	#[pallet::storage]
+	#[pallet::getter(fn last_authored_block)]
+	pub type LastAuthoredBlock<T: Config> =
+		StorageMap<_, Twox64Concat, T::AccountId, T::BlockNumber, ValueQuery>;
+
+	/// Rewards held in the pot that are owed to delegators but not claimed yet.
+	#[pallet::storage]
+	#[pallet::getter(fn unclaimed_rewards)]
+	pub type UnclaimedRewards<T> = StorageValue<_, BalanceOf<T>, ValueQuery>;
+
+	#[pallet::genesis_config]
+	pub struct GenesisConfig<T: Config> {
+		pub invulnerables: Vec<T::AccountId>,
+		pub candidacy_bond: BalanceOf<T>,
+		pub desired_collators: u32,
+	}
+
+	#[cfg(feature = "std")]
+	impl<T: Config> Default for GenesisConfig<T> {
+		fn default() -> Self {
+			Self {
+				invulnerables: Default::default(),
+				candidacy_bond: Default::default(),
+				desired_collators: 0,
+			}
+		}
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> GenesisBuild<T> for GenesisConfig<T> {
+		fn build(&self) {
+			let mut unique = self.invulnerables.clone();
+			unique.sort();
+			unique.dedup();
+			assert_eq!(
+				unique.len(),
+				self.invulnerables.len(),
+				"duplicate invulnerables in genesis."
+			);
+
+			let invulnerables: BoundedVec<_, T::MaxInvulnerables> = self
+				.invulnerables
+				.clone()
+				.try_into()
+				.expect("genesis invulnerables are more than T::MaxInvulnerables");
+			assert!(
+				self.desired_collators <= T::MaxCollators::get(),
+				"genesis desired_collators are more than T::MaxCollators",
+			);
+
+			<Invulnerables<T>>::put(invulnerables);
+			<CandidacyBond<T>>::put(self.candidacy_bond);
+			<DesiredCollators<T>>::put(self.desired_collators);
+		}
+	}
+
+	// Pallets use events to inform users when important changes are made.
+	// https://docs.substrate.io/v3/runtime/events-and-errors
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// The invulnerables were replaced. [invulnerables]
+		NewInvulnerables(Vec<T::AccountId>),
+		/// The number of desired collators changed. [desired_collators]
+		NewDesiredCollators(u32),
+		/// The candidacy bond changed. [candidacy_bond]
+		NewCandidacyBond(BalanceOf<T>),
+		/// An account registered as candidate. [candidate, bond, commission]
+		CandidateAdded(T::AccountId, BalanceOf<T>, Perbill),
+		/// A candidate left the candidate set. [candidate]
+		CandidateRemoved(T::AccountId),
+		/// A candidate was kicked for not producing blocks. [candidate]
+		CandidateKicked(T::AccountId),
+		/// A candidate changed its commission. [candidate, commission]
+		CommissionSet(T::AccountId, Perbill),
+		/// Stake was delegated to a candidate. [delegator, candidate, amount]
+		Delegated(T::AccountId, T::AccountId, BalanceOf<T>),
+		/// Stake was undelegated from a candidate. [delegator, candidate, amount, unlock_at]
+		Undelegated(T::AccountId, T::AccountId, BalanceOf<T>, T::BlockNumber),
+		/// Unbonded funds were released. [who, amount]
+		Withdrawn(T::AccountId, BalanceOf<T>),
+		/// A block author was paid. [collator, amount]
+		CollatorRewarded(T::AccountId, BalanceOf<T>),
+		/// A delegator claimed its rewards. [delegator, candidate, amount]
+		RewardsClaimed(T::AccountId, T::AccountId, BalanceOf<T>),
+		/// The collators of a new session were selected. [session, collators]
+		CollatorsSelected(SessionIndex, Vec<T::AccountId>),
+	}
+
+	// Errors inform users that something went wrong.
+	#[pallet::error]
+	pub enum Error<T> {
+		/// Too many invulnerables were given.
+		TooManyInvulnerables,
+		/// `DesiredCollators` may not exceed `MaxCollators`.
+		TooManyDesiredCollators,
+		/// The candidate set is full.
+		TooManyCandidates,
+		/// The account is already a candidate, or still has delegations from a previous candidacy.
+		AlreadyCandidate,
+		/// The account is not an active candidate.
+		NotCandidate,
+		/// Invulnerables are always collators and cannot stand as candidates.
+		AlreadyInvulnerable,
+		/// The account has no session keys registered.
+		ValidatorNotRegistered,
+		/// The account has no associated validator ID.
+		NoAssociatedValidatorId,
+		/// The bond is below `CandidacyBond`.
+		BondTooLow,
+		/// A delegation, or what is left of it, would be below `MinDelegation`.
+		DelegationTooLow,
+		/// The account has no delegation to this candidate.
+		NoDelegation,
+		/// The delegation is smaller than the amount to undelegate.
+		InsufficientDelegation,
+		/// A candidate cannot delegate to itself.
+		SelfDelegation,
+		/// Too many unbonding chunks are pending; withdraw first.
+		TooManyUnbondingChunks,
+		/// Nothing can be withdrawn yet.
+		NothingToWithdraw,
+		/// There are no rewards to claim.
+		NothingToClaim,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Replace the set of invulnerable collators.
+		#[pallet::weight(T::WeightInfo::set_invulnerables(new.len() as u32))]
+		pub fn set_invulnerables(
+			origin: OriginFor<T>,
+			new: Vec<T::AccountId>,
+		) -> DispatchResultWithPostInfo {
+			T::UpdateOrigin::ensure_origin(origin)?;
+			let bounded: BoundedVec<_, T::MaxInvulnerables> =
+				new.clone().try_into().map_err(|_| Error::<T>::TooManyInvulnerables)?;
+
+			<Invulnerables<T>>::put(bounded);
+			Self::deposit_event(Event::NewInvulnerables(new));
+			Ok(().into())
+		}
+
+		/// Set how many candidates are selected on top of the invulnerables.
+		#[pallet::weight(T::WeightInfo::set_desired_collators())]
+		pub fn set_desired_collators(origin: OriginFor<T>, max: u32) -> DispatchResultWithPostInfo {
+			T::UpdateOrigin::ensure_origin(origin)?;
+			ensure!(max <= T::MaxCollators::get(), Error::<T>::TooManyDesiredCollators);
+
+			<DesiredCollators<T>>::put(max);
+			Self::deposit_event(Event::NewDesiredCollators(max));
+			Ok(().into())
+		}
+
+		/// Set the minimum own bond of new candidates.
+		#[pallet::weight(T::WeightInfo::set_candidacy_bond())]
+		pub fn set_candidacy_bond(
+			origin: OriginFor<T>,
+			bond: BalanceOf<T>,
+		) -> DispatchResultWithPostInfo {
+			T::UpdateOrigin::ensure_origin(origin)?;
+
+			<CandidacyBond<T>>::put(bond);
+			Self::deposit_event(Event::NewCandidacyBond(bond));
+			Ok(().into())
+		}
+
+		/// Register the caller as collator candidate, reserving `bond`.
+		///
+		/// The caller must have registered session keys.
+		#[pallet::weight(T::WeightInfo::register_as_candidate())]
+		pub fn register_as_candidate(
+			origin: OriginFor<T>,
+			bond: BalanceOf<T>,
+			commission: Perbill,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+
+			ensure!(!<Candidates<T>>::contains_key(&who), Error::<T>::AlreadyCandidate);
+			ensure!(!Self::invulnerables().contains(&who), Error::<T>::AlreadyInvulnerable);
+			ensure!(bond >= Self::candidacy_bond(), Error::<T>::BondTooLow);
+			let mut active = Self::active_candidates();
+			active.try_push(who.clone()).map_err(|_| Error::<T>::TooManyCandidates)?;
+
+			let validator_key = T::ValidatorIdOf::convert(who.clone())
+				.ok_or(Error::<T>::NoAssociatedValidatorId)?;
+			ensure!(
+				T::ValidatorRegistration::is_registered(&validator_key),
+				Error::<T>::ValidatorNotRegistered
+			);
+
+			T::Currency::reserve(&who, bond)?;
+			<Candidates<T>>::insert(
+				&who,
+				CandidateInfo {
+					bond,
+					delegated: Zero::zero(),
+					delegators: 0,
+					commission,
+					reward_per_stake: 0,
+					status: CandidateStatus::Active,
+				},
+			);
+			<ActiveCandidates<T>>::put(active);
+
+			Self::deposit_event(Event::CandidateAdded(who, bond, commission));
+			Ok(().into())
+		}
+
+		/// Leave the candidate set. The own bond goes through the unbonding delay; delegators
+		/// have to undelegate themselves.
+		#[pallet::weight(T::WeightInfo::leave_candidates())]
+		pub fn leave_candidates(origin: OriginFor<T>) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+
+			Self::do_remove_candidate(&who)?;
+			Self::deposit_event(Event::CandidateRemoved(who));
+			Ok(().into())
+		}
+
+		/// Change the commission of the calling candidate.
+		#[pallet::weight(T::WeightInfo::set_commission())]
+		pub fn set_commission(
+			origin: OriginFor<T>,
+			commission: Perbill,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+
+			<Candidates<T>>::try_mutate(&who, |maybe_info| -> DispatchResult {
+				let info = maybe_info
+					.as_mut()
+					.filter(|info| info.status == CandidateStatus::Active)
+					.ok_or(Error::<T>::NotCandidate)?;
+				info.commission = commission;
+				Ok(())
+			})?;
+
+			Self::deposit_event(Event::CommissionSet(who, commission));
+			Ok(().into())
+		}
+
+		/// Delegate `amount` to `candidate`, or increase an existing delegation. Pending rewards
+		/// of an existing delegation are paid out first.
+		#[pallet::weight(T::WeightInfo::delegate())]
+		#[transactional]
+		pub fn delegate(
+			origin: OriginFor<T>,
+			candidate: T::AccountId,
+			amount: BalanceOf<T>,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			ensure!(who != candidate, Error::<T>::SelfDelegation);
+
+			let mut info = Self::candidates(&candidate)
+				.filter(|info| info.status == CandidateStatus::Active)
+				.ok_or(Error::<T>::NotCandidate)?;
+			let mut delegation = match Self::delegations(&candidate, &who) {
+				Some(delegation) => {
+					Self::pay_delegator(&who, &candidate, &info, &delegation)?;
+					delegation
+				},
+				None => {
+					info.delegators = info.delegators.saturating_add(1);
+					Delegation { amount: Zero::zero(), reward_debt: 0 }
+				},
+			};
+			delegation.amount = delegation.amount.saturating_add(amount);
+			ensure!(delegation.amount >= T::MinDelegation::get(), Error::<T>::DelegationTooLow);
+
+			T::Currency::reserve(&who, amount)?;
+
+			delegation.reward_debt = Self::accrued(delegation.amount, info.reward_per_stake);
+			info.delegated = info.delegated.saturating_add(amount);
+			<Delegations<T>>::insert(&candidate, &who, delegation);
+			<Candidates<T>>::insert(&candidate, info);
+
+			Self::deposit_event(Event::Delegated(who, candidate, amount));
+			Ok(().into())
+		}
+
+		/// Undelegate `amount` from `candidate`. Pending rewards are paid out and the amount is
+		/// released after `UnbondingDelay` blocks.
+		///
+		/// Also works for candidates that have left or were kicked.
+		#[pallet::weight(T::WeightInfo::undelegate())]
+		#[transactional]
+		pub fn undelegate(
+			origin: OriginFor<T>,
+			candidate: T::AccountId,
+			amount: BalanceOf<T>,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+
+			let mut info = Self::candidates(&candidate).ok_or(Error::<T>::NotCandidate)?;
+			let mut delegation =
+				Self::delegations(&candidate, &who).ok_or(Error::<T>::NoDelegation)?;
+			ensure!(delegation.amount >= amount, Error::<T>::InsufficientDelegation);
+			let remaining = delegation.amount.saturating_sub(amount);
+			ensure!(
+				remaining.is_zero() || remaining >= T::MinDelegation::get(),
+				Error::<T>::DelegationTooLow
+			);
+
+			Self::pay_delegator(&who, &candidate, &info, &delegation)?;
+			let unlock_at = Self::schedule_unbonding(&who, amount)?;
+
+			info.delegated = info.delegated.saturating_sub(amount);
+			if remaining.is_zero() {
+				<Delegations<T>>::remove(&candidate, &who);
+				info.delegators = info.delegators.saturating_sub(1);
+			} else {
+				delegation.amount = remaining;
+				delegation.reward_debt = Self::accrued(remaining, info.reward_per_stake);
+				<Delegations<T>>::insert(&candidate, &who, delegation);
+			}
+
+			if info.status == CandidateStatus::Leaving && info.delegators == 0 {
+				<Candidates<T>>::remove(&candidate);
+			} else {
+				<Candidates<T>>::insert(&candidate, info);
+			}
+
+			Self::deposit_event(Event::Undelegated(who, candidate, amount, unlock_at));
+			Ok(().into())
+		}
+
+		/// Claim the rewards accrued by the caller's delegation to `candidate`.
+		#[pallet::weight(T::WeightInfo::claim_rewards())]
+		#[transactional]
+		pub fn claim_rewards(
+			origin: OriginFor<T>,
+			candidate: T::AccountId,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+
+			let info = Self::candidates(&candidate).ok_or(Error::<T>::NotCandidate)?;
+			let mut delegation =
+				Self::delegations(&candidate, &who).ok_or(Error::<T>::NoDelegation)?;
+			let paid = Self::pay_delegator(&who, &candidate, &info, &delegation)?;
+			ensure!(!paid.is_zero(), Error::<T>::NothingToClaim);
+
+			delegation.reward_debt = Self::accrued(delegation.amount, info.reward_per_stake);
+			<Delegations<T>>::insert(&candidate, &who, delegation);
+			Ok(().into())
+		}
+
+		/// Release every unbonding chunk of the caller whose delay has passed.
+		#[pallet::weight(T::WeightInfo::withdraw_unbonded(T::MaxUnbondingChunks::get()))]
+		pub fn withdraw_unbonded(origin: OriginFor<T>) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			let now = frame_system::Pallet::<T>::block_number();
+
+			let mut chunks = Self::unbonding(&who);
+			let mut released: BalanceOf<T> = Zero::zero();
+			chunks.retain(|chunk| {
+				if chunk.unlock_at <= now {
+					released = released.saturating_add(chunk.amount);
+					false
+				} else {
+					true
+				}
+			});
+			ensure!(!released.is_zero(), Error::<T>::NothingToWithdraw);
+
+			T::Currency::unreserve(&who, released);
+			if chunks.is_empty() {
+				<Unbonding<T>>::remove(&who);
+			} else {
+				<Unbonding<T>>::insert(&who, chunks);
+			}
+
+			Self::deposit_event(Event::Withdrawn(who, released));
+			Ok(().into())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// Get a unique, inaccessible account id from the `PotId`.
+		pub fn account_id() -> T::AccountId {
+			T::PotId::get().into_account()
+		}
+
+		/// The rewards a delegation of `amount` has accrued at `reward_per_stake`.
+		pub(crate) fn accrued(amount: BalanceOf<T>, reward_per_stake: u128) -> u128 {
+			multiply_by_rational(amount.saturated_into(), reward_per_stake, REWARD_PRECISION)
+				.unwrap_or(u128::MAX)
+		}
+
+		/// The rewards `delegator` can currently claim from its delegation to `candidate`.
+		pub fn pending_rewards(candidate: &T::AccountId, delegator: &T::AccountId) -> BalanceOf<T> {
+			match (Self::candidates(candidate), Self::delegations(candidate, delegator)) {
+				(Some(info), Some(delegation)) =>
+					Self::accrued(delegation.amount, info.reward_per_stake)
+						.saturating_sub(delegation.reward_debt)
+						.saturated_into(),
+				_ => Zero::zero(),
+			}
+		}
+
+		/// Transfer the pending rewards of `delegation` from the pot. The caller is responsible
+		/// for resetting the reward debt.
+		fn pay_delegator(
+			delegator: &T::AccountId,
+			candidate: &T::AccountId,
+			info: &CandidateInfo<BalanceOf<T>>,
+			delegation: &Delegation<BalanceOf<T>>,
+		) -> Result<BalanceOf<T>, DispatchError> {
+			let pending: BalanceOf<T> = Self::accrued(delegation.amount, info.reward_per_stake)
+				.saturating_sub(delegation.reward_debt)
+				.saturated_into();
+			// Rounding may leave the books a unit short of what is owed.
+			let pending = pending.min(Self::unclaimed_rewards());
+			if pending.is_zero() {
+				return Ok(pending)
+			}
+
+			T::Currency::transfer(
+				&Self::account_id(),
+				delegator,
+				pending,
+				ExistenceRequirement::KeepAlive,
+			)?;
+			<UnclaimedRewards<T>>::mutate(|unclaimed| {
+				*unclaimed = unclaimed.saturating_sub(pending)
+			});
+
+			Self::deposit_event(Event::RewardsClaimed(
+				delegator.clone(),
+				candidate.clone(),
+				pending,
+			));
+			Ok(pending)
+		}
+
+		/// Queue `amount` of `who`'s reserved funds for release after the unbonding delay.
+		fn schedule_unbonding(
+			who: &T::AccountId,
+			amount: BalanceOf<T>,
+		) -> Result<T::BlockNumber, DispatchError> {
+			let unlock_at =
+				frame_system::Pallet::<T>::block_number().saturating_add(T::UnbondingDelay::get());
+			if amount.is_zero() {
+				return Ok(unlock_at)
+			}
+
+			<Unbonding<T>>::try_mutate(who, |chunks| {
+				chunks
+					.try_push(UnbondingChunk { amount, unlock_at })
+					.map_err(|_| Error::<T>::TooManyUnbondingChunks)
+			})?;
+			Ok(unlock_at)
+		}
+
+		/// Take `who` out of the election and start unbonding its own bond.
+		fn do_remove_candidate(who: &T::AccountId) -> DispatchResult {
+			let mut info = Self::candidates(who)
+				.filter(|info| info.status == CandidateStatus::Active)
+				.ok_or(Error::<T>::NotCandidate)?;
+
+			Self::schedule_unbonding(who, info.bond)?;
+			info.bond = Zero::zero();
+			info.status = CandidateStatus::Leaving;
+			if info.delegators == 0 {
+				<Candidates<T>>::remove(who);
+			} else {
+				<Candidates<T>>::insert(who, info);
+			}
+			<LastAuthoredBlock<T>>::remove(who);
+			<ActiveCandidates<T>>::mutate(|active| active.retain(|candidate| candidate != who));
+			Ok(())
+		}
+
+		/// Remove the selected candidates that have not authored a block within
+		/// `KickThreshold`.
+		fn kick_stale_candidates(now: T::BlockNumber) {
+			for who in Self::selected_candidates() {
+				let last = Self::last_authored_block(&who);
+				if now.saturating_sub(last) < T::KickThreshold::get() {
+					continue
+				}
+				match Self::do_remove_candidate(&who) {
+					Ok(()) => Self::deposit_event(Event::CandidateKicked(who)),
+					Err(e) => log::warn!(
+						target: "runtime::collator-staking",
+						"failed to kick candidate {:?}: {:?}",
+						who,
+						e,
+					),
+				}
+			}
+		}
+
+		/// Every selected candidate with its last authored block and the block from which the
+		/// next session rotation removes it, unless it authors again.
+		pub fn kick_schedule() -> Vec<(T::AccountId, T::BlockNumber, T::BlockNumber)> {
+			Self::selected_candidates()
+				.into_iter()
+				.map(|who| {
+					let last = Self::last_authored_block(&who);
+					(who, last, last.saturating_add(T::KickThreshold::get()))
+				})
+				.collect()
+		}
+
+		/// The number of active candidates.
+		pub fn candidate_count() -> u32 {
+			Self::active_candidates().len() as u32
+		}
+
+		/// The `DesiredCollators` active candidates with registered keys and the most backing.
+		///
+		/// Only walks `ActiveCandidates`, bounded by `MaxCandidates`, so that leaving candidates
+		/// waiting for their delegators do not weigh on the session rotation.
+		pub fn elect() -> Vec<T::AccountId> {
+			let mut candidates = Self::active_candidates()
+				.into_iter()
+				.filter(|who| {
+					T::ValidatorIdOf::convert(who.clone())
+						.map_or(false, |key| T::ValidatorRegistration::is_registered(&key))
+				})
+				.filter_map(|who| {
+					let info = Self::candidates(&who)?;
+					Some((who, info.total_backing()))
+				})
+				.collect::<Vec<_>>();
+			// Highest backing first; ties are resolved by account to stay deterministic.
+			candidates.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+			candidates
+				.into_iter()
+				.take(Self::desired_collators() as usize)
+				.map(|(who, _)| who)
+				.collect()
+		}
+
+		/// Pay the block reward to `author`, accruing the delegators' share.
+		fn reward_author(author: T::AccountId) {
+			let pot = Self::account_id();
+			let spendable = T::Currency::free_balance(&pot)
+				.saturating_sub(T::Currency::minimum_balance())
+				.saturating_sub(Self::unclaimed_rewards());
+			let reward = spendable / 2u32.into();
+			if reward.is_zero() {
+				return
+			}
+
+			let mut author_share = reward;
+			if let Some(mut info) =
+				Self::candidates(&author).filter(|info| !info.delegated.is_zero())
+			{
+				let shared = reward.saturating_sub(info.commission * reward);
+				let delegators_share =
+					Perbill::from_rational(info.delegated, info.total_backing()) * shared;
+				info.reward_per_stake = info.reward_per_stake.saturating_add(
+					multiply_by_rational(
+						delegators_share.saturated_into(),
+						REWARD_PRECISION,
+						info.delegated.saturated_into(),
+					)
+					.unwrap_or_default(),
+				);
+				<Candidates<T>>::insert(&author, info);
+				<UnclaimedRewards<T>>::mutate(|unclaimed| {
+					*unclaimed = unclaimed.saturating_add(delegators_share)
+				});
+				author_share = reward.saturating_sub(delegators_share);
+			}
+
+			if T::Currency::transfer(&pot, &author, author_share, ExistenceRequirement::KeepAlive)
+				.is_ok()
+			{
+				Self::deposit_event(Event::CollatorRewarded(author, author_share));
+			}
+		}
+	}
+
+	/// Keep track of the number of authored blocks per collator and pay their rewards.
+	impl<T: Config> pallet_authorship::EventHandler<T::AccountId, T::BlockNumber> for Pallet<T> {
+		fn note_author(author: T::AccountId) {
+			Self::reward_author(author.clone());
+			<LastAuthoredBlock<T>>::insert(author, frame_system::Pallet::<T>::block_number());
+
+			frame_system::Pallet::<T>::register_extra_weight_unchecked(
+				T::WeightInfo::note_author(),
+				DispatchClass::Mandatory,
+			);
+		}
+
+		fn note_uncle(_author: T::AccountId, _age: T::BlockNumber) {
+			// Uncles are not rewarded: Aura parachains have none, and the runtime keeps
+			// `UncleGenerations` at zero.
+		}
+	}
+
+	/// Play the role of the session manager.
+	impl<T: Config> SessionManager<T::AccountId> for Pallet<T> {
+		fn new_session(index: SessionIndex) -> Option<Vec<T::AccountId>> {
+			log::info!(
+				"assembling new collators for new session {} at #{:?}",
+				index,
+				<frame_system::Pallet<T>>::block_number(),
+			);
+
+			let now = frame_system::Pallet::<T>::block_number();
+			Self::kick_stale_candidates(now);
+
+			let previous = Self::selected_candidates();
+			let elected = Self::elect();
+			for who in elected.iter().filter(|who| !previous.contains(who)) {
+				// Newly selected candidates only start authoring once the session they were
+				// selected for begins, so give them a full threshold of grace.
+				<LastAuthoredBlock<T>>::insert(who, now.saturating_add(T::KickThreshold::get()));
+			}
+			// `elect` takes at most `DesiredCollators` which is bounded by `MaxCollators`.
+			<SelectedCandidates<T>>::put(
+				BoundedVec::<_, T::MaxCollators>::try_from(elected.clone()).unwrap_or_default(),
+			);
+
+			let mut collators = Self::invulnerables().to_vec();
+			collators.extend(elected);
+			Self::deposit_event(Event::CollatorsSelected(index, collators.clone()));
+
+			frame_system::Pallet::<T>::register_extra_weight_unchecked(
+				T::WeightInfo::new_session(Self::candidate_count()),
+				DispatchClass::Mandatory,
+			);
+			Some(collators)
+		}
+
+		fn start_session(_: SessionIndex) {
+			// we don't care.
+		}
+
+		fn end_session(_: SessionIndex) {
+			// we don't care.
+		}
+	}
+}
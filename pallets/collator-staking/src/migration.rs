@@ -0,0 +1,92 @@
+//! Migration from `pallet_collator_selection`, which this pallet replaces at the same index.
+
+use crate::{
+	ActiveCandidates, BalanceOf, CandidacyBond, CandidateInfo, CandidateStatus, Candidates, Config,
+	DesiredCollators, Invulnerables,
+};
+use codec::Decode;
+use frame_support::{
+	storage::{migration, unhashed},
+	traits::{Get, OnRuntimeUpgrade, ReservableCurrency},
+	weights::Weight,
+	BoundedVec, StorageHasher, Twox128,
+};
+use sp_runtime::{traits::Zero, Perbill};
+use sp_std::{marker::PhantomData, prelude::*};
+
+/// The name `pallet_collator_selection` had in the runtime.
+const OLD_PALLET: &[u8] = b"CollatorSelection";
+
+/// A candidate of `pallet_collator_selection`.
+#[derive(Decode)]
+struct OldCandidate<AccountId, Balance> {
+	who: AccountId,
+	deposit: Balance,
+}
+
+/// Moves the invulnerables, candidates and parameters of `pallet_collator_selection` into this
+/// pallet, and removes what is left of it.
+///
+/// Candidates keep their reserved deposit as their bond, without commission, as long as there is
+/// room for them. The deposit of the others is unreserved. Candidates are elected again at the
+/// next session, and start authoring with a fresh `KickThreshold`. Does nothing once the storage
+/// of `pallet_collator_selection` is gone.
+pub struct MigrateFromCollatorSelection<T>(PhantomData<T>);
+
+impl<T: Config> OnRuntimeUpgrade for MigrateFromCollatorSelection<T> {
+	fn on_runtime_upgrade() -> Weight {
+		let db = T::DbWeight::get();
+		if !migration::have_storage_value(OLD_PALLET, b"Invulnerables", &[]) {
+			return db.reads(1)
+		}
+
+		let mut invulnerables: Vec<T::AccountId> =
+			migration::take_storage_value(OLD_PALLET, b"Invulnerables", &[]).unwrap_or_default();
+		let authors = invulnerables.len() as Weight;
+		invulnerables.truncate(T::MaxInvulnerables::get() as usize);
+		<Invulnerables<T>>::put(BoundedVec::try_from(invulnerables).unwrap_or_default());
+
+		let desired: u32 = migration::take_storage_value(OLD_PALLET, b"DesiredCandidates", &[])
+			.unwrap_or_default();
+		<DesiredCollators<T>>::put(desired.min(T::MaxCollators::get()));
+
+		let bond: BalanceOf<T> =
+			migration::take_storage_value(OLD_PALLET, b"CandidacyBond", &[]).unwrap_or_default();
+		<CandidacyBond<T>>::put(bond);
+
+		let candidates: Vec<OldCandidate<T::AccountId, BalanceOf<T>>> =
+			migration::take_storage_value(OLD_PALLET, b"Candidates", &[]).unwrap_or_default();
+		let mut active = <ActiveCandidates<T>>::get();
+		for OldCandidate { who, deposit } in candidates.iter() {
+			if <Candidates<T>>::contains_key(who) || active.try_push(who.clone()).is_err() {
+				T::Currency::unreserve(who, *deposit);
+				continue
+			}
+			<Candidates<T>>::insert(
+				who,
+				CandidateInfo {
+					bond: *deposit,
+					delegated: Zero::zero(),
+					delegators: 0,
+					commission: Perbill::zero(),
+					reward_per_stake: 0,
+					status: CandidateStatus::Active,
+				},
+			);
+		}
+		let count = active.len();
+		<ActiveCandidates<T>>::put(active);
+
+		// `LastAuthoredBlock` and the storage version.
+		let _ = unhashed::kill_prefix(&Twox128::hash(OLD_PALLET), None);
+		log::info!(
+			target: "runtime::collator-staking",
+			"migrated {} candidates from collator selection",
+			count,
+		);
+
+		// The candidates and invulnerables authored at most once each.
+		let candidates = candidates.len() as Weight;
+		db.reads_writes(6 + candidates, 6 + 3 * candidates + authors)
+	}
+}
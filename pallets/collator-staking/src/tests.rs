@@ -0,0 +1,314 @@
+use crate::{
+	migration::MigrateFromCollatorSelection, mock::*, CandidateStatus, Error, Event as StakingEvent,
+};
+use codec::Encode;
+use frame_support::{
+	assert_noop, assert_ok,
+	storage::migration::{have_storage_value, put_storage_value, storage_key_iter},
+	traits::{Currency, OnRuntimeUpgrade, ReservableCurrency},
+	StorageHasher, Twox64Concat,
+};
+use pallet_authorship::EventHandler;
+use pallet_session::SessionManager;
+use sp_runtime::Perbill;
+
+fn register(who: u64, bond: u64) {
+	assert_ok!(CollatorStaking::register_as_candidate(
+		Origin::signed(who),
+		bond,
+		Perbill::from_percent(10)
+	));
+}
+
+fn fund_pot(amount: u64) {
+	Balances::make_free_balance_be(&CollatorStaking::account_id(), amount);
+}
+
+#[test]
+fn genesis_config_works() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(CollatorStaking::invulnerables().to_vec(), vec![1, 2]);
+		assert_eq!(CollatorStaking::candidacy_bond(), 10);
+		assert_eq!(CollatorStaking::desired_collators(), 2);
+	});
+}
+
+#[test]
+fn only_update_origin_sets_parameters() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			CollatorStaking::set_desired_collators(Origin::signed(1), 5),
+			sp_runtime::DispatchError::BadOrigin
+		);
+		assert_noop!(
+			CollatorStaking::set_desired_collators(Origin::signed(RootAccount::get()), 11),
+			Error::<Test>::TooManyDesiredCollators
+		);
+		assert_ok!(CollatorStaking::set_desired_collators(Origin::signed(RootAccount::get()), 5));
+		assert_eq!(CollatorStaking::desired_collators(), 5);
+
+		assert_ok!(CollatorStaking::set_invulnerables(Origin::signed(RootAccount::get()), vec![3]));
+		assert_eq!(CollatorStaking::invulnerables().to_vec(), vec![3]);
+	});
+}
+
+#[test]
+fn register_as_candidate_reserves_bond() {
+	new_test_ext().execute_with(|| {
+		register(3, 20);
+
+		assert_eq!(Balances::reserved_balance(3), 20);
+		assert_eq!(CollatorStaking::candidate_count(), 1);
+		assert_eq!(CollatorStaking::candidates(3).unwrap().status, CandidateStatus::Active);
+	});
+}
+
+#[test]
+fn cannot_register_as_candidate_twice_or_without_keys() {
+	new_test_ext().execute_with(|| {
+		let commission = Perbill::zero();
+		register(3, 20);
+		assert_noop!(
+			CollatorStaking::register_as_candidate(Origin::signed(3), 20, commission),
+			Error::<Test>::AlreadyCandidate
+		);
+		assert_noop!(
+			CollatorStaking::register_as_candidate(Origin::signed(1), 20, commission),
+			Error::<Test>::AlreadyInvulnerable
+		);
+		assert_noop!(
+			CollatorStaking::register_as_candidate(Origin::signed(4), 5, commission),
+			Error::<Test>::BondTooLow
+		);
+		assert_noop!(
+			CollatorStaking::register_as_candidate(Origin::signed(101), 20, commission),
+			Error::<Test>::ValidatorNotRegistered
+		);
+	});
+}
+
+#[test]
+fn delegate_increases_backing() {
+	new_test_ext().execute_with(|| {
+		register(3, 20);
+		assert_noop!(
+			CollatorStaking::delegate(Origin::signed(4), 3, 5),
+			Error::<Test>::DelegationTooLow
+		);
+		assert_noop!(
+			CollatorStaking::delegate(Origin::signed(3), 3, 10),
+			Error::<Test>::SelfDelegation
+		);
+		assert_noop!(
+			CollatorStaking::delegate(Origin::signed(4), 5, 10),
+			Error::<Test>::NotCandidate
+		);
+
+		assert_ok!(CollatorStaking::delegate(Origin::signed(4), 3, 10));
+		assert_ok!(CollatorStaking::delegate(Origin::signed(4), 3, 15));
+		assert_ok!(CollatorStaking::delegate(Origin::signed(5), 3, 10));
+
+		let info = CollatorStaking::candidates(3).unwrap();
+		assert_eq!(info.delegated, 35);
+		assert_eq!(info.delegators, 2);
+		assert_eq!(info.total_backing(), 55);
+		assert_eq!(Balances::reserved_balance(4), 25);
+	});
+}
+
+#[test]
+fn new_session_elects_best_backed_candidates() {
+	new_test_ext().execute_with(|| {
+		register(3, 20);
+		register(4, 30);
+		register(5, 10);
+		assert_ok!(CollatorStaking::delegate(Origin::signed(1), 5, 30));
+
+		assert_eq!(CollatorStaking::new_session(1), Some(vec![1, 2, 5, 4]));
+		assert_eq!(CollatorStaking::selected_candidates().to_vec(), vec![5, 4]);
+		System::assert_last_event(Event::CollatorStaking(StakingEvent::CollatorsSelected(
+			1,
+			vec![1, 2, 5, 4],
+		)));
+	});
+}
+
+#[test]
+fn author_and_delegators_share_rewards() {
+	new_test_ext().execute_with(|| {
+		register(3, 20);
+		assert_ok!(CollatorStaking::delegate(Origin::signed(4), 3, 20));
+		fund_pot(205);
+
+		// 200 is spendable, so the reward is 100. The 10% commission leaves 90 to split evenly
+		// between the candidate's bond and the delegation.
+		CollatorStaking::note_author(3);
+		assert_eq!(Balances::free_balance(3), 80 + 55);
+		assert_eq!(CollatorStaking::unclaimed_rewards(), 45);
+		assert_eq!(CollatorStaking::pending_rewards(&3, &4), 45);
+		assert_eq!(CollatorStaking::last_authored_block(3), 1);
+
+		assert_ok!(CollatorStaking::claim_rewards(Origin::signed(4), 3));
+		assert_eq!(Balances::free_balance(4), 80 + 45);
+		assert_eq!(CollatorStaking::unclaimed_rewards(), 0);
+		assert_noop!(
+			CollatorStaking::claim_rewards(Origin::signed(4), 3),
+			Error::<Test>::NothingToClaim
+		);
+	});
+}
+
+#[test]
+fn invulnerables_keep_whole_reward() {
+	new_test_ext().execute_with(|| {
+		fund_pot(105);
+
+		CollatorStaking::note_author(1);
+		assert_eq!(Balances::free_balance(1), 150);
+		assert_eq!(Balances::free_balance(CollatorStaking::account_id()), 55);
+	});
+}
+
+#[test]
+fn undelegated_funds_wait_for_unbonding_delay() {
+	new_test_ext().execute_with(|| {
+		register(3, 20);
+		assert_ok!(CollatorStaking::delegate(Origin::signed(4), 3, 30));
+
+		assert_noop!(
+			CollatorStaking::undelegate(Origin::signed(4), 3, 25),
+			Error::<Test>::DelegationTooLow
+		);
+		assert_ok!(CollatorStaking::undelegate(Origin::signed(4), 3, 20));
+		assert_eq!(CollatorStaking::candidates(3).unwrap().delegated, 10);
+		assert_eq!(Balances::reserved_balance(4), 30);
+
+		assert_noop!(
+			CollatorStaking::withdraw_unbonded(Origin::signed(4)),
+			Error::<Test>::NothingToWithdraw
+		);
+		System::set_block_number(11);
+		assert_ok!(CollatorStaking::withdraw_unbonded(Origin::signed(4)));
+		assert_eq!(Balances::reserved_balance(4), 10);
+		assert!(CollatorStaking::unbonding(4).is_empty());
+	});
+}
+
+#[test]
+fn leaving_candidate_lives_on_until_delegators_are_gone() {
+	new_test_ext().execute_with(|| {
+		register(3, 20);
+		assert_ok!(CollatorStaking::delegate(Origin::signed(4), 3, 10));
+
+		assert_ok!(CollatorStaking::leave_candidates(Origin::signed(3)));
+		assert_eq!(CollatorStaking::candidate_count(), 0);
+		assert_eq!(CollatorStaking::candidates(3).unwrap().status, CandidateStatus::Leaving);
+		assert_noop!(
+			CollatorStaking::delegate(Origin::signed(5), 3, 10),
+			Error::<Test>::NotCandidate
+		);
+		assert_eq!(CollatorStaking::new_session(1), Some(vec![1, 2]));
+
+		assert_ok!(CollatorStaking::undelegate(Origin::signed(4), 3, 10));
+		assert!(CollatorStaking::candidates(3).is_none());
+
+		System::set_block_number(11);
+		assert_ok!(CollatorStaking::withdraw_unbonded(Origin::signed(3)));
+		assert_eq!(Balances::reserved_balance(3), 0);
+	});
+}
+
+#[test]
+fn leaving_candidates_are_not_elected_from() {
+	new_test_ext().execute_with(|| {
+		// More candidates than `MaxCandidates` leave while still having a delegator.
+		Balances::make_free_balance_be(&5, 1_000);
+		for who in 10..50 {
+			Balances::make_free_balance_be(&who, 100);
+			register(who, 10);
+			assert_ok!(CollatorStaking::delegate(Origin::signed(5), who, 10));
+			assert_ok!(CollatorStaking::leave_candidates(Origin::signed(who)));
+		}
+		assert_eq!(CollatorStaking::candidate_count(), 0);
+		assert!(CollatorStaking::candidates(49).is_some());
+
+		register(3, 20);
+		register(4, 20);
+		assert_eq!(CollatorStaking::active_candidates().to_vec(), vec![3, 4]);
+		assert_eq!(CollatorStaking::elect(), vec![3, 4]);
+		assert_eq!(CollatorStaking::new_session(1), Some(vec![1, 2, 3, 4]));
+	});
+}
+
+#[test]
+fn unbonding_chunks_are_bounded() {
+	new_test_ext().execute_with(|| {
+		register(3, 20);
+		assert_ok!(CollatorStaking::delegate(Origin::signed(4), 3, 40));
+		for _ in 0..3 {
+			assert_ok!(CollatorStaking::undelegate(Origin::signed(4), 3, 10));
+		}
+		assert_noop!(
+			CollatorStaking::undelegate(Origin::signed(4), 3, 10),
+			Error::<Test>::TooManyUnbondingChunks
+		);
+	});
+}
+
+#[test]
+fn selected_candidates_that_stop_authoring_are_kicked() {
+	new_test_ext().execute_with(|| {
+		register(3, 20);
+		register(4, 20);
+		CollatorStaking::new_session(1);
+
+		System::set_block_number(11);
+		CollatorStaking::new_session(2);
+		assert_eq!(CollatorStaking::candidate_count(), 2);
+
+		System::set_block_number(15);
+		CollatorStaking::note_author(3);
+		assert_eq!(CollatorStaking::kick_schedule(), vec![(3, 15, 25), (4, 11, 21)]);
+
+		System::set_block_number(21);
+		assert_eq!(CollatorStaking::new_session(3), Some(vec![1, 2, 3]));
+		assert_eq!(CollatorStaking::candidates(4), None);
+		assert_eq!(Balances::reserved_balance(4), 20);
+		assert!(System::events()
+			.iter()
+			.any(|record| record.event == Event::CollatorStaking(StakingEvent::CandidateKicked(4))));
+	});
+}
+
+#[test]
+fn collator_selection_is_migrated() {
+	new_test_ext().execute_with(|| {
+		let old = b"CollatorSelection";
+		assert_ok!(Balances::reserve(&3, 20));
+		put_storage_value(old, b"Invulnerables", &[], vec![4u64, 5]);
+		put_storage_value(old, b"DesiredCandidates", &[], 3u32);
+		put_storage_value(old, b"CandidacyBond", &[], 15u64);
+		put_storage_value(old, b"Candidates", &[], vec![(3u64, 20u64)]);
+		put_storage_value(old, b"LastAuthoredBlock", &Twox64Concat::hash(&3u64.encode()), 1u64);
+
+		MigrateFromCollatorSelection::<Test>::on_runtime_upgrade();
+
+		assert_eq!(CollatorStaking::invulnerables().to_vec(), vec![4, 5]);
+		assert_eq!(CollatorStaking::desired_collators(), 3);
+		assert_eq!(CollatorStaking::candidacy_bond(), 15);
+		let info = CollatorStaking::candidates(3).unwrap();
+		assert_eq!((info.bond, info.commission), (20, Perbill::zero()));
+		assert_eq!(CollatorStaking::candidate_count(), 1);
+		assert_eq!(Balances::reserved_balance(3), 20);
+		assert!(!have_storage_value(old, b"Invulnerables", &[]));
+		assert_eq!(
+			storage_key_iter::<u64, u64, Twox64Concat>(old, b"LastAuthoredBlock").count(),
+			0
+		);
+
+		// Running it again changes nothing.
+		assert_ok!(CollatorStaking::set_desired_collators(Origin::signed(RootAccount::get()), 5));
+		MigrateFromCollatorSelection::<Test>::on_runtime_upgrade();
+		assert_eq!(CollatorStaking::desired_collators(), 5);
+	});
+}
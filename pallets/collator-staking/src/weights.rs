@@ -0,0 +1,200 @@
+//! Weights for pallet_collator_staking.
+//!
+//! Estimated from the storage each call touches, until the benchmarks of `benchmarking.rs` are
+//! run on reference hardware to replace them.
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use sp_std::marker::PhantomData;
+
+/// Weight functions needed for pallet_collator_staking.
+pub trait WeightInfo {
+	fn set_invulnerables(b: u32, ) -> Weight;
+	fn set_desired_collators() -> Weight;
+	fn set_candidacy_bond() -> Weight;
+	fn register_as_candidate() -> Weight;
+	fn leave_candidates() -> Weight;
+	fn set_commission() -> Weight;
+	fn delegate() -> Weight;
+	fn undelegate() -> Weight;
+	fn claim_rewards() -> Weight;
+	fn withdraw_unbonded(u: u32, ) -> Weight;
+	fn note_author() -> Weight;
+	fn new_session(c: u32, ) -> Weight;
+}
+
+/// Weights for pallet_collator_staking using the Substrate node and recommended hardware.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	// Storage: CollatorStaking Invulnerables (r:0 w:1)
+	fn set_invulnerables(b: u32, ) -> Weight {
+		(10_000 as Weight)
+			.saturating_add((1_000 as Weight).saturating_mul(b as Weight))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	// Storage: CollatorStaking DesiredCollators (r:0 w:1)
+	fn set_desired_collators() -> Weight {
+		(10_000 as Weight)
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	// Storage: CollatorStaking CandidacyBond (r:0 w:1)
+	fn set_candidacy_bond() -> Weight {
+		(10_000 as Weight)
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	// Storage: CollatorStaking Candidates (r:1 w:1)
+	// Storage: CollatorStaking Invulnerables (r:1 w:0)
+	// Storage: CollatorStaking CandidacyBond (r:1 w:0)
+	// Storage: CollatorStaking ActiveCandidates (r:1 w:1)
+	// Storage: Session NextKeys (r:1 w:0)
+	// Storage: System Account (r:1 w:1)
+	fn register_as_candidate() -> Weight {
+		(50_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(6 as Weight))
+			.saturating_add(T::DbWeight::get().writes(3 as Weight))
+	}
+	// Storage: CollatorStaking Candidates (r:1 w:1)
+	// Storage: CollatorStaking Unbonding (r:1 w:1)
+	// Storage: CollatorStaking LastAuthoredBlock (r:0 w:1)
+	// Storage: CollatorStaking ActiveCandidates (r:1 w:1)
+	fn leave_candidates() -> Weight {
+		(50_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(3 as Weight))
+			.saturating_add(T::DbWeight::get().writes(4 as Weight))
+	}
+	// Storage: CollatorStaking Candidates (r:1 w:1)
+	fn set_commission() -> Weight {
+		(10_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(1 as Weight))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	// Storage: CollatorStaking Candidates (r:1 w:1)
+	// Storage: CollatorStaking Delegations (r:1 w:1)
+	// Storage: CollatorStaking UnclaimedRewards (r:1 w:1)
+	// Storage: System Account (r:2 w:2)
+	fn delegate() -> Weight {
+		(50_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(5 as Weight))
+			.saturating_add(T::DbWeight::get().writes(5 as Weight))
+	}
+	// Storage: CollatorStaking Candidates (r:1 w:1)
+	// Storage: CollatorStaking Delegations (r:1 w:1)
+	// Storage: CollatorStaking UnclaimedRewards (r:1 w:1)
+	// Storage: CollatorStaking Unbonding (r:1 w:1)
+	// Storage: System Account (r:2 w:2)
+	fn undelegate() -> Weight {
+		(50_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(6 as Weight))
+			.saturating_add(T::DbWeight::get().writes(6 as Weight))
+	}
+	// Storage: CollatorStaking Candidates (r:1 w:0)
+	// Storage: CollatorStaking Delegations (r:1 w:1)
+	// Storage: CollatorStaking UnclaimedRewards (r:1 w:1)
+	// Storage: System Account (r:2 w:2)
+	fn claim_rewards() -> Weight {
+		(50_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(5 as Weight))
+			.saturating_add(T::DbWeight::get().writes(4 as Weight))
+	}
+	// Storage: CollatorStaking Unbonding (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	fn withdraw_unbonded(u: u32, ) -> Weight {
+		(30_000 as Weight)
+			.saturating_add((1_000 as Weight).saturating_mul(u as Weight))
+			.saturating_add(T::DbWeight::get().reads(2 as Weight))
+			.saturating_add(T::DbWeight::get().writes(2 as Weight))
+	}
+	// Storage: CollatorStaking Candidates (r:1 w:1)
+	// Storage: CollatorStaking UnclaimedRewards (r:1 w:1)
+	// Storage: CollatorStaking LastAuthoredBlock (r:0 w:1)
+	// Storage: System Account (r:2 w:2)
+	// Storage: System Number (r:1 w:0)
+	fn note_author() -> Weight {
+		(30_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(5 as Weight))
+			.saturating_add(T::DbWeight::get().writes(5 as Weight))
+	}
+	// Storage: CollatorStaking SelectedCandidates (r:1 w:1)
+	// Storage: CollatorStaking ActiveCandidates (r:1 w:0)
+	// Storage: CollatorStaking Candidates (r:1 w:0)
+	// Storage: CollatorStaking Invulnerables (r:1 w:0)
+	// Storage: CollatorStaking DesiredCollators (r:1 w:0)
+	// Storage: CollatorStaking LastAuthoredBlock (r:1 w:1)
+	fn new_session(c: u32, ) -> Weight {
+		(50_000 as Weight)
+			.saturating_add((10_000 as Weight).saturating_mul(c as Weight))
+			.saturating_add(T::DbWeight::get().reads(5 as Weight))
+			.saturating_add(T::DbWeight::get().reads((2 as Weight).saturating_mul(c as Weight)))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+			.saturating_add(T::DbWeight::get().writes((1 as Weight).saturating_mul(c as Weight)))
+	}
+}
+
+// For backwards compatibility and tests
+impl WeightInfo for () {
+	fn set_invulnerables(b: u32, ) -> Weight {
+		(10_000 as Weight)
+			.saturating_add((1_000 as Weight).saturating_mul(b as Weight))
+			.saturating_add(RocksDbWeight::get().writes(1 as Weight))
+	}
+	fn set_desired_collators() -> Weight {
+		(10_000 as Weight)
+			.saturating_add(RocksDbWeight::get().writes(1 as Weight))
+	}
+	fn set_candidacy_bond() -> Weight {
+		(10_000 as Weight)
+			.saturating_add(RocksDbWeight::get().writes(1 as Weight))
+	}
+	fn register_as_candidate() -> Weight {
+		(50_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(6 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(3 as Weight))
+	}
+	fn leave_candidates() -> Weight {
+		(50_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(3 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(4 as Weight))
+	}
+	fn set_commission() -> Weight {
+		(10_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(1 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(1 as Weight))
+	}
+	fn delegate() -> Weight {
+		(50_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(5 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(5 as Weight))
+	}
+	fn undelegate() -> Weight {
+		(50_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(6 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(6 as Weight))
+	}
+	fn claim_rewards() -> Weight {
+		(50_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(5 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(4 as Weight))
+	}
+	fn withdraw_unbonded(u: u32, ) -> Weight {
+		(30_000 as Weight)
+			.saturating_add((1_000 as Weight).saturating_mul(u as Weight))
+			.saturating_add(RocksDbWeight::get().reads(2 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(2 as Weight))
+	}
+	fn note_author() -> Weight {
+		(30_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(5 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(5 as Weight))
+	}
+	fn new_session(c: u32, ) -> Weight {
+		(50_000 as Weight)
+			.saturating_add((10_000 as Weight).saturating_mul(c as Weight))
+			.saturating_add(RocksDbWeight::get().reads(5 as Weight))
+			.saturating_add(RocksDbWeight::get().reads((2 as Weight).saturating_mul(c as Weight)))
+			.saturating_add(RocksDbWeight::get().writes(1 as Weight))
+			.saturating_add(RocksDbWeight::get().writes((1 as Weight).saturating_mul(c as Weight)))
+	}
+}
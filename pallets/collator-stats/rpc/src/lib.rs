@@ -0,0 +1,123 @@
+//! RPC interface for collator performance statistics.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use codec::Codec;
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+use sp_staking::SessionIndex;
+
+pub use pallet_collator_stats_runtime_api::{
+	CollatorPerformance, CollatorStatsApi as CollatorStatsRuntimeApi, KickCandidate,
+};
+
+#[rpc]
+pub trait CollatorStatsApi<BlockHash, AccountId, BlockNumber> {
+	/// The statistics of every collator in `session`, or in the current session if omitted.
+	#[rpc(name = "collatorStats_sessionStats")]
+	fn session_stats(
+		&self,
+		session: Option<SessionIndex>,
+		at: Option<BlockHash>,
+	) -> Result<Vec<(AccountId, CollatorPerformance)>>;
+
+	/// The retained per-session statistics of a collator.
+	#[rpc(name = "collatorStats_collatorStats")]
+	fn collator_stats(
+		&self,
+		who: AccountId,
+		at: Option<BlockHash>,
+	) -> Result<Vec<(SessionIndex, CollatorPerformance)>>;
+
+	/// The selected candidates and the block from which they get kicked if they stay idle.
+	#[rpc(name = "collatorStats_kickCandidates")]
+	fn kick_candidates(
+		&self,
+		at: Option<BlockHash>,
+	) -> Result<Vec<KickCandidate<AccountId, BlockNumber>>>;
+}
+
+/// Provides RPC methods to query collator performance.
+pub struct CollatorStats<C, B> {
+	/// Shared reference to the client.
+	client: Arc<C>,
+	_marker: PhantomData<B>,
+}
+
+impl<C, B> CollatorStats<C, B> {
+	/// Creates a new instance of the CollatorStats Rpc helper.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client, _marker: Default::default() }
+	}
+}
+
+/// Error type of this RPC api.
+pub enum Error {
+	/// The call to runtime failed.
+	RuntimeError,
+}
+
+impl From<Error> for i64 {
+	fn from(e: Error) -> i64 {
+		match e {
+			Error::RuntimeError => 1,
+		}
+	}
+}
+
+fn runtime_error(e: impl std::fmt::Debug) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(Error::RuntimeError.into()),
+		message: "Unable to query collator statistics.".into(),
+		data: Some(format!("{:?}", e).into()),
+	}
+}
+
+impl<C, Block, AccountId, BlockNumber>
+	CollatorStatsApi<<Block as BlockT>::Hash, AccountId, BlockNumber> for CollatorStats<C, Block>
+where
+	Block: BlockT,
+	C: 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+	C::Api: CollatorStatsRuntimeApi<Block, AccountId, BlockNumber>,
+	AccountId: Codec,
+	BlockNumber: Codec,
+{
+	fn session_stats(
+		&self,
+		session: Option<SessionIndex>,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Vec<(AccountId, CollatorPerformance)>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		let session = match session {
+			Some(session) => session,
+			None => api.current_session(&at).map_err(runtime_error)?,
+		};
+		api.session_stats(&at, session).map_err(runtime_error)
+	}
+
+	fn collator_stats(
+		&self,
+		who: AccountId,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Vec<(SessionIndex, CollatorPerformance)>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		api.collator_stats(&at, who).map_err(runtime_error)
+	}
+
+	fn kick_candidates(
+		&self,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Vec<KickCandidate<AccountId, BlockNumber>>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		api.kick_candidates(&at).map_err(runtime_error)
+	}
+}
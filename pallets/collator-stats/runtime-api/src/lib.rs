@@ -0,0 +1,29 @@
+//! Runtime API definition for collator performance statistics.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+use sp_staking::SessionIndex;
+use sp_std::prelude::*;
+
+pub use pallet_collator_stats::{CollatorPerformance, KickCandidate};
+
+sp_api::decl_runtime_apis! {
+	/// Query how collators performed and who is about to lose its candidacy.
+	pub trait CollatorStatsApi<AccountId, BlockNumber> where
+		AccountId: Codec,
+		BlockNumber: Codec,
+	{
+		/// The current session index.
+		fn current_session() -> SessionIndex;
+
+		/// The statistics of every collator in `session`, empty if it is no longer retained.
+		fn session_stats(session: SessionIndex) -> Vec<(AccountId, CollatorPerformance)>;
+
+		/// The retained statistics of `who`, oldest session first.
+		fn collator_stats(who: AccountId) -> Vec<(SessionIndex, CollatorPerformance)>;
+
+		/// The selected candidates and when they are removed unless they author a block.
+		fn kick_candidates() -> Vec<KickCandidate<AccountId, BlockNumber>>;
+	}
+}
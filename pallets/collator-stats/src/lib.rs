@@ -0,0 +1,181 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Collator performance statistics.
+//!
+//! Counts the blocks every collator authored and the Aura slots it missed, per session. Authored
+//! blocks are reported through `pallet_authorship::EventHandler`. Missed slots are derived at the
+//! end of every block from the gap between the current and the previous block's slot: Aura
+//! assigns slot `s` to the `s % n`-th of the `n` session validators, so every skipped slot is
+//! charged to that validator.
+//!
+//! Statistics of the last `HistoryDepth` sessions are retained, older ones are pruned when a new
+//! session starts.
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+use codec::{Decode, Encode, MaxEncodedLen};
+use scale_info::TypeInfo;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_runtime::RuntimeDebug;
+
+/// The performance of a collator within one session.
+#[derive(
+	Clone, Copy, Eq, PartialEq, Encode, Decode, Default, RuntimeDebug, TypeInfo, MaxEncodedLen,
+)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct CollatorPerformance {
+	/// Blocks authored by the collator.
+	pub authored: u32,
+	/// Slots assigned to the collator for which no block was produced.
+	pub missed: u32,
+}
+
+/// A collator that will be removed from the candidate set if it keeps not authoring.
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct KickCandidate<AccountId, BlockNumber> {
+	/// The collator.
+	pub who: AccountId,
+	/// The last block it authored, or the end of its grace period if it has not authored yet.
+	pub last_authored: BlockNumber,
+	/// The first session rotation at or after this block removes the collator.
+	pub kick_at: BlockNumber,
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::CollatorPerformance;
+	use frame_support::{pallet_prelude::*, traits::ValidatorSet};
+	use frame_system::pallet_prelude::*;
+	use sp_staking::SessionIndex;
+	use sp_std::prelude::*;
+
+	/// Configure the pallet by specifying the parameters and types on which it depends.
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// The current session and its validators, in the order Aura assigns slots to them.
+		type ValidatorSet: ValidatorSet<Self::AccountId, ValidatorId = Self::AccountId>;
+
+		/// The Aura slot of the current block.
+		type CurrentSlot: Get<u64>;
+
+		/// Number of sessions statistics are kept for.
+		#[pallet::constant]
+		type HistoryDepth: Get<SessionIndex>;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	/// Performance of every collator, per session.
+	#[pallet::storage]
+	#[pallet::getter(fn session_stats)]
+	pub type SessionStats<T: Config> = StorageDoubleMap<
+		_,
+		Twox64Concat,
+		SessionIndex,
+		Twox64Concat,
+		T::AccountId,
+		CollatorPerformance,
+		ValueQuery,
+	>;
+
+	/// The session the last block was produced in.
+	#[pallet::storage]
+	#[pallet::getter(fn current_session)]
+	pub type CurrentSession<T> = StorageValue<_, SessionIndex, ValueQuery>;
+
+	/// The slot of the last block, zero before the first block was tracked.
+	#[pallet::storage]
+	#[pallet::getter(fn last_slot)]
+	pub type LastSlot<T> = StorageValue<_, u64, ValueQuery>;
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_initialize(_n: T::BlockNumber) -> Weight {
+			// Account for `on_finalize`, which may touch every validator and prune a session.
+			let validators = T::ValidatorSet::validators().len() as Weight;
+			T::DbWeight::get().reads_writes(5 + validators, 3 + 2 * validators)
+		}
+
+		fn on_finalize(_n: T::BlockNumber) {
+			let session = T::ValidatorSet::session_index();
+			if session != Self::current_session() {
+				Self::start_session(session);
+			}
+
+			let slot = T::CurrentSlot::get();
+			let last = Self::last_slot();
+			if last != 0 && slot > last.saturating_add(1) {
+				Self::note_missed_slots(session, last + 1, slot);
+			}
+			<LastSlot<T>>::put(slot);
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// Remember `session` as current and prune the session that fell out of the history.
+		fn start_session(session: SessionIndex) {
+			<CurrentSession<T>>::put(session);
+			if let Some(expired) = session.checked_sub(T::HistoryDepth::get()) {
+				<SessionStats<T>>::remove_prefix(expired, None);
+			}
+		}
+
+		/// Charge every slot in `first..end` to the validator Aura assigned it to.
+		fn note_missed_slots(session: SessionIndex, first: u64, end: u64) {
+			let validators = T::ValidatorSet::validators();
+			let count = validators.len() as u64;
+			if count == 0 {
+				return
+			}
+
+			let gap = end - first;
+			// Every validator owns one slot out of `count`, so after the first `count` slots
+			// only the number of repetitions changes.
+			for offset in 0..gap.min(count) {
+				let slot = first + offset;
+				let missed = (gap - offset + count - 1) / count;
+				let who = &validators[(slot % count) as usize];
+				<SessionStats<T>>::mutate(session, who, |stats| {
+					stats.missed = stats.missed.saturating_add(missed as u32)
+				});
+			}
+		}
+
+		/// The statistics of every collator in `session`.
+		pub fn stats_of_session(session: SessionIndex) -> Vec<(T::AccountId, CollatorPerformance)> {
+			<SessionStats<T>>::iter_prefix(session).collect()
+		}
+
+		/// The retained statistics of `who`, oldest session first.
+		pub fn stats_of_collator(who: &T::AccountId) -> Vec<(SessionIndex, CollatorPerformance)> {
+			let current = Self::current_session();
+			let first = current.saturating_sub(T::HistoryDepth::get().saturating_sub(1));
+			(first..=current)
+				.filter(|session| <SessionStats<T>>::contains_key(session, who))
+				.map(|session| (session, Self::session_stats(session, who)))
+				.collect()
+		}
+	}
+
+	impl<T: Config> pallet_authorship::EventHandler<T::AccountId, T::BlockNumber> for Pallet<T> {
+		fn note_author(author: T::AccountId) {
+			let session = T::ValidatorSet::session_index();
+			<SessionStats<T>>::mutate(session, author, |stats| {
+				stats.authored = stats.authored.saturating_add(1)
+			});
+		}
+
+		fn note_uncle(_author: T::AccountId, _age: T::BlockNumber) {}
+	}
+}
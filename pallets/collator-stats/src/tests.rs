@@ -0,0 +1,79 @@
+use crate::{mock::*, CollatorPerformance};
+use frame_support::traits::Hooks;
+use pallet_authorship::EventHandler;
+
+/// Finalize a block at `slot`, authored by `author` if any.
+fn produce(slot: u64, author: Option<u64>) {
+	CurrentSlot::set(slot);
+	if let Some(author) = author {
+		CollatorStats::note_author(author);
+	}
+	CollatorStats::on_finalize(0);
+}
+
+fn stats(authored: u32, missed: u32) -> CollatorPerformance {
+	CollatorPerformance { authored, missed }
+}
+
+#[test]
+fn counts_authored_blocks() {
+	new_test_ext().execute_with(|| {
+		produce(10, Some(2));
+		produce(11, Some(3));
+		produce(13, Some(2));
+
+		assert_eq!(CollatorStats::session_stats(0, 2), stats(2, 0));
+		assert_eq!(CollatorStats::session_stats(0, 3), stats(1, 0));
+	});
+}
+
+#[test]
+fn charges_skipped_slots_to_their_owner() {
+	new_test_ext().execute_with(|| {
+		produce(10, Some(2));
+		// Slot 11 belongs to validator index 2, i.e. account 3.
+		produce(12, Some(1));
+
+		assert_eq!(CollatorStats::session_stats(0, 3), stats(0, 1));
+		assert_eq!(CollatorStats::session_stats(0, 1), stats(1, 0));
+	});
+}
+
+#[test]
+fn long_gaps_are_spread_over_validators() {
+	new_test_ext().execute_with(|| {
+		produce(9, Some(1));
+		// Slots 10..=17 are missed: 10, 13, 16 -> 2; 11, 14, 17 -> 3; 12, 15 -> 1.
+		produce(18, Some(1));
+
+		assert_eq!(CollatorStats::session_stats(0, 1), stats(2, 2));
+		assert_eq!(CollatorStats::session_stats(0, 2), stats(0, 3));
+		assert_eq!(CollatorStats::session_stats(0, 3), stats(0, 3));
+	});
+}
+
+#[test]
+fn first_tracked_block_misses_nothing() {
+	new_test_ext().execute_with(|| {
+		produce(1_000, Some(2));
+
+		assert_eq!(CollatorStats::stats_of_session(0), vec![(2, stats(1, 0))]);
+		assert_eq!(CollatorStats::last_slot(), 1_000);
+	});
+}
+
+#[test]
+fn history_is_pruned() {
+	new_test_ext().execute_with(|| {
+		produce(1, Some(2));
+		Session::set(1);
+		produce(2, Some(2));
+		assert_eq!(CollatorStats::stats_of_collator(&2), vec![(0, stats(1, 0)), (1, stats(1, 0))]);
+
+		Session::set(2);
+		produce(3, Some(2));
+		assert_eq!(CollatorStats::current_session(), 2);
+		assert_eq!(CollatorStats::stats_of_collator(&2), vec![(1, stats(1, 0)), (2, stats(1, 0))]);
+		assert!(CollatorStats::stats_of_session(0).is_empty());
+	});
+}
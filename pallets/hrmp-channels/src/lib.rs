@@ -0,0 +1,542 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! HRMP channel management.
+//!
+//! Channels between parachains are opened and closed by the relay chain's `Hrmp` pallet, on
+//! request of the parachains themselves. This pallet sends those requests as `Transact` messages
+//! to the relay chain, dispatched with the parachain origin and paid for by our sovereign account
+//! there, and follows the channels through their life:
+//!
+//! - governance (`UpdateOrigin`) requests channels to siblings, accepts their requests and closes
+//!   channels, or cancels them while they are only requested. The relay chain reports whether it
+//!   executed each request through `Outcomes`, which hands the outcome back to the pallet as its
+//!   `OnOutcome` handler: a request which failed or timed out leaves its channel as it was before;
+//! - the relay chain's notifications of new requests, accepted requests and closing channels are
+//!   intercepted by [`HrmpNotifications`], which wraps the executor of downward messages. Requests
+//!   of siblings in `AutoAccept` are accepted right away, and channels closing before they opened
+//!   are forgotten;
+//! - the channels the relay chain state proof shows open are synced into `Channels` with every
+//!   validation data, as the pallet is the `OnSystemEvent` handler of `ParachainSystem`.
+//!
+//! A `Transact` succeeds even when the relay chain rejects the call it dispatches, so governance
+//! can still drop stale channels with `remove_channel`.
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+use codec::{Decode, Encode};
+use core::marker::PhantomData;
+use cumulus_primitives_core::{ParaId, PersistedValidationData};
+use frame_support::{traits::Get, weights::Weight};
+use scale_info::TypeInfo;
+use sp_runtime::RuntimeDebug;
+use sp_std::prelude::*;
+use xcm::latest::prelude::*;
+
+/// The direction of a channel, seen from this parachain.
+#[derive(Encode, Decode, Clone, Copy, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+pub enum ChannelDirection {
+	/// From a sibling to us.
+	Ingress,
+	/// From us to a sibling.
+	Egress,
+}
+
+/// The state of a channel.
+#[derive(Encode, Decode, Clone, Copy, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+pub enum ChannelStatus {
+	/// Its sender requested it.
+	Requested,
+	/// Its recipient accepted it. It opens at the next session of the relay chain.
+	Accepted,
+	/// The relay chain state shows it open.
+	Open,
+}
+
+/// Calls of the relay chain's `Hrmp` pallet, encoded as the relay chain does.
+#[derive(Encode, Decode, Clone, Eq, PartialEq, RuntimeDebug)]
+pub enum HrmpCall {
+	#[codec(index = 0)]
+	InitOpenChannel {
+		recipient: ParaId,
+		proposed_max_capacity: u32,
+		proposed_max_message_size: u32,
+	},
+	#[codec(index = 1)]
+	AcceptOpenChannel { sender: ParaId },
+	#[codec(index = 2)]
+	CloseChannel { sender: ParaId, recipient: ParaId },
+	#[codec(index = 6)]
+	CancelOpenRequest { sender: ParaId, recipient: ParaId, open_requests: u32 },
+}
+
+/// A request sent to the relay chain, whose outcome decides the state of its channel.
+#[derive(Encode, Decode, Clone, Copy, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+pub enum ChannelRequest {
+	/// We requested a channel to the sibling.
+	Open(ParaId),
+	/// We accepted the channel request of the sibling.
+	Accept(ParaId),
+	/// We cancelled the request of the channel with the sibling in the direction.
+	Cancel(ChannelDirection, ParaId),
+}
+
+/// The HRMP channels of this parachain, as last proven by the relay chain state.
+pub trait ChannelsState {
+	/// The senders of the channels to us.
+	fn ingress() -> Vec<ParaId>;
+	/// The recipients of the channels from us.
+	fn egress() -> Vec<ParaId>;
+}
+
+impl<T: cumulus_pallet_parachain_system::Config> ChannelsState
+	for cumulus_pallet_parachain_system::Pallet<T>
+{
+	fn ingress() -> Vec<ParaId> {
+		Self::relevant_messaging_state()
+			.map(|state| state.ingress_channels.into_iter().map(|(sender, _)| sender).collect())
+			.unwrap_or_default()
+	}
+
+	fn egress() -> Vec<ParaId> {
+		Self::relevant_messaging_state()
+			.map(|state| {
+				state.egress_channels.into_iter().map(|(recipient, _)| recipient).collect()
+			})
+			.unwrap_or_default()
+	}
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::{ChannelDirection, ChannelRequest, ChannelStatus, ChannelsState, HrmpCall};
+	use cumulus_primitives_core::ParaId;
+	use frame_support::{dispatch::DispatchResultWithPostInfo, pallet_prelude::*, transactional};
+	use frame_system::pallet_prelude::*;
+	use pallet_xcm_outcomes::{OnOutcome, OutcomeStatus, ReportOutcome};
+	use sp_std::prelude::*;
+	use xcm::latest::prelude::*;
+
+	/// Configure the pallet by specifying the parameters and types on which it depends.
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// Because this pallet emits events, it depends on the runtime's definition of an event.
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// Origin allowed to manage channels.
+		type UpdateOrigin: EnsureOrigin<Self::Origin>;
+
+		/// Our parachain id.
+		type SelfParaId: Get<ParaId>;
+
+		/// Sends the requests to the relay chain.
+		type XcmSender: SendXcm;
+
+		/// The channels the relay chain state proof shows open.
+		type ChannelsState: ChannelsState;
+
+		/// Index of the `Hrmp` pallet in the relay chain runtime.
+		#[pallet::constant]
+		type HrmpPalletIndex: Get<u8>;
+
+		/// Relay chain tokens withdrawn from our sovereign account to pay for each request. The
+		/// surplus is refunded.
+		#[pallet::constant]
+		type RelayFee: Get<u128>;
+
+		/// Weight of the `Hrmp` calls on the relay chain.
+		#[pallet::constant]
+		type RelayCallWeight: Get<Weight>;
+
+		/// Upper bound of the channel requests pending on the relay chain, the witness of the
+		/// cancellation of a request.
+		#[pallet::constant]
+		type MaxOpenRequests: Get<u32>;
+
+		/// Tracks whether the relay chain executed the requests.
+		type Outcomes: ReportOutcome;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	/// The channels with siblings, requested or open.
+	#[pallet::storage]
+	#[pallet::getter(fn channels)]
+	pub type Channels<T> = StorageDoubleMap<
+		_,
+		Twox64Concat,
+		ChannelDirection,
+		Twox64Concat,
+		ParaId,
+		ChannelStatus,
+		OptionQuery,
+	>;
+
+	/// The requests changing the state of a channel whose outcome is not known yet, by query id.
+	#[pallet::storage]
+	#[pallet::getter(fn pending_requests)]
+	pub type PendingRequests<T> = StorageMap<_, Twox64Concat, QueryId, ChannelRequest, OptionQuery>;
+
+	/// The siblings whose channel requests are accepted without governance.
+	#[pallet::storage]
+	#[pallet::getter(fn auto_accept)]
+	pub type AutoAccept<T> = StorageMap<_, Twox64Concat, ParaId, (), OptionQuery>;
+
+	// Pallets use events to inform users when important changes are made.
+	// https://docs.substrate.io/v3/runtime/events-and-errors
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// A channel to a sibling was requested. [recipient]
+		OpenChannelRequested(ParaId),
+		/// A sibling requested a channel to us. [sender, max_message_size, max_capacity]
+		OpenRequestReceived(ParaId, u32, u32),
+		/// We accepted the channel request of a sibling. [sender]
+		OpenRequestAccepted(ParaId),
+		/// A sibling accepted our channel request. [recipient]
+		OpenChannelAccepted(ParaId),
+		/// The closing of a channel was requested. [sender, recipient]
+		CloseChannelRequested(ParaId, ParaId),
+		/// The cancellation of a channel request was requested. [sender, recipient]
+		CancelOpenRequestRequested(ParaId, ParaId),
+		/// The relay chain did not execute a request, the channel is as it was before it.
+		/// [query_id, request]
+		RequestFailed(QueryId, ChannelRequest),
+		/// The relay chain closes a channel at its next session. [initiator, sender, recipient]
+		ChannelClosing(ParaId, ParaId, ParaId),
+		/// The relay chain state shows a channel open. [direction, sibling]
+		ChannelOpened(ChannelDirection, ParaId),
+		/// The relay chain state no longer shows a channel, or it was dropped before it opened.
+		/// [direction, sibling]
+		ChannelClosed(ChannelDirection, ParaId),
+		/// Whether the requests of a sibling are accepted automatically changed. [sibling, enabled]
+		AutoAcceptSet(ParaId, bool),
+	}
+
+	// Errors inform users that something went wrong.
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The request could not be sent to the relay chain.
+		SendFailure,
+		/// The outcome of the request could not be tracked.
+		CannotTrackOutcome,
+		/// The channel was already requested or is open.
+		ChannelExists,
+		/// The sibling did not request a channel to us.
+		NoOpenRequest,
+		/// The channel is not known.
+		UnknownChannel,
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Request a channel to `recipient` from the relay chain.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1, 2))]
+		pub fn open_channel(
+			origin: OriginFor<T>,
+			recipient: ParaId,
+			max_capacity: u32,
+			max_message_size: u32,
+		) -> DispatchResultWithPostInfo {
+			T::UpdateOrigin::ensure_origin(origin)?;
+			ensure!(
+				!<Channels<T>>::contains_key(ChannelDirection::Egress, recipient),
+				Error::<T>::ChannelExists
+			);
+
+			let query_id = Self::send_to_relay(HrmpCall::InitOpenChannel {
+				recipient,
+				proposed_max_capacity: max_capacity,
+				proposed_max_message_size: max_message_size,
+			})?;
+			<PendingRequests<T>>::insert(query_id, ChannelRequest::Open(recipient));
+			<Channels<T>>::insert(ChannelDirection::Egress, recipient, ChannelStatus::Requested);
+			Self::deposit_event(Event::OpenChannelRequested(recipient));
+			Ok(().into())
+		}
+
+		/// Accept the channel `sender` requested to us.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1, 2))]
+		pub fn accept_channel(origin: OriginFor<T>, sender: ParaId) -> DispatchResultWithPostInfo {
+			T::UpdateOrigin::ensure_origin(origin)?;
+			ensure!(
+				Self::channels(ChannelDirection::Ingress, sender) == Some(ChannelStatus::Requested),
+				Error::<T>::NoOpenRequest
+			);
+
+			Self::accept(sender)?;
+			Ok(().into())
+		}
+
+		/// Close the channel with `sibling` in `direction`, or cancel its request if it was only
+		/// requested.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1, 1))]
+		pub fn close_channel(
+			origin: OriginFor<T>,
+			direction: ChannelDirection,
+			sibling: ParaId,
+		) -> DispatchResultWithPostInfo {
+			T::UpdateOrigin::ensure_origin(origin)?;
+			let status = Self::channels(direction, sibling).ok_or(Error::<T>::UnknownChannel)?;
+
+			let (sender, recipient) = match direction {
+				ChannelDirection::Ingress => (sibling, T::SelfParaId::get()),
+				ChannelDirection::Egress => (T::SelfParaId::get(), sibling),
+			};
+			if status == ChannelStatus::Requested {
+				let query_id = Self::send_to_relay(HrmpCall::CancelOpenRequest {
+					sender,
+					recipient,
+					open_requests: T::MaxOpenRequests::get(),
+				})?;
+				<PendingRequests<T>>::insert(query_id, ChannelRequest::Cancel(direction, sibling));
+				Self::deposit_event(Event::CancelOpenRequestRequested(sender, recipient));
+			} else {
+				Self::send_to_relay(HrmpCall::CloseChannel { sender, recipient })?;
+				Self::deposit_event(Event::CloseChannelRequested(sender, recipient));
+			}
+			Ok(().into())
+		}
+
+		/// Forget the channel with `sibling` in `direction`, left behind by a request the relay
+		/// chain rejected. Channels still open come back with the next validation data.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1, 1))]
+		pub fn remove_channel(
+			origin: OriginFor<T>,
+			direction: ChannelDirection,
+			sibling: ParaId,
+		) -> DispatchResultWithPostInfo {
+			T::UpdateOrigin::ensure_origin(origin)?;
+			ensure!(<Channels<T>>::contains_key(direction, sibling), Error::<T>::UnknownChannel);
+
+			<Channels<T>>::remove(direction, sibling);
+			Self::deposit_event(Event::ChannelClosed(direction, sibling));
+			Ok(().into())
+		}
+
+		/// Set whether the channel requests of `sibling` are accepted automatically.
+		#[pallet::weight(10_000 + T::DbWeight::get().writes(1))]
+		pub fn set_auto_accept(
+			origin: OriginFor<T>,
+			sibling: ParaId,
+			enabled: bool,
+		) -> DispatchResultWithPostInfo {
+			T::UpdateOrigin::ensure_origin(origin)?;
+
+			if enabled {
+				<AutoAccept<T>>::insert(sibling, ());
+			} else {
+				<AutoAccept<T>>::remove(sibling);
+			}
+			Self::deposit_event(Event::AutoAcceptSet(sibling, enabled));
+			Ok(().into())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// Send `call` to the relay chain, to be dispatched with our parachain origin. Returns the
+		/// query its outcome is tracked under.
+		#[transactional]
+		pub(crate) fn send_to_relay(call: HrmpCall) -> Result<QueryId, Error<T>> {
+			let (query_id, report) = T::Outcomes::report_outcome(Parent.into())
+				.map_err(|_| Error::<T>::CannotTrackOutcome)?;
+			let fees: MultiAsset = (Here, T::RelayFee::get()).into();
+			let message = Xcm(vec![
+				WithdrawAsset(fees.clone().into()),
+				BuyExecution { fees, weight_limit: Unlimited },
+				SetAppendix(report),
+				Transact {
+					origin_type: OriginKind::Native,
+					require_weight_at_most: T::RelayCallWeight::get(),
+					call: (T::HrmpPalletIndex::get(), call).encode().into(),
+				},
+				RefundSurplus,
+				DepositAsset {
+					assets: Wild(All),
+					max_assets: 1,
+					beneficiary: Parachain(T::SelfParaId::get().into()).into(),
+				},
+			]);
+			T::XcmSender::send_xcm(Parent, message).map_err(|_| Error::<T>::SendFailure)?;
+			Ok(query_id)
+		}
+
+		/// Accept the channel request of `sender`.
+		pub(crate) fn accept(sender: ParaId) -> Result<(), Error<T>> {
+			let query_id = Self::send_to_relay(HrmpCall::AcceptOpenChannel { sender })?;
+			<PendingRequests<T>>::insert(query_id, ChannelRequest::Accept(sender));
+			<Channels<T>>::insert(ChannelDirection::Ingress, sender, ChannelStatus::Accepted);
+			Self::deposit_event(Event::OpenRequestAccepted(sender));
+			Ok(())
+		}
+
+		/// Whether `instruction` is a notification of the relay chain about HRMP channels.
+		pub(crate) fn is_notification<Call>(instruction: &Instruction<Call>) -> bool {
+			matches!(
+				instruction,
+				HrmpNewChannelOpenRequest { .. } |
+					HrmpChannelAccepted { .. } |
+					HrmpChannelClosing { .. }
+			)
+		}
+
+		/// Handle a notification of the relay chain about HRMP channels.
+		pub(crate) fn handle_notification<Call>(instruction: &Instruction<Call>) {
+			match *instruction {
+				HrmpNewChannelOpenRequest { sender, max_message_size, max_capacity } => {
+					let sender = ParaId::from(sender);
+					<Channels<T>>::insert(
+						ChannelDirection::Ingress,
+						sender,
+						ChannelStatus::Requested,
+					);
+					Self::deposit_event(Event::OpenRequestReceived(
+						sender,
+						max_message_size,
+						max_capacity,
+					));
+					if <AutoAccept<T>>::contains_key(sender) {
+						// Governance can still accept the request if sending fails.
+						let _ = Self::accept(sender);
+					}
+				},
+				HrmpChannelAccepted { recipient } => {
+					let recipient = ParaId::from(recipient);
+					<Channels<T>>::insert(
+						ChannelDirection::Egress,
+						recipient,
+						ChannelStatus::Accepted,
+					);
+					Self::deposit_event(Event::OpenChannelAccepted(recipient));
+				},
+				HrmpChannelClosing { initiator, sender, recipient } => {
+					let (sender, recipient) = (ParaId::from(sender), ParaId::from(recipient));
+					Self::deposit_event(Event::ChannelClosing(initiator.into(), sender, recipient));
+					let (direction, sibling) = if sender == T::SelfParaId::get() {
+						(ChannelDirection::Egress, recipient)
+					} else {
+						(ChannelDirection::Ingress, sender)
+					};
+					// Open channels close with the next validation data.
+					if matches!(
+						Self::channels(direction, sibling),
+						Some(ChannelStatus::Requested | ChannelStatus::Accepted)
+					) {
+						Self::forget(direction, sibling);
+					}
+				},
+				_ => {},
+			}
+		}
+
+		/// Forget the channel with `sibling` in `direction`.
+		fn forget(direction: ChannelDirection, sibling: ParaId) {
+			<Channels<T>>::remove(direction, sibling);
+			Self::deposit_event(Event::ChannelClosed(direction, sibling));
+		}
+
+		/// Record the channels of `direction` the relay chain state shows `open`.
+		pub(crate) fn sync_channels(direction: ChannelDirection, open: Vec<ParaId>) {
+			for sibling in &open {
+				if Self::channels(direction, sibling) != Some(ChannelStatus::Open) {
+					<Channels<T>>::insert(direction, sibling, ChannelStatus::Open);
+					Self::deposit_event(Event::ChannelOpened(direction, *sibling));
+				}
+			}
+
+			let closed: Vec<ParaId> = <Channels<T>>::iter_prefix(direction)
+				.filter(|(sibling, status)| {
+					*status == ChannelStatus::Open && !open.contains(sibling)
+				})
+				.map(|(sibling, _)| sibling)
+				.collect();
+			for sibling in closed {
+				Self::forget(direction, sibling);
+			}
+		}
+	}
+
+	impl<T: Config> OnOutcome for Pallet<T> {
+		fn on_outcome(query_id: QueryId, status: &OutcomeStatus) {
+			let request = match <PendingRequests<T>>::take(query_id) {
+				Some(request) => request,
+				None => return,
+			};
+			let succeeded = *status == OutcomeStatus::Succeeded;
+			match request {
+				ChannelRequest::Open(recipient) if !succeeded => {
+					if Self::channels(ChannelDirection::Egress, recipient) ==
+						Some(ChannelStatus::Requested)
+					{
+						Self::forget(ChannelDirection::Egress, recipient);
+					}
+				},
+				ChannelRequest::Accept(sender) if !succeeded => {
+					if Self::channels(ChannelDirection::Ingress, sender) ==
+						Some(ChannelStatus::Accepted)
+					{
+						<Channels<T>>::insert(
+							ChannelDirection::Ingress,
+							sender,
+							ChannelStatus::Requested,
+						);
+					}
+				},
+				ChannelRequest::Cancel(direction, sibling) if succeeded => {
+					if Self::channels(direction, sibling) == Some(ChannelStatus::Requested) {
+						Self::forget(direction, sibling);
+					}
+				},
+				_ => {},
+			}
+			if !succeeded {
+				Self::deposit_event(Event::RequestFailed(query_id, request));
+			}
+		}
+	}
+}
+
+impl<T: Config> cumulus_pallet_parachain_system::OnSystemEvent for Pallet<T> {
+	fn on_validation_data(_: &PersistedValidationData) {
+		Self::sync_channels(ChannelDirection::Ingress, T::ChannelsState::ingress());
+		Self::sync_channels(ChannelDirection::Egress, T::ChannelsState::egress());
+	}
+
+	fn on_validation_code_applied() {}
+}
+
+/// Executes downward messages with `Executor`, except for the HRMP notifications of the relay
+/// chain, which `Pallet` handles.
+pub struct HrmpNotifications<T, Executor>(PhantomData<(T, Executor)>);
+
+impl<T: Config, Call, Executor: ExecuteXcm<Call>> ExecuteXcm<Call>
+	for HrmpNotifications<T, Executor>
+{
+	fn execute_xcm_in_credit(
+		origin: impl Into<MultiLocation>,
+		message: Xcm<Call>,
+		weight_limit: Weight,
+		weight_credit: Weight,
+	) -> Outcome {
+		let origin = origin.into();
+		match (&origin, &message.0[..]) {
+			(MultiLocation { parents: 1, interior: Here }, [instruction])
+				if Pallet::<T>::is_notification(instruction) =>
+			{
+				let weight = T::DbWeight::get().reads_writes(2, 1);
+				if weight > weight_limit {
+					return Outcome::Error(XcmError::WeightLimitReached(weight))
+				}
+				Pallet::<T>::handle_notification(instruction);
+				Outcome::Complete(weight)
+			},
+			_ => Executor::execute_xcm_in_credit(origin, message, weight_limit, weight_credit),
+		}
+	}
+}
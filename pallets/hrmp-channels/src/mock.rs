@@ -0,0 +1,151 @@
+use crate as pallet_hrmp_channels;
+use crate::ChannelsState;
+use cumulus_primitives_core::ParaId;
+use frame_support::{parameter_types, traits::Everything, weights::Weight};
+use frame_system as system;
+use frame_system::EnsureRoot;
+use pallet_xcm_outcomes::ReportOutcome;
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, IdentityLookup},
+};
+use std::cell::RefCell;
+use xcm::latest::prelude::*;
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+// Configure a mock runtime to test the pallet.
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+		HrmpChannels: pallet_hrmp_channels::{Pallet, Call, Storage, Event<T>},
+	}
+);
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const SS58Prefix: u8 = 42;
+}
+
+impl system::Config for Test {
+	type BaseCallFilter = Everything;
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = BlockHashCount;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = SS58Prefix;
+	type OnSetCode = ();
+	type MaxConsumers = frame_support::traits::ConstU32<16>;
+}
+
+thread_local! {
+	pub static SENT_XCM: RefCell<Vec<(MultiLocation, Xcm<()>)>> = RefCell::new(Vec::new());
+	pub static OPEN_CHANNELS: RefCell<(Vec<ParaId>, Vec<ParaId>)> = RefCell::new(Default::default());
+	pub static NEXT_QUERY_ID: RefCell<QueryId> = RefCell::new(0);
+}
+
+/// The messages sent so far.
+pub fn sent_xcm() -> Vec<(MultiLocation, Xcm<()>)> {
+	SENT_XCM.with(|q| q.borrow().clone())
+}
+
+/// Records the messages instead of sending them.
+pub struct TestSendXcm;
+impl SendXcm for TestSendXcm {
+	fn send_xcm(dest: impl Into<MultiLocation>, msg: Xcm<()>) -> SendResult {
+		SENT_XCM.with(|q| q.borrow_mut().push((dest.into(), msg)));
+		Ok(())
+	}
+}
+
+/// Make the relay chain state show these channels open.
+pub fn set_open_channels(ingress: Vec<u32>, egress: Vec<u32>) {
+	let ids = |ids: Vec<u32>| ids.into_iter().map(ParaId::from).collect();
+	OPEN_CHANNELS.with(|c| *c.borrow_mut() = (ids(ingress), ids(egress)));
+}
+
+pub struct TestChannelsState;
+impl ChannelsState for TestChannelsState {
+	fn ingress() -> Vec<ParaId> {
+		OPEN_CHANNELS.with(|c| c.borrow().0.clone())
+	}
+
+	fn egress() -> Vec<ParaId> {
+		OPEN_CHANNELS.with(|c| c.borrow().1.clone())
+	}
+}
+
+pub const SELF_PARA_ID: u32 = 1000;
+
+/// The instructions reporting the outcome of a request under `query_id`.
+pub fn outcome_report(query_id: QueryId) -> Xcm<()> {
+	Xcm(vec![ReportError {
+		query_id,
+		dest: Parachain(SELF_PARA_ID).into(),
+		max_response_weight: 0,
+	}])
+}
+
+/// Hands out query ids in sequence.
+pub struct TestOutcomes;
+impl ReportOutcome for TestOutcomes {
+	fn report_outcome(_responder: MultiLocation) -> Result<(QueryId, Xcm<()>), XcmError> {
+		let query_id = NEXT_QUERY_ID.with(|q| {
+			let query_id = *q.borrow();
+			*q.borrow_mut() += 1;
+			query_id
+		});
+		Ok((query_id, outcome_report(query_id)))
+	}
+}
+
+parameter_types! {
+	pub const SelfParaId: ParaId = ParaId::new(SELF_PARA_ID);
+	pub const HrmpPalletIndex: u8 = 60;
+	pub const RelayFee: u128 = 1_000;
+	pub const RelayCallWeight: Weight = 1_000_000;
+	pub const MaxOpenRequests: u32 = 10;
+}
+
+impl pallet_hrmp_channels::Config for Test {
+	type Event = Event;
+	type UpdateOrigin = EnsureRoot<u64>;
+	type SelfParaId = SelfParaId;
+	type XcmSender = TestSendXcm;
+	type ChannelsState = TestChannelsState;
+	type HrmpPalletIndex = HrmpPalletIndex;
+	type RelayFee = RelayFee;
+	type RelayCallWeight = RelayCallWeight;
+	type MaxOpenRequests = MaxOpenRequests;
+	type Outcomes = TestOutcomes;
+}
+
+// Build genesis storage according to the mock runtime.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	let mut ext = sp_io::TestExternalities::new(t);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
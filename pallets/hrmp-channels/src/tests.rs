@@ -0,0 +1,435 @@
+use crate::{
+	mock::*, ChannelDirection, ChannelRequest, ChannelStatus, Error, Event as HrmpChannelsEvent,
+	HrmpCall, HrmpNotifications,
+};
+use codec::Encode;
+use cumulus_pallet_parachain_system::OnSystemEvent;
+use cumulus_primitives_core::ParaId;
+use frame_support::{assert_noop, assert_ok, weights::Weight};
+use pallet_xcm_outcomes::{OnOutcome, OutcomeStatus};
+use sp_runtime::traits::BadOrigin;
+use xcm::latest::prelude::*;
+
+/// Executes nothing, to tell forwarded messages apart.
+struct NoExecutor;
+impl ExecuteXcm<()> for NoExecutor {
+	fn execute_xcm_in_credit(
+		_origin: impl Into<MultiLocation>,
+		_message: Xcm<()>,
+		_weight_limit: Weight,
+		_weight_credit: Weight,
+	) -> Outcome {
+		Outcome::Error(XcmError::Unimplemented)
+	}
+}
+
+fn notify(origin: MultiLocation, instruction: Instruction<()>) -> Outcome {
+	HrmpNotifications::<Test, NoExecutor>::execute_xcm(origin, Xcm(vec![instruction]), 1_000)
+}
+
+fn open_request(sender: u32) -> Instruction<()> {
+	HrmpNewChannelOpenRequest { sender, max_message_size: 1024, max_capacity: 8 }
+}
+
+/// The message asking the relay chain to dispatch `call` with our parachain origin, the first
+/// request of a test.
+fn relay_transact(call: HrmpCall) -> (MultiLocation, Xcm<()>) {
+	relay_request(0, call)
+}
+
+/// The message asking the relay chain to dispatch `call` with our parachain origin, tracked under
+/// `query_id`.
+fn relay_request(query_id: QueryId, call: HrmpCall) -> (MultiLocation, Xcm<()>) {
+	let fees: MultiAsset = (Here, RelayFee::get()).into();
+	let message = Xcm(vec![
+		WithdrawAsset(fees.clone().into()),
+		BuyExecution { fees, weight_limit: Unlimited },
+		SetAppendix(outcome_report(query_id)),
+		Transact {
+			origin_type: OriginKind::Native,
+			require_weight_at_most: RelayCallWeight::get(),
+			call: (60u8, call).encode().into(),
+		},
+		RefundSurplus,
+		DepositAsset {
+			assets: Wild(All),
+			max_assets: 1,
+			beneficiary: Parachain(SELF_PARA_ID).into(),
+		},
+	]);
+	(Parent.into(), message)
+}
+
+fn sync_channels() {
+	HrmpChannels::on_validation_data(&Default::default());
+}
+
+#[test]
+fn only_update_origin_manages_channels() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			HrmpChannels::open_channel(Origin::signed(1), 2000.into(), 8, 1024),
+			BadOrigin
+		);
+		assert_noop!(HrmpChannels::accept_channel(Origin::signed(1), 2000.into()), BadOrigin);
+		assert_noop!(
+			HrmpChannels::close_channel(Origin::signed(1), ChannelDirection::Egress, 2000.into()),
+			BadOrigin
+		);
+		assert_noop!(
+			HrmpChannels::set_auto_accept(Origin::signed(1), 2000.into(), true),
+			BadOrigin
+		);
+		assert_noop!(
+			HrmpChannels::remove_channel(Origin::signed(1), ChannelDirection::Egress, 2000.into()),
+			BadOrigin
+		);
+	});
+}
+
+#[test]
+fn hrmp_calls_are_encoded_as_the_relay_chain_does() {
+	let recipient = ParaId::from(2000);
+	assert_eq!(
+		HrmpCall::InitOpenChannel {
+			recipient,
+			proposed_max_capacity: 8,
+			proposed_max_message_size: 1024,
+		}
+		.encode(),
+		[vec![0], 2000u32.encode(), 8u32.encode(), 1024u32.encode()].concat()
+	);
+	assert_eq!(
+		HrmpCall::AcceptOpenChannel { sender: recipient }.encode(),
+		[vec![1], 2000u32.encode()].concat()
+	);
+	assert_eq!(
+		HrmpCall::CloseChannel { sender: 1000.into(), recipient }.encode(),
+		[vec![2], 1000u32.encode(), 2000u32.encode()].concat()
+	);
+	assert_eq!(
+		HrmpCall::CancelOpenRequest { sender: 1000.into(), recipient, open_requests: 10 }.encode(),
+		[vec![6], 1000u32.encode(), 2000u32.encode(), 10u32.encode()].concat()
+	);
+}
+
+#[test]
+fn opening_a_channel_sends_a_paid_transact_to_the_relay_chain() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(HrmpChannels::open_channel(Origin::root(), 2000.into(), 8, 1024));
+
+		assert_eq!(
+			sent_xcm(),
+			vec![relay_transact(HrmpCall::InitOpenChannel {
+				recipient: 2000.into(),
+				proposed_max_capacity: 8,
+				proposed_max_message_size: 1024,
+			})]
+		);
+		assert_eq!(
+			HrmpChannels::channels(ChannelDirection::Egress, ParaId::from(2000)),
+			Some(ChannelStatus::Requested)
+		);
+		System::assert_last_event(Event::HrmpChannels(HrmpChannelsEvent::OpenChannelRequested(
+			2000.into(),
+		)));
+		assert_noop!(
+			HrmpChannels::open_channel(Origin::root(), 2000.into(), 8, 1024),
+			Error::<Test>::ChannelExists
+		);
+
+		assert_eq!(
+			notify(Parent.into(), HrmpChannelAccepted { recipient: 2000 }),
+			Outcome::Complete(0)
+		);
+		assert_eq!(
+			HrmpChannels::channels(ChannelDirection::Egress, ParaId::from(2000)),
+			Some(ChannelStatus::Accepted)
+		);
+		System::assert_last_event(Event::HrmpChannels(HrmpChannelsEvent::OpenChannelAccepted(
+			2000.into(),
+		)));
+	});
+}
+
+#[test]
+fn requests_of_siblings_are_accepted_by_governance() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			HrmpChannels::accept_channel(Origin::root(), 2000.into()),
+			Error::<Test>::NoOpenRequest
+		);
+
+		assert_eq!(notify(Parent.into(), open_request(2000)), Outcome::Complete(0));
+		System::assert_last_event(Event::HrmpChannels(HrmpChannelsEvent::OpenRequestReceived(
+			2000.into(),
+			1024,
+			8,
+		)));
+		assert_eq!(
+			HrmpChannels::channels(ChannelDirection::Ingress, ParaId::from(2000)),
+			Some(ChannelStatus::Requested)
+		);
+		assert!(sent_xcm().is_empty());
+
+		assert_ok!(HrmpChannels::accept_channel(Origin::root(), 2000.into()));
+		assert_eq!(
+			sent_xcm(),
+			vec![relay_transact(HrmpCall::AcceptOpenChannel { sender: 2000.into() })]
+		);
+		assert_eq!(
+			HrmpChannels::channels(ChannelDirection::Ingress, ParaId::from(2000)),
+			Some(ChannelStatus::Accepted)
+		);
+		assert_noop!(
+			HrmpChannels::accept_channel(Origin::root(), 2000.into()),
+			Error::<Test>::NoOpenRequest
+		);
+	});
+}
+
+#[test]
+fn requests_of_allow_listed_siblings_are_accepted_automatically() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(HrmpChannels::set_auto_accept(Origin::root(), 2000.into(), true));
+		assert_eq!(HrmpChannels::auto_accept(ParaId::from(2000)), Some(()));
+
+		assert_eq!(notify(Parent.into(), open_request(2000)), Outcome::Complete(0));
+		assert_eq!(notify(Parent.into(), open_request(2001)), Outcome::Complete(0));
+
+		assert_eq!(
+			sent_xcm(),
+			vec![relay_transact(HrmpCall::AcceptOpenChannel { sender: 2000.into() })]
+		);
+		assert_eq!(
+			HrmpChannels::channels(ChannelDirection::Ingress, ParaId::from(2000)),
+			Some(ChannelStatus::Accepted)
+		);
+		assert_eq!(
+			HrmpChannels::channels(ChannelDirection::Ingress, ParaId::from(2001)),
+			Some(ChannelStatus::Requested)
+		);
+
+		assert_ok!(HrmpChannels::set_auto_accept(Origin::root(), 2000.into(), false));
+		assert_eq!(HrmpChannels::auto_accept(ParaId::from(2000)), None);
+	});
+}
+
+#[test]
+fn only_relay_chain_notifications_are_handled() {
+	new_test_ext().execute_with(|| {
+		let sibling = MultiLocation::new(1, X1(Parachain(2000)));
+		assert_eq!(notify(sibling, open_request(2000)), Outcome::Error(XcmError::Unimplemented));
+		assert_eq!(notify(Parent.into(), ClearOrigin), Outcome::Error(XcmError::Unimplemented));
+		assert_eq!(
+			HrmpNotifications::<Test, NoExecutor>::execute_xcm(
+				Parent,
+				Xcm(vec![open_request(2000), ClearOrigin]),
+				1_000,
+			),
+			Outcome::Error(XcmError::Unimplemented)
+		);
+		assert_eq!(HrmpChannels::channels(ChannelDirection::Ingress, ParaId::from(2000)), None);
+	});
+}
+
+#[test]
+fn channels_follow_the_relay_chain_state() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(HrmpChannels::open_channel(Origin::root(), 2000.into(), 8, 1024));
+		assert_ok!(HrmpChannels::open_channel(Origin::root(), 2001.into(), 8, 1024));
+
+		set_open_channels(vec![2002], vec![2000]);
+		sync_channels();
+		assert_eq!(
+			HrmpChannels::channels(ChannelDirection::Egress, ParaId::from(2000)),
+			Some(ChannelStatus::Open)
+		);
+		assert_eq!(
+			HrmpChannels::channels(ChannelDirection::Egress, ParaId::from(2001)),
+			Some(ChannelStatus::Requested)
+		);
+		assert_eq!(
+			HrmpChannels::channels(ChannelDirection::Ingress, ParaId::from(2002)),
+			Some(ChannelStatus::Open)
+		);
+		System::assert_has_event(Event::HrmpChannels(HrmpChannelsEvent::ChannelOpened(
+			ChannelDirection::Egress,
+			2000.into(),
+		)));
+		System::assert_has_event(Event::HrmpChannels(HrmpChannelsEvent::ChannelOpened(
+			ChannelDirection::Ingress,
+			2002.into(),
+		)));
+
+		// Nothing changed, nothing happens.
+		System::reset_events();
+		sync_channels();
+		assert!(System::events().is_empty());
+
+		set_open_channels(vec![], vec![2000]);
+		sync_channels();
+		assert_eq!(HrmpChannels::channels(ChannelDirection::Ingress, ParaId::from(2002)), None);
+		System::assert_last_event(Event::HrmpChannels(HrmpChannelsEvent::ChannelClosed(
+			ChannelDirection::Ingress,
+			2002.into(),
+		)));
+	});
+}
+
+#[test]
+fn known_channels_are_closed_through_the_relay_chain() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			HrmpChannels::close_channel(Origin::root(), ChannelDirection::Ingress, 2000.into()),
+			Error::<Test>::UnknownChannel
+		);
+
+		set_open_channels(vec![2000], vec![]);
+		sync_channels();
+		assert_ok!(HrmpChannels::close_channel(
+			Origin::root(),
+			ChannelDirection::Ingress,
+			2000.into()
+		));
+		assert_eq!(
+			sent_xcm(),
+			vec![relay_transact(HrmpCall::CloseChannel {
+				sender: 2000.into(),
+				recipient: SELF_PARA_ID.into(),
+			})]
+		);
+		System::assert_last_event(Event::HrmpChannels(HrmpChannelsEvent::CloseChannelRequested(
+			2000.into(),
+			SELF_PARA_ID.into(),
+		)));
+
+		assert_eq!(
+			notify(
+				Parent.into(),
+				HrmpChannelClosing {
+					initiator: SELF_PARA_ID,
+					sender: 2000,
+					recipient: SELF_PARA_ID
+				}
+			),
+			Outcome::Complete(0)
+		);
+		System::assert_last_event(Event::HrmpChannels(HrmpChannelsEvent::ChannelClosing(
+			SELF_PARA_ID.into(),
+			2000.into(),
+			SELF_PARA_ID.into(),
+		)));
+	});
+}
+
+#[test]
+fn failed_requests_leave_channels_as_they_were() {
+	new_test_ext().execute_with(|| {
+		let failed = OutcomeStatus::Failed(0, XcmError::TooExpensive);
+
+		assert_ok!(HrmpChannels::open_channel(Origin::root(), 2000.into(), 8, 1024));
+		assert_eq!(HrmpChannels::pending_requests(0), Some(ChannelRequest::Open(2000.into())));
+		HrmpChannels::on_outcome(0, &failed);
+		assert_eq!(HrmpChannels::channels(ChannelDirection::Egress, ParaId::from(2000)), None);
+		assert_eq!(HrmpChannels::pending_requests(0), None);
+		System::assert_has_event(Event::HrmpChannels(HrmpChannelsEvent::RequestFailed(
+			0,
+			ChannelRequest::Open(2000.into()),
+		)));
+
+		assert_eq!(notify(Parent.into(), open_request(2001)), Outcome::Complete(0));
+		assert_ok!(HrmpChannels::accept_channel(Origin::root(), 2001.into()));
+		HrmpChannels::on_outcome(1, &failed);
+		assert_eq!(
+			HrmpChannels::channels(ChannelDirection::Ingress, ParaId::from(2001)),
+			Some(ChannelStatus::Requested)
+		);
+
+		// Requests which went through wait for the relay chain.
+		assert_ok!(HrmpChannels::open_channel(Origin::root(), 2002.into(), 8, 1024));
+		HrmpChannels::on_outcome(2, &OutcomeStatus::Succeeded);
+		assert_eq!(
+			HrmpChannels::channels(ChannelDirection::Egress, ParaId::from(2002)),
+			Some(ChannelStatus::Requested)
+		);
+		assert_eq!(HrmpChannels::pending_requests(2), None);
+	});
+}
+
+#[test]
+fn requested_channels_are_cancelled_instead_of_closed() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(HrmpChannels::open_channel(Origin::root(), 2000.into(), 8, 1024));
+
+		assert_ok!(HrmpChannels::close_channel(
+			Origin::root(),
+			ChannelDirection::Egress,
+			2000.into()
+		));
+		assert_eq!(
+			sent_xcm()[1],
+			relay_request(
+				1,
+				HrmpCall::CancelOpenRequest {
+					sender: SELF_PARA_ID.into(),
+					recipient: 2000.into(),
+					open_requests: MaxOpenRequests::get(),
+				}
+			)
+		);
+		System::assert_last_event(Event::HrmpChannels(
+			HrmpChannelsEvent::CancelOpenRequestRequested(SELF_PARA_ID.into(), 2000.into()),
+		));
+
+		HrmpChannels::on_outcome(1, &OutcomeStatus::Succeeded);
+		assert_eq!(HrmpChannels::channels(ChannelDirection::Egress, ParaId::from(2000)), None);
+		System::assert_last_event(Event::HrmpChannels(HrmpChannelsEvent::ChannelClosed(
+			ChannelDirection::Egress,
+			2000.into(),
+		)));
+	});
+}
+
+#[test]
+fn channels_closing_before_they_opened_are_forgotten() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(notify(Parent.into(), open_request(2000)), Outcome::Complete(0));
+		set_open_channels(vec![2001], vec![]);
+		sync_channels();
+
+		let closing =
+			|sender| HrmpChannelClosing { initiator: sender, sender, recipient: SELF_PARA_ID };
+		assert_eq!(notify(Parent.into(), closing(2000)), Outcome::Complete(0));
+		assert_eq!(notify(Parent.into(), closing(2001)), Outcome::Complete(0));
+		assert_eq!(HrmpChannels::channels(ChannelDirection::Ingress, ParaId::from(2000)), None);
+		// Open channels close with the relay chain state.
+		assert_eq!(
+			HrmpChannels::channels(ChannelDirection::Ingress, ParaId::from(2001)),
+			Some(ChannelStatus::Open)
+		);
+	});
+}
+
+#[test]
+fn governance_drops_stale_channels() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			HrmpChannels::remove_channel(Origin::root(), ChannelDirection::Egress, 2000.into()),
+			Error::<Test>::UnknownChannel
+		);
+
+		assert_ok!(HrmpChannels::open_channel(Origin::root(), 2000.into(), 8, 1024));
+		assert_ok!(HrmpChannels::remove_channel(
+			Origin::root(),
+			ChannelDirection::Egress,
+			2000.into()
+		));
+		assert_eq!(HrmpChannels::channels(ChannelDirection::Egress, ParaId::from(2000)), None);
+		System::assert_last_event(Event::HrmpChannels(HrmpChannelsEvent::ChannelClosed(
+			ChannelDirection::Egress,
+			2000.into(),
+		)));
+		assert_ok!(HrmpChannels::open_channel(Origin::root(), 2000.into(), 8, 1024));
+	});
+}
@@ -0,0 +1,20 @@
+//! Runtime API definition for the inflation pallet.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+
+pub use pallet_inflation::InflationInfo;
+
+sp_api::decl_runtime_apis! {
+	/// Query the current inflation schedule and where it leads total issuance.
+	pub trait InflationApi<Balance> where
+		Balance: Codec,
+	{
+		/// The annual rate, the amount minted per block and the current total issuance.
+		fn inflation_info() -> InflationInfo<Balance>;
+
+		/// The total issuance after `blocks` more blocks at the current rate.
+		fn projected_issuance(blocks: u32) -> Balance;
+	}
+}
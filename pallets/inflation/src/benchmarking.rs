@@ -0,0 +1,53 @@
+//! Benchmarking setup for pallet-inflation
+
+use super::*;
+
+#[allow(unused)]
+use crate::Pallet as Inflation;
+use frame_benchmarking::{account, benchmarks, impl_benchmark_test_suite};
+use frame_support::{
+	traits::{EnsureOrigin, Get, Hooks},
+	BoundedVec,
+};
+use sp_runtime::Perbill;
+use sp_std::prelude::*;
+
+const SEED: u32 = 0;
+
+benchmarks! {
+	set_inflation_rate {
+		let origin = T::UpdateOrigin::successful_origin();
+		let rate = Perbill::from_percent(10);
+	}: {
+		Inflation::<T>::set_inflation_rate(origin, rate)?;
+	}
+	verify {
+		assert_eq!(InflationRate::<T>::get(), rate);
+	}
+
+	set_destinations {
+		let d in 1 .. T::MaxDestinations::get();
+		let origin = T::UpdateOrigin::successful_origin();
+		let destinations = (0..d)
+			.map(|i| (account("destination", i, SEED), Perbill::from_rational(1, d)))
+			.collect::<Vec<(T::AccountId, _)>>();
+	}: {
+		Inflation::<T>::set_destinations(origin, destinations)?;
+	}
+	verify {
+		assert_eq!(Destinations::<T>::get().len() as u32, d);
+	}
+
+	on_initialize {
+		let d in 1 .. T::MaxDestinations::get();
+		let destinations = (0..d)
+			.map(|i| (account("destination", i, SEED), Perbill::from_rational(1, d)))
+			.collect::<Vec<(T::AccountId, _)>>();
+		Destinations::<T>::put(BoundedVec::try_from(destinations).unwrap());
+		InflationRate::<T>::put(Perbill::from_percent(10));
+	}: {
+		Inflation::<T>::on_initialize(1u32.into());
+	}
+}
+
+impl_benchmark_test_suite!(Inflation, crate::mock::new_test_ext(), crate::mock::Test,);
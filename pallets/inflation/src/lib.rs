@@ -0,0 +1,273 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Token inflation.
+//!
+//! Every block `on_initialize` mints `InflationRate * total_issuance / BlocksPerYear` new tokens
+//! and splits them between the configured `Destinations`, e.g. the collator reward pot, the
+//! treasury and an ecosystem fund. Both the annual rate and the destinations are set by
+//! governance through `UpdateOrigin`.
+//!
+//! Shares that would not reach the existential deposit of a destination that does not exist yet
+//! are carried over to the next block instead of being lost. They are only minted once they reach
+//! it, so the shares carried over for a destination that governance removes are dropped with it.
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
+use codec::{Decode, Encode};
+use scale_info::TypeInfo;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_runtime::{Perbill, RuntimeDebug};
+
+/// The current inflation parameters, as returned by the runtime API.
+#[derive(Eq, PartialEq, Encode, Decode, Default, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct InflationInfo<Balance> {
+	/// The annual inflation rate.
+	pub annual_rate: Perbill,
+	/// The amount that will be minted in the next block.
+	pub per_block: Balance,
+	/// The current total issuance.
+	pub total_issuance: Balance,
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::InflationInfo;
+	use frame_support::{
+		dispatch::DispatchResultWithPostInfo,
+		pallet_prelude::*,
+		traits::{Currency, Imbalance},
+	};
+	use frame_system::pallet_prelude::*;
+	use sp_runtime::{
+		traits::{One, SaturatedConversion, Saturating, Zero},
+		FixedPointNumber, FixedU128, PerThing, Perbill,
+	};
+	use sp_std::prelude::*;
+
+	pub(crate) type BalanceOf<T> =
+		<<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+
+	/// Configure the pallet by specifying the parameters and types on which it depends.
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// Because this pallet emits events, it depends on the runtime's definition of an event.
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// The currency that is minted.
+		type Currency: Currency<Self::AccountId>;
+
+		/// Origin allowed to change the rate and the destinations.
+		type UpdateOrigin: EnsureOrigin<Self::Origin>;
+
+		/// The number of blocks in a year, used to spread the annual rate over blocks.
+		#[pallet::constant]
+		type BlocksPerYear: Get<u32>;
+
+		/// Maximum number of accounts the inflation is split between.
+		#[pallet::constant]
+		type MaxDestinations: Get<u32>;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	/// The annual inflation rate.
+	#[pallet::storage]
+	#[pallet::getter(fn inflation_rate)]
+	pub type InflationRate<T> = StorageValue<_, Perbill, ValueQuery>;
+
+	/// The accounts receiving newly minted tokens and their share of every block's issuance.
+	#[pallet::storage]
+	#[pallet::getter(fn destinations)]
+	pub type Destinations<T: Config> =
+		StorageValue<_, BoundedVec<(T::AccountId, Perbill), T::MaxDestinations>, ValueQuery>;
+
+	/// Shares too small to create their destination account, waiting to be topped up.
+	#[pallet::storage]
+	#[pallet::getter(fn carried_over)]
+	pub type CarriedOver<T: Config> = StorageMap<_, Twox64Concat, T::AccountId, BalanceOf<T>>;
+
+	#[pallet::genesis_config]
+	pub struct GenesisConfig<T: Config> {
+		pub inflation_rate: Perbill,
+		pub destinations: Vec<(T::AccountId, Perbill)>,
+	}
+
+	#[cfg(feature = "std")]
+	impl<T: Config> Default for GenesisConfig<T> {
+		fn default() -> Self {
+			Self { inflation_rate: Perbill::zero(), destinations: Default::default() }
+		}
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> GenesisBuild<T> for GenesisConfig<T> {
+		fn build(&self) {
+			let destinations = Pallet::<T>::bounded_destinations(self.destinations.clone())
+				.expect("genesis inflation destinations are invalid");
+
+			<InflationRate<T>>::put(self.inflation_rate);
+			<Destinations<T>>::put(destinations);
+		}
+	}
+
+	// Pallets use events to inform users when important changes are made.
+	// https://docs.substrate.io/v3/runtime/events-and-errors
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// The annual inflation rate changed. [rate]
+		InflationRateSet(Perbill),
+		/// The destinations of newly minted tokens changed. [destinations]
+		DestinationsSet(Vec<(T::AccountId, Perbill)>),
+		/// Tokens were minted into an account. [who, amount]
+		Minted(T::AccountId, BalanceOf<T>),
+	}
+
+	// Errors inform users that something went wrong.
+	#[pallet::error]
+	pub enum Error<T> {
+		/// More destinations than `MaxDestinations` were given.
+		TooManyDestinations,
+		/// The destination shares add up to more than 100%.
+		SharesExceedTotal,
+		/// The same account was given more than once.
+		DuplicateDestination,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_initialize(_n: T::BlockNumber) -> Weight {
+			let destinations = Self::destinations();
+			let minted = Self::per_block_issuance();
+			if minted.is_zero() || destinations.is_empty() {
+				return T::DbWeight::get().reads(3)
+			}
+
+			let minimum_balance = T::Currency::minimum_balance();
+			for (who, share) in destinations.iter() {
+				let amount = (*share * minted)
+					.saturating_add(<CarriedOver<T>>::take(who).unwrap_or_else(Zero::zero));
+				if amount.is_zero() {
+					continue
+				}
+				// `deposit_creating` silently drops amounts below the existential deposit for
+				// accounts that do not exist yet, so keep those until they add up.
+				if T::Currency::total_balance(who).is_zero() && amount < minimum_balance {
+					<CarriedOver<T>>::insert(who, amount);
+					continue
+				}
+
+				let deposited = T::Currency::deposit_creating(who, amount).peek();
+				Self::deposit_event(Event::Minted(who.clone(), deposited));
+			}
+
+			let count = destinations.len() as Weight;
+			T::DbWeight::get().reads_writes(3 + 2 * count, 2 * count + 1)
+		}
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Set the annual inflation rate.
+		#[pallet::weight(10_000 + T::DbWeight::get().writes(1))]
+		pub fn set_inflation_rate(
+			origin: OriginFor<T>,
+			rate: Perbill,
+		) -> DispatchResultWithPostInfo {
+			T::UpdateOrigin::ensure_origin(origin)?;
+
+			<InflationRate<T>>::put(rate);
+			Self::deposit_event(Event::InflationRateSet(rate));
+			Ok(().into())
+		}
+
+		/// Replace the destinations of newly minted tokens. The shares may add up to less than
+		/// 100%, in which case only that part of the inflation is minted. Whatever was carried
+		/// over for the destinations removed is dropped.
+		#[pallet::weight(
+			10_000 + T::DbWeight::get().reads_writes(1, 1 + T::MaxDestinations::get() as Weight)
+		)]
+		pub fn set_destinations(
+			origin: OriginFor<T>,
+			destinations: Vec<(T::AccountId, Perbill)>,
+		) -> DispatchResultWithPostInfo {
+			T::UpdateOrigin::ensure_origin(origin)?;
+
+			let bounded = Self::bounded_destinations(destinations.clone())?;
+			for (who, _) in Self::destinations().iter() {
+				if !bounded.iter().any(|(kept, _)| kept == who) {
+					<CarriedOver<T>>::remove(who);
+				}
+			}
+			<Destinations<T>>::put(bounded);
+			Self::deposit_event(Event::DestinationsSet(destinations));
+			Ok(().into())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// Check that `destinations` are unique, fit and do not hand out more than 100%.
+		fn bounded_destinations(
+			destinations: Vec<(T::AccountId, Perbill)>,
+		) -> Result<BoundedVec<(T::AccountId, Perbill), T::MaxDestinations>, Error<T>> {
+			let total = destinations
+				.iter()
+				.try_fold(0u32, |total, (_, share)| total.checked_add(share.deconstruct()))
+				.ok_or(Error::<T>::SharesExceedTotal)?;
+			ensure!(total <= Perbill::one().deconstruct(), Error::<T>::SharesExceedTotal);
+
+			let mut accounts = destinations.iter().map(|(who, _)| who).collect::<Vec<_>>();
+			accounts.sort();
+			accounts.dedup();
+			ensure!(accounts.len() == destinations.len(), Error::<T>::DuplicateDestination);
+
+			destinations.try_into().map_err(|_| Error::<T>::TooManyDestinations)
+		}
+
+		/// The amount minted at the next block, before it is split between the destinations.
+		pub fn per_block_issuance() -> BalanceOf<T> {
+			Self::inflation_rate() * T::Currency::total_issuance() /
+				T::BlocksPerYear::get().max(1).into()
+		}
+
+		/// The total issuance after `blocks` more blocks, assuming the rate and destinations do
+		/// not change.
+		pub fn projected_issuance(blocks: u32) -> BalanceOf<T> {
+			let minted_share = Self::destinations()
+				.iter()
+				.fold(Perbill::zero(), |total, (_, share)| total.saturating_add(*share));
+			let per_block_rate = FixedU128::saturating_from_rational(
+				(minted_share * Self::inflation_rate()).deconstruct() as u128,
+				Perbill::ACCURACY as u128 * T::BlocksPerYear::get().max(1) as u128,
+			);
+			// Every block compounds on the issuance of the previous one.
+			let growth = (FixedU128::one() + per_block_rate).saturating_pow(blocks as usize);
+
+			growth
+				.saturating_mul_int(T::Currency::total_issuance().saturated_into::<u128>())
+				.saturated_into()
+		}
+
+		/// The current inflation parameters.
+		pub fn inflation_info() -> InflationInfo<BalanceOf<T>> {
+			InflationInfo {
+				annual_rate: Self::inflation_rate(),
+				per_block: Self::per_block_issuance(),
+				total_issuance: T::Currency::total_issuance(),
+			}
+		}
+	}
+}
@@ -0,0 +1,145 @@
+use crate::{mock::*, Error, Event as InflationEvent};
+use frame_support::{assert_noop, assert_ok, traits::Hooks};
+use sp_runtime::Perbill;
+
+fn next_block() {
+	System::set_block_number(System::block_number() + 1);
+	Inflation::on_initialize(System::block_number());
+}
+
+#[test]
+fn mints_into_destinations_every_block() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(Inflation::per_block_issuance(), 10_010);
+
+		next_block();
+		assert_eq!(Balances::free_balance(POT), 1_000 + 5_005);
+		assert_eq!(Balances::free_balance(TREASURY), 3_003);
+		assert_eq!(Balances::free_balance(ECOSYSTEM), 2_002);
+		assert_eq!(Balances::total_issuance(), 1_011_010);
+		System::assert_last_event(Event::Inflation(InflationEvent::Minted(ECOSYSTEM, 2_002)));
+	});
+}
+
+#[test]
+fn shares_below_existential_deposit_are_carried_over() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Inflation::set_inflation_rate(
+			Origin::signed(RootAccount::get()),
+			Perbill::from_percent(2)
+		));
+
+		next_block();
+		assert_eq!(Balances::free_balance(POT), 1_100);
+		assert_eq!(Balances::free_balance(TREASURY), 0);
+		assert_eq!(Inflation::carried_over(TREASURY), Some(60));
+		assert_eq!(Inflation::carried_over(ECOSYSTEM), Some(40));
+
+		next_block();
+		assert_eq!(Balances::free_balance(TREASURY), 120);
+		assert_eq!(Inflation::carried_over(TREASURY), None);
+		assert_eq!(Inflation::carried_over(ECOSYSTEM), Some(80));
+
+		next_block();
+		assert_eq!(Balances::free_balance(ECOSYSTEM), 120);
+		assert_eq!(Inflation::carried_over(ECOSYSTEM), None);
+	});
+}
+
+#[test]
+fn carried_over_shares_are_dropped_with_their_destination() {
+	new_test_ext().execute_with(|| {
+		let root = Origin::signed(RootAccount::get());
+		assert_ok!(Inflation::set_inflation_rate(root.clone(), Perbill::from_percent(2)));
+		next_block();
+		assert_eq!(Inflation::carried_over(TREASURY), Some(60));
+		assert_eq!(Inflation::carried_over(ECOSYSTEM), Some(40));
+
+		assert_ok!(Inflation::set_destinations(
+			root,
+			vec![(POT, Perbill::from_percent(50)), (ECOSYSTEM, Perbill::from_percent(20))]
+		));
+		assert_eq!(Inflation::carried_over(TREASURY), None);
+		assert_eq!(Inflation::carried_over(ECOSYSTEM), Some(40));
+
+		next_block();
+		assert_eq!(Inflation::carried_over(ECOSYSTEM), Some(80));
+		assert_eq!(Balances::free_balance(TREASURY), 0);
+	});
+}
+
+#[test]
+fn nothing_is_minted_without_rate() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Inflation::set_inflation_rate(
+			Origin::signed(RootAccount::get()),
+			Perbill::zero()
+		));
+
+		next_block();
+		assert_eq!(Balances::total_issuance(), 1_001_000);
+	});
+}
+
+#[test]
+fn only_update_origin_changes_parameters() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Inflation::set_inflation_rate(Origin::signed(1), Perbill::one()),
+			sp_runtime::DispatchError::BadOrigin
+		);
+		assert_noop!(
+			Inflation::set_destinations(Origin::signed(1), vec![]),
+			sp_runtime::DispatchError::BadOrigin
+		);
+	});
+}
+
+#[test]
+fn destinations_are_validated() {
+	new_test_ext().execute_with(|| {
+		let root = Origin::signed(RootAccount::get());
+		assert_noop!(
+			Inflation::set_destinations(
+				root.clone(),
+				vec![(POT, Perbill::from_percent(60)), (TREASURY, Perbill::from_percent(50))]
+			),
+			Error::<Test>::SharesExceedTotal
+		);
+		assert_noop!(
+			Inflation::set_destinations(
+				root.clone(),
+				vec![(POT, Perbill::from_percent(10)), (POT, Perbill::from_percent(10))]
+			),
+			Error::<Test>::DuplicateDestination
+		);
+		assert_noop!(
+			Inflation::set_destinations(
+				root.clone(),
+				(20..24).map(|who| (who, Perbill::from_percent(10))).collect()
+			),
+			Error::<Test>::TooManyDestinations
+		);
+
+		assert_ok!(Inflation::set_destinations(root, vec![(POT, Perbill::from_percent(50))]));
+		next_block();
+		// Only the configured half of the inflation is minted.
+		assert_eq!(Balances::total_issuance(), 1_001_000 + 5_005);
+	});
+}
+
+#[test]
+fn projection_matches_minting() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(Inflation::projected_issuance(0), 1_001_000);
+		assert_eq!(Inflation::projected_issuance(1), 1_011_010);
+
+		next_block();
+		assert_eq!(Balances::total_issuance(), 1_011_010);
+
+		let info = Inflation::inflation_info();
+		assert_eq!(info.annual_rate, Perbill::one());
+		assert_eq!(info.total_issuance, 1_011_010);
+		assert_eq!(info.per_block, 10_110);
+	});
+}
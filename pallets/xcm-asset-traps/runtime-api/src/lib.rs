@@ -0,0 +1,19 @@
+//! Runtime API definition for the assets trapped by XCM executions.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+use sp_core::H256;
+use sp_std::vec::Vec;
+
+pub use pallet_xcm_asset_traps::TrappedAssets;
+
+sp_api::decl_runtime_apis! {
+	/// List the assets left in holding by XCM executions, which their origin can claim back.
+	pub trait XcmAssetTrapsApi<BlockNumber> where
+		BlockNumber: Codec,
+	{
+		/// The assets trapped and not claimed yet, by the hash `pallet_xcm` traps them under.
+		fn trapped_assets() -> Vec<(H256, TrappedAssets<BlockNumber>)>;
+	}
+}
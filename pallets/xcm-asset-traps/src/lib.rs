@@ -0,0 +1,287 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Trapped assets.
+//!
+//! When an XCM execution ends with assets left in holding, `pallet_xcm` traps them under the hash
+//! of their origin and of the assets. Only the hash is stored, so the assets can only be claimed
+//! back by someone who knows both.
+//!
+//! This pallet is the `AssetTrap` of the executor. It hands the assets to `pallet_xcm` to trap,
+//! and records their origin and contents, which the runtime API lists. When governance sets a
+//! `RefundDelay`, the assets trapped for that long are claimed from `pallet_xcm` in `on_idle` and
+//! deposited to the account of their origin with `AssetTransactor`. Traps are refunded in the order
+//! they were recorded, through a queue, one claim at a time: assets trapped several times take as
+//! many steps, each weighing `RefundWeight` per asset.
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+use codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use sp_runtime::RuntimeDebug;
+use xcm::{latest::prelude::*, VersionedMultiAssets};
+
+/// Assets trapped by the same origin.
+#[derive(Encode, Decode, Clone, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+pub struct TrappedAssets<BlockNumber> {
+	/// Whose execution left the assets in holding.
+	pub origin: MultiLocation,
+	/// The assets, as hashed by `pallet_xcm`.
+	pub assets: VersionedMultiAssets,
+	/// How many times the assets were trapped and not claimed since.
+	pub count: u32,
+	/// When the assets were last trapped.
+	pub trapped_at: BlockNumber,
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::TrappedAssets;
+	use frame_support::{
+		dispatch::DispatchResultWithPostInfo,
+		pallet_prelude::*,
+		storage::{with_transaction, TransactionOutcome},
+	};
+	use frame_system::pallet_prelude::*;
+	use sp_core::H256;
+	use sp_runtime::traits::{BlakeTwo256, Hash, Saturating};
+	use sp_std::prelude::*;
+	use xcm::{latest::prelude::*, VersionedMultiAssets};
+	use xcm_executor::{
+		traits::{ClaimAssets, DropAssets, TransactAsset},
+		Assets,
+	};
+
+	/// Configure the pallet by specifying the parameters and types on which it depends.
+	#[pallet::config]
+	pub trait Config: frame_system::Config + pallet_xcm::Config {
+		/// Because this pallet emits events, it depends on the runtime's definition of an event.
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// Origin allowed to set the refund delay.
+		type UpdateOrigin: EnsureOrigin<<Self as frame_system::Config>::Origin>;
+
+		/// Deposits refunded assets to the account of their origin.
+		type AssetTransactor: TransactAsset;
+
+		/// Weight of refunding one asset of a trap once, its share of the claim, deposit and
+		/// storage accesses included.
+		#[pallet::constant]
+		type RefundWeight: Get<Weight>;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	/// The trapped assets, by the hash `pallet_xcm` traps them under.
+	#[pallet::storage]
+	#[pallet::getter(fn trapped)]
+	pub type Trapped<T: Config> = StorageMap<_, Identity, H256, TrappedAssets<T::BlockNumber>>;
+
+	/// How long assets stay trapped before they are refunded, `None` if they are not.
+	#[pallet::storage]
+	#[pallet::getter(fn refund_delay)]
+	pub type RefundDelay<T: Config> = StorageValue<_, T::BlockNumber>;
+
+	/// The traps to refund, in the order they happened, with when they happened.
+	#[pallet::storage]
+	pub type RefundQueue<T: Config> = StorageMap<_, Twox64Concat, u32, (H256, T::BlockNumber)>;
+
+	/// The first entry of `RefundQueue`.
+	#[pallet::storage]
+	pub type RefundQueueHead<T> = StorageValue<_, u32, ValueQuery>;
+
+	/// The entry of `RefundQueue` after the last.
+	#[pallet::storage]
+	pub type RefundQueueTail<T> = StorageValue<_, u32, ValueQuery>;
+
+	// Pallets use events to inform users when important changes are made.
+	// https://docs.substrate.io/v3/runtime/events-and-errors
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// The refund delay changed. [delay]
+		RefundDelaySet(Option<T::BlockNumber>),
+		/// Trapped assets were deposited to their origin, once. [hash, origin, assets]
+		AssetsRefunded(H256, MultiLocation, VersionedMultiAssets),
+		/// Trapped assets could not be deposited to their origin and stay claimable. [hash, error]
+		RefundFailed(H256, XcmError),
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_idle(now: T::BlockNumber, remaining_weight: Weight) -> Weight {
+			Self::refund_trapped(now, remaining_weight)
+		}
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Refund trapped assets after `delay`, or never if `None`.
+		#[pallet::weight(10_000 + T::DbWeight::get().writes(1))]
+		pub fn set_refund_delay(
+			origin: OriginFor<T>,
+			delay: Option<T::BlockNumber>,
+		) -> DispatchResultWithPostInfo {
+			T::UpdateOrigin::ensure_origin(origin)?;
+
+			<RefundDelay<T>>::set(delay);
+			Self::deposit_event(Event::RefundDelaySet(delay));
+			Ok(().into())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// The assets trapped and not claimed yet, with their hash.
+		pub fn trapped_assets() -> Vec<(H256, TrappedAssets<T::BlockNumber>)> {
+			<Trapped<T>>::iter()
+				.filter_map(|(hash, mut trapped)| {
+					trapped.count = trapped.count.min(pallet_xcm::Pallet::<T>::asset_trap(hash));
+					(trapped.count > 0).then(|| (hash, trapped))
+				})
+				.collect()
+		}
+
+		/// Refund the traps at the head of the queue which are due, within `remaining_weight`.
+		pub(crate) fn refund_trapped(now: T::BlockNumber, remaining_weight: Weight) -> Weight {
+			let db_weight = T::DbWeight::get();
+			let mut used = db_weight.reads(3);
+			if used > remaining_weight {
+				return 0
+			}
+			let delay = Self::refund_delay();
+			let (mut head, tail) = (<RefundQueueHead<T>>::get(), <RefundQueueTail<T>>::get());
+
+			while head != tail && used.saturating_add(T::RefundWeight::get()) <= remaining_weight {
+				let (hash, trapped_at) = match <RefundQueue<T>>::get(head) {
+					Some(entry) => entry,
+					None => break,
+				};
+				let trapped = match <Trapped<T>>::get(hash) {
+					// Trapped again since, a later entry refunds it.
+					Some(trapped) if trapped.trapped_at != trapped_at => None,
+					trapped => trapped,
+				};
+				let weight = trapped.as_ref().map_or(T::RefundWeight::get(), Self::claim_weight);
+				if used.saturating_add(weight) > remaining_weight {
+					break
+				}
+				// Whether the entry is done with, rather than refunding more claims.
+				let mut done = true;
+				if let Some(trapped) = trapped {
+					let claimed = pallet_xcm::Pallet::<T>::asset_trap(hash) == 0;
+					match delay {
+						_ if claimed => <Trapped<T>>::remove(hash),
+						Some(delay) if now >= trapped_at.saturating_add(delay) =>
+							done = !Self::refund(hash, trapped),
+						// Not due yet, nor are the later entries.
+						_ => break,
+					}
+				}
+				used = used.saturating_add(weight);
+				if done {
+					<RefundQueue<T>>::remove(head);
+					head = head.wrapping_add(1);
+				}
+			}
+
+			<RefundQueueHead<T>>::put(head);
+			used.saturating_add(db_weight.writes(1))
+		}
+
+		/// Weight of refunding `trapped` once, `RefundWeight` per asset.
+		fn claim_weight(trapped: &TrappedAssets<T::BlockNumber>) -> Weight {
+			let assets = match &trapped.assets {
+				VersionedMultiAssets::V0(assets) => assets.len(),
+				VersionedMultiAssets::V1(assets) => assets.len(),
+			};
+			T::RefundWeight::get().saturating_mul(assets.max(1) as Weight)
+		}
+
+		/// Claim the assets trapped under `hash` once from `pallet_xcm` and deposit them to their
+		/// origin. They stay trapped if a deposit fails. Returns whether they are still trapped
+		/// more times, to be refunded next.
+		fn refund(hash: H256, mut trapped: TrappedAssets<T::BlockNumber>) -> bool {
+			let result = with_transaction(|| {
+				match Self::claim_and_deposit(&trapped.origin, &trapped.assets) {
+					Ok(claimed) => TransactionOutcome::Commit(Ok(claimed)),
+					Err(error) => TransactionOutcome::Rollback(Err(error)),
+				}
+			});
+
+			match result {
+				Ok(true) => {
+					Self::deposit_event(Event::AssetsRefunded(
+						hash,
+						trapped.origin.clone(),
+						trapped.assets.clone(),
+					));
+					trapped.count = trapped.count.saturating_sub(1);
+					if trapped.count > 0 && pallet_xcm::Pallet::<T>::asset_trap(hash) > 0 {
+						<Trapped<T>>::insert(hash, trapped);
+						return true
+					}
+					<Trapped<T>>::remove(hash);
+				},
+				Ok(false) => <Trapped<T>>::remove(hash),
+				Err(error) => Self::deposit_event(Event::RefundFailed(hash, error)),
+			}
+			false
+		}
+
+		/// Claim `versioned` trapped by `origin` once and deposit them to `origin`. Returns
+		/// whether they were still trapped.
+		fn claim_and_deposit(
+			origin: &MultiLocation,
+			versioned: &VersionedMultiAssets,
+		) -> Result<bool, XcmError> {
+			let assets = MultiAssets::try_from(versioned.clone())
+				.map_err(|()| XcmError::UnhandledXcmVersion)?;
+			if !<pallet_xcm::Pallet<T> as ClaimAssets>::claim_assets(origin, &Here.into(), &assets)
+			{
+				return Ok(false)
+			}
+			for asset in assets.inner() {
+				T::AssetTransactor::deposit_asset(asset, origin)?;
+			}
+			Ok(true)
+		}
+	}
+
+	impl<T: Config> DropAssets for Pallet<T> {
+		fn drop_assets(origin: &MultiLocation, assets: Assets) -> Weight {
+			if assets.is_empty() {
+				return 0
+			}
+			// Hashed as `pallet_xcm` does.
+			let versioned = VersionedMultiAssets::from(MultiAssets::from(assets.clone()));
+			let hash = BlakeTwo256::hash_of(&(origin, &versioned));
+			let now = frame_system::Pallet::<T>::block_number();
+
+			<Trapped<T>>::mutate(hash, |trapped| match trapped {
+				Some(trapped) => {
+					trapped.count = trapped.count.saturating_add(1);
+					trapped.trapped_at = now;
+				},
+				None =>
+					*trapped = Some(TrappedAssets {
+						origin: origin.clone(),
+						assets: versioned,
+						count: 1,
+						trapped_at: now,
+					}),
+			});
+			let tail = <RefundQueueTail<T>>::get();
+			<RefundQueue<T>>::insert(tail, (hash, now));
+			<RefundQueueTail<T>>::put(tail.wrapping_add(1));
+
+			<pallet_xcm::Pallet<T> as DropAssets>::drop_assets(origin, assets)
+				.saturating_add(T::DbWeight::get().reads_writes(2, 3))
+		}
+	}
+}
@@ -0,0 +1,146 @@
+use crate as pallet_xcm_asset_traps;
+use frame_support::{
+	parameter_types,
+	traits::{Everything, Nothing},
+	weights::Weight,
+};
+use frame_system::{self as system, EnsureRoot};
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, IdentityLookup},
+};
+use std::cell::RefCell;
+use xcm::latest::prelude::*;
+use xcm_builder::{EnsureXcmOrigin, FixedWeightBounds, LocationInverter};
+use xcm_executor::traits::TransactAsset;
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+// Configure a mock runtime to test the pallet.
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+		PolkadotXcm: pallet_xcm::{Pallet, Call, Storage, Event<T>, Origin},
+		XcmAssetTraps: pallet_xcm_asset_traps::{Pallet, Call, Storage, Event<T>},
+	}
+);
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const SS58Prefix: u8 = 42;
+}
+
+impl system::Config for Test {
+	type BaseCallFilter = Everything;
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = BlockHashCount;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = SS58Prefix;
+	type OnSetCode = ();
+	type MaxConsumers = frame_support::traits::ConstU32<16>;
+}
+
+pub const SELF_PARA_ID: u32 = 1000;
+
+/// Messages are not executed, the traps are made by hand.
+pub struct NoExecutor;
+impl ExecuteXcm<Call> for NoExecutor {
+	fn execute_xcm_in_credit(
+		_origin: impl Into<MultiLocation>,
+		_message: Xcm<Call>,
+		_weight_limit: Weight,
+		_weight_credit: Weight,
+	) -> Outcome {
+		Outcome::Error(XcmError::Unimplemented)
+	}
+}
+
+parameter_types! {
+	pub Ancestry: MultiLocation = Parachain(SELF_PARA_ID).into();
+	pub const UnitWeightCost: Weight = 1_000;
+	pub const MaxInstructions: u32 = 100;
+}
+
+impl pallet_xcm::Config for Test {
+	type Event = Event;
+	type SendXcmOrigin = EnsureXcmOrigin<Origin, ()>;
+	type XcmRouter = ();
+	type ExecuteXcmOrigin = EnsureXcmOrigin<Origin, ()>;
+	type XcmExecuteFilter = Nothing;
+	type XcmExecutor = NoExecutor;
+	type XcmTeleportFilter = Nothing;
+	type XcmReserveTransferFilter = Nothing;
+	type Weigher = FixedWeightBounds<UnitWeightCost, Call, MaxInstructions>;
+	type LocationInverter = LocationInverter<Ancestry>;
+	type Origin = Origin;
+	type Call = Call;
+
+	const VERSION_DISCOVERY_QUEUE_SIZE: u32 = 100;
+	type AdvertisedXcmVersion = pallet_xcm::CurrentXcmVersion;
+}
+
+thread_local! {
+	pub static DEPOSITED: RefCell<Vec<(MultiAsset, MultiLocation)>> = RefCell::new(Vec::new());
+}
+
+/// The origin whose deposits fail.
+pub const UNREACHABLE_PARA_ID: u32 = 666;
+
+/// Records the deposits instead of making them.
+pub struct TestAssetTransactor;
+impl TransactAsset for TestAssetTransactor {
+	fn deposit_asset(what: &MultiAsset, who: &MultiLocation) -> XcmResult {
+		if *who == MultiLocation::new(1, X1(Parachain(UNREACHABLE_PARA_ID))) {
+			return Err(XcmError::FailedToTransactAsset("unreachable"))
+		}
+		DEPOSITED.with(|d| d.borrow_mut().push((what.clone(), who.clone())));
+		Ok(())
+	}
+}
+
+pub fn deposited() -> Vec<(MultiAsset, MultiLocation)> {
+	DEPOSITED.with(|d| d.borrow().clone())
+}
+
+parameter_types! {
+	pub const RefundWeight: Weight = 1_000;
+}
+
+impl pallet_xcm_asset_traps::Config for Test {
+	type Event = Event;
+	type UpdateOrigin = EnsureRoot<u64>;
+	type AssetTransactor = TestAssetTransactor;
+	type RefundWeight = RefundWeight;
+}
+
+// Build genesis storage according to the mock runtime.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	DEPOSITED.with(|d| d.borrow_mut().clear());
+	let t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	let mut ext = sp_io::TestExternalities::new(t);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
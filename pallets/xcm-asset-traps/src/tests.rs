@@ -0,0 +1,218 @@
+use crate::{mock::*, Event as XcmAssetTrapsEvent, TrappedAssets};
+use frame_support::{assert_noop, assert_ok, traits::Hooks};
+use sp_core::H256;
+use sp_runtime::traits::{BadOrigin, BlakeTwo256, Hash};
+use xcm::{latest::prelude::*, VersionedMultiAssets};
+use xcm_executor::{
+	traits::{ClaimAssets, DropAssets},
+	Assets,
+};
+
+fn sibling(id: u32) -> MultiLocation {
+	MultiLocation::new(1, X1(Parachain(id)))
+}
+
+fn assets(amount: u128) -> MultiAssets {
+	(Here, amount).into()
+}
+
+fn trap(origin: MultiLocation, amount: u128) -> H256 {
+	XcmAssetTraps::drop_assets(&origin, Assets::from(assets(amount).into_inner()));
+	BlakeTwo256::hash_of(&(origin, VersionedMultiAssets::from(assets(amount))))
+}
+
+fn run_to_block(n: u64, remaining_weight: u64) {
+	System::set_block_number(n);
+	XcmAssetTraps::on_idle(n, remaining_weight);
+}
+
+#[test]
+fn trapped_assets_are_listed() {
+	new_test_ext().execute_with(|| {
+		let hash = trap(sibling(2000), 10);
+		System::set_block_number(2);
+		assert_eq!(trap(sibling(2000), 10), hash);
+		let other = trap(sibling(2001), 10);
+
+		assert_eq!(PolkadotXcm::asset_trap(hash), 2);
+		let mut trapped = XcmAssetTraps::trapped_assets();
+		trapped.sort_by_key(|(_, trapped)| trapped.count);
+		assert_eq!(
+			trapped,
+			vec![
+				(
+					other,
+					TrappedAssets {
+						origin: sibling(2001),
+						assets: assets(10).into(),
+						count: 1,
+						trapped_at: 2,
+					}
+				),
+				(
+					hash,
+					TrappedAssets {
+						origin: sibling(2000),
+						assets: assets(10).into(),
+						count: 2,
+						trapped_at: 2,
+					}
+				),
+			]
+		);
+	});
+}
+
+#[test]
+fn claimed_assets_are_not_listed() {
+	new_test_ext().execute_with(|| {
+		let hash = trap(sibling(2000), 10);
+		assert!(PolkadotXcm::claim_assets(&sibling(2000), &Here.into(), &assets(10)));
+
+		assert!(XcmAssetTraps::trapped_assets().is_empty());
+		run_to_block(2, u64::MAX);
+		assert_eq!(XcmAssetTraps::trapped(hash), None);
+	});
+}
+
+#[test]
+fn only_root_sets_the_refund_delay() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(XcmAssetTraps::set_refund_delay(Origin::signed(1), Some(5)), BadOrigin);
+
+		assert_ok!(XcmAssetTraps::set_refund_delay(Origin::root(), Some(5)));
+		assert_eq!(XcmAssetTraps::refund_delay(), Some(5));
+		System::assert_last_event(Event::XcmAssetTraps(XcmAssetTrapsEvent::RefundDelaySet(Some(
+			5,
+		))));
+	});
+}
+
+#[test]
+fn assets_are_not_refunded_without_a_delay() {
+	new_test_ext().execute_with(|| {
+		let hash = trap(sibling(2000), 10);
+		run_to_block(100, u64::MAX);
+
+		assert!(deposited().is_empty());
+		assert_eq!(PolkadotXcm::asset_trap(hash), 1);
+	});
+}
+
+#[test]
+fn due_assets_are_refunded_to_their_origin() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(XcmAssetTraps::set_refund_delay(Origin::root(), Some(5)));
+		let hash = trap(sibling(2000), 10);
+		trap(sibling(2000), 10);
+
+		run_to_block(5, u64::MAX);
+		assert!(deposited().is_empty());
+
+		run_to_block(6, u64::MAX);
+		let refunded: MultiAsset = (Here, 10).into();
+		assert_eq!(deposited(), vec![(refunded.clone(), sibling(2000)), (refunded, sibling(2000))]);
+		assert_eq!(PolkadotXcm::asset_trap(hash), 0);
+		assert_eq!(XcmAssetTraps::trapped(hash), None);
+		System::assert_last_event(Event::XcmAssetTraps(XcmAssetTrapsEvent::AssetsRefunded(
+			hash,
+			sibling(2000),
+			assets(10).into(),
+		)));
+	});
+}
+
+#[test]
+fn trapping_again_delays_the_refund() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(XcmAssetTraps::set_refund_delay(Origin::root(), Some(5)));
+		let hash = trap(sibling(2000), 10);
+		System::set_block_number(4);
+		trap(sibling(2000), 10);
+
+		run_to_block(6, u64::MAX);
+		assert!(deposited().is_empty());
+
+		run_to_block(9, u64::MAX);
+		assert_eq!(deposited().len(), 2);
+		assert_eq!(PolkadotXcm::asset_trap(hash), 0);
+	});
+}
+
+#[test]
+fn failed_refunds_stay_claimable() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(XcmAssetTraps::set_refund_delay(Origin::root(), Some(5)));
+		let hash = trap(sibling(UNREACHABLE_PARA_ID), 10);
+
+		run_to_block(6, u64::MAX);
+		System::assert_last_event(Event::XcmAssetTraps(XcmAssetTrapsEvent::RefundFailed(
+			hash,
+			XcmError::FailedToTransactAsset("unreachable"),
+		)));
+		assert_eq!(PolkadotXcm::asset_trap(hash), 1);
+		assert!(PolkadotXcm::claim_assets(
+			&sibling(UNREACHABLE_PARA_ID),
+			&Here.into(),
+			&assets(10)
+		));
+	});
+}
+
+#[test]
+fn refunds_stay_within_the_remaining_weight() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(XcmAssetTraps::set_refund_delay(Origin::root(), Some(5)));
+		trap(sibling(2000), 10);
+		trap(sibling(2001), 10);
+		trap(sibling(2002), 10);
+
+		run_to_block(6, RefundWeight::get() * 2);
+		assert_eq!(deposited().len(), 2);
+
+		run_to_block(7, RefundWeight::get() - 1);
+		assert_eq!(deposited().len(), 2);
+
+		run_to_block(8, RefundWeight::get());
+		assert_eq!(
+			deposited().into_iter().map(|(_, who)| who).collect::<Vec<_>>(),
+			vec![sibling(2000), sibling(2001), sibling(2002)]
+		);
+	});
+}
+
+#[test]
+fn assets_trapped_many_times_are_refunded_once_per_step() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(XcmAssetTraps::set_refund_delay(Origin::root(), Some(5)));
+		let hash = trap(sibling(2000), 10);
+		trap(sibling(2000), 10);
+		trap(sibling(2000), 10);
+
+		run_to_block(6, RefundWeight::get() * 2);
+		assert_eq!(deposited().len(), 2);
+		assert_eq!(XcmAssetTraps::trapped(hash).map(|trapped| trapped.count), Some(1));
+		assert_eq!(PolkadotXcm::asset_trap(hash), 1);
+
+		run_to_block(7, RefundWeight::get());
+		assert_eq!(deposited().len(), 3);
+		assert_eq!(XcmAssetTraps::trapped(hash), None);
+		assert_eq!(PolkadotXcm::asset_trap(hash), 0);
+	});
+}
+
+#[test]
+fn refunds_weigh_per_asset() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(XcmAssetTraps::set_refund_delay(Origin::root(), Some(5)));
+		let two: MultiAssets = vec![(Here, 10).into(), (Parent, 10).into()].into();
+		XcmAssetTraps::drop_assets(&sibling(2000), Assets::from(two.into_inner()));
+
+		run_to_block(6, RefundWeight::get());
+		assert!(deposited().is_empty());
+
+		run_to_block(7, RefundWeight::get() * 2);
+		assert_eq!(deposited().len(), 2);
+		assert!(XcmAssetTraps::trapped_assets().is_empty());
+	});
+}
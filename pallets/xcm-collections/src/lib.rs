@@ -0,0 +1,270 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! NFT collections moving through XCM.
+//!
+//! The items of our own collections are identified in XCM by `CollectionsLocation`, followed by
+//! `GeneralIndex` of their collection, and `AssetInstance::Index` of their item. Items of foreign
+//! collections, reserve deposited by the chain holding them, are minted into a local collection
+//! governance registered as their derivative, and burnt when withdrawn back. The pallet is the
+//! `Matcher` of the non-fungibles transactor of the executor.
+//!
+//! Registering a derivative does not make the deposits of its items trusted. Receiving a foreign
+//! collection takes two steps of governance: [`Pallet::register_derivative`] here, and trusting
+//! the chain holding the collection as its reserve with the executor's `IsReserve` filter, such as
+//! `pallet-xcm-reserves`. The items pay no fees, so the chain sends them unpaid, and the barrier
+//! must let it.
+//!
+//! No collection may leave the chain unless governance allows it: [`RestrictedExports`], wrapping
+//! the transactor, refuses to move the items of other collections to other chains. Derivatives
+//! going back to their reserve with `InitiateReserveWithdraw` are burnt here, and are not
+//! restricted.
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+use core::marker::PhantomData;
+use sp_std::result;
+use xcm::latest::prelude::*;
+use xcm_executor::{
+	traits::{Error as MatchError, MatchesNonFungibles, TransactAsset},
+	Assets,
+};
+
+#[frame_support::pallet]
+pub mod pallet {
+	use frame_support::{dispatch::DispatchResultWithPostInfo, pallet_prelude::*};
+	use frame_system::pallet_prelude::*;
+	use sp_std::{boxed::Box, prelude::*};
+	use xcm::{latest::prelude::*, VersionedMultiLocation};
+
+	/// Configure the pallet by specifying the parameters and types on which it depends.
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// Because this pallet emits events, it depends on the runtime's definition of an event.
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// Origin allowed to register foreign collections and to let collections leave the chain.
+		type UpdateOrigin: EnsureOrigin<Self::Origin>;
+
+		/// Identifier of a local collection.
+		type ClassId: Member + Parameter + MaxEncodedLen + Copy + TryFrom<u128>;
+
+		/// Identifier of an item in a collection.
+		type InstanceId: TryFrom<u128>;
+
+		/// The location of our collections, usually the instance of the NFT pallet.
+		#[pallet::constant]
+		type CollectionsLocation: Get<MultiLocation>;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	/// The local collection minting the derivatives of a foreign collection, by its location.
+	#[pallet::storage]
+	#[pallet::getter(fn derivative_class)]
+	pub type Derivatives<T: Config> = StorageMap<_, Blake2_128Concat, MultiLocation, T::ClassId>;
+
+	/// The location of the foreign collection a local collection mints the derivatives of.
+	#[pallet::storage]
+	#[pallet::getter(fn foreign_collection)]
+	pub type ForeignCollections<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::ClassId, MultiLocation>;
+
+	/// The collections whose items may leave the chain.
+	#[pallet::storage]
+	#[pallet::getter(fn is_exportable)]
+	pub type Exportable<T: Config> = StorageMap<_, Blake2_128Concat, T::ClassId, bool, ValueQuery>;
+
+	// Pallets use events to inform users when important changes are made.
+	// https://docs.substrate.io/v3/runtime/events-and-errors
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// A local collection mints the derivatives of a foreign collection. [collection, class]
+		DerivativeRegistered(MultiLocation, T::ClassId),
+		/// A local collection no longer mints the derivatives of a foreign collection.
+		/// [collection, class]
+		DerivativeDeregistered(MultiLocation, T::ClassId),
+		/// Whether the items of a collection may leave the chain changed. [class, exportable]
+		ExportableSet(T::ClassId, bool),
+	}
+
+	// Errors inform users that something went wrong.
+	#[pallet::error]
+	pub enum Error<T> {
+		/// A location could not be converted to the latest XCM version.
+		BadVersion,
+		/// The collection is not on another chain.
+		NotForeign,
+		/// The foreign collection, or the local collection, already has a derivative registered.
+		AlreadyRegistered,
+		/// The foreign collection has no derivative registered.
+		NotRegistered,
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Mint the items of the foreign `collection` deposited here into `class`, which must
+		/// exist, and is no longer reachable as one of our own collections. Deposits are only
+		/// accepted once the chain holding `collection` is trusted as its reserve too.
+		#[pallet::weight(10_000 + T::DbWeight::get().writes(2))]
+		pub fn register_derivative(
+			origin: OriginFor<T>,
+			collection: Box<VersionedMultiLocation>,
+			class: T::ClassId,
+		) -> DispatchResultWithPostInfo {
+			T::UpdateOrigin::ensure_origin(origin)?;
+
+			let collection =
+				MultiLocation::try_from(*collection).map_err(|()| Error::<T>::BadVersion)?;
+			ensure!(collection.parents > 0, Error::<T>::NotForeign);
+			ensure!(
+				!<Derivatives<T>>::contains_key(&collection) &&
+					!<ForeignCollections<T>>::contains_key(&class),
+				Error::<T>::AlreadyRegistered
+			);
+
+			<Derivatives<T>>::insert(&collection, class);
+			<ForeignCollections<T>>::insert(&class, &collection);
+			Self::deposit_event(Event::DerivativeRegistered(collection, class));
+			Ok(().into())
+		}
+
+		/// Stop minting the items of the foreign `collection` deposited here. Its derivatives
+		/// minted already can no longer be withdrawn back to its reserve.
+		#[pallet::weight(10_000 + T::DbWeight::get().writes(2))]
+		pub fn deregister_derivative(
+			origin: OriginFor<T>,
+			collection: Box<VersionedMultiLocation>,
+		) -> DispatchResultWithPostInfo {
+			T::UpdateOrigin::ensure_origin(origin)?;
+
+			let collection =
+				MultiLocation::try_from(*collection).map_err(|()| Error::<T>::BadVersion)?;
+			let class = <Derivatives<T>>::take(&collection).ok_or(Error::<T>::NotRegistered)?;
+			<ForeignCollections<T>>::remove(&class);
+			Self::deposit_event(Event::DerivativeDeregistered(collection, class));
+			Ok(().into())
+		}
+
+		/// Let the items of `class` leave the chain, or keep them here.
+		#[pallet::weight(10_000 + T::DbWeight::get().writes(1))]
+		pub fn set_exportable(
+			origin: OriginFor<T>,
+			class: T::ClassId,
+			exportable: bool,
+		) -> DispatchResultWithPostInfo {
+			T::UpdateOrigin::ensure_origin(origin)?;
+
+			if exportable {
+				<Exportable<T>>::insert(&class, true);
+			} else {
+				<Exportable<T>>::remove(&class);
+			}
+			Self::deposit_event(Event::ExportableSet(class, exportable));
+			Ok(().into())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// The local collection at `location`, one of our own or the derivative of a foreign one.
+		pub fn class(location: &MultiLocation) -> Option<T::ClassId> {
+			Self::local_class(location).or_else(|| Self::derivative_class(location))
+		}
+
+		/// Our own collection at `location`, as seen by us.
+		pub fn local_class(location: &MultiLocation) -> Option<T::ClassId> {
+			let mut interior = location.interior.clone();
+			let index = match interior.take_last() {
+				Some(GeneralIndex(index)) => index,
+				_ => return None,
+			};
+			if MultiLocation::new(location.parents, interior) != T::CollectionsLocation::get() {
+				return None
+			}
+			T::ClassId::try_from(index)
+				.ok()
+				.filter(|class| !<ForeignCollections<T>>::contains_key(class))
+		}
+	}
+}
+
+impl<T: Config> MatchesNonFungibles<T::ClassId, T::InstanceId> for Pallet<T> {
+	fn matches_nonfungibles(
+		a: &MultiAsset,
+	) -> result::Result<(T::ClassId, T::InstanceId), MatchError> {
+		let (location, instance) = match (&a.id, &a.fun) {
+			(Concrete(location), NonFungible(AssetInstance::Index(instance))) =>
+				(location, *instance),
+			_ => return Err(MatchError::AssetNotFound),
+		};
+		let class = Self::class(location).ok_or(MatchError::AssetNotFound)?;
+		let instance =
+			T::InstanceId::try_from(instance).map_err(|_| MatchError::InstanceConversionFailed)?;
+		Ok((class, instance))
+	}
+}
+
+/// Refuses to move the items of collections which may not leave the chain to other chains
+/// through `Transactor`.
+pub struct RestrictedExports<T, Transactor>(PhantomData<(T, Transactor)>);
+
+impl<T: Config, Transactor: TransactAsset> RestrictedExports<T, Transactor> {
+	fn ensure_exportable(asset: &MultiAsset, to: &MultiLocation) -> XcmResult {
+		if to.parents == 0 {
+			return Ok(())
+		}
+		match Pallet::<T>::matches_nonfungibles(asset) {
+			Ok((class, _)) if !Pallet::<T>::is_exportable(class) =>
+				Err(XcmError::FailedToTransactAsset("collection may not leave the chain")),
+			_ => Ok(()),
+		}
+	}
+}
+
+impl<T: Config, Transactor: TransactAsset> TransactAsset for RestrictedExports<T, Transactor> {
+	fn can_check_in(origin: &MultiLocation, what: &MultiAsset) -> XcmResult {
+		Transactor::can_check_in(origin, what)
+	}
+
+	fn check_in(origin: &MultiLocation, what: &MultiAsset) {
+		Transactor::check_in(origin, what)
+	}
+
+	fn check_out(dest: &MultiLocation, what: &MultiAsset) {
+		Transactor::check_out(dest, what)
+	}
+
+	fn deposit_asset(what: &MultiAsset, who: &MultiLocation) -> XcmResult {
+		Self::ensure_exportable(what, who)?;
+		Transactor::deposit_asset(what, who)
+	}
+
+	fn withdraw_asset(what: &MultiAsset, who: &MultiLocation) -> Result<Assets, XcmError> {
+		Transactor::withdraw_asset(what, who)
+	}
+
+	fn internal_transfer_asset(
+		asset: &MultiAsset,
+		from: &MultiLocation,
+		to: &MultiLocation,
+	) -> Result<Assets, XcmError> {
+		Self::ensure_exportable(asset, to)?;
+		Transactor::internal_transfer_asset(asset, from, to)
+	}
+
+	fn transfer_asset(
+		asset: &MultiAsset,
+		from: &MultiLocation,
+		to: &MultiLocation,
+	) -> Result<Assets, XcmError> {
+		Self::ensure_exportable(asset, to)?;
+		Transactor::transfer_asset(asset, from, to)
+	}
+}
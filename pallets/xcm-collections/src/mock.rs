@@ -0,0 +1,140 @@
+use crate as pallet_xcm_collections;
+use frame_support::{
+	parameter_types,
+	traits::{ConstU32, ConstU64, Everything, Nothing},
+};
+use frame_system::{self as system, EnsureRoot};
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, IdentityLookup},
+};
+use xcm::latest::prelude::*;
+use xcm_builder::NonFungiblesAdapter;
+use xcm_executor::traits::Convert;
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+// Configure a mock runtime to test the pallet.
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+		Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},
+		Uniques: pallet_uniques::{Pallet, Call, Storage, Event<T>},
+		XcmCollections: pallet_xcm_collections::{Pallet, Call, Storage, Event<T>},
+	}
+);
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const SS58Prefix: u8 = 42;
+}
+
+impl system::Config for Test {
+	type BaseCallFilter = Everything;
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = BlockHashCount;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = pallet_balances::AccountData<u64>;
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = SS58Prefix;
+	type OnSetCode = ();
+	type MaxConsumers = ConstU32<16>;
+}
+
+parameter_types! {
+	pub const ExistentialDeposit: u64 = 1;
+	pub const MaxReserves: u32 = 50;
+}
+
+impl pallet_balances::Config for Test {
+	type Balance = u64;
+	type Event = Event;
+	type DustRemoval = ();
+	type ExistentialDeposit = ExistentialDeposit;
+	type AccountStore = System;
+	type WeightInfo = ();
+	type MaxLocks = ();
+	type MaxReserves = MaxReserves;
+	type ReserveIdentifier = [u8; 8];
+}
+
+impl pallet_uniques::Config for Test {
+	type Event = Event;
+	type ClassId = u32;
+	type InstanceId = u32;
+	type Currency = Balances;
+	type ForceOrigin = EnsureRoot<u64>;
+	type ClassDeposit = ConstU64<0>;
+	type InstanceDeposit = ConstU64<0>;
+	type MetadataDepositBase = ConstU64<0>;
+	type AttributeDepositBase = ConstU64<0>;
+	type DepositPerByte = ConstU64<0>;
+	type StringLimit = ConstU32<50>;
+	type KeyLimit = ConstU32<32>;
+	type ValueLimit = ConstU32<64>;
+	type WeightInfo = ();
+}
+
+pub const ALICE: u64 = 1;
+pub const SIBLING_PARA_ID: u32 = 2000;
+/// The account of a sibling is its para id, offset by this.
+pub const SIBLING_ACCOUNT_OFFSET: u64 = 10_000;
+
+/// Converts local accounts to their index, and siblings to their offset para id.
+pub struct TestAccounts;
+impl Convert<MultiLocation, u64> for TestAccounts {
+	fn convert(location: MultiLocation) -> Result<u64, MultiLocation> {
+		match location {
+			MultiLocation { parents: 0, interior: X1(AccountIndex64 { index, .. }) } => Ok(index),
+			MultiLocation { parents: 1, interior: X1(Parachain(id)) } =>
+				Ok(SIBLING_ACCOUNT_OFFSET + id as u64),
+			location => Err(location),
+		}
+	}
+}
+
+pub type Transactor = pallet_xcm_collections::RestrictedExports<
+	Test,
+	NonFungiblesAdapter<Uniques, XcmCollections, TestAccounts, u64, Nothing, ()>,
+>;
+
+parameter_types! {
+	pub CollectionsLocation: MultiLocation = PalletInstance(2).into();
+}
+
+impl pallet_xcm_collections::Config for Test {
+	type Event = Event;
+	type UpdateOrigin = EnsureRoot<u64>;
+	type ClassId = u32;
+	type InstanceId = u32;
+	type CollectionsLocation = CollectionsLocation;
+}
+
+// Build genesis storage according to the mock runtime.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	let mut ext = sp_io::TestExternalities::new(t);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
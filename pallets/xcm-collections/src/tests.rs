@@ -0,0 +1,236 @@
+use crate::{mock::*, Error, Event as XcmCollectionsEvent};
+use frame_support::{assert_noop, assert_ok, traits::tokens::nonfungibles::Inspect};
+use sp_runtime::traits::BadOrigin;
+use xcm::{latest::prelude::*, VersionedMultiLocation};
+use xcm_executor::traits::{Error as MatchError, MatchesNonFungibles, TransactAsset};
+
+fn sibling(id: u32) -> MultiLocation {
+	MultiLocation::new(1, X1(Parachain(id)))
+}
+
+fn alice() -> MultiLocation {
+	AccountIndex64 { network: Any, index: ALICE }.into()
+}
+
+/// A collection of the relay's asset parachain.
+fn foreign_collection() -> MultiLocation {
+	MultiLocation::new(1, X3(Parachain(1000), PalletInstance(50), GeneralIndex(3)))
+}
+
+fn local_item(class: u32, instance: u128) -> MultiAsset {
+	let collection = MultiLocation::new(0, X2(PalletInstance(2), GeneralIndex(class.into())));
+	(collection, AssetInstance::Index(instance)).into()
+}
+
+fn foreign_item(instance: u128) -> MultiAsset {
+	(foreign_collection(), AssetInstance::Index(instance)).into()
+}
+
+fn create_class(class: u32) {
+	assert_ok!(Uniques::force_create(Origin::root(), class, ALICE, true));
+}
+
+fn register_derivative(class: u32) {
+	create_class(class);
+	assert_ok!(XcmCollections::register_derivative(
+		Origin::root(),
+		Box::new(foreign_collection().into()),
+		class
+	));
+}
+
+fn owner(class: u32, instance: u32) -> Option<u64> {
+	<Uniques as Inspect<u64>>::owner(&class, &instance)
+}
+
+#[test]
+fn only_root_registers_derivatives() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			XcmCollections::register_derivative(
+				Origin::signed(ALICE),
+				Box::new(foreign_collection().into()),
+				1
+			),
+			BadOrigin
+		);
+		assert_noop!(
+			XcmCollections::register_derivative(
+				Origin::root(),
+				// Parents only come first from v1 on.
+				Box::new(VersionedMultiLocation::V0(xcm::v0::MultiLocation::X2(
+					xcm::v0::Junction::Parachain(1000),
+					xcm::v0::Junction::Parent,
+				))),
+				1
+			),
+			Error::<Test>::BadVersion
+		);
+		assert_noop!(
+			XcmCollections::register_derivative(
+				Origin::root(),
+				Box::new(MultiLocation::new(0, X1(GeneralIndex(3))).into()),
+				1
+			),
+			Error::<Test>::NotForeign
+		);
+
+		register_derivative(1);
+		assert_eq!(XcmCollections::derivative_class(foreign_collection()), Some(1));
+		assert_eq!(XcmCollections::foreign_collection(1), Some(foreign_collection()));
+		System::assert_last_event(Event::XcmCollections(
+			XcmCollectionsEvent::DerivativeRegistered(foreign_collection(), 1),
+		));
+
+		assert_noop!(
+			XcmCollections::register_derivative(
+				Origin::root(),
+				Box::new(foreign_collection().into()),
+				2
+			),
+			Error::<Test>::AlreadyRegistered
+		);
+		assert_noop!(
+			XcmCollections::register_derivative(
+				Origin::root(),
+				Box::new(MultiLocation::new(1, X1(Parachain(2000))).into()),
+				1
+			),
+			Error::<Test>::AlreadyRegistered
+		);
+	});
+}
+
+#[test]
+fn only_root_deregisters_derivatives() {
+	new_test_ext().execute_with(|| {
+		register_derivative(1);
+
+		assert_noop!(
+			XcmCollections::deregister_derivative(
+				Origin::signed(ALICE),
+				Box::new(foreign_collection().into())
+			),
+			BadOrigin
+		);
+		assert_ok!(XcmCollections::deregister_derivative(
+			Origin::root(),
+			Box::new(foreign_collection().into())
+		));
+		assert_eq!(XcmCollections::derivative_class(foreign_collection()), None);
+		assert_eq!(XcmCollections::foreign_collection(1), None);
+		System::assert_last_event(Event::XcmCollections(
+			XcmCollectionsEvent::DerivativeDeregistered(foreign_collection(), 1),
+		));
+
+		assert_noop!(
+			XcmCollections::deregister_derivative(
+				Origin::root(),
+				Box::new(foreign_collection().into())
+			),
+			Error::<Test>::NotRegistered
+		);
+	});
+}
+
+#[test]
+fn items_are_matched_to_local_collections() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(XcmCollections::matches_nonfungibles(&local_item(0, 7)), Ok((0, 7)));
+		assert_eq!(
+			XcmCollections::matches_nonfungibles(&foreign_item(7)),
+			Err(MatchError::AssetNotFound)
+		);
+		assert_eq!(
+			XcmCollections::matches_nonfungibles(&local_item(0, u128::MAX)),
+			Err(MatchError::InstanceConversionFailed)
+		);
+		assert_eq!(
+			XcmCollections::matches_nonfungibles(&(Here, 7).into()),
+			Err(MatchError::AssetNotFound)
+		);
+
+		register_derivative(1);
+		assert_eq!(XcmCollections::matches_nonfungibles(&foreign_item(7)), Ok((1, 7)));
+		// Derivatives are only reachable through the foreign collection.
+		assert_eq!(
+			XcmCollections::matches_nonfungibles(&local_item(1, 7)),
+			Err(MatchError::AssetNotFound)
+		);
+	});
+}
+
+#[test]
+fn only_root_sets_exportable_collections() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(XcmCollections::set_exportable(Origin::signed(ALICE), 0, true), BadOrigin);
+
+		assert_ok!(XcmCollections::set_exportable(Origin::root(), 0, true));
+		assert!(XcmCollections::is_exportable(0));
+		System::assert_last_event(Event::XcmCollections(XcmCollectionsEvent::ExportableSet(
+			0, true,
+		)));
+
+		assert_ok!(XcmCollections::set_exportable(Origin::root(), 0, false));
+		assert!(!XcmCollections::is_exportable(0));
+	});
+}
+
+#[test]
+fn only_exportable_collections_leave_the_chain() {
+	new_test_ext().execute_with(|| {
+		create_class(0);
+		assert_ok!(Uniques::mint(Origin::signed(ALICE), 0, 7, ALICE));
+
+		assert_eq!(
+			Transactor::transfer_asset(&local_item(0, 7), &alice(), &sibling(SIBLING_PARA_ID))
+				.map(|_| ()),
+			Err(XcmError::FailedToTransactAsset("collection may not leave the chain"))
+		);
+		assert_eq!(owner(0, 7), Some(ALICE));
+
+		assert_ok!(XcmCollections::set_exportable(Origin::root(), 0, true));
+		assert_ok!(Transactor::transfer_asset(
+			&local_item(0, 7),
+			&alice(),
+			&sibling(SIBLING_PARA_ID)
+		));
+		assert_eq!(owner(0, 7), Some(SIBLING_ACCOUNT_OFFSET + SIBLING_PARA_ID as u64));
+	});
+}
+
+#[test]
+fn exported_items_come_back() {
+	new_test_ext().execute_with(|| {
+		create_class(0);
+		assert_ok!(XcmCollections::set_exportable(Origin::root(), 0, true));
+		assert_ok!(Uniques::mint(Origin::signed(ALICE), 0, 7, ALICE));
+		assert_ok!(Transactor::transfer_asset(
+			&local_item(0, 7),
+			&alice(),
+			&sibling(SIBLING_PARA_ID)
+		));
+		assert_ok!(XcmCollections::set_exportable(Origin::root(), 0, false));
+
+		assert_ok!(Transactor::withdraw_asset(&local_item(0, 7), &sibling(SIBLING_PARA_ID)));
+		assert_ok!(Transactor::deposit_asset(&local_item(0, 7), &alice()));
+		assert_eq!(owner(0, 7), Some(ALICE));
+	});
+}
+
+#[test]
+fn derivatives_are_minted_and_burnt() {
+	new_test_ext().execute_with(|| {
+		register_derivative(1);
+
+		assert_ok!(Transactor::deposit_asset(&foreign_item(5), &alice()));
+		assert_eq!(owner(1, 5), Some(ALICE));
+		assert_eq!(
+			Transactor::deposit_asset(&foreign_item(6), &sibling(SIBLING_PARA_ID)),
+			Err(XcmError::FailedToTransactAsset("collection may not leave the chain"))
+		);
+
+		assert_ok!(Transactor::withdraw_asset(&foreign_item(5), &alice()));
+		assert_eq!(owner(1, 5), None);
+	});
+}
@@ -0,0 +1,19 @@
+//! Runtime API definition for the outcome of outbound XCM messages.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+use xcm::latest::QueryId;
+
+pub use pallet_xcm_outcomes::{OutcomeRecord, OutcomeStatus};
+
+sp_api::decl_runtime_apis! {
+	/// Look up whether the messages our pallets sent were executed by their destination.
+	pub trait XcmOutcomesApi<BlockNumber> where
+		BlockNumber: Codec,
+	{
+		/// The outcome of the message tracked under `query_id`, `None` if there is no such query or
+		/// its record was pruned.
+		fn outcome(query_id: QueryId) -> Option<OutcomeRecord<BlockNumber>>;
+	}
+}
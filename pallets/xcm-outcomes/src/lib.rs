@@ -0,0 +1,344 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Outcomes of outbound XCM messages.
+//!
+//! Pallets sending messages to other chains ask [`ReportOutcome`] for an appendix to put in their
+//! message, right after it buys execution. The appendix makes the destination report the outcome
+//! of the message with `ReportError`, and the assets left in holding with `QueryHolding`, each
+//! answering a notify query of `pallet_xcm`. The holding shows what the destination trapped when
+//! the message failed.
+//!
+//! The pallet keeps an [`OutcomeRecord`] per query, pending until `pallet_xcm` dispatches the
+//! response to `outcome_reported`, and emits an event once the outcome is known. Messages without
+//! a response by their timeout are marked timed out. Known outcomes, timeouts included, are handed
+//! to [`OnOutcome`], for the senders to follow up on their messages.
+//!
+//! Records are pruned in `on_idle`, `OutcomeRetention` blocks after their timeout. Responses
+//! arriving after the timeout are still recorded until then.
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+use codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use sp_runtime::RuntimeDebug;
+use xcm::latest::prelude::*;
+
+/// Outcome of a message, as reported by its destination.
+#[derive(Encode, Decode, Clone, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+pub enum OutcomeStatus {
+	/// No response was received yet.
+	Pending,
+	/// The message was executed.
+	Succeeded,
+	/// The instruction at the index failed with the error.
+	Failed(u32, XcmError),
+	/// The destination answered with something else than an execution result.
+	UnexpectedResponse,
+	/// No response was received by the timeout.
+	TimedOut,
+}
+
+/// A message whose outcome is tracked.
+#[derive(Encode, Decode, Clone, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+pub struct OutcomeRecord<BlockNumber> {
+	/// The chain executing the message.
+	pub responder: MultiLocation,
+	/// When the message was sent.
+	pub sent_at: BlockNumber,
+	/// When the outcome should be known by.
+	pub timeout: BlockNumber,
+	/// The outcome so far.
+	pub status: OutcomeStatus,
+	/// The query the assets left in holding are reported under.
+	pub holding_query_id: QueryId,
+	/// The assets left in holding at the end of the message, once reported.
+	pub holding: Option<MultiAssets>,
+}
+
+/// Handles the outcomes of messages, once known.
+pub trait OnOutcome {
+	/// The outcome of the message tracked under `query_id` is known.
+	fn on_outcome(query_id: QueryId, status: &OutcomeStatus);
+}
+
+impl OnOutcome for () {
+	fn on_outcome(_: QueryId, _: &OutcomeStatus) {}
+}
+
+/// Tracks the outcome of outbound messages.
+pub trait ReportOutcome {
+	/// Start tracking a message executed by `responder`. Returns the query the outcome is tracked
+	/// under, and the instructions reporting the outcome, for a `SetAppendix` of the message.
+	fn report_outcome(responder: MultiLocation) -> Result<(QueryId, Xcm<()>), XcmError>;
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::{OnOutcome, OutcomeRecord, OutcomeStatus, ReportOutcome};
+	use frame_support::{
+		dispatch::DispatchResultWithPostInfo, pallet_prelude::*, weights::GetDispatchInfo,
+	};
+	use frame_system::pallet_prelude::*;
+	use sp_runtime::traits::{One, Saturating};
+	use sp_std::prelude::*;
+	use xcm::latest::prelude::*;
+	use xcm_executor::traits::InvertLocation;
+
+	/// Configure the pallet by specifying the parameters and types on which it depends.
+	#[pallet::config]
+	pub trait Config: frame_system::Config + pallet_xcm::Config {
+		/// Because this pallet emits events, it depends on the runtime's definition of an event.
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// The runtime origin, which `pallet_xcm` dispatches responses with.
+		type Origin: IsType<<Self as frame_system::Config>::Origin>
+			+ Into<Result<pallet_xcm::Origin, <Self as Config>::Origin>>;
+
+		/// The runtime call, which `pallet_xcm` dispatches responses to.
+		type Call: IsType<<Self as pallet_xcm::Config>::Call> + From<Call<Self>>;
+
+		/// How long after sending a message its outcome should be known.
+		#[pallet::constant]
+		type OutcomeTimeout: Get<Self::BlockNumber>;
+
+		/// How long after its timeout a record is kept.
+		#[pallet::constant]
+		type OutcomeRetention: Get<Self::BlockNumber>;
+
+		/// Follows up on the outcomes of messages.
+		type OnOutcome: OnOutcome;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	/// The tracked messages, by query id.
+	#[pallet::storage]
+	#[pallet::getter(fn outcomes)]
+	pub type Outcomes<T: Config> =
+		StorageMap<_, Blake2_128Concat, QueryId, OutcomeRecord<T::BlockNumber>>;
+
+	/// The tracked messages by the query their holding is reported under.
+	#[pallet::storage]
+	pub type HoldingQueries<T: Config> = StorageMap<_, Twox64Concat, QueryId, QueryId>;
+
+	/// The records to time out or prune, by block.
+	#[pallet::storage]
+	pub type Expiries<T: Config> =
+		StorageDoubleMap<_, Twox64Concat, T::BlockNumber, Twox64Concat, QueryId, ()>;
+
+	/// The first block whose expiries are not processed yet.
+	#[pallet::storage]
+	pub type NextExpiry<T: Config> = StorageValue<_, T::BlockNumber>;
+
+	// Pallets use events to inform users when important changes are made.
+	// https://docs.substrate.io/v3/runtime/events-and-errors
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// The outcome of a message is tracked. [query_id, responder]
+		OutcomeExpected(QueryId, MultiLocation),
+		/// A message was executed. [query_id]
+		Succeeded(QueryId),
+		/// A message failed to execute. [query_id, instruction_index, error]
+		Failed(QueryId, u32, XcmError),
+		/// The response to a query was not an execution result. [query_id]
+		UnexpectedResponse(QueryId),
+		/// No response to a query arrived by its timeout. [query_id]
+		TimedOut(QueryId),
+		/// The assets left in holding by a message were reported. [query_id, assets]
+		HoldingReported(QueryId, MultiAssets),
+		/// The record of a message was pruned. [query_id]
+		Pruned(QueryId),
+	}
+
+	// Errors inform users that something went wrong.
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The query is not tracked.
+		UnknownQuery,
+		/// The response is not the assets in holding.
+		UnexpectedResponse,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_idle(now: T::BlockNumber, remaining_weight: Weight) -> Weight {
+			let db = T::DbWeight::get();
+			let mut used = db.reads_writes(1, 1);
+			if used > remaining_weight {
+				return 0
+			}
+			let mut block = match <NextExpiry<T>>::get() {
+				Some(block) => block,
+				None => return db.reads(1),
+			};
+			// Timing out a record, with the follow-up of its sender, or pruning it.
+			let expiry_weight = db.reads_writes(4, 4);
+			while block <= now && used.saturating_add(expiry_weight) <= remaining_weight {
+				match <Expiries<T>>::iter_key_prefix(block).next() {
+					Some(query_id) => {
+						<Expiries<T>>::remove(block, query_id);
+						Self::expire(block, query_id);
+						used = used.saturating_add(expiry_weight);
+					},
+					None => {
+						block = block.saturating_add(One::one());
+						used = used.saturating_add(db.reads(1));
+					},
+				}
+			}
+			<NextExpiry<T>>::put(block);
+			used
+		}
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Record the response to `query_id`. Dispatched by `pallet_xcm` on behalf of the
+		/// responder.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(2, 2))]
+		pub fn outcome_reported(
+			origin: OriginFor<T>,
+			query_id: QueryId,
+			response: Response,
+		) -> DispatchResultWithPostInfo {
+			pallet_xcm::ensure_response(<T as Config>::Origin::from(origin))?;
+
+			let status = match response {
+				Response::ExecutionResult(None) => OutcomeStatus::Succeeded,
+				Response::ExecutionResult(Some((index, error))) =>
+					OutcomeStatus::Failed(index, error),
+				_ => OutcomeStatus::UnexpectedResponse,
+			};
+			<Outcomes<T>>::try_mutate(query_id, |record| {
+				let record = record.as_mut().ok_or(Error::<T>::UnknownQuery)?;
+				record.status = status.clone();
+				Ok::<_, Error<T>>(())
+			})?;
+			T::OnOutcome::on_outcome(query_id, &status);
+			Self::deposit_event(match status {
+				OutcomeStatus::Failed(index, error) => Event::Failed(query_id, index, error),
+				OutcomeStatus::UnexpectedResponse => Event::UnexpectedResponse(query_id),
+				_ => Event::Succeeded(query_id),
+			});
+			Ok(().into())
+		}
+
+		/// Record the assets left in holding reported under `query_id`. Dispatched by
+		/// `pallet_xcm` on behalf of the responder.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(2, 1))]
+		pub fn holding_reported(
+			origin: OriginFor<T>,
+			query_id: QueryId,
+			response: Response,
+		) -> DispatchResultWithPostInfo {
+			pallet_xcm::ensure_response(<T as Config>::Origin::from(origin))?;
+
+			let outcome_query_id =
+				<HoldingQueries<T>>::get(query_id).ok_or(Error::<T>::UnknownQuery)?;
+			let assets = match response {
+				Response::Assets(assets) => assets,
+				_ => return Err(Error::<T>::UnexpectedResponse.into()),
+			};
+			<Outcomes<T>>::try_mutate(outcome_query_id, |record| {
+				let record = record.as_mut().ok_or(Error::<T>::UnknownQuery)?;
+				record.holding = Some(assets.clone());
+				Ok::<_, Error<T>>(())
+			})?;
+			Self::deposit_event(Event::HoldingReported(outcome_query_id, assets));
+			Ok(().into())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// Time out the record of `query_id` if its response is still pending at its timeout
+		/// `block`, and keep it `OutcomeRetention` more blocks. Prune it after that.
+		fn expire(block: T::BlockNumber, query_id: QueryId) {
+			let mut record = match <Outcomes<T>>::get(query_id) {
+				Some(record) => record,
+				None => return,
+			};
+			if record.status == OutcomeStatus::Pending {
+				record.status = OutcomeStatus::TimedOut;
+				T::OnOutcome::on_outcome(query_id, &record.status);
+				Self::deposit_event(Event::TimedOut(query_id));
+			}
+			let prune_at = record.timeout.saturating_add(T::OutcomeRetention::get());
+			if block < prune_at {
+				<Outcomes<T>>::insert(query_id, record);
+				<Expiries<T>>::insert(prune_at, query_id, ());
+			} else {
+				<Outcomes<T>>::remove(query_id);
+				<HoldingQueries<T>>::remove(record.holding_query_id);
+				Self::deposit_event(Event::Pruned(query_id));
+			}
+		}
+
+		/// Process the expiries from `block` on.
+		fn expire_from(block: T::BlockNumber) {
+			if <NextExpiry<T>>::get().map_or(true, |next| block < next) {
+				<NextExpiry<T>>::put(block);
+			}
+		}
+	}
+
+	impl<T: Config> ReportOutcome for Pallet<T> {
+		fn report_outcome(responder: MultiLocation) -> Result<(QueryId, Xcm<()>), XcmError> {
+			let dest = <T as pallet_xcm::Config>::LocationInverter::invert_location(&responder)
+				.map_err(|()| XcmError::MultiLocationNotInvertible)?;
+			// `pallet_xcm` fills in the query id and the response.
+			let notify = Call::<T>::outcome_reported { query_id: 0, response: Response::Null };
+			let max_response_weight = notify.get_dispatch_info().weight;
+			let notify_holding =
+				Call::<T>::holding_reported { query_id: 0, response: Response::Null };
+			let max_holding_weight = notify_holding.get_dispatch_info().weight;
+			let now = frame_system::Pallet::<T>::block_number();
+			let timeout = now.saturating_add(T::OutcomeTimeout::get());
+			let query_id = pallet_xcm::Pallet::<T>::new_notify_query(
+				responder.clone(),
+				<T as Config>::Call::from(notify),
+				timeout,
+			);
+			let holding_query_id = pallet_xcm::Pallet::<T>::new_notify_query(
+				responder.clone(),
+				<T as Config>::Call::from(notify_holding),
+				timeout,
+			);
+
+			<Outcomes<T>>::insert(
+				query_id,
+				OutcomeRecord {
+					responder: responder.clone(),
+					sent_at: now,
+					timeout,
+					status: OutcomeStatus::Pending,
+					holding_query_id,
+					holding: None,
+				},
+			);
+			<HoldingQueries<T>>::insert(holding_query_id, query_id);
+			<Expiries<T>>::insert(timeout, query_id, ());
+			Self::expire_from(timeout);
+			Self::deposit_event(Event::OutcomeExpected(query_id, responder));
+			Ok((
+				query_id,
+				Xcm(vec![
+					ReportError { query_id, dest: dest.clone(), max_response_weight },
+					QueryHolding {
+						query_id: holding_query_id,
+						dest,
+						assets: Wild(All),
+						max_response_weight: max_holding_weight,
+					},
+				]),
+			))
+		}
+	}
+}
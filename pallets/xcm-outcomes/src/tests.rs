@@ -0,0 +1,231 @@
+use crate::{
+	mock::*, Error, Event as XcmOutcomesEvent, OutcomeRecord, OutcomeStatus, ReportOutcome,
+};
+use frame_support::{
+	assert_noop,
+	traits::Hooks,
+	weights::{GetDispatchInfo, Weight},
+};
+use sp_runtime::traits::BadOrigin;
+use xcm::latest::prelude::*;
+use xcm_executor::traits::OnResponse;
+
+fn sibling(id: u32) -> MultiLocation {
+	MultiLocation::new(1, X1(Parachain(id)))
+}
+
+fn report(responder: MultiLocation) -> QueryId {
+	XcmOutcomes::report_outcome(responder).unwrap().0
+}
+
+fn respond(responder: MultiLocation, query_id: QueryId, response: Response) {
+	PolkadotXcm::on_response(&responder, query_id, response, Weight::max_value());
+}
+
+fn status(query_id: QueryId) -> OutcomeStatus {
+	XcmOutcomes::outcomes(query_id).unwrap().status
+}
+
+#[test]
+fn messages_report_their_outcome_to_us() {
+	new_test_ext().execute_with(|| {
+		let (query_id, appendix) = XcmOutcomes::report_outcome(sibling(2000)).unwrap();
+
+		let notify =
+			crate::Call::<Test>::outcome_reported { query_id: 0, response: Response::Null };
+		let notify_holding =
+			crate::Call::<Test>::holding_reported { query_id: 0, response: Response::Null };
+		assert_eq!(
+			appendix,
+			Xcm(vec![
+				ReportError {
+					query_id,
+					dest: sibling(SELF_PARA_ID),
+					max_response_weight: notify.get_dispatch_info().weight,
+				},
+				QueryHolding {
+					query_id: query_id + 1,
+					dest: sibling(SELF_PARA_ID),
+					assets: Wild(All),
+					max_response_weight: notify_holding.get_dispatch_info().weight,
+				},
+			])
+		);
+		assert_eq!(
+			XcmOutcomes::outcomes(query_id),
+			Some(OutcomeRecord {
+				responder: sibling(2000),
+				sent_at: 1,
+				timeout: 1 + OutcomeTimeout::get(),
+				status: OutcomeStatus::Pending,
+				holding_query_id: query_id + 1,
+				holding: None,
+			})
+		);
+		System::assert_last_event(Event::XcmOutcomes(XcmOutcomesEvent::OutcomeExpected(
+			query_id,
+			sibling(2000),
+		)));
+
+		assert_ne!(report(sibling(2000)), query_id);
+	});
+}
+
+#[test]
+fn successes_are_recorded() {
+	new_test_ext().execute_with(|| {
+		let query_id = report(MultiLocation::parent());
+
+		respond(MultiLocation::parent(), query_id, Response::ExecutionResult(None));
+
+		assert_eq!(status(query_id), OutcomeStatus::Succeeded);
+		assert_eq!(handled(), vec![(query_id, OutcomeStatus::Succeeded)]);
+		System::assert_last_event(Event::XcmOutcomes(XcmOutcomesEvent::Succeeded(query_id)));
+	});
+}
+
+#[test]
+fn failures_are_recorded() {
+	new_test_ext().execute_with(|| {
+		let query_id = report(sibling(2000));
+
+		respond(
+			sibling(2000),
+			query_id,
+			Response::ExecutionResult(Some((1, XcmError::TooExpensive))),
+		);
+
+		assert_eq!(status(query_id), OutcomeStatus::Failed(1, XcmError::TooExpensive));
+		assert_eq!(handled(), vec![(query_id, OutcomeStatus::Failed(1, XcmError::TooExpensive))]);
+		System::assert_last_event(Event::XcmOutcomes(XcmOutcomesEvent::Failed(
+			query_id,
+			1,
+			XcmError::TooExpensive,
+		)));
+	});
+}
+
+#[test]
+fn other_responses_are_unexpected() {
+	new_test_ext().execute_with(|| {
+		let query_id = report(sibling(2000));
+
+		respond(sibling(2000), query_id, Response::Version(2));
+
+		assert_eq!(status(query_id), OutcomeStatus::UnexpectedResponse);
+		System::assert_last_event(Event::XcmOutcomes(XcmOutcomesEvent::UnexpectedResponse(
+			query_id,
+		)));
+	});
+}
+
+#[test]
+fn only_the_responder_reports_outcomes() {
+	new_test_ext().execute_with(|| {
+		let query_id = report(sibling(2000));
+
+		respond(sibling(2001), query_id, Response::ExecutionResult(None));
+		assert_eq!(status(query_id), OutcomeStatus::Pending);
+		assert!(handled().is_empty());
+
+		assert_noop!(
+			XcmOutcomes::outcome_reported(
+				Origin::signed(1),
+				query_id,
+				Response::ExecutionResult(None)
+			),
+			BadOrigin
+		);
+		assert_noop!(
+			XcmOutcomes::outcome_reported(
+				pallet_xcm::Origin::Response(sibling(2000)).into(),
+				query_id + 1,
+				Response::ExecutionResult(None)
+			),
+			Error::<Test>::UnknownQuery
+		);
+	});
+}
+
+#[test]
+fn assets_left_in_holding_are_recorded() {
+	new_test_ext().execute_with(|| {
+		let query_id = report(sibling(2000));
+		let holding_query_id = XcmOutcomes::outcomes(query_id).unwrap().holding_query_id;
+		let assets: MultiAssets = (Parent, 10).into();
+
+		respond(sibling(2000), holding_query_id, Response::Assets(assets.clone()));
+
+		let record = XcmOutcomes::outcomes(query_id).unwrap();
+		assert_eq!(record.holding, Some(assets.clone()));
+		assert_eq!(record.status, OutcomeStatus::Pending);
+		System::assert_last_event(Event::XcmOutcomes(XcmOutcomesEvent::HoldingReported(
+			query_id, assets,
+		)));
+		assert_noop!(
+			XcmOutcomes::holding_reported(
+				pallet_xcm::Origin::Response(sibling(2000)).into(),
+				query_id,
+				Response::Assets(MultiAssets::new())
+			),
+			Error::<Test>::UnknownQuery
+		);
+	});
+}
+
+#[test]
+fn messages_without_response_time_out() {
+	new_test_ext().execute_with(|| {
+		let query_id = report(sibling(2000));
+		let answered = report(sibling(2000));
+		respond(sibling(2000), answered, Response::ExecutionResult(None));
+		let timeout = 1 + OutcomeTimeout::get();
+
+		XcmOutcomes::on_idle(timeout - 1, Weight::max_value());
+		assert_eq!(status(query_id), OutcomeStatus::Pending);
+
+		XcmOutcomes::on_idle(timeout, Weight::max_value());
+		assert_eq!(status(query_id), OutcomeStatus::TimedOut);
+		assert_eq!(status(answered), OutcomeStatus::Succeeded);
+		assert_eq!(
+			handled(),
+			vec![(answered, OutcomeStatus::Succeeded), (query_id, OutcomeStatus::TimedOut)]
+		);
+		System::assert_has_event(Event::XcmOutcomes(XcmOutcomesEvent::TimedOut(query_id)));
+
+		// Late responses are still recorded.
+		respond(sibling(2000), query_id, Response::ExecutionResult(None));
+		assert_eq!(status(query_id), OutcomeStatus::Succeeded);
+	});
+}
+
+#[test]
+fn records_are_pruned_after_their_retention() {
+	new_test_ext().execute_with(|| {
+		let query_id = report(sibling(2000));
+		let holding_query_id = XcmOutcomes::outcomes(query_id).unwrap().holding_query_id;
+		let prune_at = 1 + OutcomeTimeout::get() + OutcomeRetention::get();
+
+		XcmOutcomes::on_idle(prune_at - 1, Weight::max_value());
+		assert_eq!(status(query_id), OutcomeStatus::TimedOut);
+
+		XcmOutcomes::on_idle(prune_at, Weight::max_value());
+		assert_eq!(XcmOutcomes::outcomes(query_id), None);
+		assert_eq!(crate::HoldingQueries::<Test>::get(holding_query_id), None);
+		System::assert_last_event(Event::XcmOutcomes(XcmOutcomesEvent::Pruned(query_id)));
+	});
+}
+
+#[test]
+fn expiries_wait_for_idle_weight() {
+	new_test_ext().execute_with(|| {
+		let query_id = report(sibling(2000));
+		let timeout = 1 + OutcomeTimeout::get();
+
+		assert_eq!(XcmOutcomes::on_idle(timeout, 0), 0);
+		assert_eq!(status(query_id), OutcomeStatus::Pending);
+
+		XcmOutcomes::on_idle(timeout + 1, Weight::max_value());
+		assert_eq!(status(query_id), OutcomeStatus::TimedOut);
+	});
+}
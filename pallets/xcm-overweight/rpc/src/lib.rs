@@ -0,0 +1,73 @@
+//! RPC interface for the overweight XCMP and DMP messages.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+
+pub use pallet_xcm_overweight_runtime_api::{
+	MessageOrigin, OverweightIndex, OverweightMessage, XcmOverweightApi as XcmOverweightRuntimeApi,
+};
+
+#[rpc]
+pub trait XcmOverweightApi<BlockHash> {
+	/// The messages the queues parked for being too heavy, with their origin and size.
+	#[rpc(name = "xcmOverweight_messages")]
+	fn overweight_messages(&self, at: Option<BlockHash>) -> Result<Vec<OverweightMessage>>;
+}
+
+/// Provides RPC methods to list overweight messages.
+pub struct XcmOverweight<C, B> {
+	/// Shared reference to the client.
+	client: Arc<C>,
+	_marker: PhantomData<B>,
+}
+
+impl<C, B> XcmOverweight<C, B> {
+	/// Creates a new instance of the XcmOverweight Rpc helper.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client, _marker: Default::default() }
+	}
+}
+
+/// Error type of this RPC api.
+pub enum Error {
+	/// The call to runtime failed.
+	RuntimeError,
+}
+
+impl From<Error> for i64 {
+	fn from(e: Error) -> i64 {
+		match e {
+			Error::RuntimeError => 1,
+		}
+	}
+}
+
+fn runtime_error(e: impl std::fmt::Debug) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(Error::RuntimeError.into()),
+		message: "Unable to list overweight messages.".into(),
+		data: Some(format!("{:?}", e).into()),
+	}
+}
+
+impl<C, Block> XcmOverweightApi<<Block as BlockT>::Hash> for XcmOverweight<C, Block>
+where
+	Block: BlockT,
+	C: 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+	C::Api: XcmOverweightRuntimeApi<Block>,
+{
+	fn overweight_messages(
+		&self,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Vec<OverweightMessage>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		api.overweight_messages(&at).map_err(runtime_error)
+	}
+}
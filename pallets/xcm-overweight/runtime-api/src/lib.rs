@@ -0,0 +1,15 @@
+//! Runtime API definition for the overweight XCMP and DMP messages.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use sp_std::vec::Vec;
+
+pub use pallet_xcm_overweight::{MessageOrigin, OverweightIndex, OverweightMessage};
+
+sp_api::decl_runtime_apis! {
+	/// List the messages the queues parked for being too heavy.
+	pub trait XcmOverweightApi {
+		/// The messages waiting to be serviced, those of the relay chain first.
+		fn overweight_messages() -> Vec<OverweightMessage>;
+	}
+}
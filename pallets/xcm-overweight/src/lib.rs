@@ -0,0 +1,436 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Overweight messages.
+//!
+//! `XcmpQueue` and `DmpQueue` park the messages heavier than they may execute at once, until root
+//! services them with `service_overweight`. This pallet services them in `on_idle` instead, with
+//! the weight left in the block.
+//!
+//! Governance gives each origin a budget, the weight its overweight messages may use per block.
+//! Origins without a budget are not serviced automatically. The messages are weighed like the
+//! executor does, and only serviced when their origin has enough budget left. Each queue is walked
+//! over the messages parked in it, in the order of its storage, from where the previous block
+//! stopped, so a message too heavy for its budget does not hold back the messages after it.
+//! Nothing is looked at while no origin has a budget.
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+use codec::{Decode, Encode};
+use core::marker::PhantomData;
+use cumulus_primitives_core::ParaId;
+use frame_support::{
+	dispatch::DispatchError,
+	storage::{
+		migration::{get_storage_value, storage_key_iter},
+		storage_prefix,
+	},
+	traits::PalletInfoAccess,
+	weights::Weight,
+	Blake2_128Concat, ReversibleStorageHasher, StorageHasher, Twox64Concat,
+};
+use scale_info::TypeInfo;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_runtime::RuntimeDebug;
+use sp_std::prelude::*;
+
+/// The index a queue parks an overweight message under.
+pub type OverweightIndex = u64;
+
+/// Where an overweight message comes from, which tells the queue it is parked in.
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Encode, Decode, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub enum MessageOrigin {
+	/// The relay chain, through `DmpQueue`.
+	Relay,
+	/// A sibling parachain, through `XcmpQueue`.
+	Sibling(u32),
+}
+
+/// A message waiting to be serviced.
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct OverweightMessage {
+	/// Where the message comes from.
+	pub origin: MessageOrigin,
+	/// The index the message is parked under.
+	pub index: OverweightIndex,
+	/// The encoded size of the message.
+	pub size: u32,
+}
+
+/// A queue parking overweight messages.
+pub trait OverweightQueue {
+	/// The number of messages ever parked, which is also the next index.
+	fn overweight_count() -> OverweightIndex;
+
+	/// The message parked under `index`, with its origin.
+	fn overweight(index: OverweightIndex) -> Option<(MessageOrigin, Vec<u8>)>;
+
+	/// The index of the message parked after the one of `index` in the order of the queue's
+	/// storage, whether `index` is still parked or not. The first one if `index` is `None`, `None`
+	/// past the last one.
+	fn next_overweight(index: Option<OverweightIndex>) -> Option<OverweightIndex>;
+
+	/// The messages parked and not serviced yet.
+	fn overweight_messages() -> Vec<OverweightMessage>;
+
+	/// Execute the message parked under `index` with up to `weight_limit`. Returns the weight
+	/// used.
+	fn service_overweight(
+		index: OverweightIndex,
+		weight_limit: Weight,
+	) -> Result<Weight, DispatchError>;
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::{MessageOrigin, OverweightIndex, OverweightMessage, OverweightQueue};
+	use codec::DecodeLimit;
+	use frame_support::{dispatch::DispatchResultWithPostInfo, pallet_prelude::*};
+	use frame_system::pallet_prelude::*;
+	use sp_runtime::traits::Saturating;
+	use sp_std::{
+		collections::{btree_map::BTreeMap, btree_set::BTreeSet},
+		prelude::*,
+	};
+	use xcm::{latest::Xcm, VersionedXcm, MAX_XCM_DECODE_DEPTH};
+	use xcm_executor::traits::WeightBounds;
+
+	/// Configure the pallet by specifying the parameters and types on which it depends.
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// Because this pallet emits events, it depends on the runtime's definition of an event.
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// Origin allowed to set the budgets.
+		type UpdateOrigin: EnsureOrigin<Self::Origin>;
+
+		/// The runtime call, which messages may transact.
+		type Call: Decode;
+
+		/// Weighs messages, as the executor does.
+		type Weigher: WeightBounds<<Self as Config>::Call>;
+
+		/// The queue of the messages from siblings.
+		type XcmpQueue: OverweightQueue;
+
+		/// The queue of the messages from the relay chain.
+		type DmpQueue: OverweightQueue;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	/// The weight the overweight messages of an origin may use per block.
+	#[pallet::storage]
+	#[pallet::getter(fn budget)]
+	pub type Budgets<T: Config> = StorageMap<_, Twox64Concat, MessageOrigin, Weight>;
+
+	/// The index of the last message of `XcmpQueue` looked at, the walk resumes after it.
+	#[pallet::storage]
+	pub type LastXcmpIndex<T> = StorageValue<_, OverweightIndex>;
+
+	/// The index of the last message of `DmpQueue` looked at, the walk resumes after it.
+	#[pallet::storage]
+	pub type LastDmpIndex<T> = StorageValue<_, OverweightIndex>;
+
+	// Pallets use events to inform users when important changes are made.
+	// https://docs.substrate.io/v3/runtime/events-and-errors
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// The budget of an origin changed. [origin, budget]
+		BudgetSet(MessageOrigin, Option<Weight>),
+		/// An overweight message was executed. [origin, index, weight_used]
+		OverweightServiced(MessageOrigin, OverweightIndex, Weight),
+		/// An overweight message could not be executed and stays parked. [origin, index, error]
+		ServiceFailed(MessageOrigin, OverweightIndex, DispatchError),
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_idle(_now: T::BlockNumber, remaining_weight: Weight) -> Weight {
+			// Whether any origin has a budget.
+			let checked = T::DbWeight::get().reads(1);
+			if checked > remaining_weight {
+				return 0
+			}
+			if <Budgets<T>>::iter_keys().next().is_none() {
+				return checked
+			}
+			let remaining_weight = remaining_weight - checked;
+
+			let mut spent = BTreeMap::new();
+			// The relay chain first.
+			let (dmp_used, last_dmp) = Self::service_queue::<T::DmpQueue>(
+				<LastDmpIndex<T>>::get(),
+				remaining_weight,
+				&mut spent,
+			);
+			<LastDmpIndex<T>>::set(last_dmp);
+			let (xcmp_used, last_xcmp) = Self::service_queue::<T::XcmpQueue>(
+				<LastXcmpIndex<T>>::get(),
+				remaining_weight.saturating_sub(dmp_used),
+				&mut spent,
+			);
+			<LastXcmpIndex<T>>::set(last_xcmp);
+			checked.saturating_add(dmp_used).saturating_add(xcmp_used)
+		}
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Let the overweight messages of `message_origin` use up to `budget` per block, or none
+		/// if `None`.
+		#[pallet::weight(10_000 + T::DbWeight::get().writes(1))]
+		pub fn set_budget(
+			origin: OriginFor<T>,
+			message_origin: MessageOrigin,
+			budget: Option<Weight>,
+		) -> DispatchResultWithPostInfo {
+			T::UpdateOrigin::ensure_origin(origin)?;
+
+			<Budgets<T>>::set(message_origin, budget);
+			Self::deposit_event(Event::BudgetSet(message_origin, budget));
+			Ok(().into())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// The messages waiting in both queues, those of the relay chain first.
+		pub fn overweight_messages() -> Vec<OverweightMessage> {
+			let mut messages = T::DmpQueue::overweight_messages();
+			messages.extend(T::XcmpQueue::overweight_messages());
+			messages
+		}
+
+		/// The weight `message` needs, `None` if it cannot be weighed.
+		pub fn weigh(message: &[u8]) -> Option<Weight> {
+			let versioned = VersionedXcm::<<T as Config>::Call>::decode_all_with_depth_limit(
+				MAX_XCM_DECODE_DEPTH,
+				&mut &message[..],
+			)
+			.ok()?;
+			let mut message = Xcm::<<T as Config>::Call>::try_from(versioned).ok()?;
+			T::Weigher::weight(&mut message).ok()
+		}
+
+		/// Walk the messages parked in `Queue` once at most, after `last`, servicing those whose
+		/// origin has budget left within `remaining_weight`. `spent` is what each origin used in
+		/// this block. Returns the weight used and the index of the last message looked at.
+		fn service_queue<Queue: OverweightQueue>(
+			last: Option<OverweightIndex>,
+			remaining_weight: Weight,
+			spent: &mut BTreeMap<MessageOrigin, Weight>,
+		) -> (Weight, Option<OverweightIndex>) {
+			let db_weight = T::DbWeight::get();
+			// The cursor, then writing it back.
+			let mut used = db_weight.reads_writes(1, 1);
+			if used > remaining_weight {
+				return (0, last)
+			}
+
+			let mut position = last;
+			let mut wrapped = false;
+			let mut looked_at = BTreeSet::new();
+			loop {
+				// The key of the next message.
+				let find = db_weight.reads(1);
+				if used.saturating_add(find) > remaining_weight {
+					break
+				}
+				used = used.saturating_add(find);
+				let index = match Queue::next_overweight(position) {
+					Some(index) => index,
+					// Past the last message, back to the first one.
+					None if position.is_some() && !wrapped => {
+						wrapped = true;
+						position = None;
+						continue
+					},
+					None => break,
+				};
+				// Back to a message looked at, the walk is over.
+				if !looked_at.insert(index) {
+					break
+				}
+
+				// The message and the budget of its origin.
+				let read = db_weight.reads(2);
+				if used.saturating_add(read) > remaining_weight {
+					break
+				}
+				used = used.saturating_add(read);
+				position = Some(index);
+
+				if let Some((origin, message)) = Queue::overweight(index) {
+					let budget_left = Self::budget(origin)
+						.unwrap_or(0)
+						.saturating_sub(spent.get(&origin).copied().unwrap_or(0));
+					// Servicing removes the message and emits an event.
+					let overhead = db_weight.reads_writes(1, 2);
+					match Self::weigh(&message) {
+						Some(required)
+							if required <= budget_left &&
+								used.saturating_add(overhead).saturating_add(required) <=
+									remaining_weight =>
+						{
+							let weight_used = match Queue::service_overweight(index, required) {
+								Ok(weight_used) => {
+									Self::deposit_event(Event::OverweightServiced(
+										origin,
+										index,
+										weight_used,
+									));
+									weight_used
+								},
+								Err(error) => {
+									Self::deposit_event(Event::ServiceFailed(origin, index, error));
+									required
+								},
+							};
+							used = used.saturating_add(overhead).saturating_add(weight_used);
+							*spent.entry(origin).or_default() += weight_used;
+						},
+						_ => (),
+					}
+				}
+			}
+
+			(used, position.or(last))
+		}
+	}
+}
+
+/// The index of the entry of the `Overweight` map of `pallet`, hashed with `Hasher`, following the
+/// key of `index`, the first one if `None`.
+fn next_overweight_index<Hasher: ReversibleStorageHasher>(
+	pallet: &[u8],
+	index: Option<OverweightIndex>,
+) -> Option<OverweightIndex> {
+	let prefix = storage_prefix(pallet, b"Overweight");
+	let mut key = prefix.to_vec();
+	if let Some(index) = index {
+		key.extend_from_slice(Hasher::hash(&index.encode()).as_ref());
+	}
+	let next = sp_io::storage::next_key(&key).filter(|next| next.starts_with(&prefix))?;
+	OverweightIndex::decode(&mut Hasher::reverse(&next[prefix.len()..])).ok()
+}
+
+/// The overweight messages of `cumulus_pallet_xcmp_queue`, which keeps its storage private.
+pub struct XcmpOverweight<T>(PhantomData<T>);
+
+impl<T: cumulus_pallet_xcmp_queue::Config> XcmpOverweight<T> {
+	fn pallet_name() -> &'static [u8] {
+		<cumulus_pallet_xcmp_queue::Pallet<T> as PalletInfoAccess>::name().as_bytes()
+	}
+}
+
+impl<T: cumulus_pallet_xcmp_queue::Config> OverweightQueue for XcmpOverweight<T> {
+	fn overweight_count() -> OverweightIndex {
+		get_storage_value(Self::pallet_name(), b"OverweightCount", &[]).unwrap_or_default()
+	}
+
+	fn overweight(index: OverweightIndex) -> Option<(MessageOrigin, Vec<u8>)> {
+		let key = Twox64Concat::hash(&index.encode());
+		let (sender, _sent_at, message): (ParaId, u32, Vec<u8>) =
+			get_storage_value(Self::pallet_name(), b"Overweight", &key)?;
+		Some((MessageOrigin::Sibling(sender.into()), message))
+	}
+
+	fn next_overweight(index: Option<OverweightIndex>) -> Option<OverweightIndex> {
+		next_overweight_index::<Twox64Concat>(Self::pallet_name(), index)
+	}
+
+	fn overweight_messages() -> Vec<OverweightMessage> {
+		storage_key_iter::<OverweightIndex, (ParaId, u32, Vec<u8>), Twox64Concat>(
+			Self::pallet_name(),
+			b"Overweight",
+		)
+		.map(|(index, (sender, _sent_at, message))| OverweightMessage {
+			origin: MessageOrigin::Sibling(sender.into()),
+			index,
+			size: message.len() as u32,
+		})
+		.collect()
+	}
+
+	fn service_overweight(
+		index: OverweightIndex,
+		weight_limit: Weight,
+	) -> Result<Weight, DispatchError> {
+		// `ExecuteOverweightOrigin` must accept root.
+		cumulus_pallet_xcmp_queue::Pallet::<T>::service_overweight(
+			frame_system::RawOrigin::Root.into(),
+			index,
+			weight_limit,
+		)
+		.map(|info| info.actual_weight.unwrap_or(weight_limit))
+		.map_err(|e| e.error)
+	}
+}
+
+/// The overweight messages of `cumulus_pallet_dmp_queue`, which keeps its storage private.
+pub struct DmpOverweight<T>(PhantomData<T>);
+
+impl<T: cumulus_pallet_dmp_queue::Config> DmpOverweight<T> {
+	fn pallet_name() -> &'static [u8] {
+		<cumulus_pallet_dmp_queue::Pallet<T> as PalletInfoAccess>::name().as_bytes()
+	}
+}
+
+impl<T: cumulus_pallet_dmp_queue::Config> OverweightQueue for DmpOverweight<T> {
+	fn overweight_count() -> OverweightIndex {
+		// `PageIndexData { begin_used, end_used, overweight_count }`, whose fields are private.
+		get_storage_value::<(u32, u32, OverweightIndex)>(Self::pallet_name(), b"PageIndex", &[])
+			.map(|(_, _, overweight_count)| overweight_count)
+			.unwrap_or_default()
+	}
+
+	fn overweight(index: OverweightIndex) -> Option<(MessageOrigin, Vec<u8>)> {
+		let key = Blake2_128Concat::hash(&index.encode());
+		let (_sent_at, message): (u32, Vec<u8>) =
+			get_storage_value(Self::pallet_name(), b"Overweight", &key)?;
+		Some((MessageOrigin::Relay, message))
+	}
+
+	fn next_overweight(index: Option<OverweightIndex>) -> Option<OverweightIndex> {
+		next_overweight_index::<Blake2_128Concat>(Self::pallet_name(), index)
+	}
+
+	fn overweight_messages() -> Vec<OverweightMessage> {
+		storage_key_iter::<OverweightIndex, (u32, Vec<u8>), Blake2_128Concat>(
+			Self::pallet_name(),
+			b"Overweight",
+		)
+		.map(|(index, (_sent_at, message))| OverweightMessage {
+			origin: MessageOrigin::Relay,
+			index,
+			size: message.len() as u32,
+		})
+		.collect()
+	}
+
+	fn service_overweight(
+		index: OverweightIndex,
+		weight_limit: Weight,
+	) -> Result<Weight, DispatchError> {
+		// `ExecuteOverweightOrigin` must accept root.
+		cumulus_pallet_dmp_queue::Pallet::<T>::service_overweight(
+			frame_system::RawOrigin::Root.into(),
+			index,
+			weight_limit,
+		)
+		.map(|info| info.actual_weight.unwrap_or(weight_limit))
+		.map_err(|e| e.error)
+	}
+}
@@ -0,0 +1,173 @@
+use crate as pallet_xcm_overweight;
+use crate::{MessageOrigin, OverweightIndex, OverweightMessage, OverweightQueue};
+use codec::Encode;
+use frame_support::{
+	dispatch::DispatchError, parameter_types, traits::Everything, weights::Weight,
+};
+use frame_system::{self as system, EnsureRoot};
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, IdentityLookup},
+};
+use std::{cell::RefCell, collections::BTreeMap};
+use xcm::{latest::prelude::*, VersionedXcm};
+use xcm_builder::FixedWeightBounds;
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+// Configure a mock runtime to test the pallet.
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+		XcmOverweight: pallet_xcm_overweight::{Pallet, Call, Storage, Event<T>},
+	}
+);
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const SS58Prefix: u8 = 42;
+}
+
+impl system::Config for Test {
+	type BaseCallFilter = Everything;
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = BlockHashCount;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = SS58Prefix;
+	type OnSetCode = ();
+	type MaxConsumers = frame_support::traits::ConstU32<16>;
+}
+
+thread_local! {
+	pub static PARKED: RefCell<BTreeMap<(bool, OverweightIndex), (MessageOrigin, Vec<u8>)>> =
+		RefCell::new(BTreeMap::new());
+	pub static PARKED_COUNT: RefCell<(OverweightIndex, OverweightIndex)> = RefCell::new((0, 0));
+	pub static SERVICE_FAILS: RefCell<bool> = RefCell::new(false);
+	pub static LOOKUPS: RefCell<u32> = RefCell::new(0);
+}
+
+/// Parks the messages of the relay chain if `RELAY`, of siblings otherwise.
+pub struct TestQueue<const RELAY: bool>;
+impl<const RELAY: bool> OverweightQueue for TestQueue<RELAY> {
+	fn overweight_count() -> OverweightIndex {
+		PARKED_COUNT.with(|c| if RELAY { c.borrow().0 } else { c.borrow().1 })
+	}
+
+	fn overweight(index: OverweightIndex) -> Option<(MessageOrigin, Vec<u8>)> {
+		LOOKUPS.with(|l| *l.borrow_mut() += 1);
+		PARKED.with(|p| p.borrow().get(&(RELAY, index)).cloned())
+	}
+
+	fn next_overweight(index: Option<OverweightIndex>) -> Option<OverweightIndex> {
+		PARKED.with(|p| {
+			p.borrow()
+				.keys()
+				.filter(|(relay, _)| *relay == RELAY)
+				.map(|(_, next)| *next)
+				.find(|next| index.map_or(true, |index| *next > index))
+		})
+	}
+
+	fn overweight_messages() -> Vec<OverweightMessage> {
+		PARKED.with(|p| {
+			p.borrow()
+				.iter()
+				.filter(|((relay, _), _)| *relay == RELAY)
+				.map(|((_, index), (origin, message))| OverweightMessage {
+					origin: *origin,
+					index: *index,
+					size: message.len() as u32,
+				})
+				.collect()
+		})
+	}
+
+	fn service_overweight(
+		index: OverweightIndex,
+		weight_limit: Weight,
+	) -> Result<Weight, DispatchError> {
+		if SERVICE_FAILS.with(|f| *f.borrow()) {
+			return Err(DispatchError::BadOrigin);
+		}
+		PARKED
+			.with(|p| p.borrow_mut().remove(&(RELAY, index)))
+			.ok_or(DispatchError::CannotLookup)?;
+		Ok(weight_limit)
+	}
+}
+
+/// Park `message` from `origin` in its queue. Returns its index.
+pub fn park_raw(origin: MessageOrigin, message: Vec<u8>) -> OverweightIndex {
+	let relay = origin == MessageOrigin::Relay;
+	let index = PARKED_COUNT.with(|c| {
+		let mut c = c.borrow_mut();
+		let count = if relay { &mut c.0 } else { &mut c.1 };
+		*count += 1;
+		*count - 1
+	});
+	PARKED.with(|p| p.borrow_mut().insert((relay, index), (origin, message)));
+	index
+}
+
+/// Park a message of `instructions` instructions from `origin`. Returns its index.
+pub fn park(origin: MessageOrigin, instructions: usize) -> OverweightIndex {
+	park_raw(origin, VersionedXcm::from(Xcm::<Call>(vec![ClearOrigin; instructions])).encode())
+}
+
+pub fn parked(origin: MessageOrigin, index: OverweightIndex) -> bool {
+	PARKED.with(|p| p.borrow().contains_key(&(origin == MessageOrigin::Relay, index)))
+}
+
+/// The number of messages looked up since the last call.
+pub fn lookups() -> u32 {
+	LOOKUPS.with(|l| l.replace(0))
+}
+
+parameter_types! {
+	pub const UnitWeightCost: Weight = 1_000;
+	pub const MaxInstructions: u32 = 100;
+}
+
+impl pallet_xcm_overweight::Config for Test {
+	type Event = Event;
+	type UpdateOrigin = EnsureRoot<u64>;
+	type Call = Call;
+	type Weigher = FixedWeightBounds<UnitWeightCost, Call, MaxInstructions>;
+	type XcmpQueue = TestQueue<false>;
+	type DmpQueue = TestQueue<true>;
+}
+
+// Build genesis storage according to the mock runtime.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	PARKED.with(|p| p.borrow_mut().clear());
+	PARKED_COUNT.with(|c| *c.borrow_mut() = (0, 0));
+	SERVICE_FAILS.with(|f| *f.borrow_mut() = false);
+	LOOKUPS.with(|l| *l.borrow_mut() = 0);
+	let t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	let mut ext = sp_io::TestExternalities::new(t);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
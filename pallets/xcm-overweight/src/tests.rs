@@ -0,0 +1,169 @@
+use crate::{
+	mock::*, Event as XcmOverweightEvent, LastXcmpIndex, MessageOrigin, OverweightMessage,
+};
+use frame_support::{assert_noop, assert_ok, dispatch::DispatchError, traits::Hooks};
+use sp_runtime::traits::BadOrigin;
+
+const SIBLING: MessageOrigin = MessageOrigin::Sibling(2000);
+
+fn run_idle(remaining_weight: u64) -> u64 {
+	XcmOverweight::on_idle(1, remaining_weight)
+}
+
+fn set_budget(origin: MessageOrigin, budget: u64) {
+	assert_ok!(XcmOverweight::set_budget(Origin::root(), origin, Some(budget)));
+}
+
+#[test]
+fn only_root_sets_budgets() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(XcmOverweight::set_budget(Origin::signed(1), SIBLING, Some(1)), BadOrigin);
+
+		set_budget(SIBLING, 5_000);
+		assert_eq!(XcmOverweight::budget(SIBLING), Some(5_000));
+		System::assert_last_event(Event::XcmOverweight(XcmOverweightEvent::BudgetSet(
+			SIBLING,
+			Some(5_000),
+		)));
+
+		assert_ok!(XcmOverweight::set_budget(Origin::root(), SIBLING, None));
+		assert_eq!(XcmOverweight::budget(SIBLING), None);
+	});
+}
+
+#[test]
+fn overweight_messages_are_listed() {
+	new_test_ext().execute_with(|| {
+		park(SIBLING, 2);
+		park_raw(MessageOrigin::Relay, vec![0; 7]);
+
+		assert_eq!(
+			XcmOverweight::overweight_messages(),
+			vec![
+				OverweightMessage { origin: MessageOrigin::Relay, index: 0, size: 7 },
+				OverweightMessage { origin: SIBLING, index: 0, size: 4 },
+			]
+		);
+	});
+}
+
+#[test]
+fn messages_without_budget_are_not_serviced() {
+	new_test_ext().execute_with(|| {
+		park(SIBLING, 2);
+		set_budget(MessageOrigin::Sibling(2001), 5_000);
+
+		assert_eq!(run_idle(u64::MAX), 0);
+		assert!(parked(SIBLING, 0));
+	});
+}
+
+#[test]
+fn nothing_is_looked_at_without_budgets() {
+	new_test_ext().execute_with(|| {
+		park(SIBLING, 2);
+
+		assert_eq!(run_idle(u64::MAX), 0);
+		assert_eq!(lookups(), 0);
+		assert_eq!(LastXcmpIndex::<Test>::get(), None);
+	});
+}
+
+#[test]
+fn serviced_messages_are_not_looked_at_again() {
+	new_test_ext().execute_with(|| {
+		set_budget(SIBLING, 10_000);
+		park(SIBLING, 2);
+		park(SIBLING, 2);
+		park(SIBLING, 2);
+		assert_eq!(run_idle(u64::MAX), 6_000);
+		assert_eq!(lookups(), 3);
+
+		park(SIBLING, 2);
+		assert_eq!(run_idle(u64::MAX), 2_000);
+		assert_eq!(lookups(), 1);
+		assert_eq!(LastXcmpIndex::<Test>::get(), Some(3));
+	});
+}
+
+#[test]
+fn messages_are_serviced_within_the_budget_of_their_origin() {
+	new_test_ext().execute_with(|| {
+		set_budget(SIBLING, 3_000);
+		park(SIBLING, 2);
+		park(SIBLING, 2);
+
+		assert_eq!(run_idle(u64::MAX), 2_000);
+		assert!(!parked(SIBLING, 0));
+		assert!(parked(SIBLING, 1));
+		System::assert_last_event(Event::XcmOverweight(XcmOverweightEvent::OverweightServiced(
+			SIBLING, 0, 2_000,
+		)));
+
+		assert_eq!(run_idle(u64::MAX), 2_000);
+		assert!(!parked(SIBLING, 1));
+	});
+}
+
+#[test]
+fn messages_are_serviced_within_the_remaining_weight() {
+	new_test_ext().execute_with(|| {
+		set_budget(SIBLING, 10_000);
+		park(SIBLING, 2);
+
+		assert_eq!(run_idle(1_999), 0);
+		assert!(parked(SIBLING, 0));
+
+		assert_eq!(run_idle(2_000), 2_000);
+		assert!(!parked(SIBLING, 0));
+	});
+}
+
+#[test]
+fn heavy_messages_do_not_hold_back_the_queue() {
+	new_test_ext().execute_with(|| {
+		set_budget(SIBLING, 3_000);
+		park(SIBLING, 5);
+		park(SIBLING, 1);
+		park_raw(SIBLING, vec![1, 2, 3]);
+		park(SIBLING, 1);
+
+		assert_eq!(run_idle(u64::MAX), 2_000);
+		assert!(parked(SIBLING, 0));
+		assert!(!parked(SIBLING, 1));
+		// Not a message, left to root.
+		assert!(parked(SIBLING, 2));
+		assert!(!parked(SIBLING, 3));
+	});
+}
+
+#[test]
+fn relay_chain_messages_come_first() {
+	new_test_ext().execute_with(|| {
+		set_budget(SIBLING, 10_000);
+		set_budget(MessageOrigin::Relay, 10_000);
+		park(SIBLING, 2);
+		park(MessageOrigin::Relay, 2);
+
+		assert_eq!(run_idle(3_000), 2_000);
+		assert!(!parked(MessageOrigin::Relay, 0));
+		assert!(parked(SIBLING, 0));
+	});
+}
+
+#[test]
+fn failed_messages_stay_parked() {
+	new_test_ext().execute_with(|| {
+		set_budget(SIBLING, 3_000);
+		park(SIBLING, 2);
+		SERVICE_FAILS.with(|f| *f.borrow_mut() = true);
+
+		assert_eq!(run_idle(u64::MAX), 2_000);
+		assert!(parked(SIBLING, 0));
+		System::assert_last_event(Event::XcmOverweight(XcmOverweightEvent::ServiceFailed(
+			SIBLING,
+			0,
+			DispatchError::BadOrigin,
+		)));
+	});
+}
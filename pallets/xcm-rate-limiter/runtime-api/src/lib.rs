@@ -0,0 +1,21 @@
+//! Runtime API definition for the outflow rate limits of XCM assets.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+use sp_std::vec::Vec;
+
+pub use pallet_xcm_rate_limiter::Utilisation;
+
+sp_api::decl_runtime_apis! {
+	/// Query how much of their limit the assets leaving the chain used.
+	pub trait XcmRateLimiterApi<BlockNumber> where
+		BlockNumber: Codec,
+	{
+		/// The outflow of every limited asset in its current window.
+		fn utilisation() -> Vec<Utilisation<BlockNumber>>;
+
+		/// Whether outflows are suspended until governance resets the circuit breaker.
+		fn is_suspended() -> bool;
+	}
+}
@@ -0,0 +1,328 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Outflow rate limits of XCM assets.
+//!
+//! Governance caps how much of an asset may leave the chain per window of `Window` blocks. The
+//! outflows are measured by [`LimitedRouter`], wrapping the router of the executor, from the
+//! assets a message carries: those reserve deposited, teleported or withdrawn from our sovereign
+//! account on the destination. Assets without a limit are not measured.
+//!
+//! A message which would exceed the limit of one of its assets is refused. A message which uses up
+//! the limit of one of its assets is sent, and trips the circuit breaker along with it: the router
+//! refuses every message to a sibling and every message carrying assets, and
+//! [`LimitedTransactor`], wrapping the asset transactor, refuses to move assets to other chains,
+//! until governance resets the breaker. As the breaker only trips with a send that succeeds,
+//! callers reverting their changes on failure cannot revert the trip.
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+use codec::{Decode, Encode};
+use core::marker::PhantomData;
+use scale_info::TypeInfo;
+use sp_runtime::RuntimeDebug;
+use xcm::latest::prelude::*;
+use xcm_executor::{traits::TransactAsset, Assets};
+
+/// How much of an asset left the chain in the current window.
+#[derive(Encode, Decode, Clone, Copy, Eq, PartialEq, Default, RuntimeDebug, TypeInfo)]
+pub struct Outflow<BlockNumber> {
+	/// When the window started.
+	pub window_start: BlockNumber,
+	/// The amount sent out since.
+	pub amount: u128,
+}
+
+/// The outflow of an asset against its limit.
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub struct Utilisation<BlockNumber> {
+	/// The location of the asset.
+	pub asset: MultiLocation,
+	/// The amount which may leave the chain per window.
+	pub limit: u128,
+	/// The amount which left the chain in the current window.
+	pub used: u128,
+	/// When the current window started.
+	pub window_start: BlockNumber,
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::{Outflow, Utilisation};
+	use frame_support::{dispatch::DispatchResultWithPostInfo, pallet_prelude::*};
+	use frame_system::pallet_prelude::*;
+	use sp_runtime::traits::{Saturating, Zero};
+	use sp_std::{boxed::Box, prelude::*};
+	use xcm::{latest::prelude::*, VersionedMultiLocation};
+	use xcm_executor::traits::InvertLocation;
+
+	/// Configure the pallet by specifying the parameters and types on which it depends.
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// Because this pallet emits events, it depends on the runtime's definition of an event.
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// Origin allowed to set the limits and to reset the circuit breaker.
+		type UpdateOrigin: EnsureOrigin<Self::Origin>;
+
+		/// How we are seen from other chains, to bring the assets of messages back to our view.
+		type LocationInverter: InvertLocation;
+
+		/// The number of blocks the limits apply to.
+		#[pallet::constant]
+		type Window: Get<Self::BlockNumber>;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	/// The amount of an asset which may leave the chain per window, by asset location.
+	#[pallet::storage]
+	#[pallet::getter(fn limit)]
+	pub type Limits<T: Config> = StorageMap<_, Blake2_128Concat, MultiLocation, u128>;
+
+	/// The outflow of the limited assets in their current window.
+	#[pallet::storage]
+	#[pallet::getter(fn outflow)]
+	pub type Outflows<T: Config> =
+		StorageMap<_, Blake2_128Concat, MultiLocation, Outflow<T::BlockNumber>, ValueQuery>;
+
+	/// Whether the circuit breaker tripped.
+	#[pallet::storage]
+	#[pallet::getter(fn is_suspended)]
+	pub type Suspended<T> = StorageValue<_, bool, ValueQuery>;
+
+	// Pallets use events to inform users when important changes are made.
+	// https://docs.substrate.io/v3/runtime/events-and-errors
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// The limit of an asset changed. [asset, limit]
+		LimitSet(MultiLocation, Option<u128>),
+		/// A message used up the limit of an asset, outflows are suspended. [asset, outflow]
+		OutflowSuspended(MultiLocation, u128),
+		/// Governance reset the circuit breaker.
+		OutflowResumed,
+	}
+
+	// Errors inform users that something went wrong.
+	#[pallet::error]
+	pub enum Error<T> {
+		/// A location could not be converted to the latest XCM version.
+		BadVersion,
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Let up to `limit` of `asset` leave the chain per window, or any amount if `None`.
+		#[pallet::weight(10_000 + T::DbWeight::get().writes(2))]
+		pub fn set_limit(
+			origin: OriginFor<T>,
+			asset: Box<VersionedMultiLocation>,
+			limit: Option<u128>,
+		) -> DispatchResultWithPostInfo {
+			T::UpdateOrigin::ensure_origin(origin)?;
+
+			let asset = MultiLocation::try_from(*asset).map_err(|()| Error::<T>::BadVersion)?;
+			<Limits<T>>::set(&asset, limit);
+			if limit.is_none() {
+				<Outflows<T>>::remove(&asset);
+			}
+			Self::deposit_event(Event::LimitSet(asset, limit));
+			Ok(().into())
+		}
+
+		/// Resume the outflows after the circuit breaker tripped. The limits still apply to the
+		/// current windows.
+		#[pallet::weight(10_000 + T::DbWeight::get().writes(1))]
+		pub fn reset(origin: OriginFor<T>) -> DispatchResultWithPostInfo {
+			T::UpdateOrigin::ensure_origin(origin)?;
+
+			<Suspended<T>>::kill();
+			Self::deposit_event(Event::OutflowResumed);
+			Ok(().into())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// The outflow of every limited asset.
+		pub fn utilisation() -> Vec<Utilisation<T::BlockNumber>> {
+			<Limits<T>>::iter()
+				.map(|(asset, limit)| {
+					let outflow = Self::current_outflow(&asset);
+					Utilisation {
+						asset,
+						limit,
+						used: outflow.amount,
+						window_start: outflow.window_start,
+					}
+				})
+				.collect()
+		}
+
+		/// The outflow of `asset` in the current window, which starts now if the last one ended.
+		fn current_outflow(asset: &MultiLocation) -> Outflow<T::BlockNumber> {
+			let now = frame_system::Pallet::<T>::block_number();
+			let outflow = Self::outflow(asset);
+			if outflow.amount.is_zero() ||
+				now >= outflow.window_start.saturating_add(T::Window::get())
+			{
+				Outflow { window_start: now, amount: 0 }
+			} else {
+				outflow
+			}
+		}
+
+		/// The outflows with `assets`, as seen by `dest`, and their limits, to record once they are
+		/// sent. Fails if one of them would exceed its limit.
+		pub(crate) fn note_outflow(
+			dest: &MultiLocation,
+			assets: &MultiAssets,
+		) -> Result<Vec<(MultiLocation, Outflow<T::BlockNumber>, u128)>, ()> {
+			let mut outflows: Vec<(MultiLocation, Outflow<T::BlockNumber>, u128)> = Vec::new();
+			for asset in assets.inner() {
+				let (location, amount) = match asset {
+					MultiAsset { id: Concrete(location), fun: Fungible(amount) } =>
+						(Self::local_location(dest, location), *amount),
+					_ => continue,
+				};
+				let limit = match Self::limit(&location) {
+					Some(limit) => limit,
+					None => continue,
+				};
+				let index = match outflows.iter().position(|(l, _, _)| *l == location) {
+					Some(index) => index,
+					None => {
+						outflows.push((location.clone(), Self::current_outflow(&location), limit));
+						outflows.len() - 1
+					},
+				};
+				let outflow = &mut outflows[index].1;
+				outflow.amount = outflow.amount.saturating_add(amount);
+				if outflow.amount > limit {
+					return Err(())
+				}
+			}
+			Ok(outflows)
+		}
+
+		/// Record the `outflows` of a message sent, tripping the circuit breaker if one of them
+		/// used up its limit.
+		pub(crate) fn record_outflows(
+			outflows: Vec<(MultiLocation, Outflow<T::BlockNumber>, u128)>,
+		) {
+			for (asset, outflow, limit) in outflows {
+				<Outflows<T>>::insert(&asset, outflow);
+				if outflow.amount >= limit && !Self::is_suspended() {
+					<Suspended<T>>::put(true);
+					Self::deposit_event(Event::OutflowSuspended(asset, outflow.amount));
+				}
+			}
+		}
+
+		/// `location`, as seen by `dest`, as seen by us.
+		pub fn local_location(dest: &MultiLocation, location: &MultiLocation) -> MultiLocation {
+			// Our own locations were reanchored under the location of us seen by `dest`.
+			if let Ok(us) = T::LocationInverter::invert_location(dest) {
+				let mut interior = location.interior.clone();
+				let mut prefix = us.interior.iter();
+				if location.parents == us.parents &&
+					prefix.all(|junction| interior.take_first().as_ref() == Some(junction))
+				{
+					return MultiLocation::new(0, interior)
+				}
+			}
+			let mut local = location.clone();
+			match local.prepend_with(dest.clone()) {
+				Ok(()) => local,
+				Err(_) => location.clone(),
+			}
+		}
+	}
+}
+
+/// Measures and limits the assets leaving the chain through `Router`.
+pub struct LimitedRouter<T, Router>(PhantomData<(T, Router)>);
+
+impl<T: Config, Router: SendXcm> SendXcm for LimitedRouter<T, Router> {
+	fn send_xcm(dest: impl Into<MultiLocation>, msg: Xcm<()>) -> SendResult {
+		let dest = dest.into();
+		let assets = match msg.0.first() {
+			Some(ReserveAssetDeposited(assets)) |
+			Some(ReceiveTeleportedAsset(assets)) |
+			Some(WithdrawAsset(assets)) => Some(assets),
+			_ => None,
+		};
+		let to_sibling = matches!(dest, MultiLocation { parents: 1, interior: X1(Parachain(_)) });
+		if Pallet::<T>::is_suspended() && (to_sibling || assets.is_some()) {
+			return Err(SendError::Transport("outbound XCM suspended"))
+		}
+
+		let outflows = match assets {
+			Some(assets) => Pallet::<T>::note_outflow(&dest, assets)
+				.map_err(|()| SendError::Transport("outflow limit exceeded"))?,
+			None => Vec::new(),
+		};
+		Router::send_xcm(dest, msg)?;
+		Pallet::<T>::record_outflows(outflows);
+		Ok(())
+	}
+}
+
+/// Refuses to move assets to other chains through `Transactor` while outflows are suspended.
+pub struct LimitedTransactor<T, Transactor>(PhantomData<(T, Transactor)>);
+
+impl<T: Config, Transactor: TransactAsset> LimitedTransactor<T, Transactor> {
+	fn ensure_not_suspended(to: &MultiLocation) -> XcmResult {
+		if to.parents > 0 && Pallet::<T>::is_suspended() {
+			return Err(XcmError::FailedToTransactAsset("outbound XCM suspended"))
+		}
+		Ok(())
+	}
+}
+
+impl<T: Config, Transactor: TransactAsset> TransactAsset for LimitedTransactor<T, Transactor> {
+	fn can_check_in(origin: &MultiLocation, what: &MultiAsset) -> XcmResult {
+		Transactor::can_check_in(origin, what)
+	}
+
+	fn check_in(origin: &MultiLocation, what: &MultiAsset) {
+		Transactor::check_in(origin, what)
+	}
+
+	fn check_out(dest: &MultiLocation, what: &MultiAsset) {
+		Transactor::check_out(dest, what)
+	}
+
+	fn deposit_asset(what: &MultiAsset, who: &MultiLocation) -> XcmResult {
+		Self::ensure_not_suspended(who)?;
+		Transactor::deposit_asset(what, who)
+	}
+
+	fn withdraw_asset(what: &MultiAsset, who: &MultiLocation) -> Result<Assets, XcmError> {
+		Transactor::withdraw_asset(what, who)
+	}
+
+	fn internal_transfer_asset(
+		asset: &MultiAsset,
+		from: &MultiLocation,
+		to: &MultiLocation,
+	) -> Result<Assets, XcmError> {
+		Self::ensure_not_suspended(to)?;
+		Transactor::internal_transfer_asset(asset, from, to)
+	}
+
+	fn transfer_asset(
+		asset: &MultiAsset,
+		from: &MultiLocation,
+		to: &MultiLocation,
+	) -> Result<Assets, XcmError> {
+		Self::ensure_not_suspended(to)?;
+		Transactor::transfer_asset(asset, from, to)
+	}
+}
@@ -0,0 +1,133 @@
+use crate as pallet_xcm_rate_limiter;
+use frame_support::{parameter_types, traits::Everything};
+use frame_system::{self as system, EnsureRoot};
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, IdentityLookup},
+};
+use std::cell::RefCell;
+use xcm::latest::prelude::*;
+use xcm_builder::LocationInverter;
+use xcm_executor::{traits::TransactAsset, Assets};
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+// Configure a mock runtime to test the pallet.
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+		XcmRateLimiter: pallet_xcm_rate_limiter::{Pallet, Call, Storage, Event<T>},
+	}
+);
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const SS58Prefix: u8 = 42;
+}
+
+impl system::Config for Test {
+	type BaseCallFilter = Everything;
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = BlockHashCount;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = SS58Prefix;
+	type OnSetCode = ();
+	type MaxConsumers = frame_support::traits::ConstU32<16>;
+}
+
+pub const SELF_PARA_ID: u32 = 1000;
+
+/// The sibling whose messages cannot be delivered.
+pub const UNREACHABLE_PARA_ID: u32 = 666;
+
+thread_local! {
+	pub static SENT_XCM: RefCell<Vec<(MultiLocation, Xcm<()>)>> = RefCell::new(Vec::new());
+	pub static MOVED: RefCell<Vec<(MultiAsset, MultiLocation)>> = RefCell::new(Vec::new());
+}
+
+/// Records the messages instead of sending them.
+pub struct TestRouter;
+impl SendXcm for TestRouter {
+	fn send_xcm(dest: impl Into<MultiLocation>, msg: Xcm<()>) -> SendResult {
+		let dest = dest.into();
+		if dest == MultiLocation::new(1, X1(Parachain(UNREACHABLE_PARA_ID))) {
+			return Err(SendError::CannotReachDestination(dest, msg))
+		}
+		SENT_XCM.with(|q| q.borrow_mut().push((dest, msg)));
+		Ok(())
+	}
+}
+
+pub fn sent_xcm() -> Vec<(MultiLocation, Xcm<()>)> {
+	SENT_XCM.with(|q| q.borrow().clone())
+}
+
+/// Records the assets deposited and transferred instead of moving them.
+pub struct TestTransactor;
+impl TransactAsset for TestTransactor {
+	fn deposit_asset(what: &MultiAsset, who: &MultiLocation) -> XcmResult {
+		MOVED.with(|m| m.borrow_mut().push((what.clone(), who.clone())));
+		Ok(())
+	}
+
+	fn transfer_asset(
+		asset: &MultiAsset,
+		_from: &MultiLocation,
+		to: &MultiLocation,
+	) -> Result<Assets, XcmError> {
+		MOVED.with(|m| m.borrow_mut().push((asset.clone(), to.clone())));
+		Ok(asset.clone().into())
+	}
+}
+
+pub fn moved() -> Vec<(MultiAsset, MultiLocation)> {
+	MOVED.with(|m| m.borrow().clone())
+}
+
+pub type Router = pallet_xcm_rate_limiter::LimitedRouter<Test, TestRouter>;
+pub type Transactor = pallet_xcm_rate_limiter::LimitedTransactor<Test, TestTransactor>;
+
+parameter_types! {
+	pub Ancestry: MultiLocation = Parachain(SELF_PARA_ID).into();
+	pub const Window: u64 = 10;
+}
+
+impl pallet_xcm_rate_limiter::Config for Test {
+	type Event = Event;
+	type UpdateOrigin = EnsureRoot<u64>;
+	type LocationInverter = LocationInverter<Ancestry>;
+	type Window = Window;
+}
+
+// Build genesis storage according to the mock runtime.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	SENT_XCM.with(|q| q.borrow_mut().clear());
+	MOVED.with(|m| m.borrow_mut().clear());
+	let t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	let mut ext = sp_io::TestExternalities::new(t);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
@@ -0,0 +1,219 @@
+use crate::{mock::*, Error, Event as XcmRateLimiterEvent, Utilisation};
+use frame_support::{
+	assert_noop, assert_ok,
+	storage::{with_transaction, TransactionOutcome},
+};
+use sp_runtime::traits::BadOrigin;
+use xcm::{latest::prelude::*, VersionedMultiLocation};
+use xcm_executor::traits::TransactAsset;
+
+fn sibling(id: u32) -> MultiLocation {
+	MultiLocation::new(1, X1(Parachain(id)))
+}
+
+/// Our native token, as seen by siblings.
+fn native_seen_by_sibling() -> MultiLocation {
+	sibling(SELF_PARA_ID)
+}
+
+fn set_limit(asset: MultiLocation, limit: u128) {
+	assert_ok!(XcmRateLimiter::set_limit(Origin::root(), Box::new(asset.into()), Some(limit)));
+}
+
+fn reserve_transfer(dest: MultiLocation, amount: u128) -> SendResult {
+	Router::send_xcm(
+		dest,
+		Xcm(vec![ReserveAssetDeposited((native_seen_by_sibling(), amount).into()), ClearOrigin]),
+	)
+}
+
+fn used(asset: MultiLocation) -> u128 {
+	XcmRateLimiter::utilisation()
+		.into_iter()
+		.find(|utilisation| utilisation.asset == asset)
+		.map_or(0, |utilisation| utilisation.used)
+}
+
+#[test]
+fn only_root_sets_limits() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			XcmRateLimiter::set_limit(Origin::signed(1), Box::new(Here.into()), Some(100)),
+			BadOrigin
+		);
+		assert_noop!(
+			XcmRateLimiter::set_limit(
+				Origin::root(),
+				// Parents only come first from v1 on.
+				Box::new(VersionedMultiLocation::V0(xcm::v0::MultiLocation::X2(
+					xcm::v0::Junction::Parachain(2000),
+					xcm::v0::Junction::Parent,
+				))),
+				Some(100),
+			),
+			Error::<Test>::BadVersion
+		);
+
+		set_limit(Here.into(), 100);
+		assert_eq!(XcmRateLimiter::limit(MultiLocation::here()), Some(100));
+		System::assert_last_event(Event::XcmRateLimiter(XcmRateLimiterEvent::LimitSet(
+			Here.into(),
+			Some(100),
+		)));
+	});
+}
+
+#[test]
+fn outflows_are_measured_as_seen_by_us() {
+	new_test_ext().execute_with(|| {
+		set_limit(Here.into(), 100);
+		set_limit(Parent.into(), 50);
+
+		assert_ok!(reserve_transfer(sibling(2000), 60));
+		// The relay chain token, withdrawn from our sovereign account on the relay chain.
+		assert_ok!(Router::send_xcm(Parent, Xcm(vec![WithdrawAsset((Here, 30).into())])));
+
+		assert_eq!(sent_xcm().len(), 2);
+		assert_eq!(
+			XcmRateLimiter::utilisation()
+				.into_iter()
+				.find(|u| u.asset == MultiLocation::here()),
+			Some(Utilisation { asset: Here.into(), limit: 100, used: 60, window_start: 1 })
+		);
+		assert_eq!(used(Parent.into()), 30);
+	});
+}
+
+#[test]
+fn unlimited_assets_are_not_measured() {
+	new_test_ext().execute_with(|| {
+		set_limit(Here.into(), 100);
+
+		assert_ok!(Router::send_xcm(Parent, Xcm(vec![WithdrawAsset((Here, 1_000).into())])));
+		assert_eq!(XcmRateLimiter::outflow(MultiLocation::parent()).amount, 0);
+		assert_eq!(used(Here.into()), 0);
+	});
+}
+
+#[test]
+fn failed_sends_are_not_measured() {
+	new_test_ext().execute_with(|| {
+		set_limit(Here.into(), 100);
+
+		assert!(reserve_transfer(sibling(UNREACHABLE_PARA_ID), 60).is_err());
+		assert_eq!(used(Here.into()), 0);
+		assert!(!XcmRateLimiter::is_suspended());
+	});
+}
+
+#[test]
+fn limits_apply_per_window() {
+	new_test_ext().execute_with(|| {
+		set_limit(Here.into(), 100);
+		assert_ok!(reserve_transfer(sibling(2000), 60));
+
+		System::set_block_number(1 + Window::get());
+		assert_ok!(reserve_transfer(sibling(2000), 60));
+		assert_eq!(
+			XcmRateLimiter::utilisation(),
+			vec![Utilisation { asset: Here.into(), limit: 100, used: 60, window_start: 11 }]
+		);
+	});
+}
+
+#[test]
+fn exceeding_a_limit_is_refused() {
+	new_test_ext().execute_with(|| {
+		set_limit(Here.into(), 100);
+		assert_ok!(reserve_transfer(sibling(2000), 60));
+
+		assert_eq!(
+			reserve_transfer(sibling(2000), 50),
+			Err(SendError::Transport("outflow limit exceeded"))
+		);
+		assert!(!XcmRateLimiter::is_suspended());
+		assert_eq!(used(Here.into()), 60);
+		assert_eq!(sent_xcm().len(), 1);
+	});
+}
+
+#[test]
+fn using_up_a_limit_suspends_outflows() {
+	new_test_ext().execute_with(|| {
+		set_limit(Here.into(), 100);
+		assert_ok!(reserve_transfer(sibling(2000), 60));
+
+		assert_ok!(reserve_transfer(sibling(2000), 40));
+		assert!(XcmRateLimiter::is_suspended());
+		System::assert_last_event(Event::XcmRateLimiter(XcmRateLimiterEvent::OutflowSuspended(
+			Here.into(),
+			100,
+		)));
+		assert_eq!(used(Here.into()), 100);
+
+		let suspended = Err(SendError::Transport("outbound XCM suspended"));
+		assert_eq!(reserve_transfer(sibling(2000), 1), suspended);
+		assert_eq!(Router::send_xcm(sibling(2000), Xcm(vec![ClearOrigin])), suspended);
+		assert_eq!(Router::send_xcm(Parent, Xcm(vec![WithdrawAsset((Here, 1).into())])), suspended);
+		// Messages to the relay chain moving no assets still go through.
+		assert_ok!(Router::send_xcm(Parent, Xcm(vec![ClearOrigin])));
+		assert_eq!(sent_xcm().len(), 3);
+	});
+}
+
+#[test]
+fn suspensions_survive_callers_reverting_later_failures() {
+	new_test_ext().execute_with(|| {
+		set_limit(Here.into(), 100);
+
+		// The trip is part of the send which used up the limit, not of the send refused after.
+		assert_ok!(reserve_transfer(sibling(2000), 100));
+		let _ = with_transaction(|| {
+			assert!(reserve_transfer(sibling(2000), 1).is_err());
+			TransactionOutcome::Rollback(())
+		});
+		assert!(XcmRateLimiter::is_suspended());
+	});
+}
+
+#[test]
+fn assets_do_not_move_to_other_chains_while_suspended() {
+	new_test_ext().execute_with(|| {
+		set_limit(Here.into(), 100);
+		assert_ok!(reserve_transfer(sibling(2000), 100));
+
+		let asset: MultiAsset = (Here, 10).into();
+		let account = MultiLocation::new(0, X1(AccountId32 { network: Any, id: [1; 32] }));
+		let suspended = XcmError::FailedToTransactAsset("outbound XCM suspended");
+		assert_eq!(Transactor::deposit_asset(&asset, &sibling(2000)), Err(suspended.clone()));
+		assert_eq!(
+			Transactor::transfer_asset(&asset, &account, &sibling(2000)).map(|_| ()),
+			Err(suspended)
+		);
+		assert_ok!(Transactor::deposit_asset(&asset, &account));
+		assert_eq!(moved(), vec![(asset, account)]);
+	});
+}
+
+#[test]
+fn only_root_resumes_outflows() {
+	new_test_ext().execute_with(|| {
+		set_limit(Here.into(), 100);
+		assert_ok!(reserve_transfer(sibling(2000), 60));
+		assert_ok!(reserve_transfer(sibling(2000), 40));
+
+		assert_noop!(XcmRateLimiter::reset(Origin::signed(1)), BadOrigin);
+		assert_ok!(XcmRateLimiter::reset(Origin::root()));
+		assert!(!XcmRateLimiter::is_suspended());
+		System::assert_last_event(Event::XcmRateLimiter(XcmRateLimiterEvent::OutflowResumed));
+
+		// The limit still applies to the current window.
+		assert_eq!(
+			reserve_transfer(sibling(2000), 1),
+			Err(SendError::Transport("outflow limit exceeded"))
+		);
+		System::set_block_number(1 + Window::get());
+		assert_ok!(reserve_transfer(sibling(2000), 40));
+		assert_eq!(used(Here.into()), 40);
+	});
+}
@@ -0,0 +1,129 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Trusted reserves of XCM assets.
+//!
+//! A chain sending `ReserveAssetDeposited` claims to hold the deposited assets in reserve for us.
+//! Believing any chain for any asset lets it mint counterfeits of assets it does not back, so
+//! this pallet keeps, for every asset location, the reserve locations trusted to back it. They
+//! are set by governance through `UpdateOrigin`.
+//!
+//! The pallet is the executor's `IsReserve` filter, or the last one of a tuple of filters: every
+//! deposit it does not trust emits `UntrustedReserve` before the executor rejects it.
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+use xcm::latest::prelude::*;
+use xcm_executor::traits::FilterAssetLocation;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use frame_support::{dispatch::DispatchResultWithPostInfo, pallet_prelude::*};
+	use frame_system::pallet_prelude::*;
+	use sp_std::{boxed::Box, prelude::*};
+	use xcm::{latest::prelude::*, VersionedMultiLocation};
+
+	/// Configure the pallet by specifying the parameters and types on which it depends.
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// Because this pallet emits events, it depends on the runtime's definition of an event.
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// Origin allowed to change the trusted reserves.
+		type UpdateOrigin: EnsureOrigin<Self::Origin>;
+
+		/// Maximum number of reserves trusted for a single asset.
+		#[pallet::constant]
+		type MaxReserves: Get<u32>;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	/// The reserve locations trusted for every asset location.
+	#[pallet::storage]
+	#[pallet::getter(fn trusted_reserves)]
+	pub type TrustedReserves<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		MultiLocation,
+		BoundedVec<MultiLocation, T::MaxReserves>,
+		ValueQuery,
+	>;
+
+	// Pallets use events to inform users when important changes are made.
+	// https://docs.substrate.io/v3/runtime/events-and-errors
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// The reserves trusted for an asset changed. [asset, reserves]
+		ReservesSet(MultiLocation, Vec<MultiLocation>),
+		/// A deposit was rejected as its origin is not a trusted reserve of the asset.
+		/// [asset, origin]
+		UntrustedReserve(MultiAsset, MultiLocation),
+	}
+
+	// Errors inform users that something went wrong.
+	#[pallet::error]
+	pub enum Error<T> {
+		/// A location could not be converted to the latest XCM version.
+		BadVersion,
+		/// More reserves than `MaxReserves` were given.
+		TooManyReserves,
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Replace the reserves trusted for `asset`. An empty list trusts no reserve at all.
+		#[pallet::weight(10_000 + T::DbWeight::get().writes(1))]
+		pub fn set_reserves(
+			origin: OriginFor<T>,
+			asset: Box<VersionedMultiLocation>,
+			reserves: Vec<VersionedMultiLocation>,
+		) -> DispatchResultWithPostInfo {
+			T::UpdateOrigin::ensure_origin(origin)?;
+
+			let asset = MultiLocation::try_from(*asset).map_err(|()| Error::<T>::BadVersion)?;
+			let reserves = reserves
+				.into_iter()
+				.map(MultiLocation::try_from)
+				.collect::<Result<Vec<_>, ()>>()
+				.map_err(|()| Error::<T>::BadVersion)?;
+			let bounded: BoundedVec<_, T::MaxReserves> =
+				reserves.clone().try_into().map_err(|_| Error::<T>::TooManyReserves)?;
+
+			if bounded.is_empty() {
+				<TrustedReserves<T>>::remove(&asset);
+			} else {
+				<TrustedReserves<T>>::insert(&asset, bounded);
+			}
+			Self::deposit_event(Event::ReservesSet(asset, reserves));
+			Ok(().into())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// Whether `origin` is a trusted reserve of `asset`.
+		pub fn is_trusted(asset: &MultiAsset, origin: &MultiLocation) -> bool {
+			match &asset.id {
+				Concrete(location) => Self::trusted_reserves(location).contains(origin),
+				Abstract(_) => false,
+			}
+		}
+	}
+}
+
+impl<T: Config> FilterAssetLocation for Pallet<T> {
+	fn filter_asset_location(asset: &MultiAsset, origin: &MultiLocation) -> bool {
+		let trusted = Self::is_trusted(asset, origin);
+		if !trusted {
+			Self::deposit_event(Event::UntrustedReserve(asset.clone(), origin.clone()));
+		}
+		trusted
+	}
+}
@@ -0,0 +1,95 @@
+use crate::{mock::*, Error, Event as XcmReservesEvent};
+use frame_support::{assert_noop, assert_ok, dispatch::DispatchResultWithPostInfo};
+use sp_runtime::traits::BadOrigin;
+use xcm::{latest::prelude::*, VersionedMultiLocation};
+use xcm_executor::traits::FilterAssetLocation;
+
+fn sibling(id: u32) -> MultiLocation {
+	MultiLocation::new(1, X1(Parachain(id)))
+}
+
+fn sibling_token(id: u32) -> MultiLocation {
+	MultiLocation::new(1, X2(Parachain(id), GeneralIndex(0)))
+}
+
+fn set_reserves(asset: MultiLocation, reserves: Vec<MultiLocation>) -> DispatchResultWithPostInfo {
+	XcmReserves::set_reserves(
+		Origin::root(),
+		Box::new(asset.into()),
+		reserves.into_iter().map(VersionedMultiLocation::from).collect(),
+	)
+}
+
+#[test]
+fn only_update_origin_sets_reserves() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			XcmReserves::set_reserves(
+				Origin::signed(1),
+				Box::new(sibling_token(2000).into()),
+				vec![sibling(2000).into()],
+			),
+			BadOrigin
+		);
+
+		assert_ok!(set_reserves(sibling_token(2000), vec![sibling(2000)]));
+		assert_eq!(
+			XcmReserves::trusted_reserves(sibling_token(2000)).to_vec(),
+			vec![sibling(2000)]
+		);
+		System::assert_last_event(Event::XcmReserves(XcmReservesEvent::ReservesSet(
+			sibling_token(2000),
+			vec![sibling(2000)],
+		)));
+	});
+}
+
+#[test]
+fn reserves_are_bounded() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			XcmReserves::set_reserves(
+				Origin::root(),
+				Box::new(sibling_token(2000).into()),
+				vec![sibling(2000).into(), sibling(2001).into(), sibling(2002).into()],
+			),
+			Error::<Test>::TooManyReserves
+		);
+	});
+}
+
+#[test]
+fn empty_reserves_remove_the_asset() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(set_reserves(sibling_token(2000), vec![sibling(2000)]));
+		assert_ok!(set_reserves(sibling_token(2000), vec![]));
+		assert!(!crate::TrustedReserves::<Test>::contains_key(sibling_token(2000)));
+	});
+}
+
+#[test]
+fn only_trusted_reserves_pass_the_filter() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(set_reserves(sibling_token(2000), vec![sibling(2000)]));
+		let asset: MultiAsset = (sibling_token(2000), 100).into();
+
+		assert!(XcmReserves::filter_asset_location(&asset, &sibling(2000)));
+		assert!(!XcmReserves::filter_asset_location(&asset, &sibling(2001)));
+		System::assert_last_event(Event::XcmReserves(XcmReservesEvent::UntrustedReserve(
+			asset,
+			sibling(2001),
+		)));
+	});
+}
+
+#[test]
+fn unregistered_assets_are_rejected() {
+	new_test_ext().execute_with(|| {
+		// A chain claiming an asset is its own native one is not enough.
+		let counterfeit: MultiAsset = (sibling(2000), 100).into();
+		assert!(!XcmReserves::filter_asset_location(&counterfeit, &sibling(2000)));
+
+		let abstract_asset: MultiAsset = (Abstract(b"DOT".to_vec()), 100).into();
+		assert!(!XcmReserves::filter_asset_location(&abstract_asset, &sibling(2000)));
+	});
+}
@@ -0,0 +1,219 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Allow-list of the calls remote origins may `Transact`.
+//!
+//! Without it, a `Transact` is only gated by fee payment: a sibling's sovereign account can
+//! dispatch anything a signed user can. [`FilteredTransactOrigin`] wraps the executor's
+//! `OriginConverter` and attaches a call filter to every origin it converts. The filter only lets
+//! through the calls governance allowed for the [`OriginClass`] of the XCM origin, either a whole
+//! pallet or a single function, identified by the names in the runtime metadata.
+//!
+//! Calls that are not allowed fail with `frame_system::Error::CallFiltered`, like calls rejected
+//! by the runtime's `BaseCallFilter`.
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+use codec::{Decode, Encode};
+use core::marker::PhantomData;
+use frame_support::traits::{GetCallMetadata, OriginTrait};
+use scale_info::TypeInfo;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_runtime::RuntimeDebug;
+use sp_std::prelude::*;
+use xcm::latest::prelude::*;
+use xcm_executor::traits::ConvertOrigin;
+
+/// The kinds of XCM origins calls are allowed for.
+#[derive(Encode, Decode, Clone, Copy, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum OriginClass {
+	/// The relay chain and its plurality bodies.
+	Relay,
+	/// A sibling parachain itself, e.g. its sovereign account.
+	Sibling,
+	/// Anything within the relay chain or a sibling, usually a user's account.
+	RemoteAccount,
+}
+
+impl OriginClass {
+	/// The class of `location`, `None` for locations outside of our consensus system.
+	pub fn of(location: &MultiLocation) -> Option<Self> {
+		match (location.parents, &location.interior) {
+			(1, Here) | (1, X1(Plurality { .. })) => Some(Self::Relay),
+			(1, X1(Parachain(_))) => Some(Self::Sibling),
+			(0..=1, _) => Some(Self::RemoteAccount),
+			_ => None,
+		}
+	}
+}
+
+/// A call, or every call of a pallet if `function` is `None`, by their metadata names.
+#[derive(Encode, Decode, Clone, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct CallPattern {
+	/// Name of the pallet in `construct_runtime!`.
+	pub pallet: Vec<u8>,
+	/// Name of the function, `None` for any function of the pallet.
+	pub function: Option<Vec<u8>>,
+}
+
+impl CallPattern {
+	/// Every call of `pallet`.
+	pub fn pallet(pallet: &str) -> Self {
+		Self { pallet: pallet.as_bytes().to_vec(), function: None }
+	}
+
+	/// The call `function` of `pallet`.
+	pub fn call(pallet: &str, function: &str) -> Self {
+		Self { pallet: pallet.as_bytes().to_vec(), function: Some(function.as_bytes().to_vec()) }
+	}
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::{CallPattern, OriginClass};
+	use frame_support::{
+		dispatch::DispatchResultWithPostInfo,
+		pallet_prelude::*,
+		traits::{CallMetadata, GetCallMetadata},
+	};
+	use frame_system::pallet_prelude::*;
+	use sp_std::prelude::*;
+
+	/// Configure the pallet by specifying the parameters and types on which it depends.
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// Because this pallet emits events, it depends on the runtime's definition of an event.
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// Origin allowed to change the allow-list.
+		type UpdateOrigin: EnsureOrigin<Self::Origin>;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	/// The calls every class of XCM origins may `Transact`.
+	#[pallet::storage]
+	pub type AllowedCalls<T> =
+		StorageDoubleMap<_, Twox64Concat, OriginClass, Blake2_128Concat, CallPattern, ()>;
+
+	#[pallet::genesis_config]
+	pub struct GenesisConfig {
+		pub allowed_calls: Vec<(OriginClass, CallPattern)>,
+	}
+
+	#[cfg(feature = "std")]
+	impl Default for GenesisConfig {
+		fn default() -> Self {
+			Self { allowed_calls: Default::default() }
+		}
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> GenesisBuild<T> for GenesisConfig {
+		fn build(&self) {
+			for (class, pattern) in &self.allowed_calls {
+				<AllowedCalls<T>>::insert(class, pattern, ());
+			}
+		}
+	}
+
+	// Pallets use events to inform users when important changes are made.
+	// https://docs.substrate.io/v3/runtime/events-and-errors
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// Calls were allowed for a class of origins. [class, calls]
+		CallAllowed(OriginClass, CallPattern),
+		/// Calls are no longer allowed for a class of origins. [class, calls]
+		CallDisallowed(OriginClass, CallPattern),
+	}
+
+	// Errors inform users that something went wrong.
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The calls are already allowed.
+		AlreadyAllowed,
+		/// The calls are not allowed.
+		NotAllowed,
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Allow origins of `class` to `Transact` the calls matching `pattern`.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1, 1))]
+		pub fn allow_call(
+			origin: OriginFor<T>,
+			class: OriginClass,
+			pattern: CallPattern,
+		) -> DispatchResultWithPostInfo {
+			T::UpdateOrigin::ensure_origin(origin)?;
+			ensure!(!<AllowedCalls<T>>::contains_key(class, &pattern), Error::<T>::AlreadyAllowed);
+
+			<AllowedCalls<T>>::insert(class, &pattern, ());
+			Self::deposit_event(Event::CallAllowed(class, pattern));
+			Ok(().into())
+		}
+
+		/// Remove `pattern` from the calls origins of `class` may `Transact`. Calls matching it
+		/// stay allowed if another pattern matches them as well.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1, 1))]
+		pub fn disallow_call(
+			origin: OriginFor<T>,
+			class: OriginClass,
+			pattern: CallPattern,
+		) -> DispatchResultWithPostInfo {
+			T::UpdateOrigin::ensure_origin(origin)?;
+			ensure!(<AllowedCalls<T>>::contains_key(class, &pattern), Error::<T>::NotAllowed);
+
+			<AllowedCalls<T>>::remove(class, &pattern);
+			Self::deposit_event(Event::CallDisallowed(class, pattern));
+			Ok(().into())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// Whether origins of `class` may `Transact` `call`.
+		pub fn is_allowed(class: OriginClass, call: &impl GetCallMetadata) -> bool {
+			let CallMetadata { pallet_name, function_name } = call.get_call_metadata();
+			let mut pattern = CallPattern::pallet(pallet_name);
+			if <AllowedCalls<T>>::contains_key(class, &pattern) {
+				return true
+			}
+			pattern.function = Some(function_name.as_bytes().to_vec());
+			<AllowedCalls<T>>::contains_key(class, &pattern)
+		}
+	}
+}
+
+/// Converts XCM origins with `Converter` and restricts the result to the calls allowed for the
+/// [`OriginClass`] of the XCM origin.
+pub struct FilteredTransactOrigin<T, Converter>(PhantomData<(T, Converter)>);
+
+impl<T, Converter> ConvertOrigin<T::Origin> for FilteredTransactOrigin<T, Converter>
+where
+	T: Config,
+	T::Call: GetCallMetadata,
+	Converter: ConvertOrigin<T::Origin>,
+{
+	fn convert_origin(
+		origin: impl Into<MultiLocation>,
+		kind: OriginKind,
+	) -> Result<T::Origin, MultiLocation> {
+		let origin = origin.into();
+		let class = OriginClass::of(&origin);
+		let mut converted = Converter::convert_origin(origin, kind)?;
+		converted.add_filter(move |call: &T::Call| {
+			class.map_or(false, |class| Pallet::<T>::is_allowed(class, call))
+		});
+		Ok(converted)
+	}
+}
@@ -0,0 +1,97 @@
+use crate::{self as pallet_xcm_transact_filter, CallPattern, FilteredTransactOrigin, OriginClass};
+use frame_support::{
+	parameter_types,
+	traits::{Everything, GenesisBuild},
+};
+use frame_system as system;
+use frame_system::EnsureRoot;
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, IdentityLookup},
+};
+use xcm::latest::prelude::*;
+use xcm_executor::traits::ConvertOrigin;
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+// Configure a mock runtime to test the pallet.
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+		TransactFilter: pallet_xcm_transact_filter::{Pallet, Call, Storage, Event<T>, Config},
+	}
+);
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const SS58Prefix: u8 = 42;
+}
+
+impl system::Config for Test {
+	type BaseCallFilter = Everything;
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = BlockHashCount;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = SS58Prefix;
+	type OnSetCode = ();
+	type MaxConsumers = frame_support::traits::ConstU32<16>;
+}
+
+impl pallet_xcm_transact_filter::Config for Test {
+	type Event = Event;
+	type UpdateOrigin = EnsureRoot<u64>;
+}
+
+/// Converts every XCM origin into the origin signed by `REMOTE`.
+pub struct SignedByRemote;
+
+impl ConvertOrigin<Origin> for SignedByRemote {
+	fn convert_origin(
+		_origin: impl Into<MultiLocation>,
+		_kind: OriginKind,
+	) -> Result<Origin, MultiLocation> {
+		Ok(Origin::signed(REMOTE))
+	}
+}
+
+pub type TransactOrigin = FilteredTransactOrigin<Test, SignedByRemote>;
+
+pub const REMOTE: u64 = 1;
+
+// Build genesis storage according to the mock runtime.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	let filter = pallet_xcm_transact_filter::GenesisConfig {
+		allowed_calls: vec![
+			(OriginClass::Relay, CallPattern::pallet("System")),
+			(OriginClass::Sibling, CallPattern::call("System", "remark")),
+		],
+	};
+	GenesisBuild::<Test>::assimilate_storage(&filter, &mut t).unwrap();
+	let mut ext = sp_io::TestExternalities::new(t);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
@@ -0,0 +1,121 @@
+use crate::{mock::*, AllowedCalls, CallPattern, Error, Event as TransactFilterEvent, OriginClass};
+use frame_support::{assert_noop, assert_ok, traits::OriginTrait};
+use sp_runtime::traits::BadOrigin;
+use xcm::latest::prelude::*;
+use xcm_executor::traits::ConvertOrigin;
+
+fn remark() -> Call {
+	frame_system::Call::<Test>::remark { remark: vec![] }.into()
+}
+
+fn remark_with_event() -> Call {
+	frame_system::Call::<Test>::remark_with_event { remark: vec![] }.into()
+}
+
+fn may_transact(location: MultiLocation, call: &Call) -> bool {
+	TransactOrigin::convert_origin(location, OriginKind::SovereignAccount)
+		.unwrap()
+		.filter_call(call)
+}
+
+#[test]
+fn origins_are_classified_by_location() {
+	let class = |location: MultiLocation| OriginClass::of(&location);
+
+	assert_eq!(class(Parent.into()), Some(OriginClass::Relay));
+	assert_eq!(
+		class((Parent, Plurality { id: BodyId::Executive, part: BodyPart::Voice }).into()),
+		Some(OriginClass::Relay)
+	);
+	assert_eq!(class((Parent, Parachain(2000)).into()), Some(OriginClass::Sibling));
+	assert_eq!(
+		class(
+			(Parent, Parachain(2000), AccountId32 { network: NetworkId::Any, id: [1; 32] }).into()
+		),
+		Some(OriginClass::RemoteAccount)
+	);
+	assert_eq!(
+		class((Parent, AccountId32 { network: NetworkId::Any, id: [1; 32] }).into()),
+		Some(OriginClass::RemoteAccount)
+	);
+	assert_eq!(class(MultiLocation::new(2, Here)), None);
+}
+
+#[test]
+fn only_update_origin_changes_the_allow_list() {
+	new_test_ext().execute_with(|| {
+		let pattern = CallPattern::pallet("Balances");
+		assert_noop!(
+			TransactFilter::allow_call(Origin::signed(1), OriginClass::Sibling, pattern.clone()),
+			BadOrigin
+		);
+
+		assert_ok!(TransactFilter::allow_call(
+			Origin::root(),
+			OriginClass::Sibling,
+			pattern.clone()
+		));
+		assert!(AllowedCalls::<Test>::contains_key(OriginClass::Sibling, &pattern));
+		System::assert_last_event(Event::TransactFilter(TransactFilterEvent::CallAllowed(
+			OriginClass::Sibling,
+			pattern.clone(),
+		)));
+		assert_noop!(
+			TransactFilter::allow_call(Origin::root(), OriginClass::Sibling, pattern.clone()),
+			Error::<Test>::AlreadyAllowed
+		);
+
+		assert_ok!(TransactFilter::disallow_call(
+			Origin::root(),
+			OriginClass::Sibling,
+			pattern.clone()
+		));
+		assert!(!AllowedCalls::<Test>::contains_key(OriginClass::Sibling, &pattern));
+		assert_noop!(
+			TransactFilter::disallow_call(Origin::root(), OriginClass::Sibling, pattern),
+			Error::<Test>::NotAllowed
+		);
+	});
+}
+
+#[test]
+fn pallets_and_single_calls_can_be_allowed() {
+	new_test_ext().execute_with(|| {
+		assert!(TransactFilter::is_allowed(OriginClass::Relay, &remark()));
+		assert!(TransactFilter::is_allowed(OriginClass::Relay, &remark_with_event()));
+
+		assert!(TransactFilter::is_allowed(OriginClass::Sibling, &remark()));
+		assert!(!TransactFilter::is_allowed(OriginClass::Sibling, &remark_with_event()));
+
+		assert!(!TransactFilter::is_allowed(OriginClass::RemoteAccount, &remark()));
+	});
+}
+
+#[test]
+fn converted_origins_only_dispatch_allowed_calls() {
+	new_test_ext().execute_with(|| {
+		let sibling: MultiLocation = (Parent, Parachain(2000)).into();
+		let user: MultiLocation =
+			(Parent, Parachain(2000), AccountId32 { network: NetworkId::Any, id: [1; 32] }).into();
+
+		assert!(may_transact(Parent.into(), &remark_with_event()));
+		assert!(may_transact(sibling.clone(), &remark()));
+		assert!(!may_transact(sibling, &remark_with_event()));
+		assert!(!may_transact(user.clone(), &remark()));
+
+		assert_ok!(TransactFilter::allow_call(
+			Origin::root(),
+			OriginClass::RemoteAccount,
+			CallPattern::call("System", "remark")
+		));
+		assert!(may_transact(user, &remark()));
+	});
+}
+
+#[test]
+fn origins_outside_consensus_dispatch_nothing() {
+	new_test_ext().execute_with(|| {
+		let location = MultiLocation::new(2, X1(Parachain(2000)));
+		assert!(!may_transact(location, &remark()));
+	});
+}
@@ -0,0 +1,498 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Cross-chain transfers of fungible assets.
+//!
+//! Users name the assets, as seen from this chain, and the beneficiary on another chain; the
+//! pallet builds the XCM. How the assets move depends on the chain holding them in reserve, the
+//! chain part of the asset location:
+//!
+//! - assets the destination may receive by teleport, per `Teleports`, are teleported;
+//! - assets we are the reserve of are reserve transferred to the destination, unless it is the
+//!   relay chain;
+//! - assets the destination is the reserve of are withdrawn back to it;
+//! - other assets are withdrawn to their reserve, which deposits them to the destination. Half of
+//!   the fees pay for the execution on the reserve and half for the one on the destination.
+//!
+//! The transferred assets pay for their execution on the other chains, up to `dest_weight`. The
+//! sender also pays a delivery fee in the native currency, priced by the size of the message.
+//!
+//! The destination reports whether the assets were deposited through `Outcomes`, under the query
+//! id of the `Transferred` event.
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+use codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use sp_runtime::RuntimeDebug;
+
+/// How assets are moved to their destination.
+#[derive(Encode, Decode, Clone, Copy, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+pub enum TransferKind {
+	/// We are the reserve of the assets.
+	SelfReserve,
+	/// The destination is the reserve of the assets.
+	ToReserve,
+	/// A third chain is the reserve of the assets.
+	ToNonReserve,
+	/// The assets are teleported.
+	Teleport,
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::TransferKind;
+	use codec::Encode;
+	use frame_support::{
+		dispatch::DispatchResultWithPostInfo,
+		pallet_prelude::*,
+		traits::{Currency, ExistenceRequirement, OnUnbalanced, WithdrawReasons},
+		transactional,
+	};
+	use frame_system::pallet_prelude::*;
+	use pallet_xcm_outcomes::ReportOutcome;
+	use sp_runtime::traits::{Convert, Saturating};
+	use sp_std::{boxed::Box, prelude::*};
+	use xcm::{
+		latest::prelude::*, VersionedMultiAsset, VersionedMultiAssets, VersionedMultiLocation,
+	};
+	use xcm_executor::traits::{FilterAssetLocation, InvertLocation, WeightBounds};
+
+	type BalanceOf<T> =
+		<<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+	type NegativeImbalanceOf<T> = <<T as Config>::Currency as Currency<
+		<T as frame_system::Config>::AccountId,
+	>>::NegativeImbalance;
+
+	/// Instructions of the longest transfer, to a chain which is not the reserve of the assets.
+	const MAX_TRANSFER_INSTRUCTIONS: Weight = 6;
+	/// Storage reads and writes of a transfer, including those tracking its outcome.
+	const TRANSFER_READS: Weight = 5;
+	const TRANSFER_WRITES: Weight = 9;
+
+	/// Configure the pallet by specifying the parameters and types on which it depends.
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// Because this pallet emits events, it depends on the runtime's definition of an event.
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// The currency delivery fees are paid in.
+		type Currency: Currency<Self::AccountId>;
+
+		/// Delivery fee of every transfer.
+		#[pallet::constant]
+		type DeliveryFeeBase: Get<BalanceOf<Self>>;
+
+		/// Delivery fee of every byte of the transfer message.
+		#[pallet::constant]
+		type DeliveryFeePerByte: Get<BalanceOf<Self>>;
+
+		/// Receives the delivery fees.
+		type OnDeliveryFee: OnUnbalanced<NegativeImbalanceOf<Self>>;
+
+		/// The location of the accounts.
+		type AccountIdToMultiLocation: Convert<Self::AccountId, MultiLocation>;
+
+		/// Our location, as seen from our siblings.
+		type SelfLocation: Get<MultiLocation>;
+
+		/// Whether an asset may be teleported to a chain.
+		type Teleports: FilterAssetLocation;
+
+		/// Executes the transfer messages.
+		type XcmExecutor: ExecuteXcm<<Self as frame_system::Config>::Call>;
+
+		/// Weighs the transfer messages.
+		type Weigher: WeightBounds<<Self as frame_system::Config>::Call>;
+
+		/// Weight of an XCM instruction.
+		#[pallet::constant]
+		type BaseXcmWeight: Get<Weight>;
+
+		/// Gives the locations of assets as seen from other chains.
+		type LocationInverter: InvertLocation;
+
+		/// Maximum number of assets transferred at once.
+		#[pallet::constant]
+		type MaxAssetsForTransfer: Get<u32>;
+
+		/// Tracks whether the assets reached the beneficiary.
+		type Outcomes: ReportOutcome;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	// Pallets use events to inform users when important changes are made.
+	// https://docs.substrate.io/v3/runtime/events-and-errors
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// Assets were sent to another chain. [sender, assets, dest, kind, delivery_fee, query_id]
+		Transferred(
+			T::AccountId,
+			Vec<MultiAsset>,
+			MultiLocation,
+			TransferKind,
+			BalanceOf<T>,
+			QueryId,
+		),
+	}
+
+	// Errors inform users that something went wrong.
+	#[pallet::error]
+	pub enum Error<T> {
+		/// A location or asset could not be converted to the latest XCM version.
+		BadVersion,
+		/// The destination is not an account on another chain.
+		InvalidDest,
+		/// An asset is not fungible, or its amount is zero.
+		InvalidAsset,
+		/// The reserve of an asset is unknown.
+		AssetHasNoReserve,
+		/// The assets do not share a reserve, or teleports, with the fee asset.
+		DistinctReserveForAssetAndFee,
+		/// No assets were given.
+		NoAssets,
+		/// More assets than `MaxAssetsForTransfer` were given.
+		TooManyAssets,
+		/// The fee item is not one of the assets.
+		FeeItemNotFound,
+		/// The location of the fees could not be given as seen from another chain.
+		CannotReanchor,
+		/// The transfer message could not be weighed.
+		UnweighableMessage,
+		/// The transfer message failed to execute.
+		XcmExecutionFailed,
+		/// The outcome of the transfer could not be tracked.
+		CannotTrackOutcome,
+		/// The relay chain is the reserve of its own token only, it does not take others in
+		/// reserve.
+		RelayChainIsNotReserve,
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Send `amount` of the asset at `asset` to `dest`, the beneficiary's location, buying up
+		/// to `dest_weight` of execution with it.
+		#[pallet::weight(Pallet::<T>::transfer_weight())]
+		#[transactional]
+		pub fn transfer(
+			origin: OriginFor<T>,
+			asset: Box<VersionedMultiLocation>,
+			amount: u128,
+			dest: Box<VersionedMultiLocation>,
+			dest_weight: Weight,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			let location = MultiLocation::try_from(*asset).map_err(|()| Error::<T>::BadVersion)?;
+			let asset: MultiAsset = (location, amount).into();
+			Self::do_transfer(who, asset.clone().into(), asset, *dest, dest_weight)
+		}
+
+		/// Send `asset` to `dest`, the beneficiary's location, buying up to `dest_weight` of
+		/// execution with it.
+		#[pallet::weight(Pallet::<T>::transfer_weight())]
+		#[transactional]
+		pub fn transfer_multiasset(
+			origin: OriginFor<T>,
+			asset: Box<VersionedMultiAsset>,
+			dest: Box<VersionedMultiLocation>,
+			dest_weight: Weight,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			let asset = MultiAsset::try_from(*asset).map_err(|()| Error::<T>::BadVersion)?;
+			Self::do_transfer(who, asset.clone().into(), asset, *dest, dest_weight)
+		}
+
+		/// Send `assets` to `dest`, the beneficiary's location, buying up to `dest_weight` of
+		/// execution with the one at `fee_item`. The assets must share their reserve.
+		#[pallet::weight(Pallet::<T>::transfer_weight())]
+		#[transactional]
+		pub fn transfer_multiassets(
+			origin: OriginFor<T>,
+			assets: Box<VersionedMultiAssets>,
+			fee_item: u32,
+			dest: Box<VersionedMultiLocation>,
+			dest_weight: Weight,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			let assets = MultiAssets::try_from(*assets).map_err(|()| Error::<T>::BadVersion)?;
+			let fee = assets.get(fee_item as usize).ok_or(Error::<T>::FeeItemNotFound)?.clone();
+			Self::do_transfer(who, assets, fee, *dest, dest_weight)
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// Upper bound of the weight of a transfer.
+		pub fn transfer_weight() -> Weight {
+			T::BaseXcmWeight::get()
+				.saturating_mul(MAX_TRANSFER_INSTRUCTIONS)
+				.saturating_add(T::DbWeight::get().reads_writes(TRANSFER_READS, TRANSFER_WRITES))
+		}
+
+		/// Delivery fee of a message of `size` bytes.
+		pub fn delivery_fee(size: u32) -> BalanceOf<T> {
+			T::DeliveryFeeBase::get()
+				.saturating_add(T::DeliveryFeePerByte::get().saturating_mul(size.into()))
+		}
+
+		fn do_transfer(
+			who: T::AccountId,
+			assets: MultiAssets,
+			fee: MultiAsset,
+			dest: VersionedMultiLocation,
+			dest_weight: Weight,
+		) -> DispatchResultWithPostInfo {
+			let dest = MultiLocation::try_from(dest).map_err(|()| Error::<T>::BadVersion)?;
+			let (chain, beneficiary) = Self::split_dest(&dest).ok_or(Error::<T>::InvalidDest)?;
+			ensure!(!assets.is_none(), Error::<T>::NoAssets);
+			ensure!(
+				assets.len() <= T::MaxAssetsForTransfer::get() as usize,
+				Error::<T>::TooManyAssets
+			);
+			ensure!(
+				assets
+					.inner()
+					.iter()
+					.all(|asset| matches!(asset.fun, Fungible(amount) if amount > 0)),
+				Error::<T>::InvalidAsset
+			);
+
+			let kind = Self::transfer_kind(&assets, &fee, &chain)?;
+			let (query_id, report) = T::Outcomes::report_outcome(chain.clone())
+				.map_err(|_| Error::<T>::CannotTrackOutcome)?;
+			let mut message = Self::transfer_message(
+				kind,
+				&assets,
+				fee,
+				chain,
+				beneficiary,
+				dest_weight,
+				report,
+			)?;
+			let weight =
+				T::Weigher::weight(&mut message).map_err(|()| Error::<T>::UnweighableMessage)?;
+
+			let delivery_fee = Self::delivery_fee(message.encoded_size() as u32);
+			let imbalance = T::Currency::withdraw(
+				&who,
+				delivery_fee,
+				WithdrawReasons::FEE,
+				ExistenceRequirement::KeepAlive,
+			)?;
+			T::OnDeliveryFee::on_unbalanced(imbalance);
+
+			let origin = T::AccountIdToMultiLocation::convert(who.clone());
+			T::XcmExecutor::execute_xcm_in_credit(origin, message, weight, weight)
+				.ensure_complete()
+				.map_err(|_| Error::<T>::XcmExecutionFailed)?;
+
+			Self::deposit_event(Event::Transferred(
+				who,
+				assets.drain(),
+				dest,
+				kind,
+				delivery_fee,
+				query_id,
+			));
+			let db_weight = T::DbWeight::get().reads_writes(TRANSFER_READS, TRANSFER_WRITES);
+			Ok(Some(weight.saturating_add(db_weight)).into())
+		}
+
+		/// Split `dest` into the chain and the beneficiary on that chain, `None` if it is not an
+		/// account on another chain.
+		fn split_dest(dest: &MultiLocation) -> Option<(MultiLocation, MultiLocation)> {
+			let (rest, first) = dest.interior.clone().split_first();
+			let (chain, beneficiary) = match (dest.parents, first) {
+				(1, Some(Parachain(id))) => (MultiLocation::new(1, X1(Parachain(id))), rest),
+				(1, _) => (MultiLocation::parent(), dest.interior.clone()),
+				_ => return None,
+			};
+			(chain != T::SelfLocation::get() && beneficiary != Here)
+				.then(|| (chain, MultiLocation::new(0, beneficiary)))
+		}
+
+		/// The chain holding `asset` in reserve, `Here` if we do.
+		fn reserve_of(asset: &MultiAsset) -> Option<MultiLocation> {
+			let location = match &asset.id {
+				Concrete(location) => location,
+				Abstract(_) => return None,
+			};
+			let reserve = match (location.parents, location.first_interior()) {
+				(0, _) => MultiLocation::here(),
+				(1, Some(Parachain(id))) => MultiLocation::new(1, X1(Parachain(*id))),
+				(1, _) => MultiLocation::parent(),
+				_ => return None,
+			};
+			Some(if reserve == T::SelfLocation::get() { MultiLocation::here() } else { reserve })
+		}
+
+		fn transfer_kind(
+			assets: &MultiAssets,
+			fee: &MultiAsset,
+			chain: &MultiLocation,
+		) -> Result<TransferKind, Error<T>> {
+			if assets
+				.inner()
+				.iter()
+				.all(|asset| T::Teleports::filter_asset_location(asset, chain))
+			{
+				return Ok(TransferKind::Teleport)
+			}
+			let reserve = Self::reserve_of(fee).ok_or(Error::<T>::AssetHasNoReserve)?;
+			ensure!(
+				assets
+					.inner()
+					.iter()
+					.all(|asset| Self::reserve_of(asset).as_ref() == Some(&reserve)),
+				Error::<T>::DistinctReserveForAssetAndFee
+			);
+			Ok(if reserve == MultiLocation::here() {
+				ensure!(*chain != MultiLocation::parent(), Error::<T>::RelayChainIsNotReserve);
+				TransferKind::SelfReserve
+			} else if reserve == *chain {
+				TransferKind::ToReserve
+			} else {
+				TransferKind::ToNonReserve
+			})
+		}
+
+		/// `asset` as seen from `chain`, with `amount` if given.
+		fn fee_at(
+			asset: &MultiAsset,
+			chain: &MultiLocation,
+			amount: Option<u128>,
+		) -> Result<MultiAsset, Error<T>> {
+			let inverted = T::LocationInverter::invert_location(chain)
+				.map_err(|()| Error::<T>::CannotReanchor)?;
+			let mut asset =
+				asset.clone().reanchored(&inverted).map_err(|_| Error::<T>::CannotReanchor)?;
+			// Assets of a sibling are seen from within the sibling itself.
+			if let (Concrete(location), MultiLocation { parents: 1, interior: X1(Parachain(id)) }) =
+				(&mut asset.id, chain)
+			{
+				if location.parents == 1 && location.first_interior() == Some(&Parachain(*id)) {
+					*location = MultiLocation::new(0, location.interior.clone().split_first().0);
+				}
+			}
+			if let Some(amount) = amount {
+				asset.fun = Fungible(amount);
+			}
+			Ok(asset)
+		}
+
+		/// The instructions buying execution, then depositing the assets to `beneficiary` and
+		/// reporting the outcome with `report`.
+		fn deposit(
+			fees: MultiAsset,
+			weight_limit: Weight,
+			max_assets: u32,
+			beneficiary: MultiLocation,
+			report: Xcm<()>,
+		) -> Xcm<()> {
+			Xcm(vec![
+				BuyExecution { fees, weight_limit: Limited(weight_limit) },
+				SetAppendix(report),
+				DepositAsset { assets: Wild(All), max_assets, beneficiary },
+			])
+		}
+
+		fn transfer_message(
+			kind: TransferKind,
+			assets: &MultiAssets,
+			fee: MultiAsset,
+			chain: MultiLocation,
+			beneficiary: MultiLocation,
+			dest_weight: Weight,
+			report: Xcm<()>,
+		) -> Result<Xcm<<T as frame_system::Config>::Call>, Error<T>> {
+			let max_assets = assets.len() as u32;
+			let at_dest = |amount| Self::fee_at(&fee, &chain, amount);
+			Ok(Xcm(match kind {
+				TransferKind::SelfReserve => vec![TransferReserveAsset {
+					assets: assets.clone(),
+					dest: chain.clone(),
+					xcm: Self::deposit(
+						at_dest(None)?,
+						dest_weight,
+						max_assets,
+						beneficiary,
+						report,
+					),
+				}],
+				TransferKind::ToReserve => vec![
+					WithdrawAsset(assets.clone()),
+					InitiateReserveWithdraw {
+						assets: Wild(All),
+						reserve: chain.clone(),
+						xcm: Self::deposit(
+							at_dest(None)?,
+							dest_weight,
+							max_assets,
+							beneficiary,
+							report,
+						),
+					},
+				],
+				TransferKind::ToNonReserve => {
+					let reserve = Self::reserve_of(&fee).ok_or(Error::<T>::AssetHasNoReserve)?;
+					let half = match fee.fun {
+						Fungible(amount) => amount / 2,
+						NonFungible(_) => return Err(Error::<T>::InvalidAsset),
+					};
+					let inverted_reserve = T::LocationInverter::invert_location(&reserve)
+						.map_err(|()| Error::<T>::CannotReanchor)?;
+					let mut dest_from_reserve = chain.clone();
+					dest_from_reserve
+						.prepend_with(inverted_reserve)
+						.map_err(|_| Error::<T>::CannotReanchor)?;
+					vec![
+						WithdrawAsset(assets.clone()),
+						InitiateReserveWithdraw {
+							assets: Wild(All),
+							reserve: reserve.clone(),
+							xcm: Xcm(vec![
+								BuyExecution {
+									fees: Self::fee_at(&fee, &reserve, Some(half))?,
+									weight_limit: Limited(dest_weight),
+								},
+								DepositReserveAsset {
+									assets: Wild(All),
+									max_assets,
+									dest: dest_from_reserve,
+									xcm: Self::deposit(
+										at_dest(Some(half))?,
+										dest_weight,
+										max_assets,
+										beneficiary,
+										report,
+									),
+								},
+							]),
+						},
+					]
+				},
+				TransferKind::Teleport => vec![
+					WithdrawAsset(assets.clone()),
+					InitiateTeleport {
+						assets: Wild(All),
+						dest: chain.clone(),
+						xcm: Self::deposit(
+							at_dest(None)?,
+							dest_weight,
+							max_assets,
+							beneficiary,
+							report,
+						),
+					},
+				],
+			}))
+		}
+	}
+}
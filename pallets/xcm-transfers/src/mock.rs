@@ -0,0 +1,207 @@
+use crate as pallet_xcm_transfers;
+use frame_support::{
+	parameter_types,
+	traits::{Everything, Imbalance, OnUnbalanced},
+	weights::Weight,
+};
+use frame_system as system;
+use pallet_xcm_outcomes::ReportOutcome;
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, Convert, IdentityLookup},
+};
+use std::cell::RefCell;
+use xcm::latest::prelude::*;
+use xcm_builder::{FixedWeightBounds, LocationInverter};
+use xcm_executor::traits::FilterAssetLocation;
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+// Configure a mock runtime to test the pallet.
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+		Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},
+		XcmTransfers: pallet_xcm_transfers::{Pallet, Call, Event<T>},
+	}
+);
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const SS58Prefix: u8 = 42;
+}
+
+impl system::Config for Test {
+	type BaseCallFilter = Everything;
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = BlockHashCount;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = pallet_balances::AccountData<u64>;
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = SS58Prefix;
+	type OnSetCode = ();
+	type MaxConsumers = frame_support::traits::ConstU32<16>;
+}
+
+parameter_types! {
+	pub const ExistentialDeposit: u64 = 1;
+}
+
+impl pallet_balances::Config for Test {
+	type Balance = u64;
+	type Event = Event;
+	type DustRemoval = ();
+	type ExistentialDeposit = ExistentialDeposit;
+	type AccountStore = System;
+	type WeightInfo = ();
+	type MaxLocks = ();
+	type MaxReserves = ();
+	type ReserveIdentifier = [u8; 8];
+}
+
+pub const ALICE: u64 = 1;
+pub const SELF_PARA_ID: u32 = 1000;
+/// The sibling the relay chain token may be teleported to.
+pub const TELEPORT_PARA_ID: u32 = 1;
+
+thread_local! {
+	pub static EXECUTED_XCM: RefCell<Vec<(MultiLocation, Xcm<Call>)>> = RefCell::new(Vec::new());
+	pub static EXECUTION_FAILS: RefCell<bool> = RefCell::new(false);
+	pub static DELIVERY_FEES: RefCell<u64> = RefCell::new(0);
+	pub static NEXT_QUERY_ID: RefCell<QueryId> = RefCell::new(0);
+}
+
+/// The messages executed so far.
+pub fn executed_xcm() -> Vec<(MultiLocation, Xcm<Call>)> {
+	EXECUTED_XCM.with(|q| q.borrow().clone())
+}
+
+pub fn set_execution_fails(fails: bool) {
+	EXECUTION_FAILS.with(|f| *f.borrow_mut() = fails);
+}
+
+/// The delivery fees collected so far.
+pub fn delivery_fees() -> u64 {
+	DELIVERY_FEES.with(|f| *f.borrow())
+}
+
+/// Records the messages instead of executing them.
+pub struct TestExecutor;
+impl ExecuteXcm<Call> for TestExecutor {
+	fn execute_xcm_in_credit(
+		origin: impl Into<MultiLocation>,
+		message: Xcm<Call>,
+		weight_limit: Weight,
+		_weight_credit: Weight,
+	) -> Outcome {
+		if EXECUTION_FAILS.with(|f| *f.borrow()) {
+			return Outcome::Error(XcmError::FailedToTransactAsset(""));
+		}
+		EXECUTED_XCM.with(|q| q.borrow_mut().push((origin.into(), message)));
+		Outcome::Complete(weight_limit)
+	}
+}
+
+pub struct CollectDeliveryFees;
+impl OnUnbalanced<pallet_balances::NegativeImbalance<Test>> for CollectDeliveryFees {
+	fn on_nonzero_unbalanced(fees: pallet_balances::NegativeImbalance<Test>) {
+		DELIVERY_FEES.with(|f| *f.borrow_mut() += fees.peek());
+	}
+}
+
+/// The instructions reporting the outcome of a transfer under `query_id`.
+pub fn outcome_report(query_id: QueryId) -> Xcm<()> {
+	Xcm(vec![ReportError {
+		query_id,
+		dest: Parachain(SELF_PARA_ID).into(),
+		max_response_weight: 0,
+	}])
+}
+
+/// Hands out query ids in sequence.
+pub struct TestOutcomes;
+impl ReportOutcome for TestOutcomes {
+	fn report_outcome(_responder: MultiLocation) -> Result<(QueryId, Xcm<()>), XcmError> {
+		let query_id = NEXT_QUERY_ID.with(|q| {
+			let query_id = *q.borrow();
+			*q.borrow_mut() += 1;
+			query_id
+		});
+		Ok((query_id, outcome_report(query_id)))
+	}
+}
+
+pub struct AccountIdToMultiLocation;
+impl Convert<u64, MultiLocation> for AccountIdToMultiLocation {
+	fn convert(who: u64) -> MultiLocation {
+		AccountIndex64 { network: NetworkId::Any, index: who }.into()
+	}
+}
+
+/// The relay chain token may be teleported to `TELEPORT_PARA_ID`.
+pub struct RelayTokenToTeleportPara;
+impl FilterAssetLocation for RelayTokenToTeleportPara {
+	fn filter_asset_location(asset: &MultiAsset, dest: &MultiLocation) -> bool {
+		asset.id == Concrete(MultiLocation::parent()) &&
+			*dest == MultiLocation::new(1, X1(Parachain(TELEPORT_PARA_ID)))
+	}
+}
+
+parameter_types! {
+	pub const DeliveryFeeBase: u64 = 100;
+	pub const DeliveryFeePerByte: u64 = 1;
+	pub SelfLocation: MultiLocation = MultiLocation::new(1, X1(Parachain(SELF_PARA_ID)));
+	pub Ancestry: MultiLocation = Parachain(SELF_PARA_ID).into();
+	pub const BaseXcmWeight: Weight = 1_000;
+	pub const MaxInstructions: u32 = 100;
+	pub const MaxAssetsForTransfer: u32 = 2;
+}
+
+impl pallet_xcm_transfers::Config for Test {
+	type Event = Event;
+	type Currency = Balances;
+	type DeliveryFeeBase = DeliveryFeeBase;
+	type DeliveryFeePerByte = DeliveryFeePerByte;
+	type OnDeliveryFee = CollectDeliveryFees;
+	type AccountIdToMultiLocation = AccountIdToMultiLocation;
+	type SelfLocation = SelfLocation;
+	type Teleports = RelayTokenToTeleportPara;
+	type XcmExecutor = TestExecutor;
+	type Weigher = FixedWeightBounds<BaseXcmWeight, Call, MaxInstructions>;
+	type BaseXcmWeight = BaseXcmWeight;
+	type LocationInverter = LocationInverter<Ancestry>;
+	type MaxAssetsForTransfer = MaxAssetsForTransfer;
+	type Outcomes = TestOutcomes;
+}
+
+// Build genesis storage according to the mock runtime.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	pallet_balances::GenesisConfig::<Test> { balances: vec![(ALICE, 10_000)] }
+		.assimilate_storage(&mut t)
+		.unwrap();
+	let mut ext = sp_io::TestExternalities::new(t);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
@@ -0,0 +1,314 @@
+use crate::{mock::*, Error, Event as XcmTransfersEvent, TransferKind};
+use frame_support::{assert_noop, assert_ok, traits::Get};
+use xcm::{latest::prelude::*, VersionedMultiAssets, VersionedMultiLocation};
+
+const DEST_WEIGHT: u64 = 5_000;
+/// The query the transfer of each test is tracked under.
+const QUERY_ID: QueryId = 0;
+
+fn native() -> MultiLocation {
+	MultiLocation::new(0, X1(PalletInstance(10)))
+}
+
+fn sibling_token(id: u32) -> MultiLocation {
+	MultiLocation::new(1, X2(Parachain(id), GeneralIndex(5)))
+}
+
+fn beneficiary() -> Junction {
+	AccountId32 { network: NetworkId::Any, id: [7; 32] }
+}
+
+fn on_sibling(id: u32) -> MultiLocation {
+	MultiLocation::new(1, X2(Parachain(id), beneficiary()))
+}
+
+fn deposit(fees: MultiAsset, max_assets: u32) -> Xcm<()> {
+	Xcm(vec![
+		BuyExecution { fees, weight_limit: Limited(DEST_WEIGHT) },
+		SetAppendix(outcome_report(QUERY_ID)),
+		DepositAsset { assets: Wild(All), max_assets, beneficiary: beneficiary().into() },
+	])
+}
+
+fn transfer(
+	asset: MultiLocation,
+	amount: u128,
+	dest: MultiLocation,
+) -> frame_support::dispatch::DispatchResultWithPostInfo {
+	XcmTransfers::transfer(
+		Origin::signed(ALICE),
+		Box::new(asset.into()),
+		amount,
+		Box::new(dest.into()),
+		DEST_WEIGHT,
+	)
+}
+
+fn alice() -> MultiLocation {
+	AccountIndex64 { network: NetworkId::Any, index: ALICE }.into()
+}
+
+fn assert_transferred(assets: Vec<MultiAsset>, dest: MultiLocation, kind: TransferKind) {
+	System::assert_last_event(Event::XcmTransfers(XcmTransfersEvent::Transferred(
+		ALICE,
+		assets,
+		dest,
+		kind,
+		delivery_fees(),
+		QUERY_ID,
+	)));
+}
+
+#[test]
+fn native_tokens_are_reserve_transferred() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(transfer(native(), 1_000, on_sibling(2000)));
+
+		let seen_from_sibling =
+			MultiLocation::new(1, X2(Parachain(SELF_PARA_ID), PalletInstance(10)));
+		assert_eq!(
+			executed_xcm(),
+			vec![(
+				alice(),
+				Xcm(vec![TransferReserveAsset {
+					assets: (native(), 1_000).into(),
+					dest: MultiLocation::new(1, X1(Parachain(2000))),
+					xcm: deposit((seen_from_sibling, 1_000).into(), 1),
+				}])
+			)]
+		);
+		assert_transferred(
+			vec![(native(), 1_000).into()],
+			on_sibling(2000),
+			TransferKind::SelfReserve,
+		);
+	});
+}
+
+#[test]
+fn assets_are_withdrawn_to_their_reserve() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(transfer(sibling_token(2000), 1_000, on_sibling(2000)));
+
+		let seen_from_reserve = MultiLocation::new(0, X1(GeneralIndex(5)));
+		assert_eq!(
+			executed_xcm(),
+			vec![(
+				alice(),
+				Xcm(vec![
+					WithdrawAsset((sibling_token(2000), 1_000).into()),
+					InitiateReserveWithdraw {
+						assets: Wild(All),
+						reserve: MultiLocation::new(1, X1(Parachain(2000))),
+						xcm: deposit((seen_from_reserve, 1_000).into(), 1),
+					},
+				])
+			)]
+		);
+		assert_transferred(
+			vec![(sibling_token(2000), 1_000).into()],
+			on_sibling(2000),
+			TransferKind::ToReserve,
+		);
+	});
+}
+
+#[test]
+fn assets_go_through_a_third_reserve() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(transfer(MultiLocation::parent(), 1_000, on_sibling(2000)));
+
+		assert_eq!(
+			executed_xcm(),
+			vec![(
+				alice(),
+				Xcm(vec![
+					WithdrawAsset((MultiLocation::parent(), 1_000).into()),
+					InitiateReserveWithdraw {
+						assets: Wild(All),
+						reserve: MultiLocation::parent(),
+						xcm: Xcm(vec![
+							BuyExecution {
+								fees: (MultiLocation::here(), 500).into(),
+								weight_limit: Limited(DEST_WEIGHT),
+							},
+							DepositReserveAsset {
+								assets: Wild(All),
+								max_assets: 1,
+								dest: MultiLocation::new(0, X1(Parachain(2000))),
+								xcm: deposit((MultiLocation::parent(), 500).into(), 1),
+							},
+						]),
+					},
+				])
+			)]
+		);
+		assert_transferred(
+			vec![(MultiLocation::parent(), 1_000).into()],
+			on_sibling(2000),
+			TransferKind::ToNonReserve,
+		);
+	});
+}
+
+#[test]
+fn teleportable_assets_are_teleported() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(transfer(MultiLocation::parent(), 1_000, on_sibling(TELEPORT_PARA_ID)));
+
+		assert_eq!(
+			executed_xcm(),
+			vec![(
+				alice(),
+				Xcm(vec![
+					WithdrawAsset((MultiLocation::parent(), 1_000).into()),
+					InitiateTeleport {
+						assets: Wild(All),
+						dest: MultiLocation::new(1, X1(Parachain(TELEPORT_PARA_ID))),
+						xcm: deposit((MultiLocation::parent(), 1_000).into(), 1),
+					},
+				])
+			)]
+		);
+		assert_transferred(
+			vec![(MultiLocation::parent(), 1_000).into()],
+			on_sibling(TELEPORT_PARA_ID),
+			TransferKind::Teleport,
+		);
+	});
+}
+
+#[test]
+fn multiple_assets_share_their_reserve() {
+	new_test_ext().execute_with(|| {
+		let assets = |assets: Vec<MultiAsset>| {
+			Box::new(VersionedMultiAssets::from(MultiAssets::from(assets)))
+		};
+		let dest = Box::new(VersionedMultiLocation::from(on_sibling(2000)));
+		let token = |index| MultiLocation::new(1, X2(Parachain(2000), GeneralIndex(index)));
+
+		assert_noop!(
+			XcmTransfers::transfer_multiassets(
+				Origin::signed(ALICE),
+				assets(vec![(native(), 100).into(), (token(5), 100).into()]),
+				0,
+				dest.clone(),
+				DEST_WEIGHT,
+			),
+			Error::<Test>::DistinctReserveForAssetAndFee
+		);
+		assert_noop!(
+			XcmTransfers::transfer_multiassets(
+				Origin::signed(ALICE),
+				assets(vec![(token(5), 100).into(), (token(6), 100).into()]),
+				2,
+				dest.clone(),
+				DEST_WEIGHT,
+			),
+			Error::<Test>::FeeItemNotFound
+		);
+		assert_noop!(
+			XcmTransfers::transfer_multiassets(
+				Origin::signed(ALICE),
+				assets(vec![
+					(token(5), 100).into(),
+					(token(6), 100).into(),
+					(token(7), 100).into()
+				]),
+				0,
+				dest.clone(),
+				DEST_WEIGHT,
+			),
+			Error::<Test>::TooManyAssets
+		);
+
+		assert_ok!(XcmTransfers::transfer_multiassets(
+			Origin::signed(ALICE),
+			assets(vec![(token(5), 100).into(), (token(6), 200).into()]),
+			1,
+			dest,
+			DEST_WEIGHT,
+		));
+		let (_, message) = executed_xcm().pop().unwrap();
+		let fees = match &message.0[1] {
+			InitiateReserveWithdraw { xcm, .. } => xcm.0[0].clone(),
+			_ => panic!("unexpected transfer message"),
+		};
+		assert_eq!(
+			fees,
+			BuyExecution {
+				fees: (MultiLocation::new(0, X1(GeneralIndex(6))), 200).into(),
+				weight_limit: Limited(DEST_WEIGHT),
+			}
+		);
+	});
+}
+
+#[test]
+fn destinations_are_accounts_on_other_chains() {
+	new_test_ext().execute_with(|| {
+		for dest in [
+			// A local account.
+			MultiLocation::new(0, X1(beneficiary())),
+			// A chain, without account.
+			MultiLocation::new(1, X1(Parachain(2000))),
+			// Ourselves.
+			on_sibling(SELF_PARA_ID),
+			// Outside of the relay chain.
+			MultiLocation::new(2, X1(beneficiary())),
+		] {
+			assert_noop!(transfer(native(), 1_000, dest), Error::<Test>::InvalidDest);
+		}
+		assert_noop!(transfer(native(), 0, on_sibling(2000)), Error::<Test>::InvalidAsset);
+		assert_noop!(
+			transfer(MultiLocation::new(2, Here), 1_000, on_sibling(2000)),
+			Error::<Test>::AssetHasNoReserve
+		);
+	});
+}
+
+#[test]
+fn relay_chain_accounts_are_destinations() {
+	new_test_ext().execute_with(|| {
+		let on_relay = MultiLocation::new(1, X1(beneficiary()));
+		assert_ok!(transfer(MultiLocation::parent(), 1_000, on_relay.clone()));
+		assert_transferred(
+			vec![(MultiLocation::parent(), 1_000).into()],
+			on_relay,
+			TransferKind::ToReserve,
+		);
+	});
+}
+
+#[test]
+fn native_tokens_are_not_reserve_transferred_to_the_relay_chain() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			transfer(native(), 1_000, MultiLocation::new(1, X1(beneficiary()))),
+			Error::<Test>::RelayChainIsNotReserve
+		);
+	});
+}
+
+#[test]
+fn senders_pay_delivery_fees() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(transfer(native(), 1_000, on_sibling(2000)));
+		let (_, message) = executed_xcm().pop().unwrap();
+		let fee = DeliveryFeeBase::get() + codec::Encode::encoded_size(&message) as u64;
+		assert_eq!(delivery_fees(), fee);
+		assert_eq!(Balances::free_balance(ALICE), 10_000 - fee);
+	});
+}
+
+#[test]
+fn failed_transfers_are_reverted() {
+	new_test_ext().execute_with(|| {
+		set_execution_fails(true);
+		assert_noop!(
+			transfer(native(), 1_000, on_sibling(2000)),
+			Error::<Test>::XcmExecutionFailed
+		);
+		assert_eq!(Balances::free_balance(ALICE), 10_000);
+	});
+}
@@ -0,0 +1,151 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Sibling parachains trusted with unpaid XCM execution.
+//!
+//! Partner chains coordinating with us at the system level should not have to fund their
+//! sovereign accounts here. Governance trusts them through `UpdateOrigin`: a sibling as a whole,
+//! for the messages it sends as itself, or one of its plurality bodies, for the messages it sends
+//! descending into that body with a leading `DescendOrigin`. Each is trusted on its own: trusting
+//! a sibling does not trust its bodies, nor the reverse.
+//!
+//! [`AllowUnpaidExecutionFromTrusted`] is the barrier letting their messages through without
+//! `BuyExecution`. The messages of any other sibling, or of users of a trusted one, still pay.
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+use core::marker::PhantomData;
+use frame_support::weights::Weight;
+use xcm::latest::prelude::*;
+use xcm_executor::traits::ShouldExecute;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use frame_support::{dispatch::DispatchResultWithPostInfo, pallet_prelude::*};
+	use frame_system::pallet_prelude::*;
+	use xcm::latest::prelude::*;
+
+	/// Configure the pallet by specifying the parameters and types on which it depends.
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// Because this pallet emits events, it depends on the runtime's definition of an event.
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// Origin allowed to trust and distrust siblings.
+		type UpdateOrigin: EnsureOrigin<Self::Origin>;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	/// The trusted siblings by para id, as a whole under `None`, and their trusted bodies.
+	#[pallet::storage]
+	pub type Trusted<T: Config> =
+		StorageDoubleMap<_, Twox64Concat, u32, Blake2_128Concat, Option<BodyId>, ()>;
+
+	// Pallets use events to inform users when important changes are made.
+	// https://docs.substrate.io/v3/runtime/events-and-errors
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// A sibling, or one of its bodies, gets unpaid execution. [para_id, body]
+		SiblingTrusted(u32, Option<BodyId>),
+		/// A sibling, or one of its bodies, pays for execution again. [para_id, body]
+		SiblingDistrusted(u32, Option<BodyId>),
+	}
+
+	// Errors inform users that something went wrong.
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The sibling, or its body, is trusted already.
+		AlreadyTrusted,
+		/// The sibling, or its body, is not trusted.
+		NotTrusted,
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Let the sibling `para_id`, or its plurality `body`, execute messages without paying.
+		#[pallet::weight(10_000 + T::DbWeight::get().writes(1))]
+		pub fn trust(
+			origin: OriginFor<T>,
+			para_id: u32,
+			body: Option<BodyId>,
+		) -> DispatchResultWithPostInfo {
+			T::UpdateOrigin::ensure_origin(origin)?;
+
+			ensure!(!<Trusted<T>>::contains_key(para_id, &body), Error::<T>::AlreadyTrusted);
+			<Trusted<T>>::insert(para_id, &body, ());
+			Self::deposit_event(Event::SiblingTrusted(para_id, body));
+			Ok(().into())
+		}
+
+		/// Make the sibling `para_id`, or its plurality `body`, pay for execution again.
+		#[pallet::weight(10_000 + T::DbWeight::get().writes(1))]
+		pub fn distrust(
+			origin: OriginFor<T>,
+			para_id: u32,
+			body: Option<BodyId>,
+		) -> DispatchResultWithPostInfo {
+			T::UpdateOrigin::ensure_origin(origin)?;
+
+			ensure!(<Trusted<T>>::contains_key(para_id, &body), Error::<T>::NotTrusted);
+			<Trusted<T>>::remove(para_id, &body);
+			Self::deposit_event(Event::SiblingDistrusted(para_id, body));
+			Ok(().into())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// Whether the sibling `para_id`, or its plurality `body`, gets unpaid execution.
+		pub fn is_trusted(para_id: u32, body: &Option<BodyId>) -> bool {
+			<Trusted<T>>::contains_key(para_id, body)
+		}
+	}
+}
+
+/// Allows the messages of trusted siblings, sent as themselves or descending into one of their
+/// trusted bodies, to execute without paying.
+pub struct AllowUnpaidExecutionFromTrusted<T>(PhantomData<T>);
+
+impl<T: Config> ShouldExecute for AllowUnpaidExecutionFromTrusted<T> {
+	fn should_execute<Call>(
+		origin: &MultiLocation,
+		message: &mut Xcm<Call>,
+		_max_weight: Weight,
+		_weight_credit: &mut Weight,
+	) -> Result<(), ()> {
+		let para_id = match origin {
+			MultiLocation { parents: 1, interior: X1(Parachain(para_id)) } => *para_id,
+			_ => return Err(()),
+		};
+		let (body, rest) = match message.0.split_first() {
+			Some((DescendOrigin(X1(Plurality { id, .. })), rest)) => (Some(id.clone()), rest),
+			_ => (None, &message.0[..]),
+		};
+		// Users of the sibling, or anything else it descends into, are not trusted, even after
+		// the leading instruction.
+		if descends(rest) {
+			return Err(())
+		}
+		if Pallet::<T>::is_trusted(para_id, &body) {
+			Ok(())
+		} else {
+			Err(())
+		}
+	}
+}
+
+/// Whether `instructions`, or those of their handlers, change the origin with `DescendOrigin`.
+fn descends<Call>(instructions: &[Instruction<Call>]) -> bool {
+	instructions.iter().any(|instruction| match instruction {
+		DescendOrigin(_) => true,
+		SetErrorHandler(xcm) | SetAppendix(xcm) => descends(&xcm.0),
+		_ => false,
+	})
+}
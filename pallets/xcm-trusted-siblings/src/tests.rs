@@ -0,0 +1,155 @@
+use crate::{mock::*, Error, Event as XcmTrustedSiblingsEvent};
+use frame_support::{assert_noop, assert_ok};
+use sp_runtime::traits::BadOrigin;
+use xcm::latest::prelude::*;
+use xcm_executor::traits::ShouldExecute;
+
+const PARA_ID: u32 = 2000;
+
+fn sibling(id: u32) -> MultiLocation {
+	MultiLocation::new(1, X1(Parachain(id)))
+}
+
+fn technical() -> Junctions {
+	X1(Plurality { id: BodyId::Technical, part: BodyPart::Voice })
+}
+
+fn trust(para_id: u32, body: Option<BodyId>) {
+	assert_ok!(XcmTrustedSiblings::trust(Origin::root(), para_id, body));
+}
+
+fn execute(origin: MultiLocation, instructions: Vec<Instruction<()>>) -> Result<(), ()> {
+	Barrier::should_execute(&origin, &mut Xcm(instructions), 1_000, &mut 0)
+}
+
+fn unpaid(descend: Option<Junctions>) -> Vec<Instruction<()>> {
+	let mut instructions: Vec<_> = descend.into_iter().map(DescendOrigin).collect();
+	instructions.push(ClearOrigin);
+	instructions
+}
+
+fn paid() -> Vec<Instruction<()>> {
+	let fees: MultiAsset = (Parent, 1_000).into();
+	vec![
+		WithdrawAsset(fees.clone().into()),
+		BuyExecution { fees, weight_limit: Unlimited },
+		ClearOrigin,
+	]
+}
+
+#[test]
+fn only_root_trusts_siblings() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(XcmTrustedSiblings::trust(Origin::signed(1), PARA_ID, None), BadOrigin);
+
+		trust(PARA_ID, None);
+		assert!(XcmTrustedSiblings::is_trusted(PARA_ID, &None));
+		assert!(!XcmTrustedSiblings::is_trusted(PARA_ID, &Some(BodyId::Technical)));
+		System::assert_last_event(Event::XcmTrustedSiblings(
+			XcmTrustedSiblingsEvent::SiblingTrusted(PARA_ID, None),
+		));
+
+		assert_noop!(
+			XcmTrustedSiblings::trust(Origin::root(), PARA_ID, None),
+			Error::<Test>::AlreadyTrusted
+		);
+	});
+}
+
+#[test]
+fn only_root_distrusts_siblings() {
+	new_test_ext().execute_with(|| {
+		trust(PARA_ID, Some(BodyId::Technical));
+
+		assert_noop!(
+			XcmTrustedSiblings::distrust(Origin::signed(1), PARA_ID, Some(BodyId::Technical)),
+			BadOrigin
+		);
+		assert_noop!(
+			XcmTrustedSiblings::distrust(Origin::root(), PARA_ID, None),
+			Error::<Test>::NotTrusted
+		);
+
+		assert_ok!(XcmTrustedSiblings::distrust(Origin::root(), PARA_ID, Some(BodyId::Technical)));
+		assert!(!XcmTrustedSiblings::is_trusted(PARA_ID, &Some(BodyId::Technical)));
+		System::assert_last_event(Event::XcmTrustedSiblings(
+			XcmTrustedSiblingsEvent::SiblingDistrusted(PARA_ID, Some(BodyId::Technical)),
+		));
+	});
+}
+
+#[test]
+fn trusted_siblings_execute_unpaid() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(execute(sibling(PARA_ID), unpaid(None)), Err(()));
+
+		trust(PARA_ID, None);
+		assert_ok!(execute(sibling(PARA_ID), unpaid(None)));
+		// Their bodies are not trusted along.
+		assert_eq!(execute(sibling(PARA_ID), unpaid(Some(technical()))), Err(()));
+	});
+}
+
+#[test]
+fn trusted_bodies_execute_unpaid() {
+	new_test_ext().execute_with(|| {
+		trust(PARA_ID, Some(BodyId::Technical));
+
+		assert_ok!(execute(sibling(PARA_ID), unpaid(Some(technical()))));
+		let council = X1(Plurality { id: BodyId::Legislative, part: BodyPart::Voice });
+		assert_eq!(execute(sibling(PARA_ID), unpaid(Some(council))), Err(()));
+		// Nor is the sibling trusted along.
+		assert_eq!(execute(sibling(PARA_ID), unpaid(None)), Err(()));
+	});
+}
+
+#[test]
+fn users_of_trusted_siblings_are_not_trusted() {
+	new_test_ext().execute_with(|| {
+		trust(PARA_ID, None);
+
+		let user = X1(AccountId32 { network: Any, id: [1; 32] });
+		assert_eq!(execute(sibling(PARA_ID), unpaid(Some(user))), Err(()));
+	});
+}
+
+#[test]
+fn later_descents_are_not_trusted() {
+	new_test_ext().execute_with(|| {
+		trust(PARA_ID, None);
+		trust(PARA_ID, Some(BodyId::Technical));
+		let user = X1(AccountId32 { network: Any, id: [1; 32] });
+
+		// A user of a trusted body.
+		let mut instructions = unpaid(Some(technical()));
+		instructions.insert(1, DescendOrigin(user.clone()));
+		assert_eq!(execute(sibling(PARA_ID), instructions), Err(()));
+		// A user of a trusted sibling, behind another instruction.
+		assert_eq!(
+			execute(sibling(PARA_ID), vec![ClearError, DescendOrigin(user.clone()), ClearOrigin]),
+			Err(())
+		);
+		// Or in a handler.
+		assert_eq!(
+			execute(sibling(PARA_ID), vec![SetAppendix(Xcm(vec![DescendOrigin(user)]))]),
+			Err(())
+		);
+	});
+}
+
+#[test]
+fn unlisted_siblings_need_buy_execution() {
+	new_test_ext().execute_with(|| {
+		trust(PARA_ID, None);
+		trust(PARA_ID + 1, Some(BodyId::Technical));
+
+		assert_eq!(execute(sibling(PARA_ID + 1), unpaid(None)), Err(()));
+		assert_eq!(execute(sibling(PARA_ID + 2), unpaid(None)), Err(()));
+		assert_eq!(execute(sibling(PARA_ID + 2), unpaid(Some(technical()))), Err(()));
+		// Nor does a trusted sibling vouch for anyone else.
+		let nested = MultiLocation::new(1, X2(Parachain(PARA_ID), Parachain(PARA_ID + 2)));
+		assert_eq!(execute(nested, unpaid(None)), Err(()));
+
+		assert_ok!(execute(sibling(PARA_ID + 2), paid()));
+	});
+}
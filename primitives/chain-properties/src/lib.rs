@@ -0,0 +1,15 @@
+#![no_std]
+
+//! Properties of the native token.
+//!
+//! Wallets read them from the chain spec, and offline signers from the metadata hash, which the
+//! runtime's build script computes before the runtime exists. Both take them from here.
+
+/// The symbol of the native token.
+pub const TOKEN_SYMBOL: &str = "UNIT";
+
+/// The decimals of the native token.
+pub const TOKEN_DECIMALS: u8 = 12;
+
+/// The prefix of SS58 addresses, 42 is the generic Substrate prefix.
+pub const SS58_PREFIX: u16 = 42;
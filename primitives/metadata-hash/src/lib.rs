@@ -0,0 +1,164 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Metadata hash for offline signers.
+//!
+//! Hardware wallets cannot hold the metadata of every chain, so they cannot decode what they are
+//! asked to sign. Instead the runtime commits to the merkleized hash of its metadata (RFC-0078):
+//! a signer sets [`CheckMetadataHash`] to [`Mode::Enabled`], which adds the hash to the signed
+//! payload, and ships a proof of the metadata parts needed to decode the extrinsic along with it.
+//! The wallet checks the proof against the hash it signs, so a transaction signed with wrong
+//! metadata is simply invalid.
+//!
+//! The hash is computed by the runtime's build script and passed in with the
+//! `RUNTIME_METADATA_HASH` environment variable, see [`decode_hash`]. Proofs are served by the
+//! [`MetadataHashApi`] runtime API. The merkleization of the V14 metadata of our Substrate version
+//! lives in [`merkleized`], as the published implementations of the RFC need newer metadata.
+
+extern crate alloc;
+
+use codec::{Decode, Encode};
+use frame_metadata::{RuntimeMetadata, RuntimeMetadataPrefixed};
+use frame_support::{traits::Get, CloneNoBound, DebugNoBound, EqNoBound, PartialEqNoBound};
+use scale_info::TypeInfo;
+use sp_runtime::{
+	traits::{DispatchInfoOf, SignedExtension},
+	transaction_validity::TransactionValidityError,
+	RuntimeDebug,
+};
+use sp_std::{marker::PhantomData, prelude::*};
+
+pub mod merkleized;
+
+pub use merkleized::ExtraInfo;
+
+#[cfg(test)]
+mod tests;
+
+/// Whether a transaction commits to the metadata hash.
+#[derive(Encode, Decode, Clone, Copy, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+pub enum Mode {
+	/// The metadata hash is not part of the signed payload.
+	Disabled,
+	/// The metadata hash of the runtime is part of the signed payload.
+	Enabled,
+}
+
+/// Adds the metadata hash `H` to the signed payload of transactions in [`Mode::Enabled`].
+///
+/// A runtime built without a metadata hash adds `None`, so enabled transactions can only be
+/// signed for it by clients that also know there is no hash.
+#[derive(Encode, Decode, CloneNoBound, EqNoBound, PartialEqNoBound, DebugNoBound, TypeInfo)]
+#[scale_info(skip_type_params(T, H))]
+pub struct CheckMetadataHash<T, H> {
+	mode: Mode,
+	_marker: PhantomData<(T, H)>,
+}
+
+impl<T, H> CheckMetadataHash<T, H> {
+	/// Check the metadata hash if `enable` is set.
+	pub fn new(enable: bool) -> Self {
+		let mode = if enable { Mode::Enabled } else { Mode::Disabled };
+		Self { mode, _marker: PhantomData }
+	}
+
+	/// The mode the transaction was signed with.
+	pub fn mode(&self) -> Mode {
+		self.mode
+	}
+}
+
+impl<T, H> SignedExtension for CheckMetadataHash<T, H>
+where
+	T: frame_system::Config + Send + Sync,
+	H: Get<Option<[u8; 32]>> + Send + Sync + 'static,
+{
+	const IDENTIFIER: &'static str = "CheckMetadataHash";
+	type AccountId = T::AccountId;
+	type Call = T::Call;
+	type AdditionalSigned = Option<[u8; 32]>;
+	type Pre = ();
+
+	fn additional_signed(&self) -> Result<Self::AdditionalSigned, TransactionValidityError> {
+		Ok(match self.mode {
+			Mode::Disabled => None,
+			Mode::Enabled => H::get(),
+		})
+	}
+
+	fn pre_dispatch(
+		self,
+		_who: &Self::AccountId,
+		_call: &Self::Call,
+		_info: &DispatchInfoOf<Self::Call>,
+		_len: usize,
+	) -> Result<Self::Pre, TransactionValidityError> {
+		Ok(())
+	}
+}
+
+/// Decode the hex encoded metadata hash the build script hands over, with or without `0x`.
+///
+/// Panics on malformed input, which fails the build when evaluated in a constant.
+pub const fn decode_hash(hex: &str) -> [u8; 32] {
+	let hex = hex.as_bytes();
+	let offset = if hex.len() == 66 && hex[0] == b'0' && hex[1] == b'x' { 2 } else { 0 };
+	assert!(hex.len() == offset + 64, "the metadata hash must be 32 hex encoded bytes");
+
+	let mut hash = [0u8; 32];
+	let mut i = 0;
+	while i < 32 {
+		hash[i] = hex_digit(hex[offset + 2 * i]) << 4 | hex_digit(hex[offset + 2 * i + 1]);
+		i += 1;
+	}
+	hash
+}
+
+const fn hex_digit(c: u8) -> u8 {
+	match c {
+		b'0'..=b'9' => c - b'0',
+		b'a'..=b'f' => c - b'a' + 10,
+		b'A'..=b'F' => c - b'A' + 10,
+		_ => panic!("the metadata hash must be hex encoded"),
+	}
+}
+
+/// The type information of the SCALE encoded, prefixed `metadata`.
+fn type_information(metadata: &[u8]) -> Option<merkleized::TypeInformation> {
+	match RuntimeMetadataPrefixed::decode(&mut &metadata[..]).ok()?.1 {
+		RuntimeMetadata::V14(metadata) => merkleized::TypeInformation::from_v14(&metadata).ok(),
+		_ => None,
+	}
+}
+
+/// The merkleized hash of the SCALE encoded, prefixed `metadata`.
+pub fn metadata_hash(metadata: &[u8], info: ExtraInfo) -> Option<[u8; 32]> {
+	Some(type_information(metadata)?.digest(info).hash())
+}
+
+/// A SCALE encoded [`merkleized::MetadataProof`] of the parts of `metadata` needed to decode
+/// `extrinsic`.
+///
+/// `additional_signed` is the encoded additional signed data of the signed extensions, required
+/// to show an offline signer what it signs for an extrinsic that is not signed yet.
+pub fn metadata_proof(
+	metadata: &[u8],
+	extrinsic: &[u8],
+	additional_signed: Option<&[u8]>,
+	info: ExtraInfo,
+) -> Option<Vec<u8>> {
+	let types = type_information(metadata)?;
+	let proof = types.proof_for_extrinsic(extrinsic, additional_signed)?;
+	Some(merkleized::MetadataProof { proof, extrinsic: types.extrinsic, extra_info: info }.encode())
+}
+
+sp_api::decl_runtime_apis! {
+	/// The metadata hash of the runtime and proofs against it.
+	pub trait MetadataHashApi {
+		/// The hash signed over by transactions in [`Mode::Enabled`], if the runtime has one.
+		fn metadata_hash() -> Option<[u8; 32]>;
+
+		/// A SCALE encoded proof of the metadata needed to decode `extrinsic`, or `None` if it
+		/// cannot be decoded. Pass the `additional_signed` data for extrinsics yet to be signed.
+		fn metadata_proof(extrinsic: Vec<u8>, additional_signed: Option<Vec<u8>>) -> Option<Vec<u8>>;
+	}
+}
@@ -0,0 +1,660 @@
+//! Merkleization of V14 metadata as specified by RFC-0078.
+//!
+//! The types reachable from the extrinsic format are flattened into leaves: every type becomes one
+//! leaf, except enumerations, which become one leaf per variant so that a proof only carries the
+//! variants an extrinsic uses. Primitives and compact integers are referenced inline. The leaves
+//! are sorted by their new type id and variant index and hashed into a complete binary tree, laid
+//! out like a binary heap: node `i` has the children `2i + 1` and `2i + 2`, and the leaves take the
+//! last positions. Proofs list their leaves and the hashes of the other nodes needed in the order
+//! they are met walking the tree depth-first from the left, as offline signers read them.
+
+use alloc::string::String;
+use codec::{Compact, Decode, Encode};
+use frame_metadata::v14::RuntimeMetadataV14;
+use scale_info::{form::PortableForm, PortableRegistry, TypeDefPrimitive};
+use sp_std::{
+	collections::{btree_map::BTreeMap, btree_set::BTreeSet},
+	iter::Peekable,
+	prelude::*,
+	slice,
+};
+
+/// A blake3 hash.
+pub type Hash = [u8; 32];
+
+fn hash(data: &[u8]) -> Hash {
+	blake3::hash(data).into()
+}
+
+/// Chain information signed over next to the metadata.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+pub struct ExtraInfo {
+	/// The spec version of the runtime.
+	pub spec_version: u32,
+	/// The spec name of the runtime.
+	pub spec_name: String,
+	/// The SS58 prefix of addresses.
+	pub base58_prefix: u16,
+	/// The number of decimals of the native token.
+	pub decimals: u8,
+	/// The symbol of the native token.
+	pub token_symbol: String,
+}
+
+/// A reference to a type, inline for primitives.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TypeRef {
+	Bool,
+	Char,
+	Str,
+	U8,
+	U16,
+	U32,
+	U64,
+	U128,
+	U256,
+	I8,
+	I16,
+	I32,
+	I64,
+	I128,
+	I256,
+	CompactU8,
+	CompactU16,
+	CompactU32,
+	CompactU64,
+	CompactU128,
+	CompactU256,
+	/// A type without any data, like the empty tuple.
+	Void,
+	/// The leaves with this type id.
+	PerId(Compact<u32>),
+}
+
+/// A field of a composite or an enumeration variant.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+pub struct Field {
+	pub name: Option<String>,
+	pub ty: TypeRef,
+	pub type_name: Option<String>,
+}
+
+/// A single variant of an enumeration.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+pub struct EnumerationVariant {
+	pub name: String,
+	pub fields: Vec<Field>,
+	pub index: Compact<u32>,
+}
+
+/// The layout of a bit sequence.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+pub struct BitSequence {
+	/// The number of bytes of the store type.
+	pub num_bytes: u8,
+	/// Whether the bits are ordered least significant first.
+	pub least_significant_bit_first: bool,
+}
+
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+pub enum TypeDef {
+	Composite(Vec<Field>),
+	Enumeration(EnumerationVariant),
+	Sequence(TypeRef),
+	Array(u32, TypeRef),
+	Tuple(Vec<TypeRef>),
+	BitSequence(BitSequence),
+}
+
+/// A leaf of the type information tree.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+pub struct Type {
+	pub path: Vec<String>,
+	pub type_def: TypeDef,
+	pub type_id: Compact<u32>,
+}
+
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+pub struct SignedExtensionMetadata {
+	pub identifier: String,
+	pub included_in_extrinsic: TypeRef,
+	pub included_in_signed_data: TypeRef,
+}
+
+/// The extrinsic format, hashed next to the type information tree.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+pub struct ExtrinsicMetadata {
+	pub version: u8,
+	pub address_ty: TypeRef,
+	pub call_ty: TypeRef,
+	pub signature_ty: TypeRef,
+	pub signed_extensions: Vec<SignedExtensionMetadata>,
+}
+
+/// What the metadata hash commits to.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+pub enum MetadataDigest {
+	#[codec(index = 1)]
+	V1 {
+		type_information_tree_root: Hash,
+		extrinsic_metadata_hash: Hash,
+		spec_version: u32,
+		spec_name: String,
+		base58_prefix: u16,
+		decimals: u8,
+		token_symbol: String,
+	},
+}
+
+impl MetadataDigest {
+	pub fn hash(&self) -> Hash {
+		hash(&self.encode())
+	}
+}
+
+/// The leaves an extrinsic needs, and the hashes of the tree nodes to compute the root from them.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+pub struct Proof {
+	/// The leaves, in the order [`Proof::root`] consumes them.
+	pub leaves: Vec<Type>,
+	/// The position in the tree of each leaf.
+	pub leaf_indices: Vec<u32>,
+	/// The hashes of the other nodes needed, in the order [`Proof::root`] consumes them.
+	pub nodes: Vec<Hash>,
+}
+
+impl Proof {
+	/// The root of the tree the proof was generated for, `None` if the proof is malformed.
+	pub fn root(&self) -> Option<Hash> {
+		if self.leaves.len() != self.leaf_indices.len() {
+			return None
+		}
+		let mut leaves = self.leaf_indices.iter().copied().zip(&self.leaves).peekable();
+		let mut nodes = self.nodes.iter();
+		let root = proof_node(0, &mut leaves, &mut nodes)?;
+		// Leaves or nodes left over mean the proof was not generated for this tree.
+		(leaves.next().is_none() && nodes.next().is_none()).then(|| root)
+	}
+}
+
+/// The hash of the node at `index`, walking the tree depth-first from the left: the node is the
+/// next leaf of the proof, an ancestor of it hashed from its children, or the next node hash.
+fn proof_node<'a>(
+	index: u32,
+	leaves: &mut Peekable<impl Iterator<Item = (u32, &'a Type)>>,
+	nodes: &mut slice::Iter<Hash>,
+) -> Option<Hash> {
+	match leaves.peek() {
+		Some((leaf, ty)) if *leaf == index => {
+			let node = hash(&ty.encode());
+			leaves.next();
+			Some(node)
+		},
+		Some((leaf, _)) if is_ancestor(index, *leaf) => {
+			let left = proof_node(2 * index + 1, leaves, nodes)?;
+			let right = proof_node(2 * index + 2, leaves, nodes)?;
+			Some(hash(&[left, right].concat()))
+		},
+		_ => nodes.next().copied(),
+	}
+}
+
+/// Whether the node at `index` lies above the node at `descendant` in the tree.
+fn is_ancestor(index: u32, descendant: u32) -> bool {
+	// Counting from one, the ancestors of a node are its index shifted right.
+	let (index, descendant) = (index as u64 + 1, descendant as u64 + 1);
+	let depth = |index: u64| 63 - index.leading_zeros();
+	index < descendant && descendant >> (depth(descendant) - depth(index)) == index
+}
+
+/// A proof of the metadata needed to decode an extrinsic, as handed to offline signers.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+pub struct MetadataProof {
+	pub proof: Proof,
+	pub extrinsic: ExtrinsicMetadata,
+	pub extra_info: ExtraInfo,
+}
+
+/// The type information tree and extrinsic format of some metadata.
+pub struct TypeInformation {
+	/// The leaves, sorted by type id and variant index.
+	pub leaves: Vec<Type>,
+	pub extrinsic: ExtrinsicMetadata,
+}
+
+impl TypeInformation {
+	/// Flatten the types of `metadata` reachable from its extrinsic format.
+	pub fn from_v14(metadata: &RuntimeMetadataV14) -> Result<Self, &'static str> {
+		let registry = &metadata.types;
+		let extrinsic_ty = metadata.extrinsic.ty.id();
+		let param = |name: &str| -> Result<u32, &'static str> {
+			extrinsic_param(registry, extrinsic_ty, name).ok_or("extrinsic type parameter missing")
+		};
+		let (address, call, signature) = (param("Address")?, param("Call")?, param("Signature")?);
+
+		let mut pending = vec![address, call, signature];
+		for extension in &metadata.extrinsic.signed_extensions {
+			pending.push(extension.ty.id());
+			pending.push(extension.additional_signed.id());
+		}
+
+		// Collect every type reachable from the extrinsic format that needs a leaf, and give them
+		// new, consecutive ids in the order of their old ones.
+		let mut collected = BTreeSet::new();
+		while let Some(id) = pending.pop() {
+			if collected.contains(&id) {
+				continue
+			}
+			let ty = registry.resolve(id).ok_or("unknown type id")?;
+			match ty.type_def() {
+				scale_info::TypeDef::Composite(composite) => {
+					if !composite.fields().is_empty() {
+						collected.insert(id);
+					}
+					pending.extend(composite.fields().iter().map(|field| field.ty().id()));
+				},
+				scale_info::TypeDef::Variant(variant) => {
+					if !variant.variants().is_empty() {
+						collected.insert(id);
+					}
+					pending.extend(
+						variant
+							.variants()
+							.iter()
+							.flat_map(|variant| variant.fields())
+							.map(|field| field.ty().id()),
+					);
+				},
+				scale_info::TypeDef::Sequence(sequence) => {
+					collected.insert(id);
+					pending.push(sequence.type_param().id());
+				},
+				scale_info::TypeDef::Array(array) => {
+					collected.insert(id);
+					pending.push(array.type_param().id());
+				},
+				scale_info::TypeDef::Tuple(tuple) => {
+					if !tuple.fields().is_empty() {
+						collected.insert(id);
+					}
+					pending.extend(tuple.fields().iter().map(|field| field.id()));
+				},
+				scale_info::TypeDef::BitSequence(_) => {
+					collected.insert(id);
+				},
+				scale_info::TypeDef::Primitive(_) | scale_info::TypeDef::Compact(_) => {},
+			}
+		}
+		let ids = collected
+			.iter()
+			.enumerate()
+			.map(|(new, old)| (*old, new as u32))
+			.collect::<BTreeMap<_, _>>();
+		let converter = Converter { registry, ids: &ids };
+
+		let mut leaves = Vec::new();
+		for (old, new) in &ids {
+			let ty = registry.resolve(*old).ok_or("unknown type id")?;
+			let path = ty.path().segments().to_vec();
+			let type_id = Compact(*new);
+			let mut leaf = |type_def| leaves.push(Type { path: path.clone(), type_def, type_id });
+			match ty.type_def() {
+				scale_info::TypeDef::Composite(composite) =>
+					leaf(TypeDef::Composite(converter.fields(composite.fields())?)),
+				scale_info::TypeDef::Variant(variant) => {
+					let mut variants = variant.variants().iter().collect::<Vec<_>>();
+					variants.sort_by_key(|variant| variant.index());
+					for variant in variants {
+						leaf(TypeDef::Enumeration(EnumerationVariant {
+							name: variant.name().clone(),
+							fields: converter.fields(variant.fields())?,
+							index: Compact(variant.index().into()),
+						}));
+					}
+				},
+				scale_info::TypeDef::Sequence(sequence) =>
+					leaf(TypeDef::Sequence(converter.type_ref(sequence.type_param().id())?)),
+				scale_info::TypeDef::Array(array) =>
+					leaf(TypeDef::Array(array.len(), converter.type_ref(array.type_param().id())?)),
+				scale_info::TypeDef::Tuple(tuple) => leaf(TypeDef::Tuple(
+					tuple
+						.fields()
+						.iter()
+						.map(|field| converter.type_ref(field.id()))
+						.collect::<Result<_, _>>()?,
+				)),
+				scale_info::TypeDef::BitSequence(bits) => {
+					let store = registry.resolve(bits.bit_store_type().id());
+					let num_bytes = match store.map(|store| store.type_def()) {
+						Some(scale_info::TypeDef::Primitive(TypeDefPrimitive::U8)) => 1,
+						Some(scale_info::TypeDef::Primitive(TypeDefPrimitive::U16)) => 2,
+						Some(scale_info::TypeDef::Primitive(TypeDefPrimitive::U32)) => 4,
+						Some(scale_info::TypeDef::Primitive(TypeDefPrimitive::U64)) => 8,
+						_ => return Err("unsupported bit sequence store type"),
+					};
+					let order = registry.resolve(bits.bit_order_type().id());
+					let least_significant_bit_first = order
+						.and_then(|order| order.path().segments().last())
+						.map_or(false, |name| name == "Lsb0");
+					leaf(TypeDef::BitSequence(BitSequence {
+						num_bytes,
+						least_significant_bit_first,
+					}));
+				},
+				scale_info::TypeDef::Primitive(_) | scale_info::TypeDef::Compact(_) => {},
+			}
+		}
+
+		let extrinsic = ExtrinsicMetadata {
+			version: metadata.extrinsic.version,
+			address_ty: converter.type_ref(address)?,
+			call_ty: converter.type_ref(call)?,
+			signature_ty: converter.type_ref(signature)?,
+			signed_extensions: metadata
+				.extrinsic
+				.signed_extensions
+				.iter()
+				.map(|extension| {
+					Ok(SignedExtensionMetadata {
+						identifier: extension.identifier.clone(),
+						included_in_extrinsic: converter.type_ref(extension.ty.id())?,
+						included_in_signed_data: converter
+							.type_ref(extension.additional_signed.id())?,
+					})
+				})
+				.collect::<Result<_, &'static str>>()?,
+		};
+
+		Ok(Self { leaves, extrinsic })
+	}
+
+	/// The hashes of all tree nodes, the root first and the leaves last.
+	fn tree(&self) -> Vec<Hash> {
+		let leaves = self.leaves.len();
+		if leaves == 0 {
+			return vec![[0; 32]]
+		}
+		let mut nodes = vec![[0; 32]; 2 * leaves - 1];
+		for (index, leaf) in self.leaves.iter().enumerate() {
+			nodes[leaves - 1 + index] = hash(&leaf.encode());
+		}
+		for index in (0..leaves - 1).rev() {
+			nodes[index] = hash(&[nodes[2 * index + 1], nodes[2 * index + 2]].concat());
+		}
+		nodes
+	}
+
+	/// The root of the type information tree.
+	pub fn root(&self) -> Hash {
+		self.tree()[0]
+	}
+
+	/// The digest of the metadata with `info`.
+	pub fn digest(&self, info: ExtraInfo) -> MetadataDigest {
+		MetadataDigest::V1 {
+			type_information_tree_root: self.root(),
+			extrinsic_metadata_hash: hash(&self.extrinsic.encode()),
+			spec_version: info.spec_version,
+			spec_name: info.spec_name,
+			base58_prefix: info.base58_prefix,
+			decimals: info.decimals,
+			token_symbol: info.token_symbol,
+		}
+	}
+
+	/// A proof of the leaves needed to decode `extrinsic`, and the types of the signed extensions
+	/// in `additional_signed` if given. `None` if they cannot be decoded.
+	pub fn proof_for_extrinsic(
+		&self,
+		extrinsic: &[u8],
+		additional_signed: Option<&[u8]>,
+	) -> Option<Proof> {
+		let mut decoder = Decoder { leaves: &self.leaves, used: BTreeSet::new() };
+
+		let mut input = extrinsic;
+		let length = Compact::<u32>::decode(&mut input).ok()?.0 as usize;
+		if input.len() != length {
+			return None
+		}
+		let version = *input.first()?;
+		input = &input[1..];
+		if version & 0x7f != self.extrinsic.version {
+			return None
+		}
+		if version & 0x80 != 0 {
+			decoder.decode(self.extrinsic.address_ty, &mut input)?;
+			decoder.decode(self.extrinsic.signature_ty, &mut input)?;
+			for extension in &self.extrinsic.signed_extensions {
+				decoder.decode(extension.included_in_extrinsic, &mut input)?;
+			}
+		}
+		decoder.decode(self.extrinsic.call_ty, &mut input)?;
+		if !input.is_empty() {
+			return None
+		}
+
+		if let Some(mut input) = additional_signed {
+			for extension in &self.extrinsic.signed_extensions {
+				decoder.decode(extension.included_in_signed_data, &mut input)?;
+			}
+			if !input.is_empty() {
+				return None
+			}
+		}
+
+		Some(self.proof(decoder.used))
+	}
+
+	/// A proof of the leaves at the positions `used`.
+	fn proof(&self, used: BTreeSet<usize>) -> Proof {
+		let tree = self.tree();
+		let first_leaf = self.leaves.len().saturating_sub(1);
+		let used = used.iter().map(|leaf| first_leaf + leaf).collect::<BTreeSet<_>>();
+		let mut ancestors = BTreeSet::new();
+		for mut index in used.iter().copied() {
+			while index > 0 {
+				index = (index - 1) / 2;
+				if !ancestors.insert(index) {
+					break
+				}
+			}
+		}
+
+		// Walk the tree depth-first from the left like `Proof::root` does, taking the leaves and
+		// the hashes of the nodes it cannot compute as they come.
+		let mut proof = Proof { leaves: Vec::new(), leaf_indices: Vec::new(), nodes: Vec::new() };
+		let mut stack = vec![0];
+		while let Some(index) = stack.pop() {
+			if used.contains(&index) {
+				proof.leaves.push(self.leaves[index - first_leaf].clone());
+				proof.leaf_indices.push(index as u32);
+			} else if ancestors.contains(&index) {
+				stack.push(2 * index + 2);
+				stack.push(2 * index + 1);
+			} else {
+				proof.nodes.push(tree[index]);
+			}
+		}
+		proof
+	}
+}
+
+/// The type parameter `name` of the extrinsic type, looking through wrappers of it.
+fn extrinsic_param(registry: &PortableRegistry, mut id: u32, name: &str) -> Option<u32> {
+	loop {
+		let ty = registry.resolve(id)?;
+		if let Some(param) = ty.type_params().iter().find(|param| param.name() == name) {
+			return param.ty().map(|ty| ty.id())
+		}
+		match ty.type_def() {
+			scale_info::TypeDef::Composite(composite) if composite.fields().len() == 1 =>
+				id = composite.fields()[0].ty().id(),
+			_ => return None,
+		}
+	}
+}
+
+struct Converter<'a> {
+	registry: &'a PortableRegistry,
+	ids: &'a BTreeMap<u32, u32>,
+}
+
+impl<'a> Converter<'a> {
+	fn type_ref(&self, id: u32) -> Result<TypeRef, &'static str> {
+		if let Some(new) = self.ids.get(&id) {
+			return Ok(TypeRef::PerId(Compact(*new)))
+		}
+		let ty = self.registry.resolve(id).ok_or("unknown type id")?;
+		Ok(match ty.type_def() {
+			scale_info::TypeDef::Primitive(primitive) => match primitive {
+				TypeDefPrimitive::Bool => TypeRef::Bool,
+				TypeDefPrimitive::Char => TypeRef::Char,
+				TypeDefPrimitive::Str => TypeRef::Str,
+				TypeDefPrimitive::U8 => TypeRef::U8,
+				TypeDefPrimitive::U16 => TypeRef::U16,
+				TypeDefPrimitive::U32 => TypeRef::U32,
+				TypeDefPrimitive::U64 => TypeRef::U64,
+				TypeDefPrimitive::U128 => TypeRef::U128,
+				TypeDefPrimitive::U256 => TypeRef::U256,
+				TypeDefPrimitive::I8 => TypeRef::I8,
+				TypeDefPrimitive::I16 => TypeRef::I16,
+				TypeDefPrimitive::I32 => TypeRef::I32,
+				TypeDefPrimitive::I64 => TypeRef::I64,
+				TypeDefPrimitive::I128 => TypeRef::I128,
+				TypeDefPrimitive::I256 => TypeRef::I256,
+			},
+			scale_info::TypeDef::Compact(compact) => self.compact(compact.type_param().id())?,
+			// Only types without any data are left.
+			_ => TypeRef::Void,
+		})
+	}
+
+	/// The compact encoding of the integer `id`, or of the type wrapping it.
+	fn compact(&self, mut id: u32) -> Result<TypeRef, &'static str> {
+		loop {
+			let ty = self.registry.resolve(id).ok_or("unknown type id")?;
+			match ty.type_def() {
+				scale_info::TypeDef::Primitive(primitive) =>
+					return match primitive {
+						TypeDefPrimitive::U8 => Ok(TypeRef::CompactU8),
+						TypeDefPrimitive::U16 => Ok(TypeRef::CompactU16),
+						TypeDefPrimitive::U32 => Ok(TypeRef::CompactU32),
+						TypeDefPrimitive::U64 => Ok(TypeRef::CompactU64),
+						TypeDefPrimitive::U128 => Ok(TypeRef::CompactU128),
+						TypeDefPrimitive::U256 => Ok(TypeRef::CompactU256),
+						_ => Err("unsupported compact type"),
+					},
+				scale_info::TypeDef::Composite(composite) if composite.fields().len() == 1 =>
+					id = composite.fields()[0].ty().id(),
+				scale_info::TypeDef::Composite(composite) if composite.fields().is_empty() =>
+					return Ok(TypeRef::Void),
+				_ => return Err("unsupported compact type"),
+			}
+		}
+	}
+
+	fn fields(
+		&self,
+		fields: &[scale_info::Field<PortableForm>],
+	) -> Result<Vec<Field>, &'static str> {
+		fields
+			.iter()
+			.map(|field| {
+				Ok(Field {
+					name: field.name().cloned(),
+					ty: self.type_ref(field.ty().id())?,
+					type_name: field.type_name().cloned(),
+				})
+			})
+			.collect()
+	}
+}
+
+/// Decodes values of the tree's types, noting the leaves used.
+struct Decoder<'a> {
+	leaves: &'a [Type],
+	used: BTreeSet<usize>,
+}
+
+impl<'a> Decoder<'a> {
+	fn decode(&mut self, ty: TypeRef, input: &mut &[u8]) -> Option<()> {
+		match ty {
+			TypeRef::Bool | TypeRef::U8 | TypeRef::I8 => skip(input, 1),
+			TypeRef::U16 | TypeRef::I16 => skip(input, 2),
+			TypeRef::Char | TypeRef::U32 | TypeRef::I32 => skip(input, 4),
+			TypeRef::U64 | TypeRef::I64 => skip(input, 8),
+			TypeRef::U128 | TypeRef::I128 => skip(input, 16),
+			TypeRef::U256 | TypeRef::I256 => skip(input, 32),
+			TypeRef::Str => {
+				let length = Compact::<u32>::decode(input).ok()?.0;
+				skip(input, length as usize)
+			},
+			TypeRef::CompactU8 |
+			TypeRef::CompactU16 |
+			TypeRef::CompactU32 |
+			TypeRef::CompactU64 |
+			TypeRef::CompactU128 |
+			TypeRef::CompactU256 => {
+				let length = match input.first()? & 0b11 {
+					0b00 => 1,
+					0b01 => 2,
+					0b10 => 4,
+					_ => (input[0] >> 2) as usize + 5,
+				};
+				skip(input, length)
+			},
+			TypeRef::Void => Some(()),
+			TypeRef::PerId(Compact(id)) => self.decode_leaf(id, input),
+		}
+	}
+
+	fn decode_leaf(&mut self, id: u32, input: &mut &[u8]) -> Option<()> {
+		// Copied out, so the leaf found does not borrow `self`.
+		let all: &'a [Type] = self.leaves;
+		let first = all.partition_point(|leaf| leaf.type_id.0 < id);
+		let leaves = all[first..].iter().take_while(|leaf| leaf.type_id.0 == id);
+
+		let (position, leaf) = if let TypeDef::Enumeration(_) = &all.get(first)?.type_def {
+			let index = u32::from(u8::decode(input).ok()?);
+			leaves
+				.enumerate()
+				.find(|(_, leaf)| match &leaf.type_def {
+					TypeDef::Enumeration(variant) => variant.index.0 == index,
+					_ => false,
+				})
+				.map(|(offset, leaf)| (first + offset, leaf))?
+		} else {
+			(first, all.get(first)?)
+		};
+		self.used.insert(position);
+
+		match &leaf.type_def {
+			TypeDef::Composite(fields) => self.decode_fields(fields, input),
+			TypeDef::Enumeration(variant) => self.decode_fields(&variant.fields, input),
+			TypeDef::Sequence(ty) => {
+				let length = Compact::<u32>::decode(input).ok()?.0;
+				(0..length).try_for_each(|_| self.decode(*ty, input))
+			},
+			TypeDef::Array(length, ty) => (0..*length).try_for_each(|_| self.decode(*ty, input)),
+			TypeDef::Tuple(tys) => tys.iter().try_for_each(|ty| self.decode(*ty, input)),
+			TypeDef::BitSequence(bits) => {
+				let length = Compact::<u32>::decode(input).ok()?.0 as usize;
+				let store_bits = 8 * bits.num_bytes as usize;
+				let stores = (length + store_bits - 1) / store_bits;
+				skip(input, stores * bits.num_bytes as usize)
+			},
+		}
+	}
+
+	fn decode_fields(&mut self, fields: &[Field], input: &mut &[u8]) -> Option<()> {
+		fields.iter().try_for_each(|field| self.decode(field.ty, input))
+	}
+}
+
+fn skip(input: &mut &[u8], length: usize) -> Option<()> {
+	if input.len() < length {
+		return None
+	}
+	*input = &input[length..];
+	Some(())
+}
@@ -0,0 +1,238 @@
+use super::*;
+use frame_support::{parameter_types, traits::Everything};
+use hex_literal::hex;
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, IdentityLookup},
+};
+
+type UncheckedExtrinsic =
+	frame_system::mocking::MockUncheckedExtrinsic<Test, (), CheckMetadataHash<Test, MetadataHash>>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+	}
+);
+
+const HASH: [u8; 32] = [0xab; 32];
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const MetadataHash: Option<[u8; 32]> = Some(HASH);
+}
+
+impl frame_system::Config for Test {
+	type BaseCallFilter = Everything;
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = BlockHashCount;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = ();
+	type OnSetCode = ();
+	type MaxConsumers = frame_support::traits::ConstU32<16>;
+}
+
+fn extra_info() -> ExtraInfo {
+	ExtraInfo {
+		spec_version: 1,
+		spec_name: "test".into(),
+		base58_prefix: 42,
+		decimals: 12,
+		token_symbol: "UNIT".into(),
+	}
+}
+
+#[test]
+fn hashes_are_decoded_from_hex() {
+	let hex = "abababababababababababababababababababababababababababababababab";
+	assert_eq!(decode_hash(hex), HASH);
+	assert_eq!(decode_hash(&format!("0x{}", hex)), HASH);
+	assert_eq!(decode_hash(&hex.to_uppercase()), HASH);
+}
+
+#[test]
+#[should_panic(expected = "the metadata hash must be 32 hex encoded bytes")]
+fn short_hashes_are_rejected() {
+	decode_hash("abab");
+}
+
+#[test]
+#[should_panic(expected = "the metadata hash must be hex encoded")]
+fn non_hex_hashes_are_rejected() {
+	decode_hash("zzababababababababababababababababababababababababababababababab");
+}
+
+#[test]
+fn only_enabled_transactions_sign_the_hash() {
+	let disabled = CheckMetadataHash::<Test, MetadataHash>::new(false);
+	assert_eq!(disabled.mode(), Mode::Disabled);
+	assert_eq!(disabled.additional_signed(), Ok(None));
+	assert_eq!(disabled.encode(), vec![0]);
+
+	let enabled = CheckMetadataHash::<Test, MetadataHash>::new(true);
+	assert_eq!(enabled.mode(), Mode::Enabled);
+	assert_eq!(enabled.additional_signed(), Ok(Some(HASH)));
+	assert_eq!(enabled.encode(), vec![1]);
+}
+
+#[test]
+fn runtimes_without_hash_sign_none() {
+	let enabled = CheckMetadataHash::<Test, ()>::new(true);
+	assert_eq!(enabled.additional_signed(), Ok(None));
+}
+
+#[test]
+fn metadata_hash_commits_to_metadata_and_chain_info() {
+	let metadata = Test::metadata().encode();
+	let hash = metadata_hash(&metadata, extra_info()).unwrap();
+
+	assert_eq!(metadata_hash(&metadata, extra_info()), Some(hash));
+	let other_version = ExtraInfo { spec_version: 2, ..extra_info() };
+	assert_ne!(metadata_hash(&metadata, other_version), Some(hash));
+	assert_eq!(metadata_hash(b"not metadata", extra_info()), None);
+}
+
+#[test]
+fn proofs_are_generated_for_decodable_extrinsics() {
+	let metadata = Test::metadata().encode();
+	let remark = UncheckedExtrinsic::new_unsigned(
+		frame_system::Call::<Test>::remark { remark: vec![1, 2, 3] }.into(),
+	);
+
+	assert!(metadata_proof(&metadata, &remark.encode(), None, extra_info()).is_some());
+	assert_eq!(metadata_proof(&metadata, &[0xff; 4], None, extra_info()), None);
+}
+
+#[test]
+fn proofs_lead_to_the_hashed_tree_root() {
+	let metadata = Test::metadata().encode();
+	let types = type_information(&metadata).unwrap();
+	let remark = UncheckedExtrinsic::new_unsigned(
+		frame_system::Call::<Test>::remark { remark: vec![1, 2, 3] }.into(),
+	);
+
+	let proof = metadata_proof(&metadata, &remark.encode(), None, extra_info()).unwrap();
+	let proof = merkleized::MetadataProof::decode(&mut &proof[..]).unwrap();
+	assert_eq!(proof.proof.root(), Some(types.root()));
+	assert_eq!(proof.extrinsic, types.extrinsic);
+	assert_eq!(proof.extra_info, extra_info());
+	// Only the variants of the calls used are proven.
+	assert!(proof.proof.leaves.len() < types.leaves.len());
+
+	let mut tampered = proof.proof.clone();
+	tampered.nodes.push([0; 32]);
+	assert_eq!(tampered.root(), None);
+	tampered.nodes.pop();
+	tampered.leaves[0].path.push("Other".into());
+	assert_ne!(tampered.root(), Some(types.root()));
+}
+
+/// The V14 metadata of a made-up runtime: two pallets of calls with compact, optional, tuple, bit
+/// sequence and array fields, three signed extensions, and an event not reachable from the
+/// extrinsic.
+const METADATA: [u8; 1213] = hex![
+	"6d6574610e88000830746573745f72756e74696d652853797374656d43616c6c00010c1872656d61726b0401"
+	"1872656d61726b04011c5665633c75383e000000387365745f686561705f706167657304011470616765730c"
+	"010c753634000300347365745f636f64655f686173680c0110686173681001304f7074696f6e3c483235363e"
+	"000114666c6167731c012c28626f6f6c2c2075313629000110626974732801404269745665633c75382c204c"
+	"7362303e000200000400000208000800000503000c00000506001004184f7074696f6e04045401140108104e"
+	"6f6e6500000010536f6d650400140000010000140830746573745f72756e74696d6510483235360000040018"
+	"01205b75383b2033325d0000180000032000000008001c000004082024002000000500002400000504002800"
+	"0007082c002c0c18626974766563146f72646572104c73623000000000300830746573745f72756e74696d65"
+	"144576656e740001042052656d61726b656408011873656e6465721801205b75383b2033325d000110686173"
+	"68140110483235360000000034102873705f72756e74696d651c67656e657269634c756e636865636b65645f"
+	"65787472696e73696348556e636865636b656445787472696e736963101c4164647265737301381043616c6c"
+	"0144245369676e61747572650168144578747261017000040004000000380830746573745f72756e74696d65"
+	"304d756c74694164647265737300010808496404001801205b75383b2033325d00000014496e64657804003c"
+	"010c753332000200003c0000064000400000050500440830746573745f72756e74696d651043616c6c000108"
+	"1853797374656d040000012853797374656d43616c6c0000002042616c616e636573040048013042616c616e"
+	"63657343616c6c00050000480830746573745f72756e74696d653042616c616e63657343616c6c0001082074"
+	"72616e73666572080110646573743801304d756c74694164647265737300011476616c75654c011075313238"
+	"000000207365745f6e616d650c01106e616d65540118537472696e670001146e657665725801285665633c4e"
+	"657665723e000110706169726001205b6931363b20325d000100004c00000650005000000507005400000502"
+	"00580000025c005c0830746573745f72756e74696d65144e6576657200010000600000030200000064006400"
+	"00050a00680830746573745f72756e74696d65384d756c74695369676e61747572650001081c456432353531"
+	"3904006c01205b75383b2036345d0000001c5372323535313904006c01205b75383b2036345d000100006c00"
+	"0003400000000800700000040c74787c00740830746573745f72756e74696d6528436865636b4e6f6e636500"
+	"0004003c010c7533320000780830746573745f72756e74696d6530436865636b47656e65736973000000007c"
+	"0830746573745f72756e74696d65104d6f64650001082044697361626c65640000001c456e61626c65640001"
+	"00008000000400008404184f7074696f6e04045401180108104e6f6e6500000010536f6d6504001800000100"
+	"00041853797374656d000100013000000034040c28436865636b4e6f6e6365748030436865636b47656e6573"
+	"6973781444436865636b4d65746164617461486173687c8480"
+];
+
+/// The tree root, hash and proof of [`METADATA`] given by the `merkleized-metadata` reference
+/// implementation, fed the same metadata as V15.
+#[test]
+fn merkleization_matches_the_reference_implementation() {
+	let types = type_information(&METADATA).unwrap();
+	assert_eq!(
+		types.root(),
+		hex!["96cbfd8228c16d95878f0e8ab6f92a8211ed950eac85be0aa654a302a52cace9"]
+	);
+	assert_eq!(
+		metadata_hash(&METADATA, extra_info()),
+		Some(hex!["77910e1b20765bd74a51774de6411d91fc52ca09cdd946c04f27be2e951e5419"]),
+	);
+
+	// A signed `set_code_hash`, proven with the additional signed data of its extensions.
+	let extrinsic = hex![
+		"3502840002020202020202020202020202020202020202020202020202020202020202020103030303030303"
+		"0303030303030303030303030303030303030303030303030303030303030303030303030303030303030303"
+		"0303030303030303030303030304010002010505050505050505050505050505050505050505050505050505"
+		"0505050505050107001415"
+	];
+	let additional_signed =
+		hex!["090909090909090909090909090909090909090909090909090909090909090900"];
+	let proof =
+		metadata_proof(&METADATA, &extrinsic, Some(&additional_signed), extra_info()).unwrap();
+	let proof = merkleized::MetadataProof::decode(&mut &proof[..]).unwrap();
+	assert_eq!(proof.proof.root(), Some(types.root()));
+	assert_eq!(
+		proof.proof.encode(),
+		hex![
+			"340830746573745f72756e74696d651048323536000400161001205b75383b2033325d0c0003200000000310"
+			"00040800041400050101180830746573745f72756e74696d65304d756c746941646472657373010849640400"
+			"161001205b75383b2033325d001c0830746573745f72756e74696d651043616c6c011853797374656d040016"
+			"00012853797374656d43616c6c00200830746573745f72756e74696d65384d756c74695369676e6174757265"
+			"011c537232353531390400163401205b75383b2036345d043000034000000003340830746573745f72756e74"
+			"696d6528436865636b4e6f6e636500040011010c753332380830746573745f72756e74696d65104d6f646501"
+			"1c456e61626c656400043c04184f7074696f6e01104e6f6e650000400830746573745f72756e74696d652853"
+			"797374656d43616c6c01347365745f636f64655f686173680c011068617368160801304f7074696f6e3c4832"
+			"35363e0114666c6167731614012c28626f6f6c2c2075313629011062697473161801404269745665633c7538"
+			"2c204c7362303e080004184f7074696f6e0110536f6d650400160c000408341f000000200000002100000022"
+			"00000023000000250000002c0000002d0000002e00000030000000310000001a0000001e000000247f050878"
+			"785fa24dff53bf02138b05ac4297f78d7a11b469f10c586d3f9218d92fe3c8320da6a590f7eab51d67b72403"
+			"02b4101c03990944a6ee91aba4c94e5463e7617e7d23bd3ca8d046950548ca35af08c180f6da57c0b7d49043"
+			"4c2a1e951c5ade91c0719906b062ede0e2c20635323e2b569ba0933bcd1cf3ffeaed6da7be76ce2ebeb82aed"
+			"1f16ad79896f83f8cfb427c2322577979096b5bab26eb482096951c50434409de512c5d23550fd6d3d9cfbb3"
+			"4c2066b48246b4d1e6609141b28989f70c4518d096e8bf83150268c680bf33a9c022ed42e1f047c1836e4c7c"
+			"886bd435dafbebf8ecccf5c6d87a4e5f72571aa9825d57455248de3dc486aebfaf6efc8b7f8ce0c9f60f1ca3"
+			"93c08db6341de47df0637210864b3850a0e79580"
+		]
+		.to_vec(),
+	);
+}
@@ -0,0 +1,155 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Unified accounts for Ethereum keys.
+//!
+//! Every H160 address owns the 32-byte account `b"evm:" ++ address ++ [0; 8]`. Wallets can
+//! compute it without talking to the chain, and the address can be read back from the account,
+//! so the EVM, XCM `AccountKey20` locations and native extrinsics all agree on who an Ethereum
+//! key is.
+//!
+//! Native extrinsics may be signed by such an account with an EIP-712 signature. The typed data
+//! is `Transaction(bytes32 payload)` under the domain
+//! `EIP712Domain(string name,string version,uint256 chainId)`, where `payload` is the keccak-256
+//! hash of the regular signing payload: the call, the `SignedExtra` and its additional signed
+//! data. The signature therefore authorises the nonce, era, tip and genesis hash just like a
+//! Substrate signature does, which also protects it from being replayed on other chains. The
+//! `chainId` is the EVM chain id, so wallets show and check the chain they sign for.
+//!
+//! The EIP-712 signatures are a variant of the extrinsic signature type, [`UnifiedSignature`],
+//! rather than a `SignedExtension`: the signature of an extrinsic is checked before any signed
+//! extension runs, and must already be valid for the account signing it.
+
+use codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use sp_core::{ecdsa, ed25519, sr25519, H160, U256};
+use sp_io::hashing::keccak_256;
+use sp_runtime::{
+	traits::{Lazy, Verify},
+	AccountId32, MultiSignature, MultiSigner, RuntimeDebug,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Prefix of the accounts owned by H160 addresses.
+pub const EVM_ACCOUNT_PREFIX: &[u8; 4] = b"evm:";
+
+/// Name of the EIP-712 domain native extrinsics are signed under.
+pub const EIP712_DOMAIN_NAME: &[u8] = b"template-parachain";
+
+/// Version of the EIP-712 domain native extrinsics are signed under.
+pub const EIP712_DOMAIN_VERSION: &[u8] = b"1";
+
+/// Chain id of the EIP-712 domain native extrinsics are signed under, the EIP-155 chain id of the
+/// runtime's EVM.
+pub const EIP712_CHAIN_ID: u64 = 2000;
+
+/// The account owned by `address`.
+pub fn account_of(address: H160) -> AccountId32 {
+	let mut account = [0u8; 32];
+	account[..4].copy_from_slice(EVM_ACCOUNT_PREFIX);
+	account[4..24].copy_from_slice(address.as_bytes());
+	account.into()
+}
+
+/// The address owning `account`, if it is owned by one.
+pub fn address_of(account: &AccountId32) -> Option<H160> {
+	let account: &[u8; 32] = account.as_ref();
+	let owned = account.starts_with(EVM_ACCOUNT_PREFIX) && account[24..].iter().all(|b| *b == 0);
+	owned.then(|| H160::from_slice(&account[4..24]))
+}
+
+/// The address of the secp256k1 public key `public`, given uncompressed without its prefix.
+pub fn address_of_public(public: &[u8; 64]) -> H160 {
+	H160::from_slice(&keccak_256(public)[12..])
+}
+
+/// The EIP-712 digest an Ethereum wallet signs to authorise `payload`.
+pub fn eip712_digest(payload: &[u8]) -> [u8; 32] {
+	let domain_separator = keccak_256(
+		&[
+			keccak_256(b"EIP712Domain(string name,string version,uint256 chainId)"),
+			keccak_256(EIP712_DOMAIN_NAME),
+			keccak_256(EIP712_DOMAIN_VERSION),
+			U256::from(EIP712_CHAIN_ID).into(),
+		]
+		.concat(),
+	);
+	let message =
+		keccak_256(&[keccak_256(b"Transaction(bytes32 payload)"), keccak_256(payload)].concat());
+
+	keccak_256(&[&b"\x19\x01"[..], &domain_separator, &message].concat())
+}
+
+/// Recover the address that signed `digest`. Accepts both `v = 0 | 1` and `v = 27 | 28`.
+pub fn recover_signer(signature: &ecdsa::Signature, digest: &[u8; 32]) -> Option<H160> {
+	let mut signature: [u8; 65] = signature.0;
+	if signature[64] >= 27 {
+		signature[64] -= 27;
+	}
+	let public = sp_io::crypto::secp256k1_ecdsa_recover(&signature, digest).ok()?;
+	Some(address_of_public(&public))
+}
+
+/// The signature of a native extrinsic.
+///
+/// Encodes like [`MultiSignature`] for Substrate keys, so existing signers keep working, and adds
+/// EIP-712 signatures of Ethereum keys.
+#[derive(Eq, PartialEq, Clone, Encode, Decode, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+pub enum UnifiedSignature {
+	/// An Ed25519 signature.
+	Ed25519(ed25519::Signature),
+	/// An Sr25519 signature.
+	Sr25519(sr25519::Signature),
+	/// An ECDSA/SECP256k1 signature over the blake2 hash of the payload.
+	Ecdsa(ecdsa::Signature),
+	/// An ECDSA/SECP256k1 signature over the EIP-712 digest of the payload, made by the key
+	/// owning the signer's account.
+	Eip712(ecdsa::Signature),
+}
+
+impl Verify for UnifiedSignature {
+	type Signer = MultiSigner;
+
+	fn verify<L: Lazy<[u8]>>(&self, mut msg: L, signer: &AccountId32) -> bool {
+		let signature = match self {
+			Self::Ed25519(signature) => MultiSignature::Ed25519(signature.clone()),
+			Self::Sr25519(signature) => MultiSignature::Sr25519(signature.clone()),
+			Self::Ecdsa(signature) => MultiSignature::Ecdsa(signature.clone()),
+			Self::Eip712(signature) => {
+				let digest = eip712_digest(msg.get());
+				return recover_signer(signature, &digest).map(account_of).as_ref() == Some(signer)
+			},
+		};
+		signature.verify(msg, signer)
+	}
+}
+
+impl From<MultiSignature> for UnifiedSignature {
+	fn from(signature: MultiSignature) -> Self {
+		match signature {
+			MultiSignature::Ed25519(signature) => Self::Ed25519(signature),
+			MultiSignature::Sr25519(signature) => Self::Sr25519(signature),
+			MultiSignature::Ecdsa(signature) => Self::Ecdsa(signature),
+		}
+	}
+}
+
+impl From<sr25519::Signature> for UnifiedSignature {
+	fn from(signature: sr25519::Signature) -> Self {
+		Self::Sr25519(signature)
+	}
+}
+
+impl From<ed25519::Signature> for UnifiedSignature {
+	fn from(signature: ed25519::Signature) -> Self {
+		Self::Ed25519(signature)
+	}
+}
+
+impl From<ecdsa::Signature> for UnifiedSignature {
+	fn from(signature: ecdsa::Signature) -> Self {
+		Self::Ecdsa(signature)
+	}
+}
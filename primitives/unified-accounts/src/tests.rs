@@ -0,0 +1,88 @@
+use super::*;
+use hex_literal::hex;
+use sp_core::Pair;
+use sp_runtime::traits::IdentifyAccount;
+
+fn secret(byte: u8) -> libsecp256k1::SecretKey {
+	let mut secret = [0u8; 32];
+	secret[31] = byte;
+	libsecp256k1::SecretKey::parse(&secret).unwrap()
+}
+
+fn address(secret: &libsecp256k1::SecretKey) -> H160 {
+	let public = libsecp256k1::PublicKey::from_secret_key(secret).serialize();
+	address_of_public(&public[1..].try_into().unwrap())
+}
+
+fn eip712_sign(secret: &libsecp256k1::SecretKey, payload: &[u8]) -> ecdsa::Signature {
+	let message = libsecp256k1::Message::parse(&eip712_digest(payload));
+	let (signature, recovery_id) = libsecp256k1::sign(&message, secret);
+	let mut raw = [0u8; 65];
+	raw[..64].copy_from_slice(&signature.serialize());
+	raw[64] = recovery_id.serialize();
+	ecdsa::Signature::from_raw(raw)
+}
+
+#[test]
+fn addresses_and_accounts_map_both_ways() {
+	let address = H160::repeat_byte(0xab);
+	let account = account_of(address);
+
+	assert_eq!(&<AccountId32 as AsRef<[u8; 32]>>::as_ref(&account)[..4], b"evm:");
+	assert_eq!(address_of(&account), Some(address));
+	assert_eq!(address_of(&AccountId32::new([1; 32])), None);
+	// The padding must be zero, or the account could be claimed by two parties.
+	let mut bytes = <[u8; 32]>::from(account);
+	bytes[31] = 1;
+	assert_eq!(address_of(&AccountId32::new(bytes)), None);
+}
+
+#[test]
+fn derives_ethereum_addresses() {
+	assert_eq!(address(&secret(1)), H160(hex!("7e5f4552091a69125d5dfcb7b8c2659029395bdf")));
+}
+
+#[test]
+fn eip712_digest_matches_typed_data_hashing() {
+	// `Transaction { payload: keccak256("payload") }` under
+	// `{ name: "template-parachain", version: "1", chainId: 2000 }`.
+	assert_eq!(
+		eip712_digest(b"payload"),
+		hex!("6b9224df3a186ef1edebb5561253c61b60f843ed30591e855f3730d85699ba48")
+	);
+}
+
+#[test]
+fn eip712_signatures_verify_for_the_owned_account_only() {
+	let alice = secret(1);
+	let signature = UnifiedSignature::Eip712(eip712_sign(&alice, b"payload"));
+
+	assert!(signature.verify(&b"payload"[..], &account_of(address(&alice))));
+	assert!(!signature.verify(&b"other payload"[..], &account_of(address(&alice))));
+	assert!(!signature.verify(&b"payload"[..], &account_of(address(&secret(2)))));
+	// The hashed account of the key is not the one it owns.
+	assert!(!signature.verify(&b"payload"[..], &AccountId32::new([0; 32])));
+}
+
+#[test]
+fn eip712_signatures_accept_ethereum_recovery_ids() {
+	let alice = secret(1);
+	let mut raw = eip712_sign(&alice, b"payload").0;
+	raw[64] += 27;
+
+	let signature = UnifiedSignature::Eip712(ecdsa::Signature::from_raw(raw));
+	assert!(signature.verify(&b"payload"[..], &account_of(address(&alice))));
+}
+
+#[test]
+fn substrate_signatures_encode_and_verify_like_multi_signature() {
+	let pair = sr25519::Pair::from_seed(&[7; 32]);
+	let signature = pair.sign(b"payload");
+	let signer = MultiSigner::from(pair.public()).into_account();
+
+	assert_eq!(
+		UnifiedSignature::from(signature.clone()).encode(),
+		MultiSignature::from(signature.clone()).encode(),
+	);
+	assert!(UnifiedSignature::from(signature).verify(&b"payload"[..], &signer));
+}
@@ -0,0 +1,81 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Accounts of users on sibling chains.
+//!
+//! A user of a sibling parachain who sends `DescendOrigin` + `Transact` reaches us as
+//! `../Parachain(id)/AccountId32` or `../Parachain(id)/AccountKey20`. The converters of this crate
+//! give such locations an account of their own: the blake2-256 hash of a description of the
+//! location, so the account stays the same whatever route the message takes, and it cannot
+//! collide with the sovereign account of the sibling or with a local account.
+//!
+//! Descriptions follow the `HashedDescription` family of later XCM versions, that is
+//! `(b"SiblingChain", Compact(para_id), (b"AccountId32", id).encode())` SCALE encoded, so wallets
+//! and other chains derive the same accounts.
+
+use codec::{Codec, Compact, Encode};
+use core::{borrow::Borrow, marker::PhantomData};
+use sp_io::hashing::blake2_256;
+use sp_std::prelude::*;
+use xcm::{latest::prelude::*, VersionedMultiLocation};
+use xcm_executor::traits::Convert;
+
+#[cfg(test)]
+mod tests;
+
+/// Description of `account`, itself the description of an account junction, on the sibling
+/// parachain `para_id`.
+fn describe_sibling_account(para_id: u32, account: Vec<u8>) -> Vec<u8> {
+	(b"SiblingChain", Compact::<u32>::from(para_id), account).encode()
+}
+
+/// Converts `../Parachain(id)/AccountId32` locations into the hash of their description.
+/// The network of the junction is ignored.
+pub struct SiblingAccountId32Hash<AccountId>(PhantomData<AccountId>);
+
+impl<AccountId: From<[u8; 32]> + Clone> Convert<MultiLocation, AccountId>
+	for SiblingAccountId32Hash<AccountId>
+{
+	fn convert_ref(location: impl Borrow<MultiLocation>) -> Result<AccountId, ()> {
+		match location.borrow() {
+			MultiLocation {
+				parents: 1,
+				interior: X2(Parachain(para_id), AccountId32 { id, .. }),
+			} => {
+				let description = describe_sibling_account(*para_id, (b"AccountId32", id).encode());
+				Ok(blake2_256(&description).into())
+			},
+			_ => Err(()),
+		}
+	}
+}
+
+/// Converts `../Parachain(id)/AccountKey20` locations into the hash of their description.
+/// The network of the junction is ignored.
+pub struct SiblingAccountKey20Hash<AccountId>(PhantomData<AccountId>);
+
+impl<AccountId: From<[u8; 32]> + Clone> Convert<MultiLocation, AccountId>
+	for SiblingAccountKey20Hash<AccountId>
+{
+	fn convert_ref(location: impl Borrow<MultiLocation>) -> Result<AccountId, ()> {
+		match location.borrow() {
+			MultiLocation {
+				parents: 1,
+				interior: X2(Parachain(para_id), AccountKey20 { key, .. }),
+			} => {
+				let description =
+					describe_sibling_account(*para_id, (b"AccountKey20", key).encode());
+				Ok(blake2_256(&description).into())
+			},
+			_ => Err(()),
+		}
+	}
+}
+
+sp_api::decl_runtime_apis! {
+	/// The accounts XCM locations control on this chain.
+	pub trait XcmAccountsApi<AccountId> where AccountId: Codec {
+		/// The account `location` controls, as used for its assets and `Transact`, or `None` if it
+		/// has none or is of an unsupported XCM version.
+		fn location_to_account(location: VersionedMultiLocation) -> Option<AccountId>;
+	}
+}
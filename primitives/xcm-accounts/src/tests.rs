@@ -0,0 +1,57 @@
+use super::*;
+use hex_literal::hex;
+use sp_runtime::AccountId32 as AccountId;
+
+fn sibling_account32(para_id: u32, id: [u8; 32]) -> MultiLocation {
+	MultiLocation::new(1, X2(Parachain(para_id), AccountId32 { network: NetworkId::Any, id }))
+}
+
+fn sibling_key20(para_id: u32, key: [u8; 20]) -> MultiLocation {
+	MultiLocation::new(1, X2(Parachain(para_id), AccountKey20 { network: NetworkId::Any, key }))
+}
+
+#[test]
+fn account_id32_locations_derive_known_accounts() {
+	assert_eq!(
+		SiblingAccountId32Hash::<AccountId>::convert_ref(&sibling_account32(2000, [1; 32])),
+		Ok(hex!["b5d854040034d5fc69542873beb2305279ed7598df5d604d3e794357781a0628"].into()),
+	);
+}
+
+#[test]
+fn account_key20_locations_derive_known_accounts() {
+	assert_eq!(
+		SiblingAccountKey20Hash::<AccountId>::convert_ref(&sibling_key20(2000, [0xab; 20])),
+		Ok(hex!["c298565c837b3e0c98df3e58e73a60fd0075613f547857462a22b186661facd2"].into()),
+	);
+}
+
+#[test]
+fn accounts_depend_on_chain_and_user_but_not_network() {
+	let account = |location| SiblingAccountId32Hash::<AccountId>::convert_ref(location).unwrap();
+	let alice = account(&sibling_account32(2000, [1; 32]));
+
+	assert_ne!(account(&sibling_account32(2001, [1; 32])), alice);
+	assert_ne!(account(&sibling_account32(2000, [2; 32])), alice);
+	let on_polkadot = MultiLocation::new(
+		1,
+		X2(Parachain(2000), AccountId32 { network: NetworkId::Polkadot, id: [1; 32] }),
+	);
+	assert_eq!(account(&on_polkadot), alice);
+}
+
+#[test]
+fn other_locations_are_not_converted() {
+	let local = MultiLocation::new(0, X1(AccountId32 { network: NetworkId::Any, id: [1; 32] }));
+	let relay_user =
+		MultiLocation::new(1, X1(AccountId32 { network: NetworkId::Any, id: [1; 32] }));
+	let sibling = MultiLocation::new(1, X1(Parachain(2000)));
+
+	for location in [local, relay_user, sibling, sibling_key20(2000, [0xab; 20])] {
+		assert_eq!(SiblingAccountId32Hash::<AccountId>::convert_ref(&location), Err(()));
+	}
+	assert_eq!(
+		SiblingAccountKey20Hash::<AccountId>::convert_ref(&sibling_account32(2000, [1; 32])),
+		Err(())
+	);
+}
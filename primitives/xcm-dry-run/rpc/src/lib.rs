@@ -0,0 +1,108 @@
+//! RPC interface dry-running XCM messages.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use codec::{Codec, Decode, Encode};
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use sc_rpc_api::DenyUnsafe;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::Bytes;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+use xcm::{VersionedMultiLocation, VersionedXcm};
+
+pub use xcm_dry_run::{XcmDryRunApi as XcmDryRunRuntimeApi, XcmDryRunEffects, XcmDryRunError};
+
+#[rpc]
+pub trait XcmDryRunApi<BlockHash> {
+	/// Execute a SCALE encoded `VersionedXcm` from a SCALE encoded `VersionedMultiLocation`
+	/// without committing anything. Returns the SCALE encoded
+	/// `Result<XcmDryRunEffects, XcmDryRunError>`.
+	#[rpc(name = "xcm_dryRun")]
+	fn dry_run(
+		&self,
+		origin: Bytes,
+		message: Bytes,
+		max_weight: u64,
+		at: Option<BlockHash>,
+	) -> Result<Bytes>;
+}
+
+/// Provides RPC methods to dry-run XCM messages.
+pub struct XcmDryRun<C, B, Call, Event> {
+	/// Shared reference to the client.
+	client: Arc<C>,
+	/// Dry runs are costly, so they are unsafe.
+	deny_unsafe: DenyUnsafe,
+	_marker: PhantomData<(B, Call, Event)>,
+}
+
+impl<C, B, Call, Event> XcmDryRun<C, B, Call, Event> {
+	/// Creates a new instance of the XcmDryRun Rpc helper.
+	pub fn new(client: Arc<C>, deny_unsafe: DenyUnsafe) -> Self {
+		Self { client, deny_unsafe, _marker: Default::default() }
+	}
+}
+
+/// Error type of this RPC api.
+pub enum Error {
+	/// The call to runtime failed.
+	RuntimeError,
+	/// The origin or the message could not be decoded.
+	DecodeError,
+}
+
+impl From<Error> for i64 {
+	fn from(e: Error) -> i64 {
+		match e {
+			Error::RuntimeError => 1,
+			Error::DecodeError => 2,
+		}
+	}
+}
+
+fn runtime_error(e: impl std::fmt::Debug) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(Error::RuntimeError.into()),
+		message: "Unable to dry-run the message.".into(),
+		data: Some(format!("{:?}", e).into()),
+	}
+}
+
+fn decode_error(what: &str, e: codec::Error) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(Error::DecodeError.into()),
+		message: format!("Unable to decode the {}.", what),
+		data: Some(format!("{:?}", e).into()),
+	}
+}
+
+impl<C, Block, Call, Event> XcmDryRunApi<<Block as BlockT>::Hash>
+	for XcmDryRun<C, Block, Call, Event>
+where
+	Block: BlockT,
+	C: 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+	C::Api: XcmDryRunRuntimeApi<Block, Call, Event>,
+	Call: Codec + Send + Sync + 'static,
+	Event: Codec + Send + Sync + 'static,
+{
+	fn dry_run(
+		&self,
+		origin: Bytes,
+		message: Bytes,
+		max_weight: u64,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Bytes> {
+		self.deny_unsafe.check_if_safe()?;
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		let origin =
+			VersionedMultiLocation::decode(&mut &*origin).map_err(|e| decode_error("origin", e))?;
+		let message =
+			VersionedXcm::<Call>::decode(&mut &*message).map_err(|e| decode_error("message", e))?;
+		let effects = api.dry_run_xcm(&at, origin, message, max_weight).map_err(runtime_error)?;
+		Ok(effects.encode().into())
+	}
+}
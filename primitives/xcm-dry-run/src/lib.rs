@@ -0,0 +1,90 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Dry runs of XCM messages.
+//!
+//! [`XcmDryRunApi`] executes a message as if it arrived from some origin, with the barrier and
+//! every other part of the executor configuration, and returns what happened. Runtime API calls
+//! never commit their changes, so the dry run leaves no trace on chain.
+//!
+//! The messages the execution would send are recorded by [`RecordingRouter`], which wraps the
+//! router of the runtime and only records within [`record_forwarded`]. The record lives in memory
+//! rather than in storage, so the router costs nothing outside of dry runs.
+
+use codec::{Codec, Decode, Encode};
+use core::marker::PhantomData;
+use frame_support::weights::Weight;
+use scale_info::TypeInfo;
+use sp_runtime::RuntimeDebug;
+use sp_std::prelude::*;
+use xcm::{latest::prelude::*, VersionedMultiLocation, VersionedXcm};
+
+#[cfg(test)]
+mod tests;
+
+/// What a message would do if it were executed.
+#[derive(Encode, Decode, Clone, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+pub struct XcmDryRunEffects<Event> {
+	/// The outcome of the execution, `Error` if the barrier rejected the message.
+	pub outcome: Outcome,
+	/// The weight the execution used.
+	pub weight_used: Weight,
+	/// The events the execution emitted.
+	pub emitted_events: Vec<Event>,
+	/// The messages the execution sent, by destination.
+	pub forwarded_xcms: Vec<(VersionedMultiLocation, Vec<VersionedXcm<()>>)>,
+}
+
+/// Why a message could not be dry-run.
+#[derive(Encode, Decode, Clone, Copy, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+pub enum XcmDryRunError {
+	/// The origin or the message could not be converted to the latest XCM version.
+	VersionedConversionFailed,
+}
+
+environmental::environmental!(forwarded: Vec<(MultiLocation, Xcm<()>)>);
+
+/// Routes messages through `Router`, recording them during dry runs.
+pub struct RecordingRouter<Router>(PhantomData<Router>);
+
+impl<Router: SendXcm> SendXcm for RecordingRouter<Router> {
+	fn send_xcm(dest: impl Into<MultiLocation>, msg: Xcm<()>) -> SendResult {
+		let dest = dest.into();
+		let recording = forwarded::with(|_| ()).is_some();
+		if !recording {
+			return Router::send_xcm(dest, msg)
+		}
+
+		Router::send_xcm(dest.clone(), msg.clone())?;
+		forwarded::with(|forwarded| forwarded.push((dest, msg)));
+		Ok(())
+	}
+}
+
+/// Run `f`, returning the messages `RecordingRouter` sent meanwhile, grouped by destination.
+pub fn record_forwarded<R>(
+	f: impl FnOnce() -> R,
+) -> (R, Vec<(VersionedMultiLocation, Vec<VersionedXcm<()>>)>) {
+	let mut sent = Vec::new();
+	let result = forwarded::using(&mut sent, f);
+
+	let mut by_dest: Vec<(MultiLocation, Vec<VersionedXcm<()>>)> = Vec::new();
+	for (dest, msg) in sent {
+		match by_dest.iter_mut().find(|(d, _)| *d == dest) {
+			Some((_, msgs)) => msgs.push(msg.into()),
+			None => by_dest.push((dest, vec![msg.into()])),
+		}
+	}
+	(result, by_dest.into_iter().map(|(dest, msgs)| (dest.into(), msgs)).collect())
+}
+
+sp_api::decl_runtime_apis! {
+	/// Dry-run XCM messages against the executor of the runtime.
+	pub trait XcmDryRunApi<Call, Event> where Call: Codec, Event: Codec {
+		/// Execute `message` from `origin` with up to `max_weight`, without committing anything.
+		fn dry_run_xcm(
+			origin: VersionedMultiLocation,
+			message: VersionedXcm<Call>,
+			max_weight: Weight,
+		) -> Result<XcmDryRunEffects<Event>, XcmDryRunError>;
+	}
+}
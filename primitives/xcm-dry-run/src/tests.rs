@@ -0,0 +1,77 @@
+use super::*;
+use sp_io::TestExternalities;
+use std::cell::RefCell;
+
+thread_local! {
+	static SENT_XCM: RefCell<Vec<(MultiLocation, Xcm<()>)>> = RefCell::new(Vec::new());
+}
+
+/// Records the messages instead of sending them, and refuses those to the relay chain.
+struct TestRouter;
+impl SendXcm for TestRouter {
+	fn send_xcm(dest: impl Into<MultiLocation>, msg: Xcm<()>) -> SendResult {
+		let dest = dest.into();
+		if dest == MultiLocation::parent() {
+			return Err(SendError::CannotReachDestination(dest, msg))
+		}
+		SENT_XCM.with(|q| q.borrow_mut().push((dest, msg)));
+		Ok(())
+	}
+}
+
+type Router = RecordingRouter<TestRouter>;
+
+fn sibling(id: u32) -> MultiLocation {
+	MultiLocation::new(1, X1(Parachain(id)))
+}
+
+fn message(amount: u128) -> Xcm<()> {
+	Xcm(vec![WithdrawAsset((Here, amount).into())])
+}
+
+#[test]
+fn messages_are_only_recorded_during_dry_runs() {
+	TestExternalities::default().execute_with(|| {
+		assert_eq!(Router::send_xcm(sibling(2000), message(1)), Ok(()));
+		let (_, forwarded) = record_forwarded(|| ());
+		assert!(forwarded.is_empty());
+
+		let (result, forwarded) = record_forwarded(|| Router::send_xcm(sibling(2000), message(2)));
+		assert_eq!(result, Ok(()));
+		assert_eq!(forwarded, vec![(sibling(2000).into(), vec![message(2).into()])]);
+
+		assert_eq!(Router::send_xcm(sibling(2000), message(3)), Ok(()));
+		assert_eq!(SENT_XCM.with(|q| q.borrow().len()), 3);
+		assert!(forwarded::with(|_| ()).is_none());
+	});
+}
+
+#[test]
+fn recorded_messages_are_grouped_by_destination() {
+	TestExternalities::default().execute_with(|| {
+		let (_, forwarded) = record_forwarded(|| {
+			Router::send_xcm(sibling(2000), message(1)).unwrap();
+			Router::send_xcm(sibling(2001), message(2)).unwrap();
+			Router::send_xcm(sibling(2000), message(3)).unwrap();
+		});
+
+		assert_eq!(
+			forwarded,
+			vec![
+				(sibling(2000).into(), vec![message(1).into(), message(3).into()]),
+				(sibling(2001).into(), vec![message(2).into()]),
+			]
+		);
+	});
+}
+
+#[test]
+fn failed_sends_are_not_recorded() {
+	TestExternalities::default().execute_with(|| {
+		let (result, forwarded) =
+			record_forwarded(|| Router::send_xcm(MultiLocation::parent(), message(1)));
+
+		assert!(result.is_err());
+		assert!(forwarded.is_empty());
+	});
+}
@@ -0,0 +1,106 @@
+//! RPC interface estimating the fees of XCM messages.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use codec::{Codec, Decode, Encode};
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::Bytes;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+use xcm::{VersionedMultiLocation, VersionedXcm};
+
+pub use xcm_fees::{XcmFees, XcmFeesApi as XcmFeesRuntimeApi, XcmFeesError};
+
+#[rpc]
+pub trait XcmFeesApi<BlockHash> {
+	/// The fees of a SCALE encoded `VersionedXcm` sent by a SCALE encoded `VersionedMultiLocation`,
+	/// paying for execution with the asset at a SCALE encoded `VersionedMultiLocation`. Returns
+	/// the SCALE encoded `Result<XcmFees, XcmFeesError>`.
+	#[rpc(name = "xcm_queryFees")]
+	fn query_fees(
+		&self,
+		origin: Bytes,
+		message: Bytes,
+		fee_asset: Bytes,
+		at: Option<BlockHash>,
+	) -> Result<Bytes>;
+}
+
+/// Provides RPC methods to estimate the fees of XCM messages.
+pub struct XcmFeesEstimator<C, B, Call, Balance> {
+	/// Shared reference to the client.
+	client: Arc<C>,
+	_marker: PhantomData<(B, Call, Balance)>,
+}
+
+impl<C, B, Call, Balance> XcmFeesEstimator<C, B, Call, Balance> {
+	/// Creates a new instance of the XcmFeesEstimator Rpc helper.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client, _marker: Default::default() }
+	}
+}
+
+/// Error type of this RPC api.
+pub enum Error {
+	/// The call to runtime failed.
+	RuntimeError,
+	/// The origin, the message or the asset could not be decoded.
+	DecodeError,
+}
+
+impl From<Error> for i64 {
+	fn from(e: Error) -> i64 {
+		match e {
+			Error::RuntimeError => 1,
+			Error::DecodeError => 2,
+		}
+	}
+}
+
+fn runtime_error(e: impl std::fmt::Debug) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(Error::RuntimeError.into()),
+		message: "Unable to estimate the fees of the message.".into(),
+		data: Some(format!("{:?}", e).into()),
+	}
+}
+
+fn decode_error(what: &str, e: codec::Error) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(Error::DecodeError.into()),
+		message: format!("Unable to decode the {}.", what),
+		data: Some(format!("{:?}", e).into()),
+	}
+}
+
+impl<C, Block, Call, Balance> XcmFeesApi<<Block as BlockT>::Hash>
+	for XcmFeesEstimator<C, Block, Call, Balance>
+where
+	Block: BlockT,
+	C: 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+	C::Api: XcmFeesRuntimeApi<Block, Call, Balance>,
+	Call: Codec + Send + Sync + 'static,
+	Balance: Codec + Send + Sync + 'static,
+{
+	fn query_fees(
+		&self,
+		origin: Bytes,
+		message: Bytes,
+		fee_asset: Bytes,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Bytes> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		let origin =
+			VersionedMultiLocation::decode(&mut &*origin).map_err(|e| decode_error("origin", e))?;
+		let message =
+			VersionedXcm::<Call>::decode(&mut &*message).map_err(|e| decode_error("message", e))?;
+		let fee_asset = VersionedMultiLocation::decode(&mut &*fee_asset)
+			.map_err(|e| decode_error("fee asset", e))?;
+		let fees = api.query_xcm_fees(&at, origin, message, fee_asset).map_err(runtime_error)?;
+		Ok(fees.encode().into())
+	}
+}
@@ -0,0 +1,50 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Fees of XCM messages.
+//!
+//! [`XcmFeesApi`] tells clients what an inbound message costs before they send it: the weight the
+//! executor charges for it, the fee for that weight in a given asset, as the trader of the runtime
+//! prices it, and the delivery fees of the messages its execution sends onwards.
+
+use codec::{Codec, Decode, Encode};
+use frame_support::weights::Weight;
+use scale_info::TypeInfo;
+use sp_runtime::RuntimeDebug;
+use sp_std::prelude::*;
+use xcm::{VersionedMultiLocation, VersionedXcm};
+
+/// The fees of a message.
+#[derive(Encode, Decode, Clone, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+pub struct XcmFees<Balance> {
+	/// The weight the executor charges for the message.
+	pub weight: Weight,
+	/// The fee for `weight`, in the requested asset.
+	pub execution_fee: u128,
+	/// The delivery fees of the messages the execution sends, by destination, in the native
+	/// token.
+	pub delivery_fees: Vec<(VersionedMultiLocation, Balance)>,
+}
+
+/// Why the fees of a message could not be estimated.
+#[derive(Encode, Decode, Clone, Copy, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+pub enum XcmFeesError {
+	/// The origin, message or asset could not be converted to the latest XCM version.
+	VersionedConversionFailed,
+	/// The message could not be weighed, for example because it is too long.
+	UnweighableMessage,
+	/// Execution cannot be paid for with the asset.
+	AssetNotAccepted,
+}
+
+sp_api::decl_runtime_apis! {
+	/// Estimate the fees of XCM messages executed by the runtime.
+	pub trait XcmFeesApi<Call, Balance> where Call: Codec, Balance: Codec {
+		/// The fees of `message` sent by `origin`, paying for execution with the asset at
+		/// `fee_asset`.
+		fn query_xcm_fees(
+			origin: VersionedMultiLocation,
+			message: VersionedXcm<Call>,
+			fee_asset: VersionedMultiLocation,
+		) -> Result<XcmFees<Balance>, XcmFeesError>;
+	}
+}
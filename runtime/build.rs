@@ -1,9 +1,85 @@
 use substrate_wasm_builder::WasmBuilder;
 
-fn main() {
+fn build_wasm() {
 	WasmBuilder::new()
 		.with_current_project()
 		.export_heap_base()
 		.import_memory()
 		.build()
 }
+
+#[cfg(not(feature = "with-metadata-hash"))]
+fn main() {
+	build_wasm()
+}
+
+/// Builds the runtime twice: once to read its metadata, and once more with the merkleized hash of
+/// that metadata in `RUNTIME_METADATA_HASH`, which `CheckMetadataHash` signs over.
+///
+/// The hash does not depend on itself, as the metadata only describes the type of the signed
+/// extension and not the value it adds.
+#[cfg(feature = "with-metadata-hash")]
+fn main() {
+	build_wasm();
+
+	if let Some(hash) = metadata::compute() {
+		let hash: String = hash.iter().map(|byte| format!("{:02x}", byte)).collect();
+		// Seen by the nested Wasm build, which inherits the environment.
+		std::env::set_var("RUNTIME_METADATA_HASH", &hash);
+		// Seen by the native runtime.
+		println!("cargo:rustc-env=RUNTIME_METADATA_HASH={}", hash);
+		build_wasm();
+	}
+}
+
+#[cfg(feature = "with-metadata-hash")]
+mod metadata {
+	use chain_properties::{SS58_PREFIX, TOKEN_DECIMALS, TOKEN_SYMBOL};
+	use codec::Decode;
+	use sc_executor::{WasmExecutionMethod, WasmExecutor};
+	use sc_executor_common::runtime_blob::RuntimeBlob;
+	use std::{env, fs, path::Path};
+
+	/// The metadata hash of the Wasm runtime just built, `None` if the Wasm build was skipped.
+	pub fn compute() -> Option<[u8; 32]> {
+		if env::var_os("SKIP_WASM_BUILD").is_some() {
+			return None
+		}
+
+		let out_dir = env::var("OUT_DIR").expect("`OUT_DIR` is set by cargo");
+		let wasm_binary = fs::read_to_string(Path::new(&out_dir).join("wasm_binary.rs")).ok()?;
+		let path = wasm_binary.split("include_bytes!(\"").nth(1)?.split("\")").next()?;
+		let wasm = fs::read(path).expect("the Wasm runtime was just built");
+
+		let metadata = call(&wasm, "Metadata_metadata");
+		let metadata = Vec::<u8>::decode(&mut &metadata[..]).expect("`OpaqueMetadata` is bytes");
+		let version = sp_version::RuntimeVersion::decode(&mut &call(&wasm, "Core_version")[..])
+			.expect("`Core_version` returns the runtime version");
+
+		let info = metadata_hash::ExtraInfo {
+			spec_version: version.spec_version,
+			spec_name: version.spec_name.to_string(),
+			base58_prefix: SS58_PREFIX,
+			decimals: TOKEN_DECIMALS,
+			token_symbol: TOKEN_SYMBOL.into(),
+		};
+		let hash = metadata_hash::metadata_hash(&metadata, info)
+			.expect("the runtime metadata can be merkleized");
+		Some(hash)
+	}
+
+	fn call(wasm: &[u8], method: &str) -> Vec<u8> {
+		let executor = WasmExecutor::<sp_io::SubstrateHostFunctions>::new(
+			WasmExecutionMethod::Interpreted,
+			None,
+			1,
+			None,
+			1,
+		);
+		let blob = RuntimeBlob::uncompress_if_needed(wasm).expect("the Wasm runtime is valid");
+		let mut ext = sp_io::TestExternalities::default();
+		executor
+			.uncached_call(blob, &mut ext.ext(), true, method, &[])
+			.unwrap_or_else(|e| panic!("calling `{}` failed: {}", method, e))
+	}
+}
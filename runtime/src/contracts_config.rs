@@ -0,0 +1,271 @@
+use super::{
+	Balance, Balances, Call, Event, RandomnessCollectiveFlip, Runtime, RuntimeBlockWeights,
+	Timestamp, TransactionPayment, MICROUNIT, MILLIUNIT,
+};
+use codec::Encode;
+use frame_support::{
+	log, parameter_types,
+	traits::{Get, Nothing},
+	weights::{GetDispatchInfo, Weight},
+};
+use frame_system::RawOrigin;
+use pallet_contracts::{
+	chain_extension::{
+		ChainExtension, Environment, Ext, InitState, RetVal, SysConfig, UncheckedFrom,
+	},
+	weights::WeightInfo,
+};
+use sp_runtime::{DispatchError, Perbill};
+
+/// Whether the contracts runtime API returns the debug buffer of a dry run. Never used on chain.
+pub const CONTRACTS_DEBUG_OUTPUT: bool = true;
+
+/// The storage deposit for `items` storage items using `bytes` bytes in total.
+pub const fn deposit(items: u32, bytes: u32) -> Balance {
+	(items as Balance) * 20 * MILLIUNIT + (bytes as Balance) * 100 * MICROUNIT
+}
+
+parameter_types! {
+	pub const DepositPerItem: Balance = deposit(1, 0);
+	pub const DepositPerByte: Balance = deposit(0, 1);
+	// The lazy deletion runs inside on_initialize.
+	pub DeletionWeightLimit: Weight = Perbill::from_percent(10) *
+		RuntimeBlockWeights::get().max_block;
+	// The weight needed for decoding the queue should be less or equal than a fifth
+	// of the overall weight dedicated to the lazy deletion.
+	pub DeletionQueueDepth: u32 = ((DeletionWeightLimit::get() / (
+			<Runtime as pallet_contracts::Config>::WeightInfo::on_initialize_per_queue_item(1) -
+			<Runtime as pallet_contracts::Config>::WeightInfo::on_initialize_per_queue_item(0)
+		)) / 5) as u32;
+	// The schedule is derived from the benchmarked `WeightInfo`.
+	pub Schedule: pallet_contracts::Schedule<Runtime> = Default::default();
+}
+
+impl pallet_contracts::Config for Runtime {
+	type Time = Timestamp;
+	type Randomness = RandomnessCollectiveFlip;
+	type Currency = Balances;
+	type Event = Event;
+	type Call = Call;
+	/// Contracts may not dispatch runtime calls. They reach the runtime through the chain
+	/// extension only.
+	type CallFilter = Nothing;
+	type DepositPerItem = DepositPerItem;
+	type DepositPerByte = DepositPerByte;
+	type CallStack = [pallet_contracts::Frame<Self>; 31];
+	type WeightPrice = TransactionPayment;
+	type WeightInfo = pallet_contracts::weights::SubstrateWeight<Self>;
+	type ChainExtension = TemplateExtension;
+	type DeletionQueueDepth = DeletionQueueDepth;
+	type DeletionWeightLimit = DeletionWeightLimit;
+	type Schedule = Schedule;
+	type AddressGenerator = pallet_contracts::DefaultAddressGenerator;
+}
+
+/// Function ids of the [`TemplateExtension`].
+#[repr(u32)]
+pub enum TemplateFunc {
+	/// Read `pallet_template::Something`. Returns an encoded `Option<u32>`.
+	Something = 1,
+	/// Store the encoded `u32` input through `pallet_template::do_something`, with the contract
+	/// as the signer.
+	DoSomething = 2,
+}
+
+impl TryFrom<u32> for TemplateFunc {
+	type Error = DispatchError;
+
+	fn try_from(func_id: u32) -> Result<Self, Self::Error> {
+		match func_id {
+			1 => Ok(Self::Something),
+			2 => Ok(Self::DoSomething),
+			_ => {
+				log::error!(target: "runtime::contracts", "Unknown chain extension function {}", func_id);
+				Err(DispatchError::Other("Unimplemented func_id"))
+			},
+		}
+	}
+}
+
+/// Chain extension giving ink! contracts access to `pallet_template` storage.
+pub struct TemplateExtension;
+
+impl ChainExtension<Runtime> for TemplateExtension {
+	fn call<E>(func_id: u32, env: Environment<E, InitState>) -> Result<RetVal, DispatchError>
+	where
+		E: Ext<T = Runtime>,
+		<E::T as SysConfig>::AccountId: UncheckedFrom<<E::T as SysConfig>::Hash> + AsRef<[u8]>,
+	{
+		let mut env = env.buf_in_buf_out();
+		match TemplateFunc::try_from(func_id)? {
+			TemplateFunc::Something => {
+				env.charge_weight(<Runtime as frame_system::Config>::DbWeight::get().reads(1))?;
+				let something = pallet_template::Pallet::<Runtime>::something();
+				env.write(&something.encode(), false, None)?;
+			},
+			TemplateFunc::DoSomething => {
+				let something: u32 = env.read_as()?;
+				let call = pallet_template::Call::<Runtime>::do_something { something };
+				env.charge_weight(call.get_dispatch_info().weight)?;
+				let contract = env.ext().address().clone();
+				pallet_template::Pallet::<Runtime>::do_something(
+					RawOrigin::Signed(contract).into(),
+					something,
+				)
+				.map_err(|e| e.error)?;
+			},
+		}
+		Ok(RetVal::Converging(0))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{AccountId, Contracts, System, TemplatePallet, UNIT};
+	use frame_support::assert_ok;
+	use pallet_contracts_primitives::{Code, ExecReturnValue};
+	use sp_runtime::BuildStorage;
+
+	const ALICE: AccountId = AccountId::new([1; 32]);
+	const GAS_LIMIT: Weight = 100_000_000_000;
+
+	/// Calls the chain extension function given in the first four bytes of its input with the
+	/// rest of the input, and returns the output of the extension.
+	const CALLER: &str = r#"
+(module
+	(import "seal0" "seal_input" (func $seal_input (param i32 i32)))
+	(import "seal0" "seal_call_chain_extension"
+		(func $seal_call_chain_extension (param i32 i32 i32 i32 i32) (result i32))
+	)
+	(import "seal0" "seal_return" (func $seal_return (param i32 i32 i32)))
+	(import "env" "memory" (memory 1 1))
+
+	;; [0, 4) length of the input buffer
+	(data (i32.const 0) "\20")
+	;; [4, 36) input buffer, starting with the function id
+	;; [36, 40) length of the output buffer
+	(data (i32.const 36) "\20")
+	;; [40, 72) output buffer
+
+	(func (export "deploy"))
+
+	(func (export "call")
+		(call $seal_input (i32.const 4) (i32.const 0))
+		(drop (call $seal_call_chain_extension
+			(i32.load (i32.const 4))
+			(i32.const 8)
+			(i32.sub (i32.load (i32.const 0)) (i32.const 4))
+			(i32.const 40)
+			(i32.const 36)
+		))
+		(call $seal_return (i32.const 0) (i32.const 40) (i32.load (i32.const 36)))
+	)
+)
+"#;
+
+	fn new_test_ext() -> sp_io::TestExternalities {
+		let mut t = frame_system::GenesisConfig::default().build_storage::<Runtime>().unwrap();
+		pallet_balances::GenesisConfig::<Runtime> { balances: vec![(ALICE, 1_000 * UNIT)] }
+			.assimilate_storage(&mut t)
+			.unwrap();
+		let mut ext = sp_io::TestExternalities::new(t);
+		ext.execute_with(|| System::set_block_number(1));
+		ext
+	}
+
+	fn instantiate_caller() -> AccountId {
+		let wasm = wat::parse_str(CALLER).unwrap();
+		Contracts::bare_instantiate(
+			ALICE,
+			0,
+			GAS_LIMIT,
+			None,
+			Code::Upload(wasm.into()),
+			vec![],
+			vec![],
+			false,
+		)
+		.result
+		.expect("caller contract instantiates")
+		.account_id
+	}
+
+	fn call_extension(
+		contract: &AccountId,
+		func_id: u32,
+		input: &[u8],
+		gas_limit: Weight,
+	) -> Result<ExecReturnValue, DispatchError> {
+		let data = func_id.encode().into_iter().chain(input.iter().copied()).collect();
+		Contracts::bare_call(ALICE, contract.clone(), 0, gas_limit, None, data, false).result
+	}
+
+	#[test]
+	fn reads_something() {
+		new_test_ext().execute_with(|| {
+			let contract = instantiate_caller();
+
+			let output = call_extension(&contract, 1, &[], GAS_LIMIT).unwrap();
+			assert_eq!(output.data.0, None::<u32>.encode());
+
+			assert_ok!(TemplatePallet::do_something(crate::Origin::signed(ALICE), 42));
+			let output = call_extension(&contract, 1, &[], GAS_LIMIT).unwrap();
+			assert_eq!(output.data.0, Some(42u32).encode());
+		});
+	}
+
+	#[test]
+	fn does_something_as_the_contract() {
+		new_test_ext().execute_with(|| {
+			let contract = instantiate_caller();
+
+			assert_ok!(call_extension(&contract, 2, &42u32.encode(), GAS_LIMIT));
+			assert_eq!(TemplatePallet::something(), Some(42));
+			System::assert_has_event(
+				pallet_template::Event::<Runtime>::SomethingStored(42, contract).into(),
+			);
+		});
+	}
+
+	#[test]
+	fn undecodable_input_is_rejected() {
+		new_test_ext().execute_with(|| {
+			let contract = instantiate_caller();
+
+			assert_eq!(
+				call_extension(&contract, 2, &[42, 0], GAS_LIMIT),
+				Err(pallet_contracts::Error::<Runtime>::DecodingFailed.into())
+			);
+			assert_eq!(TemplatePallet::something(), None);
+		});
+	}
+
+	#[test]
+	fn unknown_functions_are_rejected() {
+		new_test_ext().execute_with(|| {
+			let contract = instantiate_caller();
+
+			assert!(matches!(
+				call_extension(&contract, 3, &[], GAS_LIMIT),
+				Err(DispatchError::Other(_))
+			));
+		});
+	}
+
+	#[test]
+	fn dispatch_weight_is_charged() {
+		new_test_ext().execute_with(|| {
+			let contract = instantiate_caller();
+			let call = pallet_template::Call::<Runtime>::do_something { something: 42 };
+			let gas_limit = call.get_dispatch_info().weight;
+
+			// The call itself uses some gas before the extension charges for the dispatch.
+			assert_eq!(
+				call_extension(&contract, 2, &42u32.encode(), gas_limit),
+				Err(pallet_contracts::Error::<Runtime>::OutOfGas.into())
+			);
+			assert_eq!(TemplatePallet::something(), None);
+		});
+	}
+}
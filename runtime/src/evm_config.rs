@@ -0,0 +1,214 @@
+use super::{
+	opaque, precompiles::FrontierPrecompiles, AccountId, Aura, Balances, BaseFee, Call, Event,
+	Origin, Runtime, UncheckedExtrinsic, MAXIMUM_BLOCK_WEIGHT, NORMAL_DISPATCH_RATIO,
+};
+use codec::{Decode, Encode};
+use core::marker::PhantomData;
+use frame_support::{
+	parameter_types,
+	traits::FindAuthor,
+	weights::{constants::WEIGHT_PER_SECOND, Weight},
+	ConsensusEngineId,
+};
+use frame_system::RawOrigin;
+use pallet_evm::{AddressMapping, EnsureAddressOrigin};
+use sp_core::{crypto::ByteArray, H160, U256};
+use sp_runtime::{
+	traits::{Dispatchable, PostDispatchInfoOf},
+	transaction_validity::{TransactionValidity, TransactionValidityError},
+	Permill,
+};
+
+/// Approximate gas per second of EVM execution over compiled Wasm.
+pub const GAS_PER_SECOND: u64 = 40_000_000;
+
+/// Approximate ratio of the amount of weight per gas.
+pub const WEIGHT_PER_GAS: u64 = WEIGHT_PER_SECOND / GAS_PER_SECOND;
+
+/// Converts between EVM gas and runtime weight at [`WEIGHT_PER_GAS`].
+pub struct GasWeightMapping;
+
+impl pallet_evm::GasWeightMapping for GasWeightMapping {
+	fn gas_to_weight(gas: u64) -> Weight {
+		gas.saturating_mul(WEIGHT_PER_GAS)
+	}
+
+	fn weight_to_gas(weight: Weight) -> u64 {
+		weight.wrapping_div(WEIGHT_PER_GAS)
+	}
+}
+
+/// Reports the Aura author of a block as the EVM coinbase, truncated to its first 20 bytes.
+pub struct FindAuthorTruncated<F>(PhantomData<F>);
+
+impl<F: FindAuthor<u32>> FindAuthor<H160> for FindAuthorTruncated<F> {
+	fn find_author<'a, I>(digests: I) -> Option<H160>
+	where
+		I: 'a + IntoIterator<Item = (ConsensusEngineId, &'a [u8])>,
+	{
+		let index = F::find_author(digests)?;
+		let authority = Aura::authorities().get(index as usize)?.clone();
+		Some(H160::from_slice(&authority.to_raw_vec()[4..24]))
+	}
+}
+
+/// Maps H160 addresses to the accounts they own, see [`unified_accounts`].
+pub struct UnifiedAddressMapping;
+
+impl AddressMapping<AccountId> for UnifiedAddressMapping {
+	fn into_account_id(address: H160) -> AccountId {
+		unified_accounts::account_of(address)
+	}
+}
+
+/// Ensures the origin is signed by the account an H160 address owns.
+pub struct EnsureAddressUnified;
+
+impl<OuterOrigin> EnsureAddressOrigin<OuterOrigin> for EnsureAddressUnified
+where
+	OuterOrigin: Into<Result<RawOrigin<AccountId>, OuterOrigin>> + From<RawOrigin<AccountId>>,
+{
+	type Success = AccountId;
+
+	fn try_address_origin(address: &H160, origin: OuterOrigin) -> Result<AccountId, OuterOrigin> {
+		origin.into().and_then(|origin| match origin {
+			RawOrigin::Signed(who) if who == unified_accounts::account_of(*address) => Ok(who),
+			origin => Err(OuterOrigin::from(origin)),
+		})
+	}
+}
+
+parameter_types! {
+	/// The EIP-155 chain id wallets sign Ethereum transactions, and EIP-712 native extrinsics,
+	/// for.
+	pub const ChainId: u64 = unified_accounts::EIP712_CHAIN_ID;
+	pub BlockGasLimit: U256 = U256::from(NORMAL_DISPATCH_RATIO * MAXIMUM_BLOCK_WEIGHT / WEIGHT_PER_GAS);
+	pub PrecompilesValue: FrontierPrecompiles = FrontierPrecompiles;
+}
+
+impl pallet_evm::Config for Runtime {
+	type FeeCalculator = BaseFee;
+	type GasWeightMapping = GasWeightMapping;
+	type BlockHashMapping = pallet_ethereum::EthereumBlockHashMapping<Self>;
+	type CallOrigin = EnsureAddressUnified;
+	type WithdrawOrigin = EnsureAddressUnified;
+	type AddressMapping = UnifiedAddressMapping;
+	type Currency = Balances;
+	type Event = Event;
+	type Runner = pallet_evm::runner::stack::Runner<Self>;
+	type PrecompilesType = FrontierPrecompiles;
+	type PrecompilesValue = PrecompilesValue;
+	type ChainId = ChainId;
+	type BlockGasLimit = BlockGasLimit;
+	type OnChargeTransaction = ();
+	type FindAuthor = FindAuthorTruncated<Aura>;
+}
+
+impl pallet_ethereum::Config for Runtime {
+	type Event = Event;
+	type StateRoot = pallet_ethereum::IntermediateStateRoot;
+}
+
+/// Bounds within which the block fullness leaves the base fee unchanged.
+pub struct BaseFeeThreshold;
+
+impl pallet_base_fee::BaseFeeThreshold for BaseFeeThreshold {
+	fn lower() -> Permill {
+		Permill::zero()
+	}
+
+	fn ideal() -> Permill {
+		Permill::from_parts(500_000)
+	}
+
+	fn upper() -> Permill {
+		Permill::from_parts(1_000_000)
+	}
+}
+
+parameter_types! {
+	pub const IsActive: bool = false;
+	pub DefaultBaseFeePerGas: U256 = U256::from(1_000_000_000);
+}
+
+impl pallet_base_fee::Config for Runtime {
+	type Event = Event;
+	type Threshold = BaseFeeThreshold;
+	type IsActive = IsActive;
+	type DefaultBaseFeePerGas = DefaultBaseFeePerGas;
+}
+
+/// Ethereum transactions carry their own signature and are applied as self-contained calls of
+/// `pallet_ethereum`, signed by the recovered H160.
+impl fp_self_contained::SelfContainedCall for Call {
+	type SignedInfo = H160;
+
+	fn is_self_contained(&self) -> bool {
+		match self {
+			Call::Ethereum(call) => call.is_self_contained(),
+			_ => false,
+		}
+	}
+
+	fn check_self_contained(&self) -> Option<Result<Self::SignedInfo, TransactionValidityError>> {
+		match self {
+			Call::Ethereum(call) => call.check_self_contained(),
+			_ => None,
+		}
+	}
+
+	fn validate_self_contained(&self, info: &Self::SignedInfo) -> Option<TransactionValidity> {
+		match self {
+			Call::Ethereum(call) => call.validate_self_contained(info),
+			_ => None,
+		}
+	}
+
+	fn pre_dispatch_self_contained(
+		&self,
+		info: &Self::SignedInfo,
+	) -> Option<Result<(), TransactionValidityError>> {
+		match self {
+			Call::Ethereum(call) => call.pre_dispatch_self_contained(info),
+			_ => None,
+		}
+	}
+
+	fn apply_self_contained(
+		self,
+		info: Self::SignedInfo,
+	) -> Option<sp_runtime::DispatchResultWithInfo<PostDispatchInfoOf<Self>>> {
+		match self {
+			call @ Call::Ethereum(pallet_ethereum::Call::transact { .. }) => Some(
+				call.dispatch(Origin::from(pallet_ethereum::RawOrigin::EthereumTransaction(info))),
+			),
+			_ => None,
+		}
+	}
+}
+
+/// Wraps Ethereum transactions submitted over RPC into extrinsics.
+#[derive(Clone)]
+pub struct TransactionConverter;
+
+impl fp_rpc::ConvertTransaction<UncheckedExtrinsic> for TransactionConverter {
+	fn convert_transaction(&self, transaction: pallet_ethereum::Transaction) -> UncheckedExtrinsic {
+		UncheckedExtrinsic::new_unsigned(
+			pallet_ethereum::Call::<Runtime>::transact { transaction }.into(),
+		)
+	}
+}
+
+impl fp_rpc::ConvertTransaction<opaque::UncheckedExtrinsic> for TransactionConverter {
+	fn convert_transaction(
+		&self,
+		transaction: pallet_ethereum::Transaction,
+	) -> opaque::UncheckedExtrinsic {
+		let extrinsic = UncheckedExtrinsic::new_unsigned(
+			pallet_ethereum::Call::<Runtime>::transact { transaction }.into(),
+		);
+		let encoded = extrinsic.encode();
+		opaque::UncheckedExtrinsic::decode(&mut &encoded[..])
+			.expect("Encoded extrinsic is always valid")
+	}
+}
@@ -6,17 +6,21 @@
 #[cfg(feature = "std")]
 include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
 
+pub mod contracts_config;
+pub mod evm_config;
+pub mod precompiles;
 mod weights;
 pub mod xcm_config;
 
+use codec::Encode;
 use smallvec::smallvec;
 use sp_api::impl_runtime_apis;
-use sp_core::{crypto::KeyTypeId, OpaqueMetadata};
+use sp_core::{crypto::KeyTypeId, OpaqueMetadata, H160, H256, U256};
 use sp_runtime::{
 	create_runtime_str, generic, impl_opaque_keys,
 	traits::{AccountIdLookup, BlakeTwo256, Block as BlockT, IdentifyAccount, Verify},
 	transaction_validity::{TransactionSource, TransactionValidity},
-	ApplyExtrinsicResult, MultiSignature,
+	ApplyExtrinsicResult,
 };
 
 use sp_std::prelude::*;
@@ -26,7 +30,7 @@ use sp_version::RuntimeVersion;
 
 use frame_support::{
 	construct_runtime, parameter_types,
-	traits::Everything,
+	traits::{EnsureOneOf, Everything, Get},
 	weights::{
 		constants::WEIGHT_PER_SECOND, ConstantMultiplier, DispatchClass, Weight,
 		WeightToFeeCoefficient, WeightToFeeCoefficients, WeightToFeePolynomial,
@@ -39,7 +43,7 @@ use frame_system::{
 };
 pub use sp_consensus_aura::sr25519::AuthorityId as AuraId;
 pub use sp_runtime::{MultiAddress, Perbill, Permill};
-use xcm_config::{XcmConfig, XcmOriginToTransactDispatchOrigin};
+use xcm_config::{RelayLocation, XcmConfig, XcmOriginToTransactDispatchOrigin};
 
 #[cfg(any(feature = "std", test))]
 pub use sp_runtime::BuildStorage;
@@ -50,6 +54,7 @@ use polkadot_runtime_common::{BlockHashCount, SlowAdjustingFeeUpdate};
 use weights::{BlockExecutionWeight, ExtrinsicBaseWeight, RocksDbWeight};
 
 // XCM Imports
+use pallet_xcm::{EnsureXcm, IsMajorityOfBody};
 use xcm::latest::prelude::BodyId;
 use xcm_executor::XcmExecutor;
 
@@ -57,7 +62,8 @@ use xcm_executor::XcmExecutor;
 pub use pallet_template;
 
 /// Alias to 512-bit hash when used in the context of a transaction signature on the chain.
-pub type Signature = MultiSignature;
+/// Next to Substrate keys it accepts EIP-712 signatures of Ethereum keys.
+pub type Signature = unified_accounts::UnifiedSignature;
 
 /// Some way of identifying an account on the chain. We intentionally make it equivalent
 /// to the public key of our transaction signing scheme.
@@ -66,6 +72,9 @@ pub type AccountId = <<Signature as Verify>::Signer as IdentifyAccount>::Account
 /// Balance of an account.
 pub type Balance = u128;
 
+/// Identifier of a fungible asset held by `pallet_assets`.
+pub type AssetId = u32;
+
 /// Index of a transaction in the chain.
 pub type Index = u32;
 
@@ -90,6 +99,20 @@ pub type SignedBlock = generic::SignedBlock<Block>;
 /// BlockId type as expected by this runtime.
 pub type BlockId = generic::BlockId<Block>;
 
+/// The merkleized metadata hash of this runtime, computed by the build script when the
+/// `with-metadata-hash` feature is enabled.
+pub const RUNTIME_METADATA_HASH: Option<[u8; 32]> = match option_env!("RUNTIME_METADATA_HASH") {
+	Some(hash) => Some(metadata_hash::decode_hash(hash)),
+	None => None,
+};
+
+parameter_types! {
+	pub const RuntimeMetadataHash: Option<[u8; 32]> = RUNTIME_METADATA_HASH;
+}
+
+/// The native token as shown by wallets and offline signers.
+pub use chain_properties::{SS58_PREFIX, TOKEN_DECIMALS, TOKEN_SYMBOL};
+
 /// The SignedExtension to the basic transaction logic.
 pub type SignedExtra = (
 	frame_system::CheckNonZeroSender<Runtime>,
@@ -100,13 +123,16 @@ pub type SignedExtra = (
 	frame_system::CheckNonce<Runtime>,
 	frame_system::CheckWeight<Runtime>,
 	pallet_transaction_payment::ChargeTransactionPayment<Runtime>,
+	metadata_hash::CheckMetadataHash<Runtime, RuntimeMetadataHash>,
 );
 
-/// Unchecked extrinsic type as expected by this runtime.
-pub type UncheckedExtrinsic = generic::UncheckedExtrinsic<Address, Call, Signature, SignedExtra>;
+/// Unchecked extrinsic type as expected by this runtime. Next to signed and unsigned extrinsics
+/// it carries self-contained Ethereum transactions.
+pub type UncheckedExtrinsic =
+	fp_self_contained::UncheckedExtrinsic<Address, Call, Signature, SignedExtra>;
 
 /// Extrinsic type that has already been checked.
-pub type CheckedExtrinsic = generic::CheckedExtrinsic<AccountId, Call, SignedExtra>;
+pub type CheckedExtrinsic = fp_self_contained::CheckedExtrinsic<AccountId, Call, SignedExtra, H160>;
 
 /// Executive: handles dispatch to the various modules.
 pub type Executive = frame_executive::Executive<
@@ -115,8 +141,13 @@ pub type Executive = frame_executive::Executive<
 	frame_system::ChainContext<Runtime>,
 	Runtime,
 	AllPalletsWithSystem,
+	Migrations,
 >;
 
+/// Runtime upgrades to run before those of the pallets. `CollatorStaking` took the index of
+/// `CollatorSelection`, whose collators it takes over.
+pub type Migrations = pallet_collator_staking::migration::MigrateFromCollatorSelection<Runtime>;
+
 /// Handles converting a weight scalar to a fee value, based on the scale and granularity of the
 /// node's balance type.
 ///
@@ -172,10 +203,10 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	spec_name: create_runtime_str!("template-parachain"),
 	impl_name: create_runtime_str!("template-parachain"),
 	authoring_version: 1,
-	spec_version: 1,
+	spec_version: 2,
 	impl_version: 0,
 	apis: RUNTIME_API_VERSIONS,
-	transaction_version: 1,
+	transaction_version: 2,
 	state_version: 1,
 };
 
@@ -248,7 +279,7 @@ parameter_types! {
 		})
 		.avg_block_initialization(AVERAGE_ON_INITIALIZE_RATIO)
 		.build_or_panic();
-	pub const SS58Prefix: u16 = 42;
+	pub const SS58Prefix: u16 = SS58_PREFIX;
 }
 
 // Configure FRAME pallets to include in runtime.
@@ -323,7 +354,7 @@ impl pallet_authorship::Config for Runtime {
 	type FindAuthor = pallet_session::FindAccountFromAuthorIndex<Self, Aura>;
 	type UncleGenerations = UncleGenerations;
 	type FilterUncle = ();
-	type EventHandler = (CollatorSelection,);
+	type EventHandler = (CollatorStaking, CollatorStats);
 }
 
 parameter_types! {
@@ -360,6 +391,77 @@ impl pallet_transaction_payment::Config for Runtime {
 	type OperationalFeeMultiplier = OperationalFeeMultiplier;
 }
 
+parameter_types! {
+	pub const AssetDeposit: Balance = 10 * UNIT;
+	pub const AssetAccountDeposit: Balance = 10 * MILLIUNIT;
+	pub const MetadataDepositBase: Balance = UNIT;
+	pub const MetadataDepositPerByte: Balance = 10 * MILLIUNIT;
+	pub const ApprovalDeposit: Balance = EXISTENTIAL_DEPOSIT;
+	pub const AssetsStringLimit: u32 = 50;
+}
+
+/// Fungible assets other than the native token, such as the relay chain token.
+impl pallet_assets::Config for Runtime {
+	type Event = Event;
+	type Balance = Balance;
+	type AssetId = AssetId;
+	type Currency = Balances;
+	type ForceOrigin = EnsureRoot<AccountId>;
+	type AssetDeposit = AssetDeposit;
+	type AssetAccountDeposit = AssetAccountDeposit;
+	type MetadataDepositBase = MetadataDepositBase;
+	type MetadataDepositPerByte = MetadataDepositPerByte;
+	type ApprovalDeposit = ApprovalDeposit;
+	type StringLimit = AssetsStringLimit;
+	type Freezer = ();
+	type Extra = ();
+	type WeightInfo = pallet_assets::weights::SubstrateWeight<Runtime>;
+}
+
+parameter_types! {
+	pub const UniquesClassDeposit: Balance = 10 * UNIT;
+	pub const UniquesInstanceDeposit: Balance = 10 * MILLIUNIT;
+	pub const UniquesMetadataDepositBase: Balance = UNIT;
+	pub const UniquesAttributeDepositBase: Balance = 10 * MILLIUNIT;
+	pub const UniquesDepositPerByte: Balance = 10 * MILLIUNIT;
+	pub const UniquesKeyLimit: u32 = 32;
+	pub const UniquesValueLimit: u32 = 64;
+}
+
+/// NFTs, moving to and from other chains through XCM. Foreign collections are minted into the
+/// local collections `XcmCollections` registered for them.
+impl pallet_uniques::Config for Runtime {
+	type Event = Event;
+	type ClassId = u32;
+	type InstanceId = u32;
+	type Currency = Balances;
+	type ForceOrigin = EnsureRoot<AccountId>;
+	type ClassDeposit = UniquesClassDeposit;
+	type InstanceDeposit = UniquesInstanceDeposit;
+	type MetadataDepositBase = UniquesMetadataDepositBase;
+	type AttributeDepositBase = UniquesAttributeDepositBase;
+	type DepositPerByte = UniquesDepositPerByte;
+	type StringLimit = AssetsStringLimit;
+	type KeyLimit = UniquesKeyLimit;
+	type ValueLimit = UniquesValueLimit;
+	type WeightInfo = pallet_uniques::weights::SubstrateWeight<Runtime>;
+}
+
+parameter_types! {
+	pub const BlocksPerYear: u32 = 365 * DAYS;
+	pub const MaxInflationDestinations: u32 = 8;
+	pub const TreasuryPalletId: PalletId = PalletId(*b"py/trsry");
+	pub const EcosystemPalletId: PalletId = PalletId(*b"py/ecsys");
+}
+
+impl pallet_inflation::Config for Runtime {
+	type Event = Event;
+	type Currency = Balances;
+	type UpdateOrigin = EnsureRoot<AccountId>;
+	type BlocksPerYear = BlocksPerYear;
+	type MaxDestinations = MaxInflationDestinations;
+}
+
 parameter_types! {
 	pub const ReservedXcmpWeight: Weight = MAXIMUM_BLOCK_WEIGHT / 4;
 	pub const ReservedDmpWeight: Weight = MAXIMUM_BLOCK_WEIGHT / 4;
@@ -367,7 +469,7 @@ parameter_types! {
 
 impl cumulus_pallet_parachain_system::Config for Runtime {
 	type Event = Event;
-	type OnSystemEvent = ();
+	type OnSystemEvent = HrmpChannels;
 	type SelfParaId = parachain_info::Pallet<Runtime>;
 	type DmpMessageHandler = DmpQueue;
 	type ReservedDmpWeight = ReservedDmpWeight;
@@ -386,14 +488,15 @@ impl cumulus_pallet_xcmp_queue::Config for Runtime {
 	type ChannelInfo = ParachainSystem;
 	type VersionWrapper = ();
 	type ExecuteOverweightOrigin = EnsureRoot<AccountId>;
-	type ControllerOrigin = EnsureRoot<AccountId>;
+	type ControllerOrigin = RootOrExecutiveMajority;
 	type ControllerOriginConverter = XcmOriginToTransactDispatchOrigin;
 	type WeightInfo = ();
 }
 
 impl cumulus_pallet_dmp_queue::Config for Runtime {
 	type Event = Event;
-	type XcmExecutor = XcmExecutor<XcmConfig>;
+	// HRMP notifications of the relay chain are handled by `HrmpChannels`.
+	type XcmExecutor = pallet_hrmp_channels::HrmpNotifications<Runtime, XcmExecutor<XcmConfig>>;
 	type ExecuteOverweightOrigin = EnsureRoot<AccountId>;
 }
 
@@ -407,10 +510,10 @@ impl pallet_session::Config for Runtime {
 	type Event = Event;
 	type ValidatorId = <Self as frame_system::Config>::AccountId;
 	// we don't have stash and controller, thus we don't need the convert as well.
-	type ValidatorIdOf = pallet_collator_selection::IdentityCollator;
+	type ValidatorIdOf = pallet_collator_staking::IdentityCollator;
 	type ShouldEndSession = pallet_session::PeriodicSessions<Period, Offset>;
 	type NextSessionRotation = pallet_session::PeriodicSessions<Period, Offset>;
-	type SessionManager = CollatorSelection;
+	type SessionManager = CollatorStaking;
 	// Essentially just Aura, but lets be pedantic.
 	type SessionHandler = <SessionKeys as sp_runtime::traits::OpaqueKeys>::KeyTypeIdProviders;
 	type Keys = SessionKeys;
@@ -419,38 +522,82 @@ impl pallet_session::Config for Runtime {
 
 impl pallet_aura::Config for Runtime {
 	type AuthorityId = AuraId;
-	type DisabledValidators = ();
+	type DisabledValidators = Session;
 	type MaxAuthorities = MaxAuthorities;
 }
 
 parameter_types! {
 	pub const PotId: PalletId = PalletId(*b"PotStake");
 	pub const MaxCandidates: u32 = 1000;
-	pub const MinCandidates: u32 = 5;
-	pub const SessionLength: BlockNumber = 6 * HOURS;
 	pub const MaxInvulnerables: u32 = 100;
+	pub const MaxCollators: u32 = 100;
+	pub const MinDelegation: Balance = 10 * UNIT;
+	pub const UnbondingDelay: BlockNumber = 7 * DAYS;
+	pub const MaxUnbondingChunks: u32 = 32;
 	pub const ExecutiveBody: BodyId = BodyId::Executive;
 }
 
-// We allow root only to execute privileged collator selection operations.
-pub type CollatorSelectionUpdateOrigin = EnsureRoot<AccountId>;
+/// Root, or a majority of the relay chain's executive body sending `Transact` with
+/// `OriginKind::Xcm`, as system parachains are governed.
+pub type RootOrExecutiveMajority =
+	EnsureOneOf<EnsureRoot<AccountId>, EnsureXcm<IsMajorityOfBody<RelayLocation, ExecutiveBody>>>;
 
-impl pallet_collator_selection::Config for Runtime {
+// We allow root and the relay chain's executive body to execute privileged collator selection
+// operations.
+pub type CollatorSelectionUpdateOrigin = RootOrExecutiveMajority;
+
+impl pallet_collator_staking::Config for Runtime {
 	type Event = Event;
 	type Currency = Balances;
 	type UpdateOrigin = CollatorSelectionUpdateOrigin;
 	type PotId = PotId;
 	type MaxCandidates = MaxCandidates;
-	type MinCandidates = MinCandidates;
 	type MaxInvulnerables = MaxInvulnerables;
+	type MaxCollators = MaxCollators;
+	type MinDelegation = MinDelegation;
+	type UnbondingDelay = UnbondingDelay;
+	type MaxUnbondingChunks = MaxUnbondingChunks;
 	// should be a multiple of session or things will get inconsistent
 	type KickThreshold = Period;
 	type ValidatorId = <Self as frame_system::Config>::AccountId;
-	type ValidatorIdOf = pallet_collator_selection::IdentityCollator;
+	type ValidatorIdOf = pallet_collator_staking::IdentityCollator;
 	type ValidatorRegistration = Session;
-	type WeightInfo = ();
+	type WeightInfo = pallet_collator_staking::weights::SubstrateWeight<Runtime>;
 }
 
+/// The slot of the current block, as seen by Aura.
+pub struct AuraSlot;
+impl Get<u64> for AuraSlot {
+	fn get() -> u64 {
+		Aura::current_slot().into()
+	}
+}
+
+parameter_types! {
+	pub const StatsHistoryDepth: u32 = 28;
+}
+
+impl pallet_collator_stats::Config for Runtime {
+	type ValidatorSet = Session;
+	type CurrentSlot = AuraSlot;
+	type HistoryDepth = StatsHistoryDepth;
+}
+
+impl<C> frame_system::offchain::SendTransactionTypes<C> for Runtime
+where
+	Call: From<C>,
+{
+	type OverarchingCall = Call;
+	type Extrinsic = UncheckedExtrinsic;
+}
+
+impl pallet_collator_offences::Config for Runtime {
+	type Event = Event;
+	type DisableOrigin = CollatorSelectionUpdateOrigin;
+}
+
+impl pallet_randomness_collective_flip::Config for Runtime {}
+
 /// Configure the pallet template in pallets/template.
 impl pallet_template::Config for Runtime {
 	type Event = Event;
@@ -470,26 +617,50 @@ construct_runtime!(
 		} = 1,
 		Timestamp: pallet_timestamp::{Pallet, Call, Storage, Inherent} = 2,
 		ParachainInfo: parachain_info::{Pallet, Storage, Config} = 3,
+		RandomnessCollectiveFlip: pallet_randomness_collective_flip::{Pallet, Storage} = 4,
 
 		// Monetary stuff.
 		Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>} = 10,
 		TransactionPayment: pallet_transaction_payment::{Pallet, Storage} = 11,
+		Inflation: pallet_inflation::{Pallet, Call, Storage, Event<T>, Config<T>} = 12,
+		Assets: pallet_assets::{Pallet, Call, Storage, Event<T>, Config<T>} = 13,
+		Uniques: pallet_uniques::{Pallet, Call, Storage, Event<T>} = 14,
 
 		// Collator support. The order of these 4 are important and shall not change.
 		Authorship: pallet_authorship::{Pallet, Call, Storage} = 20,
-		CollatorSelection: pallet_collator_selection::{Pallet, Call, Storage, Event<T>, Config<T>} = 21,
+		CollatorStaking: pallet_collator_staking::{Pallet, Call, Storage, Event<T>, Config<T>} = 21,
 		Session: pallet_session::{Pallet, Call, Storage, Event, Config<T>} = 22,
 		Aura: pallet_aura::{Pallet, Storage, Config<T>} = 23,
 		AuraExt: cumulus_pallet_aura_ext::{Pallet, Storage, Config} = 24,
+		CollatorStats: pallet_collator_stats::{Pallet, Storage} = 25,
+		CollatorOffences: pallet_collator_offences::{Pallet, Call, Event<T>, ValidateUnsigned} = 26,
 
 		// XCM helpers.
 		XcmpQueue: cumulus_pallet_xcmp_queue::{Pallet, Call, Storage, Event<T>} = 30,
 		PolkadotXcm: pallet_xcm::{Pallet, Call, Event<T>, Origin, Config} = 31,
 		CumulusXcm: cumulus_pallet_xcm::{Pallet, Event<T>, Origin} = 32,
 		DmpQueue: cumulus_pallet_dmp_queue::{Pallet, Call, Storage, Event<T>} = 33,
+		XcmReserves: pallet_xcm_reserves::{Pallet, Call, Storage, Event<T>} = 34,
+		TransactFilter: pallet_xcm_transact_filter::{Pallet, Call, Storage, Event<T>, Config} = 35,
+		HrmpChannels: pallet_hrmp_channels::{Pallet, Call, Storage, Event<T>} = 36,
+		XcmTransfers: pallet_xcm_transfers::{Pallet, Call, Event<T>} = 37,
+		XcmOutcomes: pallet_xcm_outcomes::{Pallet, Call, Storage, Event<T>} = 38,
+		XcmAssetTraps: pallet_xcm_asset_traps::{Pallet, Call, Storage, Event<T>} = 39,
+		XcmOverweight: pallet_xcm_overweight::{Pallet, Call, Storage, Event<T>} = 41,
+		XcmRateLimiter: pallet_xcm_rate_limiter::{Pallet, Call, Storage, Event<T>} = 42,
+		XcmCollections: pallet_xcm_collections::{Pallet, Call, Storage, Event<T>} = 43,
+		XcmTrustedSiblings: pallet_xcm_trusted_siblings::{Pallet, Call, Storage, Event<T>} = 44,
 
 		// Template
 		TemplatePallet: pallet_template::{Pallet, Call, Storage, Event<T>}  = 40,
+
+		// Smart contracts.
+		Contracts: pallet_contracts::{Pallet, Call, Storage, Event<T>} = 50,
+
+		// Ethereum compatibility.
+		EVM: pallet_evm::{Pallet, Config, Call, Storage, Event<T>} = 60,
+		Ethereum: pallet_ethereum::{Pallet, Call, Storage, Event, Config, Origin} = 61,
+		BaseFee: pallet_base_fee::{Pallet, Call, Storage, Config<T>, Event} = 62,
 	}
 );
 
@@ -502,10 +673,14 @@ mod benches {
 	define_benchmarks!(
 		[frame_system, SystemBench::<Runtime>]
 		[pallet_balances, Balances]
+		[pallet_assets, Assets]
+		[pallet_uniques, Uniques]
+		[pallet_inflation, Inflation]
 		[pallet_session, SessionBench::<Runtime>]
 		[pallet_timestamp, Timestamp]
-		[pallet_collator_selection, CollatorSelection]
+		[pallet_collator_staking, CollatorStaking]
 		[cumulus_pallet_xcmp_queue, XcmpQueue]
+		[pallet_contracts, Contracts]
 	);
 }
 
@@ -540,6 +715,28 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl metadata_hash::MetadataHashApi<Block> for Runtime {
+		fn metadata_hash() -> Option<[u8; 32]> {
+			RUNTIME_METADATA_HASH
+		}
+
+		fn metadata_proof(extrinsic: Vec<u8>, additional_signed: Option<Vec<u8>>) -> Option<Vec<u8>> {
+			let info = metadata_hash::ExtraInfo {
+				spec_version: VERSION.spec_version,
+				spec_name: (*VERSION.spec_name).to_owned(),
+				base58_prefix: SS58Prefix::get(),
+				decimals: TOKEN_DECIMALS,
+				token_symbol: TOKEN_SYMBOL.to_owned(),
+			};
+			metadata_hash::metadata_proof(
+				&Runtime::metadata().encode(),
+				&extrinsic,
+				additional_signed.as_deref(),
+				info,
+			)
+		}
+	}
+
 	impl sp_block_builder::BlockBuilder<Block> for Runtime {
 		fn apply_extrinsic(extrinsic: <Block as BlockT>::Extrinsic) -> ApplyExtrinsicResult {
 			Executive::apply_extrinsic(extrinsic)
@@ -610,6 +807,366 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl pallet_inflation_runtime_api::InflationApi<Block, Balance> for Runtime {
+		fn inflation_info() -> pallet_inflation_runtime_api::InflationInfo<Balance> {
+			Inflation::inflation_info()
+		}
+
+		fn projected_issuance(blocks: u32) -> Balance {
+			Inflation::projected_issuance(blocks)
+		}
+	}
+
+	impl pallet_collator_stats_runtime_api::CollatorStatsApi<Block, AccountId, BlockNumber> for Runtime {
+		fn current_session() -> u32 {
+			CollatorStats::current_session()
+		}
+
+		fn session_stats(
+			session: u32,
+		) -> Vec<(AccountId, pallet_collator_stats_runtime_api::CollatorPerformance)> {
+			CollatorStats::stats_of_session(session)
+		}
+
+		fn collator_stats(
+			who: AccountId,
+		) -> Vec<(u32, pallet_collator_stats_runtime_api::CollatorPerformance)> {
+			CollatorStats::stats_of_collator(&who)
+		}
+
+		fn kick_candidates() -> Vec<pallet_collator_stats_runtime_api::KickCandidate<AccountId, BlockNumber>> {
+			CollatorStaking::kick_schedule()
+				.into_iter()
+				.map(|(who, last_authored, kick_at)| {
+					pallet_collator_stats_runtime_api::KickCandidate { who, last_authored, kick_at }
+				})
+				.collect()
+		}
+	}
+
+	impl pallet_collator_offences_runtime_api::CollatorOffencesApi<Block, AuraId> for Runtime {
+		fn submit_report_equivocation_unsigned_extrinsic(
+			equivocation_proof: pallet_collator_offences_runtime_api::EquivocationProof<
+				<Block as BlockT>::Header,
+				AuraId,
+			>,
+		) -> Option<()> {
+			CollatorOffences::submit_unsigned_equivocation_report(equivocation_proof)
+		}
+	}
+
+	impl xcm_accounts::XcmAccountsApi<Block, AccountId> for Runtime {
+		fn location_to_account(location: xcm::VersionedMultiLocation) -> Option<AccountId> {
+			use xcm_executor::traits::Convert;
+
+			let location = xcm::latest::MultiLocation::try_from(location).ok()?;
+			xcm_config::LocationToAccountId::convert(location).ok()
+		}
+	}
+
+	impl xcm_dry_run::XcmDryRunApi<Block, Call, Event> for Runtime {
+		fn dry_run_xcm(
+			origin: xcm::VersionedMultiLocation,
+			message: xcm::VersionedXcm<Call>,
+			max_weight: Weight,
+		) -> Result<xcm_dry_run::XcmDryRunEffects<Event>, xcm_dry_run::XcmDryRunError> {
+			use xcm::latest::{ExecuteXcm, MultiLocation, Xcm};
+
+			let origin = MultiLocation::try_from(origin)
+				.map_err(|()| xcm_dry_run::XcmDryRunError::VersionedConversionFailed)?;
+			let message = Xcm::<Call>::try_from(message)
+				.map_err(|()| xcm_dry_run::XcmDryRunError::VersionedConversionFailed)?;
+
+			System::reset_events();
+			let (outcome, forwarded_xcms) = xcm_dry_run::record_forwarded(|| {
+				XcmExecutor::<XcmConfig>::execute_xcm(origin, message, max_weight)
+			});
+			Ok(xcm_dry_run::XcmDryRunEffects {
+				weight_used: outcome.weight_used(),
+				outcome,
+				emitted_events: System::events().into_iter().map(|record| record.event).collect(),
+				forwarded_xcms,
+			})
+		}
+	}
+
+	impl xcm_fees::XcmFeesApi<Block, Call, Balance> for Runtime {
+		fn query_xcm_fees(
+			origin: xcm::VersionedMultiLocation,
+			message: xcm::VersionedXcm<Call>,
+			fee_asset: xcm::VersionedMultiLocation,
+		) -> Result<xcm_fees::XcmFees<Balance>, xcm_fees::XcmFeesError> {
+			use xcm::latest::{prelude::*, MultiLocation, Xcm};
+			use xcm_executor::traits::{WeightBounds, WeightTrader};
+			use xcm_fees::XcmFeesError;
+
+			let origin = MultiLocation::try_from(origin)
+				.map_err(|()| XcmFeesError::VersionedConversionFailed)?;
+			let mut message = Xcm::<Call>::try_from(message)
+				.map_err(|()| XcmFeesError::VersionedConversionFailed)?;
+			let fee_asset = MultiLocation::try_from(fee_asset)
+				.map_err(|()| XcmFeesError::VersionedConversionFailed)?;
+
+			let weight = <XcmConfig as xcm_executor::Config>::Weigher::weight(&mut message)
+				.map_err(|()| XcmFeesError::UnweighableMessage)?;
+			// Offer as much as possible and see how much the trader leaves.
+			let offered: MultiAsset = (fee_asset.clone(), u128::MAX).into();
+			let unused = <XcmConfig as xcm_executor::Config>::Trader::new()
+				.buy_weight(weight, offered.into())
+				.map_err(|_| XcmFeesError::AssetNotAccepted)?;
+			let execution_fee =
+				u128::MAX - unused.fungible.get(&Concrete(fee_asset)).copied().unwrap_or_default();
+
+			// The weight credit lets the message past the barrier, even if it does not pay yet.
+			let (_, forwarded_xcms) = xcm_dry_run::record_forwarded(|| {
+				XcmExecutor::<XcmConfig>::execute_xcm_in_credit(origin, message, weight, weight)
+			});
+			// The router of the executor delivers for free. Only `XcmTransfers` charges a delivery
+			// fee, for the transfers it sends itself.
+			let delivery_fees = forwarded_xcms.into_iter().map(|(dest, _)| (dest, 0)).collect();
+
+			Ok(xcm_fees::XcmFees { weight, execution_fee, delivery_fees })
+		}
+	}
+
+	impl pallet_xcm_outcomes_runtime_api::XcmOutcomesApi<Block, BlockNumber> for Runtime {
+		fn outcome(
+			query_id: xcm::latest::QueryId,
+		) -> Option<pallet_xcm_outcomes_runtime_api::OutcomeRecord<BlockNumber>> {
+			XcmOutcomes::outcomes(query_id)
+		}
+	}
+
+	impl pallet_xcm_asset_traps_runtime_api::XcmAssetTrapsApi<Block, BlockNumber> for Runtime {
+		fn trapped_assets(
+		) -> Vec<(H256, pallet_xcm_asset_traps_runtime_api::TrappedAssets<BlockNumber>)> {
+			XcmAssetTraps::trapped_assets()
+		}
+	}
+
+	impl pallet_xcm_overweight_runtime_api::XcmOverweightApi<Block> for Runtime {
+		fn overweight_messages() -> Vec<pallet_xcm_overweight_runtime_api::OverweightMessage> {
+			XcmOverweight::overweight_messages()
+		}
+	}
+
+	impl pallet_xcm_rate_limiter_runtime_api::XcmRateLimiterApi<Block, BlockNumber> for Runtime {
+		fn utilisation() -> Vec<pallet_xcm_rate_limiter_runtime_api::Utilisation<BlockNumber>> {
+			XcmRateLimiter::utilisation()
+		}
+
+		fn is_suspended() -> bool {
+			XcmRateLimiter::is_suspended()
+		}
+	}
+
+	impl pallet_contracts_rpc_runtime_api::ContractsApi<Block, AccountId, Balance, BlockNumber, Hash>
+		for Runtime
+	{
+		fn call(
+			origin: AccountId,
+			dest: AccountId,
+			value: Balance,
+			gas_limit: u64,
+			storage_deposit_limit: Option<Balance>,
+			input_data: Vec<u8>,
+		) -> pallet_contracts_primitives::ContractExecResult<Balance> {
+			Contracts::bare_call(
+				origin,
+				dest,
+				value,
+				gas_limit,
+				storage_deposit_limit,
+				input_data,
+				contracts_config::CONTRACTS_DEBUG_OUTPUT,
+			)
+		}
+
+		fn instantiate(
+			origin: AccountId,
+			value: Balance,
+			gas_limit: u64,
+			storage_deposit_limit: Option<Balance>,
+			code: pallet_contracts_primitives::Code<Hash>,
+			data: Vec<u8>,
+			salt: Vec<u8>,
+		) -> pallet_contracts_primitives::ContractInstantiateResult<AccountId, Balance> {
+			Contracts::bare_instantiate(
+				origin,
+				value,
+				gas_limit,
+				storage_deposit_limit,
+				code,
+				data,
+				salt,
+				contracts_config::CONTRACTS_DEBUG_OUTPUT,
+			)
+		}
+
+		fn upload_code(
+			origin: AccountId,
+			code: Vec<u8>,
+			storage_deposit_limit: Option<Balance>,
+		) -> pallet_contracts_primitives::CodeUploadResult<Hash, Balance> {
+			Contracts::bare_upload_code(origin, code, storage_deposit_limit)
+		}
+
+		fn get_storage(
+			address: AccountId,
+			key: [u8; 32],
+		) -> pallet_contracts_primitives::GetStorageResult {
+			Contracts::get_storage(address, key)
+		}
+	}
+
+	impl fp_rpc::EthereumRuntimeRPCApi<Block> for Runtime {
+		fn chain_id() -> u64 {
+			<Runtime as pallet_evm::Config>::ChainId::get()
+		}
+
+		fn account_basic(address: H160) -> pallet_evm::Account {
+			EVM::account_basic(&address)
+		}
+
+		fn gas_price() -> U256 {
+			use pallet_evm::FeeCalculator;
+			<Runtime as pallet_evm::Config>::FeeCalculator::min_gas_price()
+		}
+
+		fn account_code_at(address: H160) -> Vec<u8> {
+			EVM::account_codes(address)
+		}
+
+		fn author() -> H160 {
+			<pallet_evm::Pallet<Runtime>>::find_author()
+		}
+
+		fn storage_at(address: H160, index: U256) -> H256 {
+			let mut tmp = [0u8; 32];
+			index.to_big_endian(&mut tmp);
+			EVM::account_storages(address, H256::from_slice(&tmp[..]))
+		}
+
+		fn call(
+			from: H160,
+			to: H160,
+			data: Vec<u8>,
+			value: U256,
+			gas_limit: U256,
+			max_fee_per_gas: Option<U256>,
+			max_priority_fee_per_gas: Option<U256>,
+			nonce: Option<U256>,
+			estimate: bool,
+		) -> Result<pallet_evm::CallInfo, sp_runtime::DispatchError> {
+			use pallet_evm::Runner;
+
+			let config = if estimate {
+				let mut config = <Runtime as pallet_evm::Config>::config().clone();
+				config.estimate = true;
+				Some(config)
+			} else {
+				None
+			};
+
+			<Runtime as pallet_evm::Config>::Runner::call(
+				from,
+				to,
+				data,
+				value,
+				gas_limit.low_u64(),
+				max_fee_per_gas,
+				max_priority_fee_per_gas,
+				nonce,
+				Vec::new(),
+				config.as_ref().unwrap_or_else(|| <Runtime as pallet_evm::Config>::config()),
+			)
+			.map_err(|err| err.into())
+		}
+
+		fn create(
+			from: H160,
+			data: Vec<u8>,
+			value: U256,
+			gas_limit: U256,
+			max_fee_per_gas: Option<U256>,
+			max_priority_fee_per_gas: Option<U256>,
+			nonce: Option<U256>,
+			estimate: bool,
+		) -> Result<pallet_evm::CreateInfo, sp_runtime::DispatchError> {
+			use pallet_evm::Runner;
+
+			let config = if estimate {
+				let mut config = <Runtime as pallet_evm::Config>::config().clone();
+				config.estimate = true;
+				Some(config)
+			} else {
+				None
+			};
+
+			<Runtime as pallet_evm::Config>::Runner::create(
+				from,
+				data,
+				value,
+				gas_limit.low_u64(),
+				max_fee_per_gas,
+				max_priority_fee_per_gas,
+				nonce,
+				Vec::new(),
+				config.as_ref().unwrap_or_else(|| <Runtime as pallet_evm::Config>::config()),
+			)
+			.map_err(|err| err.into())
+		}
+
+		fn current_transaction_statuses() -> Option<Vec<fp_rpc::TransactionStatus>> {
+			Ethereum::current_transaction_statuses()
+		}
+
+		fn current_block() -> Option<pallet_ethereum::Block> {
+			Ethereum::current_block()
+		}
+
+		fn current_receipts() -> Option<Vec<pallet_ethereum::Receipt>> {
+			Ethereum::current_receipts()
+		}
+
+		fn current_all() -> (
+			Option<pallet_ethereum::Block>,
+			Option<Vec<pallet_ethereum::Receipt>>,
+			Option<Vec<fp_rpc::TransactionStatus>>,
+		) {
+			(
+				Ethereum::current_block(),
+				Ethereum::current_receipts(),
+				Ethereum::current_transaction_statuses(),
+			)
+		}
+
+		fn extrinsic_filter(
+			xts: Vec<<Block as BlockT>::Extrinsic>,
+		) -> Vec<pallet_ethereum::Transaction> {
+			xts.into_iter()
+				.filter_map(|xt| match xt.0.function {
+					Call::Ethereum(pallet_ethereum::Call::transact { transaction }) => Some(transaction),
+					_ => None,
+				})
+				.collect()
+		}
+
+		fn elasticity() -> Option<Permill> {
+			Some(BaseFee::elasticity())
+		}
+	}
+
+	impl fp_rpc::ConvertTransactionRuntimeApi<Block> for Runtime {
+		fn convert_transaction(
+			transaction: pallet_ethereum::Transaction,
+		) -> <Block as BlockT>::Extrinsic {
+			UncheckedExtrinsic::new_unsigned(
+				pallet_ethereum::Call::<Runtime>::transact { transaction }.into(),
+			)
+		}
+	}
+
 	impl cumulus_primitives_core::CollectCollationInfo<Block> for Runtime {
 		fn collect_collation_info(header: &<Block as BlockT>::Header) -> cumulus_primitives_core::CollationInfo {
 			ParachainSystem::collect_collation_info(header)
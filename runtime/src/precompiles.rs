@@ -0,0 +1,247 @@
+//! EVM precompiles of the runtime.
+//!
+//! Next to the Ethereum ones, the runtime exposes `Balances` at `0x800` and `pallet_template` at
+//! `0x801`. Both speak the Solidity ABI, so dApps can call them through a regular interface:
+//!
+//! ```solidity
+//! interface Balances {
+//!     function totalSupply() external view returns (uint256);
+//!     function balanceOf(address who) external view returns (uint256);
+//!     function transfer(address to, uint256 value) external returns (bool);
+//! }
+//!
+//! interface Template {
+//!     function something() external view returns (uint256);
+//!     function doSomething(uint256 value) external;
+//! }
+//! ```
+//!
+//! Addresses are mapped to accounts with the runtime's `pallet_evm::Config::AddressMapping`, and
+//! calls changing state are dispatched signed by the caller's account.
+
+use super::{Balances, Call, Runtime};
+use frame_support::{
+	traits::{Currency, Get},
+	weights::{GetDispatchInfo, Weight},
+};
+use pallet_evm::{
+	AddressMapping, ExitRevert, ExitSucceed, GasWeightMapping, Precompile, PrecompileFailure,
+	PrecompileHandle, PrecompileOutput, PrecompileResult, PrecompileSet,
+};
+use pallet_evm_precompile_modexp::Modexp;
+use pallet_evm_precompile_sha3fips::Sha3FIPS256;
+use pallet_evm_precompile_simple::{ECRecover, ECRecoverPublicKey, Identity, Ripemd160, Sha256};
+use sp_core::{H160, U256};
+use sp_runtime::{traits::Dispatchable, MultiAddress};
+use sp_std::{prelude::*, vec};
+
+/// `balanceOf(address)`
+const SELECTOR_BALANCE_OF: [u8; 4] = [0x70, 0xa0, 0x82, 0x31];
+/// `totalSupply()`
+const SELECTOR_TOTAL_SUPPLY: [u8; 4] = [0x18, 0x16, 0x0d, 0xdd];
+/// `transfer(address,uint256)`
+const SELECTOR_TRANSFER: [u8; 4] = [0xa9, 0x05, 0x9c, 0xbb];
+/// `something()`
+const SELECTOR_SOMETHING: [u8; 4] = [0xa7, 0xa0, 0xd5, 0x37];
+/// `doSomething(uint256)`
+const SELECTOR_DO_SOMETHING: [u8; 4] = [0xa6, 0xb2, 0x06, 0xbf];
+
+/// The precompiles of the runtime.
+#[derive(Default)]
+pub struct FrontierPrecompiles;
+
+impl FrontierPrecompiles {
+	/// Addresses of all precompiles.
+	pub fn used_addresses() -> [H160; 9] {
+		[
+			hash(1),
+			hash(2),
+			hash(3),
+			hash(4),
+			hash(5),
+			hash(1024),
+			hash(1025),
+			hash(2048),
+			hash(2049),
+		]
+	}
+}
+
+impl PrecompileSet for FrontierPrecompiles {
+	fn execute(&self, handle: &mut impl PrecompileHandle) -> Option<PrecompileResult> {
+		let address = handle.code_address();
+		// The runtime precompiles act on behalf of the caller, which must not be spoofed through
+		// `DELEGATECALL` or `CALLCODE`.
+		if address >= hash(2048) &&
+			self.is_precompile(address) &&
+			handle.context().address != address
+		{
+			return Some(Err(revert("cannot be called with DELEGATECALL or CALLCODE")))
+		}
+
+		match address {
+			// Ethereum precompiles.
+			a if a == hash(1) => Some(ECRecover::execute(handle)),
+			a if a == hash(2) => Some(Sha256::execute(handle)),
+			a if a == hash(3) => Some(Ripemd160::execute(handle)),
+			a if a == hash(4) => Some(Identity::execute(handle)),
+			a if a == hash(5) => Some(Modexp::execute(handle)),
+			// Non-Frontier specific nor Ethereum precompiles.
+			a if a == hash(1024) => Some(Sha3FIPS256::execute(handle)),
+			a if a == hash(1025) => Some(ECRecoverPublicKey::execute(handle)),
+			// Runtime precompiles.
+			a if a == hash(2048) => Some(BalancesPrecompile::execute(handle)),
+			a if a == hash(2049) => Some(TemplatePrecompile::execute(handle)),
+			_ => None,
+		}
+	}
+
+	fn is_precompile(&self, address: H160) -> bool {
+		Self::used_addresses().contains(&address)
+	}
+}
+
+/// ERC-20 like access to the native balances.
+pub struct BalancesPrecompile;
+
+impl Precompile for BalancesPrecompile {
+	fn execute(handle: &mut impl PrecompileHandle) -> PrecompileResult {
+		let input = handle.input().to_vec();
+		match selector(&input)? {
+			SELECTOR_TOTAL_SUPPLY => {
+				record_weight(handle, db_reads(1))?;
+				succeed(encode_u256(Balances::total_issuance().into()))
+			},
+			SELECTOR_BALANCE_OF => {
+				let who = read_address(&input, 0)?;
+				record_weight(handle, db_reads(1))?;
+				let balance = Balances::free_balance(&account_of(who));
+				succeed(encode_u256(balance.into()))
+			},
+			SELECTOR_TRANSFER => {
+				let to = read_address(&input, 0)?;
+				let value = read_u256(&input, 1)?;
+				let value =
+					u128::try_from(value).map_err(|_| revert("value does not fit a balance"))?;
+				dispatch(
+					handle,
+					pallet_balances::Call::<Runtime>::transfer {
+						dest: MultiAddress::Id(account_of(to)),
+						value,
+					}
+					.into(),
+				)?;
+				succeed(encode_u256(U256::one()))
+			},
+			_ => Err(revert("unknown selector")),
+		}
+	}
+}
+
+/// Access to the `pallet_template` storage value.
+pub struct TemplatePrecompile;
+
+impl Precompile for TemplatePrecompile {
+	fn execute(handle: &mut impl PrecompileHandle) -> PrecompileResult {
+		let input = handle.input().to_vec();
+		match selector(&input)? {
+			SELECTOR_SOMETHING => {
+				record_weight(handle, db_reads(1))?;
+				let something = pallet_template::Pallet::<Runtime>::something().unwrap_or_default();
+				succeed(encode_u256(something.into()))
+			},
+			SELECTOR_DO_SOMETHING => {
+				let something = read_u256(&input, 0)?;
+				if something > U256::from(u32::MAX) {
+					return Err(revert("value does not fit a u32"))
+				}
+				dispatch(
+					handle,
+					pallet_template::Call::<Runtime>::do_something {
+						something: something.low_u32(),
+					}
+					.into(),
+				)?;
+				succeed(Vec::new())
+			},
+			_ => Err(revert("unknown selector")),
+		}
+	}
+}
+
+fn hash(a: u64) -> H160 {
+	H160::from_low_u64_be(a)
+}
+
+/// The account `address` is mapped to.
+fn account_of(address: H160) -> <Runtime as frame_system::Config>::AccountId {
+	<Runtime as pallet_evm::Config>::AddressMapping::into_account_id(address)
+}
+
+fn db_reads(reads: u64) -> Weight {
+	<Runtime as frame_system::Config>::DbWeight::get().reads(reads)
+}
+
+/// Charge the gas equivalent of `weight`.
+fn record_weight(
+	handle: &mut impl PrecompileHandle,
+	weight: Weight,
+) -> Result<(), PrecompileFailure> {
+	handle
+		.record_cost(<Runtime as pallet_evm::Config>::GasWeightMapping::weight_to_gas(weight))
+		.map_err(|exit_status| PrecompileFailure::Error { exit_status })
+}
+
+/// Dispatch `call` signed by the caller's account, charging its weight as gas.
+fn dispatch(handle: &mut impl PrecompileHandle, call: Call) -> Result<(), PrecompileFailure> {
+	if handle.is_static() {
+		return Err(revert("cannot modify state in a static call"))
+	}
+	if !handle.context().apparent_value.is_zero() {
+		return Err(revert("function is not payable"))
+	}
+
+	record_weight(handle, call.get_dispatch_info().weight)?;
+	let origin = account_of(handle.context().caller);
+	call.dispatch(Some(origin).into())
+		.map(|_| ())
+		.map_err(|e| revert(<&'static str>::from(e.error)))
+}
+
+fn selector(input: &[u8]) -> Result<[u8; 4], PrecompileFailure> {
+	input
+		.get(0..4)
+		.and_then(|selector| selector.try_into().ok())
+		.ok_or_else(|| revert("input too short"))
+}
+
+/// The 32 byte argument at `index`.
+fn read_word(input: &[u8], index: usize) -> Result<&[u8], PrecompileFailure> {
+	let start = 4 + index * 32;
+	input.get(start..start + 32).ok_or_else(|| revert("input too short"))
+}
+
+fn read_address(input: &[u8], index: usize) -> Result<H160, PrecompileFailure> {
+	Ok(H160::from_slice(&read_word(input, index)?[12..]))
+}
+
+fn read_u256(input: &[u8], index: usize) -> Result<U256, PrecompileFailure> {
+	Ok(U256::from_big_endian(read_word(input, index)?))
+}
+
+fn encode_u256(value: U256) -> Vec<u8> {
+	let mut output = vec![0u8; 32];
+	value.to_big_endian(&mut output);
+	output
+}
+
+fn succeed(output: Vec<u8>) -> PrecompileResult {
+	Ok(PrecompileOutput { exit_status: ExitSucceed::Returned, output })
+}
+
+fn revert(message: &str) -> PrecompileFailure {
+	PrecompileFailure::Revert {
+		exit_status: ExitRevert::Reverted,
+		output: message.as_bytes().to_vec(),
+	}
+}
@@ -1,31 +1,60 @@
 use super::{
-	AccountId, Balances, Call, Event, Origin, ParachainInfo, ParachainSystem, PolkadotXcm, Runtime,
-	WeightToFee, XcmpQueue,
+	AccountId, AssetId, Assets, Balance, Balances, BlockNumber, Call, DmpQueue, Event,
+	HrmpChannels, Origin, ParachainInfo, ParachainSystem, PolkadotXcm, Runtime, Uniques,
+	WeightToFee, XcmAssetTraps, XcmCollections, XcmOutcomes, XcmReserves, XcmpQueue, HOURS,
+	MICROUNIT, MILLIUNIT, MINUTES,
 };
 use core::marker::PhantomData;
 use frame_support::{
-	log, match_types, parameter_types,
-	traits::{Everything, Nothing},
-	weights::Weight,
+	match_types, parameter_types,
+	traits::{Contains, Everything, Get, Nothing, PalletInfoAccess},
+	weights::{constants::WEIGHT_PER_SECOND, Weight, WeightToFeePolynomial},
 };
+use frame_system::EnsureRoot;
 use pallet_xcm::XcmPassthrough;
+use pallet_xcm_collections::RestrictedExports;
+use pallet_xcm_rate_limiter::{LimitedRouter, LimitedTransactor};
+use pallet_xcm_transact_filter::FilteredTransactOrigin;
+use pallet_xcm_trusted_siblings::AllowUnpaidExecutionFromTrusted;
 use polkadot_parachain::primitives::Sibling;
 use polkadot_runtime_common::impls::ToAuthor;
+use sp_std::prelude::*;
 use xcm::latest::prelude::*;
+use xcm_accounts::{SiblingAccountId32Hash, SiblingAccountKey20Hash};
 use xcm_builder::{
-	AccountId32Aliases, AllowTopLevelPaidExecutionFrom, AllowUnpaidExecutionFrom, CurrencyAdapter,
-	EnsureXcmOrigin, FixedWeightBounds, IsConcrete, LocationInverter, NativeAsset, ParentIsPreset,
-	RelayChainAsNative, SiblingParachainAsNative, SiblingParachainConvertsVia,
+	AccountId32Aliases, AllowKnownQueryResponses, AllowTopLevelPaidExecutionFrom,
+	AllowUnpaidExecutionFrom, CurrencyAdapter, EnsureXcmOrigin, FixedRateOfFungible,
+	FixedWeightBounds, FungiblesAdapter, IsConcrete, LocationInverter, NonFungiblesAdapter,
+	ParentIsPreset, RelayChainAsNative, SiblingParachainAsNative, SiblingParachainConvertsVia,
 	SignedAccountId32AsNative, SignedToAccountId32, SovereignSignedViaLocation, TakeWeightCredit,
 	UsingComponents,
 };
-use xcm_executor::{traits::ShouldExecute, XcmExecutor};
+use xcm_dry_run::RecordingRouter;
+use xcm_executor::{
+	traits::{Convert, Error as MatchError, FilterAssetLocation, MatchesFungibles, ShouldExecute},
+	XcmExecutor,
+};
 
 parameter_types! {
 	pub const RelayLocation: MultiLocation = MultiLocation::parent();
 	pub const RelayNetwork: NetworkId = NetworkId::Any;
 	pub RelayChainOrigin: Origin = cumulus_pallet_xcm::Origin::Relay.into();
 	pub Ancestry: MultiLocation = Parachain(ParachainInfo::parachain_id().into()).into();
+	/// The native token, addressed by the `Balances` pallet instance. Siblings see it as
+	/// `../Parachain(id)/PalletInstance(10)`.
+	pub SelfReserve: MultiLocation =
+		PalletInstance(<Balances as PalletInfoAccess>::index() as u8).into();
+	/// Our NFT collections, addressed by the `Uniques` pallet instance, followed by the
+	/// `GeneralIndex` of the collection.
+	pub UniquesLocation: MultiLocation =
+		PalletInstance(<Uniques as PalletInfoAccess>::index() as u8).into();
+	/// The relay chain token is held in `Assets` under this id.
+	pub const RelayAssetId: AssetId = 0;
+	/// The owner of the relay chain token asset: the sovereign account of the relay chain.
+	pub RelayAssetOwner: AccountId = ParentIsPreset::<AccountId>::convert(RelayLocation::get())
+		.expect("the relay chain has a sovereign account; qed");
+	/// The account teleported assets are checked against, unused while teleports are disabled.
+	pub CheckingAccount: AccountId = PolkadotXcm::check_account();
 }
 
 /// Type for specifying how a `MultiLocation` can be converted into an `AccountId`. This is used
@@ -38,14 +67,42 @@ pub type LocationToAccountId = (
 	SiblingParachainConvertsVia<Sibling, AccountId>,
 	// Straight up local `AccountId32` origins just alias directly to `AccountId`.
 	AccountId32Aliases<RelayNetwork, AccountId>,
+	// Local `AccountKey20` origins convert to the account their H160 address owns.
+	AccountKey20Unified<RelayNetwork>,
+	// Users of sibling parachains convert to the hash of their location's description.
+	SiblingAccountId32Hash<AccountId>,
+	SiblingAccountKey20Hash<AccountId>,
 );
 
-/// Means for transacting assets on this chain.
-pub type LocalAssetTransactor = CurrencyAdapter<
+/// Converts local `AccountKey20` locations into the accounts their H160 addresses own, the same
+/// accounts the EVM uses for them. See `unified_accounts`.
+pub struct AccountKey20Unified<Network>(PhantomData<Network>);
+
+impl<Network: Get<NetworkId>> Convert<MultiLocation, AccountId> for AccountKey20Unified<Network> {
+	fn convert(location: MultiLocation) -> Result<AccountId, MultiLocation> {
+		match location {
+			MultiLocation { parents: 0, interior: X1(AccountKey20 { key, network }) }
+				if network == NetworkId::Any || network == Network::get() =>
+				Ok(unified_accounts::account_of(key.into())),
+			location => Err(location),
+		}
+	}
+
+	fn reverse(who: AccountId) -> Result<MultiLocation, AccountId> {
+		match unified_accounts::address_of(&who) {
+			Some(address) =>
+				Ok(AccountKey20 { network: Network::get(), key: address.into() }.into()),
+			None => Err(who),
+		}
+	}
+}
+
+/// Means for transacting the native token.
+pub type NativeAssetTransactor = CurrencyAdapter<
 	// Use this currency:
 	Balances,
 	// Use this currency when it is a fungible asset matching the given location or name:
-	IsConcrete<RelayLocation>,
+	IsConcrete<SelfReserve>,
 	// Do a simple punn to convert an AccountId32 MultiLocation into a native chain account ID:
 	LocationToAccountId,
 	// Our chain's account ID type (we can't get away without mentioning it explicitly):
@@ -54,10 +111,64 @@ pub type LocalAssetTransactor = CurrencyAdapter<
 	(),
 >;
 
-/// This is the type we use to convert an (incoming) XCM origin into a local `Origin` instance,
-/// ready for dispatching a transaction with Xcm's `Transact`. There is an `OriginKind` which can
-/// biases the kind of local `Origin` it will become.
-pub type XcmOriginToTransactDispatchOrigin = (
+/// Means for transacting NFTs, held in `Uniques`: the items of our own collections, and the
+/// derivatives of foreign collections. Only the collections governance allows may leave the chain.
+pub type UniquesTransactor = RestrictedExports<
+	Runtime,
+	NonFungiblesAdapter<
+		// Use this non-fungibles implementation:
+		Uniques,
+		// Use this collection when it is ours, or the derivative of a foreign one:
+		XcmCollections,
+		// Convert an XCM MultiLocation into a local account id:
+		LocationToAccountId,
+		// Our chain's account ID type (we can't get away without mentioning it explicitly):
+		AccountId,
+		// We don't track any teleports.
+		Nothing,
+		(),
+	>,
+>;
+
+/// Means for transacting the assets we are the reserve of, and the derivatives of foreign NFTs.
+pub type LocalAssetTransactor = (NativeAssetTransactor, UniquesTransactor);
+
+/// Matches the relay chain token to the `RelayAssetId` asset.
+pub struct RelayTokenAsAsset;
+
+impl MatchesFungibles<AssetId, Balance> for RelayTokenAsAsset {
+	fn matches_fungibles(asset: &MultiAsset) -> Result<(AssetId, Balance), MatchError> {
+		match (&asset.id, &asset.fun) {
+			(Concrete(location), Fungible(amount)) if *location == RelayLocation::get() =>
+				Ok((RelayAssetId::get(), *amount)),
+			_ => Err(MatchError::AssetNotFound),
+		}
+	}
+}
+
+/// Means for transacting the relay chain token, held in `Assets`.
+pub type RelayAssetTransactor = FungiblesAdapter<
+	// Use this fungibles implementation:
+	Assets,
+	// Use this asset when it is the relay chain token:
+	RelayTokenAsAsset,
+	// Convert an XCM MultiLocation into a local account id:
+	LocationToAccountId,
+	// Our chain's account ID type (we can't get away without mentioning it explicitly):
+	AccountId,
+	// We don't track any teleports.
+	Nothing,
+	CheckingAccount,
+>;
+
+/// Means for transacting assets on this chain. Assets stop moving to other chains while outflows
+/// are suspended.
+pub type AssetTransactors =
+	LimitedTransactor<Runtime, (LocalAssetTransactor, RelayAssetTransactor)>;
+
+/// This is the type we use to convert an (incoming) XCM origin into a local `Origin` instance.
+/// There is an `OriginKind` which can biases the kind of local `Origin` it will become.
+pub type XcmOriginConverter = (
 	// Sovereign account converter; this attempts to derive an `AccountId` from the origin location
 	// using `LocationToAccountId` and then turn that into the usual `Signed` origin. Useful for
 	// foreign chains who want to have a local sovereign account on this chain which they control.
@@ -75,10 +186,17 @@ pub type XcmOriginToTransactDispatchOrigin = (
 	XcmPassthrough<Origin>,
 );
 
+/// Converts an (incoming) XCM origin into a local `Origin` ready for dispatching a transaction
+/// with Xcm's `Transact`, restricted to the calls `TransactFilter` allows for its kind.
+pub type XcmOriginToTransactDispatchOrigin = FilteredTransactOrigin<Runtime, XcmOriginConverter>;
+
 parameter_types! {
 	// One XCM operation is 1_000_000_000 weight - almost certainly a conservative estimate.
 	pub UnitWeightCost: Weight = 1_000_000_000;
 	pub const MaxInstructions: u32 = 100;
+	/// Execution bought with the relay chain token costs as many plancks as it would in `UNIT`.
+	pub RelayPerSecond: (xcm::latest::AssetId, u128) =
+		(Concrete(RelayLocation::get()), WeightToFee::calc(&WEIGHT_PER_SECOND));
 }
 
 match_types! {
@@ -121,31 +239,34 @@ impl ShouldExecute for DenyReserveTransferToRelayChain {
 		_max_weight: Weight,
 		_weight_credit: &mut Weight,
 	) -> Result<(), ()> {
-		if message.0.iter().any(|inst| {
-			matches!(
-				inst,
-				InitiateReserveWithdraw {
-					reserve: MultiLocation { parents: 1, interior: Here },
-					..
-				} | DepositReserveAsset { dest: MultiLocation { parents: 1, interior: Here }, .. } |
-					TransferReserveAsset {
-						dest: MultiLocation { parents: 1, interior: Here },
-						..
+		let relay = MultiLocation::parent();
+		let is_relay_token = |asset: &MultiAsset| asset.id == Concrete(relay.clone());
+		// Whether the holding has only the relay chain token, as when our users withdraw it.
+		let mut holds_relay_token = true;
+		for inst in message.0.iter() {
+			match inst {
+				// Local origins are our users, whose transfers send the relay chain token back to
+				// its reserve.
+				InitiateReserveWithdraw { assets, reserve, .. } if *reserve == relay => {
+					let relay_token_only = match assets {
+						Definite(assets) => assets.inner().iter().all(is_relay_token),
+						Wild(AllOf { id, .. }) => *id == Concrete(relay.clone()),
+						Wild(All) => holds_relay_token,
+					};
+					if origin.parents != 0 || !relay_token_only {
+						return Err(()) // Deny
 					}
-			)
-		}) {
-			return Err(()) // Deny
+				},
+				DepositReserveAsset { dest, .. } | TransferReserveAsset { dest, .. }
+					if *dest == relay =>
+					return Err(()), // Deny
+				WithdrawAsset(assets) =>
+					holds_relay_token &= assets.inner().iter().all(is_relay_token),
+				_ => holds_relay_token = false,
+			}
 		}
 
-		// allow reserve transfers to arrive from relay chain
-		if matches!(origin, MultiLocation { parents: 1, interior: Here }) &&
-			message.0.iter().any(|inst| matches!(inst, ReserveAssetDeposited { .. }))
-		{
-			log::warn!(
-				target: "xcm::barriers",
-				"Unexpected ReserveAssetDeposited from the relay chain",
-			);
-		}
+		// Reserve deposits are checked against the trusted reserves by `IsReserve`.
 		// Permit everything else
 		Ok(())
 	}
@@ -158,25 +279,60 @@ pub type Barrier = DenyThenTry<
 		AllowTopLevelPaidExecutionFrom<Everything>,
 		AllowUnpaidExecutionFrom<ParentOrParentsExecutivePlurality>,
 		// ^^^ Parent and its exec plurality get free execution
+		// Siblings, and bodies on them, trusted by governance too.
+		AllowUnpaidExecutionFromTrusted<Runtime>,
+		// Expected responses, such as the outcomes of our messages, are free too.
+		AllowKnownQueryResponses<PolkadotXcm>,
 	),
 >;
 
+/// The relay chain is always trusted as reserve of its own token. We are the reserve of our
+/// native token, which siblings hold on our behalf and send back with `WithdrawAsset`.
+pub struct RelayTokenFromRelay;
+
+impl FilterAssetLocation for RelayTokenFromRelay {
+	fn filter_asset_location(asset: &MultiAsset, origin: &MultiLocation) -> bool {
+		let relay = RelayLocation::get();
+		*origin == relay && matches!(&asset.id, Concrete(location) if *location == relay)
+	}
+}
+
+/// Only the native token and the items of our own NFT collections may be reserve transferred out
+/// of the chain.
+pub struct OnlySelfReserve;
+
+impl Contains<(MultiLocation, Vec<MultiAsset>)> for OnlySelfReserve {
+	fn contains((_, assets): &(MultiLocation, Vec<MultiAsset>)) -> bool {
+		let native = SelfReserve::get();
+		assets.iter().all(|asset| match (&asset.id, &asset.fun) {
+			(Concrete(location), Fungible(_)) => *location == native,
+			(Concrete(location), NonFungible(_)) => XcmCollections::local_class(location).is_some(),
+			_ => false,
+		})
+	}
+}
+
 pub struct XcmConfig;
 impl xcm_executor::Config for XcmConfig {
 	type Call = Call;
 	type XcmSender = XcmRouter;
 	// How to withdraw and deposit an asset.
-	type AssetTransactor = LocalAssetTransactor;
+	type AssetTransactor = AssetTransactors;
 	type OriginConverter = XcmOriginToTransactDispatchOrigin;
-	type IsReserve = NativeAsset;
+	// Any other asset must come from one of the reserves governance registered for it.
+	type IsReserve = (RelayTokenFromRelay, XcmReserves);
 	type IsTeleporter = (); // Teleporting is disabled.
 	type LocationInverter = LocationInverter<Ancestry>;
 	type Barrier = Barrier;
 	type Weigher = FixedWeightBounds<UnitWeightCost, Call, MaxInstructions>;
-	type Trader =
-		UsingComponents<WeightToFee, RelayLocation, AccountId, Balances, ToAuthor<Runtime>>;
+	type Trader = (
+		UsingComponents<WeightToFee, SelfReserve, AccountId, Balances, ToAuthor<Runtime>>,
+		// The relay chain token paid for execution is burnt.
+		FixedRateOfFungible<RelayPerSecond, ()>,
+	);
 	type ResponseHandler = PolkadotXcm;
-	type AssetTrap = PolkadotXcm;
+	// Trapped assets are recorded, then trapped by `PolkadotXcm`, which lets them be claimed.
+	type AssetTrap = XcmAssetTraps;
 	type AssetClaims = PolkadotXcm;
 	type SubscriptionService = PolkadotXcm;
 }
@@ -185,13 +341,18 @@ impl xcm_executor::Config for XcmConfig {
 pub type LocalOriginToLocation = SignedToAccountId32<Origin, AccountId, RelayNetwork>;
 
 /// The means for routing XCM messages which are not for local execution into the right message
-/// queues.
-pub type XcmRouter = (
-	// Two routers - use UMP to communicate with the relay chain:
-	cumulus_primitives_utility::ParentAsUmp<ParachainSystem, ()>,
-	// ..and XCMP to communicate with the sibling chains.
-	XcmpQueue,
-);
+/// queues. Messages routed during dry runs are recorded, the assets they carry out are limited.
+pub type XcmRouter = RecordingRouter<
+	LimitedRouter<
+		Runtime,
+		(
+			// Two routers - use UMP to communicate with the relay chain:
+			cumulus_primitives_utility::ParentAsUmp<ParachainSystem, ()>,
+			// ..and XCMP to communicate with the sibling chains.
+			XcmpQueue,
+		),
+	>,
+>;
 
 impl pallet_xcm::Config for Runtime {
 	type Event = Event;
@@ -203,7 +364,7 @@ impl pallet_xcm::Config for Runtime {
 	// Needs to be `Everything` for local testing.
 	type XcmExecutor = XcmExecutor<XcmConfig>;
 	type XcmTeleportFilter = Everything;
-	type XcmReserveTransferFilter = Nothing;
+	type XcmReserveTransferFilter = OnlySelfReserve;
 	type Weigher = FixedWeightBounds<UnitWeightCost, Call, MaxInstructions>;
 	type LocationInverter = LocationInverter<Ancestry>;
 	type Origin = Origin;
@@ -214,6 +375,142 @@ impl pallet_xcm::Config for Runtime {
 	type AdvertisedXcmVersion = pallet_xcm::CurrentXcmVersion;
 }
 
+parameter_types! {
+	pub const OutcomeTimeout: BlockNumber = 10 * MINUTES;
+	pub const OutcomeRetention: BlockNumber = HOURS;
+}
+
+impl pallet_xcm_outcomes::Config for Runtime {
+	type Event = Event;
+	type Origin = Origin;
+	type Call = Call;
+	type OutcomeTimeout = OutcomeTimeout;
+	type OutcomeRetention = OutcomeRetention;
+	type OnOutcome = HrmpChannels;
+}
+
+parameter_types! {
+	/// A claim and a deposit, for every asset of a trap.
+	pub RefundWeight: Weight = 2 * UnitWeightCost::get();
+}
+
+impl pallet_xcm_asset_traps::Config for Runtime {
+	type Event = Event;
+	type UpdateOrigin = EnsureRoot<AccountId>;
+	type AssetTransactor = AssetTransactors;
+	type RefundWeight = RefundWeight;
+}
+
+impl pallet_xcm_overweight::Config for Runtime {
+	type Event = Event;
+	type UpdateOrigin = EnsureRoot<AccountId>;
+	type Call = Call;
+	type Weigher = FixedWeightBounds<UnitWeightCost, Call, MaxInstructions>;
+	// Both queues execute overweight messages for root.
+	type XcmpQueue = pallet_xcm_overweight::XcmpOverweight<Runtime>;
+	type DmpQueue = pallet_xcm_overweight::DmpOverweight<Runtime>;
+}
+
+parameter_types! {
+	pub const OutflowWindow: BlockNumber = HOURS;
+}
+
+impl pallet_xcm_rate_limiter::Config for Runtime {
+	type Event = Event;
+	type UpdateOrigin = EnsureRoot<AccountId>;
+	type LocationInverter = LocationInverter<Ancestry>;
+	type Window = OutflowWindow;
+}
+
+parameter_types! {
+	pub const MaxTrustedReserves: u32 = 8;
+}
+
+/// Receiving a foreign collection takes two steps of governance: `XcmCollections` registers the
+/// local collection minting its derivatives, and `XcmReserves` trusts the chain holding it as its
+/// reserve. Its items are sent unpaid, so the chain must be one of `XcmTrustedSiblings` too.
+impl pallet_xcm_collections::Config for Runtime {
+	type Event = Event;
+	type UpdateOrigin = EnsureRoot<AccountId>;
+	type ClassId = u32;
+	type InstanceId = u32;
+	type CollectionsLocation = UniquesLocation;
+}
+
+impl pallet_xcm_reserves::Config for Runtime {
+	type Event = Event;
+	type UpdateOrigin = EnsureRoot<AccountId>;
+	type MaxReserves = MaxTrustedReserves;
+}
+
+impl pallet_xcm_transact_filter::Config for Runtime {
+	type Event = Event;
+	type UpdateOrigin = EnsureRoot<AccountId>;
+}
+
+impl pallet_xcm_trusted_siblings::Config for Runtime {
+	type Event = Event;
+	type UpdateOrigin = EnsureRoot<AccountId>;
+}
+
+parameter_types! {
+	/// Index of the `Hrmp` pallet in the Rococo, Kusama and Polkadot runtimes.
+	pub const RelayHrmpPalletIndex: u8 = 60;
+	/// One relay chain token, at 12 decimals, for the fees of each HRMP request.
+	pub const RelayHrmpFee: u128 = 1_000_000_000_000;
+	pub const RelayHrmpCallWeight: Weight = 1_000_000_000;
+	/// Witness of the cancellations, above the channel requests pending on the relay chain.
+	pub const RelayHrmpMaxOpenRequests: u32 = 100;
+}
+
+impl pallet_hrmp_channels::Config for Runtime {
+	type Event = Event;
+	type UpdateOrigin = EnsureRoot<AccountId>;
+	type SelfParaId = ParachainInfo;
+	type XcmSender = XcmRouter;
+	type ChannelsState = ParachainSystem;
+	type HrmpPalletIndex = RelayHrmpPalletIndex;
+	type RelayFee = RelayHrmpFee;
+	type RelayCallWeight = RelayHrmpCallWeight;
+	type MaxOpenRequests = RelayHrmpMaxOpenRequests;
+	type Outcomes = XcmOutcomes;
+}
+
+/// Users are sent XCM as `AccountId32` junctions of the relay chain network.
+pub struct AccountIdToMultiLocation;
+
+impl sp_runtime::traits::Convert<AccountId, MultiLocation> for AccountIdToMultiLocation {
+	fn convert(account: AccountId) -> MultiLocation {
+		AccountId32 { network: RelayNetwork::get(), id: account.into() }.into()
+	}
+}
+
+parameter_types! {
+	/// Our location, as seen from our siblings.
+	pub SelfLocation: MultiLocation =
+		MultiLocation::new(1, X1(Parachain(ParachainInfo::parachain_id().into())));
+	pub const TransferDeliveryFeeBase: Balance = MILLIUNIT;
+	pub const TransferDeliveryFeePerByte: Balance = 10 * MICROUNIT;
+	pub const MaxAssetsForTransfer: u32 = 2;
+}
+
+impl pallet_xcm_transfers::Config for Runtime {
+	type Event = Event;
+	type Currency = Balances;
+	type DeliveryFeeBase = TransferDeliveryFeeBase;
+	type DeliveryFeePerByte = TransferDeliveryFeePerByte;
+	type OnDeliveryFee = ToAuthor<Runtime>;
+	type AccountIdToMultiLocation = AccountIdToMultiLocation;
+	type SelfLocation = SelfLocation;
+	type Teleports = (); // Teleporting is disabled.
+	type XcmExecutor = XcmExecutor<XcmConfig>;
+	type Weigher = FixedWeightBounds<UnitWeightCost, Call, MaxInstructions>;
+	type BaseXcmWeight = UnitWeightCost;
+	type LocationInverter = LocationInverter<Ancestry>;
+	type MaxAssetsForTransfer = MaxAssetsForTransfer;
+	type Outcomes = XcmOutcomes;
+}
+
 impl cumulus_pallet_xcm::Config for Runtime {
 	type Event = Event;
 	type XcmExecutor = XcmExecutor<XcmConfig>;
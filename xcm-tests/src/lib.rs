@@ -0,0 +1,147 @@
+//! XCM integration tests of the parachain configuration, run in a simulated network of a relay
+//! chain and two parachains: `ParaA` runs `parachain-template-runtime`, with its XCM configuration
+//! and message queues, `ParaB` is a mock sibling sending it messages.
+//!
+//! Messages `ParaA` sends are not delivered: those to the relay chain are left in the upward queue
+//! of `ParachainSystem`, see [`upward_messages`], those to siblings find no HRMP channel.
+#![cfg(test)]
+
+mod parachain;
+mod relay_chain;
+mod tests;
+
+use codec::Decode;
+use frame_support::{storage, traits::GenesisBuild};
+use polkadot_parachain::primitives::{Id as ParaId, Sibling};
+use sp_runtime::traits::AccountIdConversion;
+use xcm::{latest::Xcm, VersionedXcm};
+use xcm_simulator::{decl_test_network, decl_test_parachain, decl_test_relay_chain};
+
+pub const ALICE: sp_runtime::AccountId32 = sp_runtime::AccountId32::new([1u8; 32]);
+pub const INITIAL_BALANCE: u128 = 1_000 * parachain_template_runtime::UNIT;
+
+decl_test_parachain! {
+	pub struct ParaA {
+		Runtime = parachain_template_runtime::Runtime,
+		XcmpMessageHandler = parachain_template_runtime::XcmpQueue,
+		DmpMessageHandler = parachain_template_runtime::DmpQueue,
+		new_ext = para_a_ext(),
+	}
+}
+
+decl_test_parachain! {
+	pub struct ParaB {
+		Runtime = parachain::Runtime,
+		XcmpMessageHandler = parachain::MsgQueue,
+		DmpMessageHandler = parachain::MsgQueue,
+		new_ext = para_b_ext(),
+	}
+}
+
+decl_test_relay_chain! {
+	pub struct Relay {
+		Runtime = relay_chain::Runtime,
+		XcmConfig = relay_chain::XcmConfig,
+		new_ext = relay_ext(),
+	}
+}
+
+decl_test_network! {
+	pub struct MockNet {
+		relay_chain = Relay,
+		parachains = vec![
+			(1, ParaA),
+			(2, ParaB),
+		],
+	}
+}
+
+/// The account of the parachain `id` on the relay chain.
+pub fn para_account_id(id: u32) -> relay_chain::AccountId {
+	ParaId::from(id).into_account()
+}
+
+/// The sovereign account of the sibling parachain `id` on `ParaA`.
+pub fn sibling_account_id(id: u32) -> parachain_template_runtime::AccountId {
+	Sibling::from(id).into_account()
+}
+
+/// The messages `ParaA` sent to the relay chain, still in the upward queue of `ParachainSystem`.
+pub fn upward_messages() -> Vec<Xcm<()>> {
+	// The queue has no getter.
+	let key = storage::storage_prefix(b"ParachainSystem", b"PendingUpwardMessages");
+	storage::unhashed::get::<Vec<Vec<u8>>>(&key)
+		.unwrap_or_default()
+		.into_iter()
+		.map(|message| {
+			let message = VersionedXcm::<()>::decode(&mut &message[..]).expect("sent as XCM");
+			message.try_into().expect("sent in the latest version")
+		})
+		.collect()
+}
+
+pub fn para_a_ext() -> sp_io::TestExternalities {
+	use parachain_template_runtime::{
+		xcm_config::{RelayAssetId, RelayAssetOwner},
+		Runtime, System, EXISTENTIAL_DEPOSIT,
+	};
+
+	let mut t = frame_system::GenesisConfig::default().build_storage::<Runtime>().unwrap();
+
+	pallet_balances::GenesisConfig::<Runtime> {
+		balances: vec![(ALICE, INITIAL_BALANCE), (sibling_account_id(2), INITIAL_BALANCE)],
+	}
+	.assimilate_storage(&mut t)
+	.unwrap();
+
+	pallet_assets::GenesisConfig::<Runtime> {
+		assets: vec![(RelayAssetId::get(), RelayAssetOwner::get(), true, EXISTENTIAL_DEPOSIT)],
+		metadata: vec![],
+		accounts: vec![(RelayAssetId::get(), ALICE, INITIAL_BALANCE)],
+	}
+	.assimilate_storage(&mut t)
+	.unwrap();
+
+	<parachain_info::GenesisConfig as GenesisBuild<Runtime>>::assimilate_storage(
+		&parachain_info::GenesisConfig { parachain_id: 1.into() },
+		&mut t,
+	)
+	.unwrap();
+
+	let mut ext = sp_io::TestExternalities::new(t);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
+
+pub fn para_b_ext() -> sp_io::TestExternalities {
+	use parachain::{MsgQueue, Runtime, System};
+
+	let mut t = frame_system::GenesisConfig::default().build_storage::<Runtime>().unwrap();
+
+	pallet_balances::GenesisConfig::<Runtime> { balances: vec![(ALICE, INITIAL_BALANCE)] }
+		.assimilate_storage(&mut t)
+		.unwrap();
+
+	let mut ext = sp_io::TestExternalities::new(t);
+	ext.execute_with(|| {
+		System::set_block_number(1);
+		MsgQueue::set_para_id(2.into());
+	});
+	ext
+}
+
+pub fn relay_ext() -> sp_io::TestExternalities {
+	use relay_chain::{Runtime, System};
+
+	let mut t = frame_system::GenesisConfig::default().build_storage::<Runtime>().unwrap();
+
+	pallet_balances::GenesisConfig::<Runtime> {
+		balances: vec![(ALICE, INITIAL_BALANCE), (para_account_id(1), INITIAL_BALANCE)],
+	}
+	.assimilate_storage(&mut t)
+	.unwrap();
+
+	let mut ext = sp_io::TestExternalities::new(t);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
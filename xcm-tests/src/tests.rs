@@ -0,0 +1,680 @@
+use super::*;
+use codec::Encode;
+use frame_support::{
+	assert_noop, assert_ok,
+	traits::Hooks,
+	weights::{Weight, WeightToFeePolynomial},
+};
+use pallet_xcm_overweight::{DmpOverweight, MessageOrigin, OverweightQueue, XcmpOverweight};
+use pallet_xcm_transact_filter::{CallPattern, OriginClass};
+use parachain_template_runtime as runtime;
+use polkadot_parachain::primitives::{DmpMessageHandler, XcmpMessageFormat, XcmpMessageHandler};
+use xcm::latest::prelude::*;
+use xcm_executor::traits::Convert;
+use xcm_simulator::TestExt;
+
+const FEES: u128 = runtime::UNIT;
+const TRANSACT_WEIGHT: u64 = 1_000_000_000;
+
+fn remark() -> runtime::Call {
+	frame_system::Call::remark_with_event { remark: b"hello".to_vec() }.into()
+}
+
+fn transact(call: runtime::Call) -> Instruction<()> {
+	Transact {
+		origin_type: OriginKind::SovereignAccount,
+		require_weight_at_most: TRANSACT_WEIGHT,
+		call: call.encode().into(),
+	}
+}
+
+/// A paid `Transact` of `call`, as a sibling sends it.
+fn paid_transact(call: runtime::Call) -> Xcm<()> {
+	let fees: MultiAsset = (PalletInstance(10), FEES).into();
+	Xcm(vec![
+		WithdrawAsset(fees.clone().into()),
+		BuyExecution { fees, weight_limit: Unlimited },
+		transact(call),
+	])
+}
+
+fn remarked_by(who: runtime::AccountId) -> bool {
+	runtime::System::events().iter().any(|record| {
+		matches!(
+			&record.event,
+			runtime::Event::System(frame_system::Event::Remarked { sender, .. }) if *sender == who
+		)
+	})
+}
+
+fn allow(class: OriginClass, pattern: CallPattern) {
+	assert_ok!(runtime::TransactFilter::allow_call(runtime::Origin::root(), class, pattern));
+}
+
+#[test]
+fn relay_transact_needs_allowed_call() {
+	MockNet::reset();
+	let relay_sovereign =
+		runtime::xcm_config::LocationToAccountId::convert_ref(&MultiLocation::parent())
+			.expect("the relay chain has a sovereign account");
+
+	Relay::execute_with(|| {
+		assert_ok!(relay_chain::XcmPallet::send_xcm(
+			Here,
+			Parachain(1),
+			Xcm(vec![transact(remark())]),
+		));
+	});
+	ParaA::execute_with(|| {
+		assert!(!remarked_by(relay_sovereign.clone()));
+		allow(OriginClass::Relay, CallPattern::pallet("System"));
+	});
+
+	Relay::execute_with(|| {
+		assert_ok!(relay_chain::XcmPallet::send_xcm(
+			Here,
+			Parachain(1),
+			Xcm(vec![transact(remark())]),
+		));
+	});
+	ParaA::execute_with(|| {
+		assert!(remarked_by(relay_sovereign));
+	});
+}
+
+#[test]
+fn messages_to_the_relay_chain_are_queued_upward() {
+	MockNet::reset();
+	let alice = AccountId32 { network: NetworkId::Any, id: ALICE.into() };
+
+	ParaA::execute_with(|| {
+		assert_ok!(runtime::PolkadotXcm::send(
+			runtime::Origin::signed(ALICE),
+			Box::new(Parent.into()),
+			Box::new(VersionedXcm::from(Xcm(vec![ClearOrigin]))),
+		));
+		assert_eq!(upward_messages(), vec![Xcm(vec![DescendOrigin(X1(alice)), ClearOrigin])]);
+	});
+}
+
+#[test]
+fn sibling_transact_needs_allowed_call() {
+	MockNet::reset();
+
+	ParaB::execute_with(|| {
+		assert_ok!(parachain::PolkadotXcm::send_xcm(
+			Here,
+			(Parent, Parachain(1)),
+			paid_transact(remark()),
+		));
+	});
+	ParaA::execute_with(|| {
+		// Fees were paid, but the call was filtered.
+		assert!(runtime::Balances::free_balance(&sibling_account_id(2)) < INITIAL_BALANCE);
+		assert!(!remarked_by(sibling_account_id(2)));
+	});
+
+	ParaA::execute_with(|| {
+		allow(OriginClass::Sibling, CallPattern::call("System", "remark_with_event"));
+	});
+	ParaB::execute_with(|| {
+		assert_ok!(parachain::PolkadotXcm::send_xcm(
+			Here,
+			(Parent, Parachain(1)),
+			paid_transact(remark()),
+		));
+	});
+	ParaA::execute_with(|| {
+		assert!(remarked_by(sibling_account_id(2)));
+	});
+}
+
+#[test]
+fn allowed_calls_are_per_origin_class() {
+	MockNet::reset();
+
+	ParaA::execute_with(|| {
+		allow(OriginClass::Relay, CallPattern::pallet("System"));
+	});
+	ParaB::execute_with(|| {
+		assert_ok!(parachain::PolkadotXcm::send_xcm(
+			Here,
+			(Parent, Parachain(1)),
+			paid_transact(remark()),
+		));
+	});
+	ParaA::execute_with(|| {
+		assert!(!remarked_by(sibling_account_id(2)));
+	});
+}
+
+#[test]
+fn sibling_users_transact_from_derived_accounts() {
+	MockNet::reset();
+	let alice = AccountId32 { network: NetworkId::Any, id: ALICE.into() };
+	let derived = runtime::xcm_config::LocationToAccountId::convert_ref(&MultiLocation::new(
+		1,
+		X2(Parachain(2), alice.clone()),
+	))
+	.expect("sibling users have derived accounts");
+	assert_ne!(derived, sibling_account_id(2));
+	assert_ne!(derived, ALICE);
+
+	ParaA::execute_with(|| {
+		allow(OriginClass::RemoteAccount, CallPattern::call("System", "remark_with_event"));
+	});
+	ParaB::execute_with(|| {
+		// The sibling pays for the execution, its user dispatches the call.
+		let fees: MultiAsset = (PalletInstance(10), FEES).into();
+		assert_ok!(parachain::PolkadotXcm::send_xcm(
+			Here,
+			(Parent, Parachain(1)),
+			Xcm(vec![
+				WithdrawAsset(fees.clone().into()),
+				BuyExecution { fees, weight_limit: Unlimited },
+				DescendOrigin(X1(alice)),
+				transact(remark()),
+			]),
+		));
+	});
+	ParaA::execute_with(|| {
+		assert!(remarked_by(derived));
+		assert!(!remarked_by(sibling_account_id(2)));
+	});
+}
+
+fn set_invulnerables(new: Vec<runtime::AccountId>) -> runtime::Call {
+	pallet_collator_staking::Call::set_invulnerables { new }.into()
+}
+
+/// Sends `call` to `ParaA` as `body` of the relay chain, with `OriginKind::Xcm`.
+fn transact_as_relay_body(id: BodyId, part: BodyPart, call: runtime::Call) {
+	Relay::execute_with(|| {
+		assert_ok!(relay_chain::XcmPallet::send_xcm(
+			X1(Plurality { id, part }),
+			Parachain(1),
+			Xcm(vec![Transact {
+				origin_type: OriginKind::Xcm,
+				require_weight_at_most: TRANSACT_WEIGHT,
+				call: call.encode().into(),
+			}]),
+		));
+	});
+}
+
+#[test]
+fn relay_executive_majority_manages_invulnerables() {
+	MockNet::reset();
+	let majority = BodyPart::Fraction { nom: 2, denom: 3 };
+
+	ParaA::execute_with(|| {
+		allow(OriginClass::Relay, CallPattern::pallet("CollatorStaking"));
+	});
+	transact_as_relay_body(BodyId::Executive, majority, set_invulnerables(vec![ALICE]));
+	ParaA::execute_with(|| {
+		assert_eq!(runtime::CollatorStaking::invulnerables(), vec![ALICE]);
+	});
+
+	transact_as_relay_body(BodyId::Executive, majority, set_invulnerables(vec![]));
+	ParaA::execute_with(|| {
+		assert!(runtime::CollatorStaking::invulnerables().is_empty());
+	});
+}
+
+#[test]
+fn other_relay_origins_cannot_manage_invulnerables() {
+	MockNet::reset();
+
+	ParaA::execute_with(|| {
+		allow(OriginClass::Relay, CallPattern::pallet("CollatorStaking"));
+	});
+	// A minority of the executive body.
+	transact_as_relay_body(
+		BodyId::Executive,
+		BodyPart::Fraction { nom: 1, denom: 3 },
+		set_invulnerables(vec![ALICE]),
+	);
+	// A majority of another body.
+	transact_as_relay_body(
+		BodyId::Technical,
+		BodyPart::Fraction { nom: 2, denom: 3 },
+		set_invulnerables(vec![ALICE]),
+	);
+	// The relay chain itself.
+	Relay::execute_with(|| {
+		assert_ok!(relay_chain::XcmPallet::send_xcm(
+			Here,
+			Parachain(1),
+			Xcm(vec![Transact {
+				origin_type: OriginKind::Xcm,
+				require_weight_at_most: TRANSACT_WEIGHT,
+				call: set_invulnerables(vec![ALICE]).encode().into(),
+			}]),
+		));
+	});
+	ParaA::execute_with(|| {
+		assert!(runtime::CollatorStaking::invulnerables().is_empty());
+	});
+}
+
+#[test]
+fn executive_majority_needs_allowed_call() {
+	MockNet::reset();
+
+	transact_as_relay_body(
+		BodyId::Executive,
+		BodyPart::Fraction { nom: 2, denom: 3 },
+		set_invulnerables(vec![ALICE]),
+	);
+	ParaA::execute_with(|| {
+		assert!(runtime::CollatorStaking::invulnerables().is_empty());
+	});
+}
+
+fn trust(para_id: u32, body: Option<BodyId>) {
+	assert_ok!(runtime::XcmTrustedSiblings::trust(runtime::Origin::root(), para_id, body));
+}
+
+/// Sends `message` from `ParaB` to `ParaA`, descending into `interior` first.
+fn send_to_para_a(interior: impl Into<Junctions>, message: Xcm<()>) {
+	ParaB::execute_with(|| {
+		assert_ok!(parachain::PolkadotXcm::send_xcm(interior, (Parent, Parachain(1)), message));
+	});
+}
+
+/// The outcome of the last message a sibling sent to `ParaA`, as `XcmpQueue` reports it.
+fn last_sibling_outcome() -> Option<Result<(), XcmError>> {
+	use cumulus_pallet_xcmp_queue::Event as XcmpEvent;
+
+	ParaA::execute_with(|| {
+		runtime::System::events()
+			.into_iter()
+			.rev()
+			.find_map(|record| match record.event {
+				runtime::Event::XcmpQueue(XcmpEvent::Success(_)) => Some(Ok(())),
+				runtime::Event::XcmpQueue(XcmpEvent::Fail(_, error)) => Some(Err(error)),
+				_ => None,
+			})
+	})
+}
+
+#[test]
+fn trusted_siblings_transact_unpaid() {
+	MockNet::reset();
+
+	ParaA::execute_with(|| {
+		allow(OriginClass::Sibling, CallPattern::call("System", "remark_with_event"));
+	});
+	send_to_para_a(Here, Xcm(vec![transact(remark())]));
+	assert_eq!(last_sibling_outcome(), Some(Err(XcmError::Barrier)));
+
+	ParaA::execute_with(|| trust(2, None));
+	send_to_para_a(Here, Xcm(vec![transact(remark())]));
+	ParaA::execute_with(|| {
+		assert!(remarked_by(sibling_account_id(2)));
+		assert_eq!(runtime::Balances::free_balance(&sibling_account_id(2)), INITIAL_BALANCE);
+	});
+}
+
+#[test]
+fn trusted_bodies_execute_unpaid() {
+	MockNet::reset();
+
+	ParaA::execute_with(|| trust(2, Some(BodyId::Technical)));
+	send_to_para_a(
+		X1(Plurality { id: BodyId::Technical, part: BodyPart::Voice }),
+		Xcm(vec![ClearOrigin]),
+	);
+	assert_eq!(last_sibling_outcome(), Some(Ok(())));
+
+	send_to_para_a(
+		X1(Plurality { id: BodyId::Legislative, part: BodyPart::Voice }),
+		Xcm(vec![ClearOrigin]),
+	);
+	assert_eq!(last_sibling_outcome(), Some(Err(XcmError::Barrier)));
+}
+
+#[test]
+fn unlisted_siblings_need_buy_execution() {
+	MockNet::reset();
+
+	ParaA::execute_with(|| {
+		allow(OriginClass::Sibling, CallPattern::call("System", "remark_with_event"));
+		// Only a body of the sibling is trusted, not the sibling itself.
+		trust(2, Some(BodyId::Technical));
+	});
+	send_to_para_a(Here, Xcm(vec![transact(remark())]));
+	assert_eq!(last_sibling_outcome(), Some(Err(XcmError::Barrier)));
+	ParaA::execute_with(|| {
+		assert!(!remarked_by(sibling_account_id(2)));
+	});
+
+	send_to_para_a(Here, paid_transact(remark()));
+	ParaA::execute_with(|| {
+		assert!(remarked_by(sibling_account_id(2)));
+		assert!(runtime::Balances::free_balance(&sibling_account_id(2)) < INITIAL_BALANCE);
+	});
+}
+
+const AMOUNT: u128 = 10 * runtime::UNIT;
+const DEST_WEIGHT: u64 = 4_000_000_000;
+
+fn alice() -> Junction {
+	AccountId32 { network: NetworkId::Any, id: ALICE.into() }
+}
+
+/// `ALICE` sends `AMOUNT` of the relay chain token to `dest` with `XcmTransfers`.
+fn transfer_relay_token(dest: MultiLocation) {
+	assert_ok!(runtime::XcmTransfers::transfer(
+		runtime::Origin::signed(ALICE),
+		Box::new(MultiLocation::parent().into()),
+		AMOUNT,
+		Box::new(dest.into()),
+		DEST_WEIGHT,
+	));
+}
+
+fn relay_token_of(who: &runtime::AccountId) -> u128 {
+	runtime::Assets::balance(runtime::xcm_config::RelayAssetId::get(), who)
+}
+
+#[test]
+fn relay_token_goes_back_to_the_relay_chain() {
+	MockNet::reset();
+
+	ParaA::execute_with(|| {
+		transfer_relay_token(MultiLocation::new(1, X1(alice())));
+		assert_eq!(relay_token_of(&ALICE), INITIAL_BALANCE - AMOUNT);
+
+		let message = upward_messages().pop().expect("sent to the relay chain");
+		assert!(matches!(message.0.first(), Some(WithdrawAsset(_))));
+	});
+}
+
+#[test]
+fn relay_token_goes_to_siblings_through_the_relay_chain() {
+	MockNet::reset();
+
+	ParaA::execute_with(|| {
+		transfer_relay_token(MultiLocation::new(1, X2(Parachain(2), alice())));
+		assert_eq!(relay_token_of(&ALICE), INITIAL_BALANCE - AMOUNT);
+
+		let message = upward_messages().pop().expect("sent to the relay chain");
+		let to_sibling = MultiLocation::new(0, X1(Parachain(2)));
+		assert!(message.0.iter().any(|instruction| {
+			matches!(instruction, DepositReserveAsset { dest, .. } if *dest == to_sibling)
+		}));
+	});
+}
+
+#[test]
+fn siblings_cannot_withdraw_to_the_relay_chain() {
+	MockNet::reset();
+
+	ParaA::execute_with(|| trust(2, None));
+	send_to_para_a(
+		Here,
+		Xcm(vec![InitiateReserveWithdraw {
+			assets: Wild(All),
+			reserve: MultiLocation::parent(),
+			xcm: Xcm(vec![]),
+		}]),
+	);
+	assert_eq!(last_sibling_outcome(), Some(Err(XcmError::Barrier)));
+}
+
+/// The fee of executing `instructions`, in the native token or the relay chain token alike.
+fn execution_fee(instructions: u64) -> u128 {
+	runtime::WeightToFee::calc(&(instructions * runtime::xcm_config::UnitWeightCost::get()))
+}
+
+#[test]
+fn native_token_is_addressed_by_its_pallet_instance() {
+	MockNet::reset();
+
+	// The sibling sends back some of the native token it holds in reserve.
+	let native: MultiAsset = (PalletInstance(10), AMOUNT).into();
+	send_to_para_a(
+		Here,
+		Xcm(vec![
+			WithdrawAsset(native.clone().into()),
+			BuyExecution { fees: native, weight_limit: Unlimited },
+			DepositAsset { assets: Wild(All), max_assets: 1, beneficiary: alice().into() },
+		]),
+	);
+	ParaA::execute_with(|| {
+		assert_eq!(
+			runtime::Balances::free_balance(&sibling_account_id(2)),
+			INITIAL_BALANCE - AMOUNT
+		);
+		assert_eq!(
+			runtime::Balances::free_balance(&ALICE),
+			INITIAL_BALANCE + AMOUNT - execution_fee(3)
+		);
+		assert_eq!(relay_token_of(&ALICE), INITIAL_BALANCE);
+	});
+}
+
+#[test]
+fn relay_token_is_held_as_an_asset() {
+	MockNet::reset();
+
+	// Execution is bought with the relay chain token at a fixed rate.
+	let relay_token: MultiAsset = (Parent, AMOUNT).into();
+	Relay::execute_with(|| {
+		assert_ok!(relay_chain::XcmPallet::send_xcm(
+			Here,
+			Parachain(1),
+			Xcm(vec![
+				ReserveAssetDeposited(relay_token.clone().into()),
+				BuyExecution { fees: relay_token, weight_limit: Unlimited },
+				DepositAsset { assets: Wild(All), max_assets: 1, beneficiary: alice().into() },
+			]),
+		));
+	});
+	ParaA::execute_with(|| {
+		assert_eq!(relay_token_of(&ALICE), INITIAL_BALANCE + AMOUNT - execution_fee(3));
+		assert_eq!(runtime::Balances::free_balance(&ALICE), INITIAL_BALANCE);
+	});
+}
+
+#[test]
+fn only_self_reserve_assets_are_reserve_transferred() {
+	MockNet::reset();
+	let transfer = |asset: MultiAsset| {
+		runtime::PolkadotXcm::reserve_transfer_assets(
+			runtime::Origin::signed(ALICE),
+			Box::new((Parent, Parachain(2)).into()),
+			Box::new(alice().into()),
+			Box::new(asset.into()),
+			0,
+		)
+	};
+
+	ParaA::execute_with(|| {
+		assert_noop!(
+			transfer((Parent, AMOUNT).into()),
+			pallet_xcm::Error::<runtime::Runtime>::Filtered
+		);
+
+		// The sibling's sovereign account holds the native token in reserve.
+		assert_ok!(transfer((PalletInstance(10), AMOUNT).into()));
+		assert_eq!(runtime::Balances::free_balance(&ALICE), INITIAL_BALANCE - AMOUNT);
+		assert_eq!(
+			runtime::Balances::free_balance(&sibling_account_id(2)),
+			INITIAL_BALANCE + AMOUNT
+		);
+	});
+}
+
+#[test]
+fn native_token_is_not_sent_to_the_relay_chain() {
+	MockNet::reset();
+
+	ParaA::execute_with(|| {
+		let on_relay = MultiLocation::new(1, X1(alice()));
+		assert_noop!(
+			runtime::XcmTransfers::transfer(
+				runtime::Origin::signed(ALICE),
+				Box::new(MultiLocation::new(0, X1(PalletInstance(10))).into()),
+				AMOUNT,
+				Box::new(on_relay.into()),
+				DEST_WEIGHT,
+			),
+			pallet_xcm_transfers::Error::<runtime::Runtime>::RelayChainIsNotReserve
+		);
+
+		// The relay chain would not take the native token in reserve, the barrier stops it.
+		assert_ok!(runtime::PolkadotXcm::reserve_transfer_assets(
+			runtime::Origin::signed(ALICE),
+			Box::new(Parent.into()),
+			Box::new(alice().into()),
+			Box::new((PalletInstance(10), AMOUNT).into()),
+			0,
+		));
+		runtime::System::assert_last_event(
+			pallet_xcm::Event::Attempted(Outcome::Error(XcmError::Barrier)).into(),
+		);
+		assert_eq!(runtime::Balances::free_balance(&ALICE), INITIAL_BALANCE);
+		assert!(upward_messages().is_empty());
+	});
+}
+
+fn unit_weight() -> Weight {
+	runtime::xcm_config::UnitWeightCost::get()
+}
+
+fn service_overweight(origin: MessageOrigin, budget: Weight) {
+	assert_ok!(runtime::XcmOverweight::set_budget(runtime::Origin::root(), origin, Some(budget)));
+	runtime::XcmOverweight::on_idle(1, Weight::MAX);
+}
+
+// The simulated network delivers messages with unlimited weight, in which none is overweight. These
+// deliver them straight to the queues of `ParaA`, with `weight` left for them.
+
+fn deliver_xcmp(sender: u32, message: Xcm<()>, weight: Weight) {
+	let mut data = XcmpMessageFormat::ConcatenatedVersionedXcm.encode();
+	data.extend(VersionedXcm::from(message).encode());
+	ParaA::execute_with(|| {
+		runtime::XcmpQueue::handle_xcmp_messages(
+			std::iter::once((sender.into(), 1, &data[..])),
+			weight,
+		);
+	});
+}
+
+fn deliver_dmp(message: Xcm<()>, weight: Weight) {
+	let data = VersionedXcm::from(message).encode();
+	ParaA::execute_with(|| {
+		runtime::DmpQueue::handle_dmp_messages(std::iter::once((1, data)), weight);
+	});
+}
+
+#[test]
+fn overweight_messages_are_read_from_xcmp_queue() {
+	type Queue = XcmpOverweight<runtime::Runtime>;
+	MockNet::reset();
+	// `XcmpQueue` executes up to 20 instructions at once.
+	let message = Xcm(vec![ClearOrigin; 25]);
+
+	ParaA::execute_with(|| trust(2, None));
+	deliver_xcmp(2, message.clone(), 20 * unit_weight());
+	ParaA::execute_with(|| {
+		assert_eq!(Queue::overweight_count(), 1);
+		assert_eq!(
+			Queue::overweight(0),
+			Some((MessageOrigin::Sibling(2), VersionedXcm::from(message).encode()))
+		);
+		assert_eq!(Queue::next_overweight(None), Some(0));
+		assert_eq!(Queue::next_overweight(Some(0)), None);
+
+		service_overweight(MessageOrigin::Sibling(2), 25 * unit_weight());
+		assert_eq!(Queue::overweight(0), None);
+		assert_eq!(Queue::next_overweight(None), None);
+		assert_eq!(Queue::overweight_count(), 1);
+	});
+}
+
+#[test]
+fn overweight_messages_are_read_from_dmp_queue() {
+	type Queue = DmpOverweight<runtime::Runtime>;
+	MockNet::reset();
+	// `DmpQueue` executes up to 10 instructions at once.
+	let message = Xcm(vec![ClearOrigin; 15]);
+
+	deliver_dmp(message.clone(), 10 * unit_weight());
+	ParaA::execute_with(|| {
+		assert_eq!(Queue::overweight_count(), 1);
+		assert_eq!(
+			Queue::overweight(0),
+			Some((MessageOrigin::Relay, VersionedXcm::from(message).encode()))
+		);
+		assert_eq!(Queue::next_overweight(None), Some(0));
+		assert_eq!(Queue::next_overweight(Some(0)), None);
+
+		service_overweight(MessageOrigin::Relay, 15 * unit_weight());
+		assert_eq!(Queue::overweight(0), None);
+		assert_eq!(Queue::next_overweight(None), None);
+		assert_eq!(Queue::overweight_count(), 1);
+	});
+}
+
+const DERIVATIVE_CLASS: u32 = 7;
+const ITEM: u32 = 42;
+
+/// A collection of `ParaB`.
+fn foreign_collection() -> MultiLocation {
+	MultiLocation::new(1, X3(Parachain(2), PalletInstance(14), GeneralIndex(0)))
+}
+
+/// `ParaB` deposits `ITEM` of its collection to `ALICE`.
+fn deposit_foreign_item() {
+	let item: MultiAsset = (foreign_collection(), AssetInstance::Index(ITEM.into())).into();
+	send_to_para_a(
+		Here,
+		Xcm(vec![
+			ReserveAssetDeposited(item.into()),
+			ClearOrigin,
+			DepositAsset { assets: Wild(All), max_assets: 1, beneficiary: alice().into() },
+		]),
+	);
+}
+
+#[test]
+fn foreign_items_are_minted_once_their_reserve_is_trusted() {
+	MockNet::reset();
+
+	// NFTs pay no fees: the sibling must be trusted to execute unpaid.
+	ParaA::execute_with(|| {
+		trust(2, None);
+		assert_ok!(runtime::Uniques::force_create(
+			runtime::Origin::root(),
+			DERIVATIVE_CLASS,
+			ALICE.into(),
+			true,
+		));
+		// First step: the derivative collection.
+		assert_ok!(runtime::XcmCollections::register_derivative(
+			runtime::Origin::root(),
+			Box::new(foreign_collection().into()),
+			DERIVATIVE_CLASS,
+		));
+	});
+	deposit_foreign_item();
+	assert_eq!(last_sibling_outcome(), Some(Err(XcmError::UntrustedReserveLocation)));
+	ParaA::execute_with(|| {
+		assert_eq!(runtime::Uniques::owner(DERIVATIVE_CLASS, ITEM), None);
+		// Second step: the sibling as the reserve of its collection.
+		assert_ok!(runtime::XcmReserves::set_reserves(
+			runtime::Origin::root(),
+			Box::new(foreign_collection().into()),
+			vec![MultiLocation::new(1, X1(Parachain(2))).into()],
+		));
+	});
+
+	deposit_foreign_item();
+	assert_eq!(last_sibling_outcome(), Some(Ok(())));
+	ParaA::execute_with(|| {
+		assert_eq!(runtime::Uniques::owner(DERIVATIVE_CLASS, ITEM), Some(ALICE));
+	});
+}